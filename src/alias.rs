@@ -0,0 +1,422 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Tables of alternative names for licenses.
+
+use std::{collections::HashMap, fmt::Write, iter::FromIterator, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::SpdxExpressionError, expression::SpdxExpression, expression_variant::SimpleExpression,
+    trove,
+};
+
+/// Legacy Fedora license tags, from the Callaway system used before Fedora moved to SPDX, that
+/// map to a single SPDX expression.
+const FEDORA: &[(&str, &str)] = &[
+    ("AGPLv3", "AGPL-3.0-only"),
+    ("AGPLv3+", "AGPL-3.0-or-later"),
+    ("ASL 1.0", "Apache-1.0"),
+    ("ASL 1.1", "Apache-1.1"),
+    ("ASL 2.0", "Apache-2.0"),
+    ("Artistic 2.0", "Artistic-2.0"),
+    ("Boost", "BSL-1.0"),
+    ("CC0", "CC0-1.0"),
+    ("CDDL", "CDDL-1.0"),
+    ("EPL-1.0", "EPL-1.0"),
+    ("EPL-2.0", "EPL-2.0"),
+    ("GPL+", "GPL-1.0-or-later"),
+    ("GPLv2", "GPL-2.0-only"),
+    ("GPLv2+", "GPL-2.0-or-later"),
+    ("GPLv3", "GPL-3.0-only"),
+    ("GPLv3+", "GPL-3.0-or-later"),
+    ("ISC", "ISC"),
+    ("LGPLv2+", "LGPL-2.0-or-later"),
+    ("LGPLv3", "LGPL-3.0-only"),
+    ("LGPLv3+", "LGPL-3.0-or-later"),
+    ("MIT", "MIT"),
+    ("MPLv1.0", "MPL-1.0"),
+    ("MPLv1.1", "MPL-1.1"),
+    ("MPLv2.0", "MPL-2.0"),
+    ("OFL", "OFL-1.1"),
+    ("OpenSSL", "OpenSSL"),
+    ("Public Domain", "LicenseRef-Fedora-Public-Domain"),
+    ("Ruby", "Ruby"),
+    ("Unlicense", "Unlicense"),
+    ("WTFPL", "WTFPL"),
+    ("zlib", "Zlib"),
+];
+
+/// License short names of Debian machine-readable copyright files that differ from the SPDX
+/// identifiers.
+const DEBIAN: &[(&str, &str)] = &[
+    ("Apache-2.0", "Apache-2.0"),
+    ("Artistic", "Artistic-1.0-Perl"),
+    ("BSD-2-clause", "BSD-2-Clause"),
+    ("BSD-3-clause", "BSD-3-Clause"),
+    ("BSD-4-clause", "BSD-4-Clause"),
+    ("Expat", "MIT"),
+    ("GPL-1+", "GPL-1.0-or-later"),
+    ("GPL-2", "GPL-2.0-only"),
+    ("GPL-2+", "GPL-2.0-or-later"),
+    ("GPL-3", "GPL-3.0-only"),
+    ("GPL-3+", "GPL-3.0-or-later"),
+    ("LGPL-2", "LGPL-2.0-only"),
+    ("LGPL-2+", "LGPL-2.0-or-later"),
+    ("LGPL-2.1", "LGPL-2.1-only"),
+    ("LGPL-2.1+", "LGPL-2.1-or-later"),
+    ("LGPL-3", "LGPL-3.0-only"),
+    ("LGPL-3+", "LGPL-3.0-or-later"),
+    ("MPL-2.0", "MPL-2.0"),
+    ("Zlib", "Zlib"),
+    ("public-domain", "LicenseRef-public-domain"),
+];
+
+/// Table of alternative names for licenses and the SPDX expressions they stand for.
+///
+/// Aliases can be for example the legacy license tags of Linux distributions, Python license
+/// classifiers or an organization's own spellings of license names.
+///
+/// Tables for legacy Fedora tags, Debian short names and Python license classifiers are bundled,
+/// and aliases can be added to or overridden in any table. Tables can be serialized and
+/// deserialized as maps from aliases to expressions, so custom aliases can be loaded from
+/// configuration files.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{AliasTable, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut aliases = AliasTable::fedora();
+/// aliases.insert("Vendor License", SpdxExpression::parse("LicenseRef-Vendor")?);
+///
+/// assert_eq!(
+///     aliases.translate("(GPLv2+ or ASL 2.0) and Vendor License")?,
+///     SpdxExpression::parse("(GPL-2.0-or-later OR Apache-2.0) AND LicenseRef-Vendor")?
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AliasTable {
+    aliases: HashMap<String, SpdxExpression>,
+}
+
+impl AliasTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table of the legacy Fedora license tags that have a direct SPDX equivalent.
+    /// Tags that were used for several different licenses, like `BSD`, are not included.
+    pub fn fedora() -> Self {
+        Self::bundled(FEDORA)
+    }
+
+    /// Create a table of the Debian short names for licenses in machine-readable copyright
+    /// files.
+    pub fn debian() -> Self {
+        Self::bundled(DEBIAN)
+    }
+
+    /// Create a table of the Python license classifiers that identify a single license, like
+    /// `License :: OSI Approved :: MIT License`.
+    pub fn trove() -> Self {
+        Self::bundled(trove::CLASSIFIERS)
+    }
+
+    /// Get the shared table of Python license classifiers.
+    pub(crate) fn bundled_trove() -> &'static Self {
+        static TROVE: OnceLock<AliasTable> = OnceLock::new();
+        TROVE.get_or_init(Self::trove)
+    }
+
+    fn bundled(aliases: &[(&str, &str)]) -> Self {
+        aliases
+            .iter()
+            .map(|(alias, expression)| {
+                (
+                    (*alias).to_string(),
+                    SpdxExpression::parse(expression).expect("bundled aliases are valid"),
+                )
+            })
+            .collect()
+    }
+
+    /// Add an alias to the table, replacing the previous expression of the alias.
+    pub fn insert(&mut self, alias: &str, expression: SpdxExpression) {
+        self.aliases.insert(alias.to_string(), expression);
+    }
+
+    /// Get the expression of a single alias.
+    pub fn get(&self, alias: &str) -> Option<&SpdxExpression> {
+        self.aliases.get(alias)
+    }
+
+    /// Translate a license field of package metadata to an SPDX expression. The field may
+    /// combine aliases with `and`, `or` and `with`, in any case, and parentheses.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Conversion` if the field contains a name that is not in the
+    /// table, and `SpdxExpressionError::Parse` if the operators and parentheses of the field
+    /// don't form a valid expression.
+    pub fn translate(&self, field: &str) -> Result<SpdxExpression, SpdxExpressionError> {
+        self.translate_field(field, false)
+    }
+
+    /// Translate `field` like [`AliasTable::translate`], keeping names that are not in the table
+    /// but are valid SPDX license identifiers.
+    pub(crate) fn translate_lenient(
+        &self,
+        field: &str,
+    ) -> Result<SpdxExpression, SpdxExpressionError> {
+        self.translate_field(field, true)
+    }
+
+    fn translate_field(
+        &self,
+        field: &str,
+        lenient: bool,
+    ) -> Result<SpdxExpression, SpdxExpressionError> {
+        let mut translated = String::new();
+        let mut name = Vec::new();
+        let mut exception = false;
+
+        for token in tokenize(field) {
+            match token {
+                "(" | ")" => {
+                    self.push_name(&mut translated, &mut name, lenient, exception)?;
+                    translated.push_str(token);
+                }
+                _ if ["and", "or", "with"]
+                    .iter()
+                    .any(|operator| token.eq_ignore_ascii_case(operator)) =>
+                {
+                    self.push_name(&mut translated, &mut name, lenient, exception)?;
+                    exception = token.eq_ignore_ascii_case("with");
+                    translated.push(' ');
+                    translated.push_str(&token.to_ascii_uppercase());
+                    translated.push(' ');
+                }
+                _ => name.push(token),
+            }
+        }
+        self.push_name(&mut translated, &mut name, lenient, exception)?;
+
+        SpdxExpression::parse(&translated)
+    }
+
+    /// Translate the words of the name and append the translation to `translated`. Exceptions
+    /// are not translated.
+    fn push_name(
+        &self,
+        translated: &mut String,
+        name: &mut Vec<&str>,
+        lenient: bool,
+        exception: bool,
+    ) -> Result<(), SpdxExpressionError> {
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        let joined = name.join(" ");
+        name.clear();
+
+        if exception {
+            translated.push_str(&joined);
+            return Ok(());
+        }
+
+        match self.get(&joined) {
+            Some(expression) if expression.inner().flatten().is_some() => {
+                // Writing to a `String` can't fail.
+                let _ = write!(translated, "({expression})");
+            }
+            Some(expression) => {
+                let _ = write!(translated, "{expression}");
+            }
+            None if lenient && SimpleExpression::parse(&joined).is_ok() => {
+                translated.push_str(&joined);
+            }
+            None => {
+                return Err(SpdxExpressionError::Conversion(format!(
+                    "unknown license name `{joined}`"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromIterator<(String, SpdxExpression)> for AliasTable {
+    fn from_iter<T: IntoIterator<Item = (String, SpdxExpression)>>(iter: T) -> Self {
+        Self {
+            aliases: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(String, SpdxExpression)> for AliasTable {
+    fn extend<T: IntoIterator<Item = (String, SpdxExpression)>>(&mut self, iter: T) {
+        self.aliases.extend(iter);
+    }
+}
+
+impl Serialize for AliasTable {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.aliases.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AliasTable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        HashMap::deserialize(deserializer).map(|aliases| Self { aliases })
+    }
+}
+
+/// Split a license field to parentheses and whitespace separated words.
+fn tokenize(field: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for word in field.split_whitespace() {
+        let mut rest = word;
+        while let Some(index) = rest.find(['(', ')']) {
+            if index > 0 {
+                tokens.push(&rest[..index]);
+            }
+            tokens.push(&rest[index..=index]);
+            rest = &rest[index + 1..];
+        }
+        if !rest.is_empty() {
+            tokens.push(rest);
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_tables_are_valid() {
+        assert_eq!(AliasTable::fedora().aliases.len(), FEDORA.len());
+        assert_eq!(AliasTable::debian().aliases.len(), DEBIAN.len());
+        assert_eq!(AliasTable::trove().aliases.len(), trove::CLASSIFIERS.len());
+    }
+
+    #[test]
+    fn tokenize_parentheses() {
+        assert_eq!(
+            tokenize("(GPLv2+ or ASL 2.0)and(MIT)"),
+            ["(", "GPLv2+", "or", "ASL", "2.0", ")", "and", "(", "MIT", ")"]
+        );
+    }
+
+    #[test]
+    fn translate_fields() {
+        let aliases = AliasTable::fedora();
+
+        assert_eq!(
+            aliases.translate("Public Domain").unwrap().to_string(),
+            "LicenseRef-Fedora-Public-Domain"
+        );
+        assert_eq!(
+            aliases
+                .translate("GPLv2+ and (MIT or ASL 2.0)")
+                .unwrap()
+                .to_string(),
+            "GPL-2.0-or-later AND (MIT OR Apache-2.0)"
+        );
+        assert_eq!(
+            AliasTable::debian()
+                .translate("GPL-2+ OR Expat")
+                .unwrap()
+                .to_string(),
+            "GPL-2.0-or-later OR MIT"
+        );
+    }
+
+    #[test]
+    fn parenthesize_compound_translations() {
+        let mut aliases = AliasTable::new();
+        aliases.insert("Dual", SpdxExpression::parse("MIT OR ISC").unwrap());
+        aliases.insert("Other", SpdxExpression::parse("0BSD").unwrap());
+
+        assert_eq!(
+            aliases.translate("Dual and Other").unwrap().to_string(),
+            "(MIT OR ISC) AND 0BSD"
+        );
+    }
+
+    #[test]
+    fn reject_unknown_names_and_invalid_fields() {
+        let aliases = AliasTable::fedora();
+
+        assert!(matches!(
+            aliases.translate("GPLv2 and Unknown"),
+            Err(SpdxExpressionError::Conversion(_))
+        ));
+        assert!(matches!(
+            aliases.translate("GPLv2 and"),
+            Err(SpdxExpressionError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn translate_exceptions_as_is() {
+        assert_eq!(
+            AliasTable::fedora()
+                .translate("GPLv2+ with Classpath-exception-2.0 or MIT")
+                .unwrap()
+                .to_string(),
+            "GPL-2.0-or-later WITH Classpath-exception-2.0 OR MIT"
+        );
+    }
+
+    #[test]
+    fn keep_valid_identifiers_when_lenient() {
+        let aliases = AliasTable::fedora();
+
+        assert_eq!(
+            aliases
+                .translate_lenient("ASL 2.0 OR LicenseRef-a")
+                .unwrap()
+                .to_string(),
+            "Apache-2.0 OR LicenseRef-a"
+        );
+        assert!(aliases.translate("ASL 2.0 OR LicenseRef-a").is_err());
+        assert!(aliases.translate_lenient("Unknown License").is_err());
+    }
+
+    #[test]
+    fn load_from_map() {
+        let aliases: AliasTable = serde_json::from_str(
+            r#"{ "Vendor License": "LicenseRef-Vendor", "Dual": "MIT OR Apache-2.0" }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            aliases
+                .translate("Dual and Vendor License")
+                .unwrap()
+                .to_string(),
+            "(MIT OR Apache-2.0) AND LicenseRef-Vendor"
+        );
+        assert_eq!(
+            serde_json::from_value::<AliasTable>(serde_json::to_value(&aliases).unwrap()).unwrap(),
+            aliases
+        );
+        assert!(serde_json::from_str::<AliasTable>(r#"{ "Broken": "MIT OR" }"#).is_err());
+    }
+}
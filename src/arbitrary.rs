@@ -0,0 +1,206 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! [`quickcheck::Arbitrary`] implementations.
+//!
+//! Generated expressions are structured like the parser would structure them, so displaying and
+//! parsing a generated expression results in an equal expression. Compound expressions shrink
+//! towards their subtrees.
+
+use std::sync::Arc;
+
+use quickcheck::{Arbitrary, Gen};
+
+use crate::{
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+};
+
+/// Maximum nesting depth of generated compound expressions.
+const MAX_DEPTH: usize = 4;
+
+const ID_START: &[char] = &[
+    'a', 'b', 'c', 'x', 'y', 'z', 'A', 'B', 'C', 'X', 'Y', 'Z', '0', '1', '2', '9',
+];
+const ID_CONTINUE: &[char] = &[
+    'a', 'b', 'c', 'x', 'y', 'z', 'A', 'B', 'C', 'X', 'Y', 'Z', '0', '1', '2', '9', '-', '.',
+];
+
+/// Generate an `idstring` of the SPDX specification that is not an operator keyword.
+fn idstring(g: &mut Gen) -> String {
+    loop {
+        let length = usize::arbitrary(g) % 8;
+        let mut id = g.choose(ID_START).copied().into_iter().collect::<String>();
+        id.extend((0..length).filter_map(|_| g.choose(ID_CONTINUE).copied()));
+
+        if !["AND", "OR", "WITH"]
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(&id))
+        {
+            return id;
+        }
+    }
+}
+
+impl Arbitrary for SimpleExpression {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match u8::arbitrary(g) % 4 {
+            0 => Self::new(idstring(g), None, true),
+            1 => Self::new(idstring(g), Some(idstring(g)), true),
+            2 => Self::new(idstring(g), None, false).with_or_later(true),
+            _ => Self::new(idstring(g), None, false),
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let mut candidates = Vec::new();
+
+        if self.document_ref.is_some() {
+            candidates.push(Self::new(self.identifier.clone(), None, self.license_ref));
+        }
+        if self.or_later {
+            candidates.push(self.clone().with_or_later(false));
+        }
+        if self.identifier.len() > 1 {
+            candidates.push(Self::new(
+                self.identifier[..1].to_string(),
+                self.document_ref.clone(),
+                self.license_ref,
+            ));
+        }
+
+        Box::new(candidates.into_iter())
+    }
+}
+
+fn arbitrary_variant(g: &mut Gen, depth: usize) -> ExpressionVariant {
+    let choice = if depth == 0 {
+        usize::arbitrary(g) % 2
+    } else {
+        usize::arbitrary(g) % 5
+    };
+
+    match choice {
+        0 => ExpressionVariant::Simple(SimpleExpression::arbitrary(g)),
+        1 => ExpressionVariant::With(Box::new(WithExpression::new(
+            SimpleExpression::arbitrary(g),
+            idstring(g),
+        ))),
+        2 => ExpressionVariant::and(
+            arbitrary_variant(g, depth - 1),
+            arbitrary_variant(g, depth - 1),
+        ),
+        3 => ExpressionVariant::or(
+            arbitrary_variant(g, depth - 1),
+            arbitrary_variant(g, depth - 1),
+        ),
+        _ => ExpressionVariant::Parens(Arc::new(arbitrary_variant(g, depth - 1))),
+    }
+}
+
+/// Shrink candidates of `expression`: its subtrees first, then the expression with one child
+/// shrunk.
+fn shrink_variant(expression: &ExpressionVariant) -> Vec<ExpressionVariant> {
+    let mut candidates = Vec::new();
+
+    match expression {
+        ExpressionVariant::Simple(license) => {
+            candidates.extend(license.shrink().map(ExpressionVariant::Simple));
+        }
+        ExpressionVariant::With(with) => {
+            candidates.push(ExpressionVariant::Simple(with.license.clone()));
+            candidates.extend(with.license.shrink().map(|license| {
+                ExpressionVariant::With(Box::new(WithExpression::new(
+                    license,
+                    with.exception.clone(),
+                )))
+            }));
+        }
+        ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right) => {
+            let join = if matches!(expression, ExpressionVariant::And(..)) {
+                ExpressionVariant::and
+            } else {
+                ExpressionVariant::or
+            };
+
+            candidates.push(left.as_ref().clone());
+            candidates.push(right.as_ref().clone());
+            candidates.extend(
+                shrink_variant(left)
+                    .into_iter()
+                    .map(|left| join(left, right.as_ref().clone())),
+            );
+            candidates.extend(
+                shrink_variant(right)
+                    .into_iter()
+                    .map(|right| join(left.as_ref().clone(), right)),
+            );
+        }
+        ExpressionVariant::Parens(inner) => {
+            candidates.push(inner.as_ref().clone());
+            candidates.extend(
+                shrink_variant(inner)
+                    .into_iter()
+                    .map(|inner| ExpressionVariant::Parens(Arc::new(inner))),
+            );
+        }
+    }
+
+    // Re-adding parentheses can reproduce the original expression, which would never stop
+    // shrinking.
+    candidates.retain(|candidate| candidate != expression);
+    candidates
+}
+
+impl Arbitrary for SpdxExpression {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let depth = g.size().min(MAX_DEPTH);
+        Self::from_inner(arbitrary_variant(g, depth))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(
+            shrink_variant(self.inner())
+                .into_iter()
+                .map(Self::from_inner),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::*;
+
+    quickcheck! {
+        fn simple_expressions_round_trip(license: SimpleExpression) -> bool {
+            SimpleExpression::parse(&license.to_string()).unwrap() == license
+        }
+
+        fn expressions_round_trip(expression: SpdxExpression) -> bool {
+            SpdxExpression::parse(&expression.to_string()).unwrap() == expression
+        }
+
+        fn shrunk_expressions_round_trip(expression: SpdxExpression) -> bool {
+            expression
+                .shrink()
+                .all(|shrunk| SpdxExpression::parse(&shrunk.to_string()).unwrap() == shrunk)
+        }
+    }
+
+    #[test]
+    fn shrink_towards_subtrees() {
+        let expression = SpdxExpression::parse("(MIT OR ISC) AND Apache-2.0").unwrap();
+
+        let shrunk = expression
+            .shrink()
+            .map(|expression| expression.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(shrunk[..2], ["(MIT OR ISC)", "Apache-2.0"]);
+        assert!(shrunk.contains(&"(MIT) AND Apache-2.0".to_string()));
+        assert!(shrunk.contains(&"(MIT OR ISC) AND A".to_string()));
+    }
+}
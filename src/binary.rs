@@ -0,0 +1,456 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Compact binary encoding of parsed expressions.
+//!
+//! The encoding starts with a single format version byte followed by the nodes of the expression
+//! tree in prefix order. Every node starts with a tag byte. Strings are encoded as a LEB128
+//! length followed by UTF-8 bytes. The format for a given version byte will not change, so
+//! encoded expressions can be safely cached between runs and crate upgrades.
+
+use std::sync::Arc;
+
+use crate::{
+    error::SpdxExpressionError,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser,
+};
+
+/// Version of the binary format written by [`encode`].
+pub const FORMAT_VERSION: u8 = 1;
+
+const TAG_SIMPLE: u8 = 0;
+const TAG_WITH: u8 = 1;
+const TAG_AND: u8 = 2;
+const TAG_OR: u8 = 3;
+const TAG_PARENS: u8 = 4;
+
+/// Maximum nesting depth of decoded expressions, counting parentheses and right operands, which
+/// are nested in the text form too. The left operands of chains like `a AND b AND c` don't
+/// count, so chains of any length decode. Decoding doesn't recurse, but other operations on the
+/// tree do, so deeper input is rejected.
+const MAX_DEPTH: usize = 256;
+
+const FLAG_LICENSE_REF: u8 = 0b01;
+const FLAG_DOCUMENT_REF: u8 = 0b10;
+
+pub fn encode(expression: &ExpressionVariant) -> Vec<u8> {
+    let mut buffer = vec![FORMAT_VERSION];
+    encode_node(expression, &mut buffer);
+    buffer
+}
+
+pub fn decode(bytes: &[u8]) -> Result<ExpressionVariant, SpdxExpressionError> {
+    let mut decoder = Decoder { bytes, position: 0 };
+
+    let version = decoder.byte()?;
+    if version != FORMAT_VERSION {
+        return Err(SpdxExpressionError::Decode(format!(
+            "unsupported format version {version}"
+        )));
+    }
+
+    let expression = decoder.expression()?;
+
+    if decoder.position == bytes.len() {
+        Ok(expression)
+    } else {
+        Err(SpdxExpressionError::Decode(format!(
+            "trailing bytes after position {}",
+            decoder.position
+        )))
+    }
+}
+
+fn encode_node(expression: &ExpressionVariant, buffer: &mut Vec<u8>) {
+    match expression {
+        ExpressionVariant::Simple(simple) => {
+            buffer.push(TAG_SIMPLE);
+            encode_simple(simple, buffer);
+        }
+        ExpressionVariant::With(with) => {
+            buffer.push(TAG_WITH);
+            encode_simple(&with.license, buffer);
+            encode_str(&with.exception, buffer);
+        }
+        ExpressionVariant::And(left, right) => {
+            buffer.push(TAG_AND);
+            encode_node(left, buffer);
+            encode_node(right, buffer);
+        }
+        ExpressionVariant::Or(left, right) => {
+            buffer.push(TAG_OR);
+            encode_node(left, buffer);
+            encode_node(right, buffer);
+        }
+        ExpressionVariant::Parens(inner) => {
+            buffer.push(TAG_PARENS);
+            encode_node(inner, buffer);
+        }
+    }
+}
+
+fn encode_simple(simple: &SimpleExpression, buffer: &mut Vec<u8>) {
+    let mut flags = 0;
+    if simple.license_ref {
+        flags |= FLAG_LICENSE_REF;
+    }
+    if simple.document_ref.is_some() {
+        flags |= FLAG_DOCUMENT_REF;
+    }
+    buffer.push(flags);
+
+    if let Some(document_ref) = &simple.document_ref {
+        encode_str(document_ref, buffer);
+    }
+    // The "or later" `+` is kept in the identifier like in the text form.
+    if simple.or_later {
+        encode_str(&format!("{}+", simple.identifier), buffer);
+    } else {
+        encode_str(&simple.identifier, buffer);
+    }
+}
+
+fn encode_str(value: &str, buffer: &mut Vec<u8>) {
+    let mut length = value.len();
+    loop {
+        // Truncation is intended, only the lowest seven bits are kept.
+        #[allow(clippy::cast_possible_truncation)]
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            buffer.push(byte);
+            break;
+        }
+        buffer.push(byte | 0x80);
+    }
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Result<u8, SpdxExpressionError> {
+        let byte = self.bytes.get(self.position).copied().ok_or_else(|| {
+            SpdxExpressionError::Decode(format!(
+                "unexpected end of input at position {}",
+                self.position
+            ))
+        })?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn length(&mut self) -> Result<usize, SpdxExpressionError> {
+        let mut length = 0_usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = usize::from(byte & 0x7f);
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(SpdxExpressionError::Decode(format!(
+                    "string length overflows at position {}",
+                    self.position
+                )));
+            }
+            length |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(length);
+            }
+            shift += 7;
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SpdxExpressionError> {
+        let length = self.length()?;
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| {
+                SpdxExpressionError::Decode(format!(
+                    "string of length {length} at position {} exceeds input",
+                    self.position
+                ))
+            })?;
+
+        let value = std::str::from_utf8(&self.bytes[self.position..end]).map_err(|err| {
+            SpdxExpressionError::Decode(format!(
+                "invalid UTF-8 at position {}: {err}",
+                self.position
+            ))
+        })?;
+        self.position = end;
+
+        Ok(value.to_string())
+    }
+
+    /// Read a string that must be a valid idstring.
+    fn idstring(&mut self) -> Result<String, SpdxExpressionError> {
+        let position = self.position;
+        let value = self.string()?;
+        Self::check_idstring(value, position)
+    }
+
+    fn check_idstring(value: String, position: usize) -> Result<String, SpdxExpressionError> {
+        if parser::is_idstring(&value) {
+            Ok(value)
+        } else {
+            Err(SpdxExpressionError::Decode(format!(
+                "invalid identifier {value:?} at position {position}"
+            )))
+        }
+    }
+
+    fn simple(&mut self) -> Result<SimpleExpression, SpdxExpressionError> {
+        let flags = self.byte()?;
+        // Only `LicenseRef`s can be in other documents.
+        if flags & !(FLAG_LICENSE_REF | FLAG_DOCUMENT_REF) != 0 || flags == FLAG_DOCUMENT_REF {
+            return Err(SpdxExpressionError::Decode(format!(
+                "invalid flags {flags:#04x} at position {}",
+                self.position - 1
+            )));
+        }
+
+        let document_ref = if flags & FLAG_DOCUMENT_REF == 0 {
+            None
+        } else {
+            Some(self.idstring()?)
+        };
+        let position = self.position;
+        let mut identifier = self.string()?;
+        let or_later = identifier.ends_with('+');
+        if or_later {
+            identifier.pop();
+        }
+        let identifier = Self::check_idstring(identifier, position)?;
+
+        Ok(
+            SimpleExpression::new(identifier, document_ref, flags & FLAG_LICENSE_REF != 0)
+                .with_or_later(or_later),
+        )
+    }
+
+    /// Decode the nodes of an expression in prefix order, keeping the nodes whose operands
+    /// are still being decoded on a stack instead of recursing.
+    fn expression(&mut self) -> Result<ExpressionVariant, SpdxExpressionError> {
+        let mut pending: Vec<Pending> = Vec::new();
+        let mut depth = 0;
+
+        loop {
+            if depth > MAX_DEPTH {
+                return Err(SpdxExpressionError::Decode(format!(
+                    "expression nests deeper than {MAX_DEPTH} levels at position {}",
+                    self.position
+                )));
+            }
+
+            let mut node = match self.byte()? {
+                TAG_SIMPLE => ExpressionVariant::Simple(self.simple()?),
+                TAG_WITH => {
+                    let license = self.simple()?;
+                    let exception = self.idstring()?;
+                    ExpressionVariant::With(Box::new(WithExpression::new(license, exception)))
+                }
+                tag @ (TAG_AND | TAG_OR) => {
+                    pending.push(Pending::Operator {
+                        tag,
+                        left: None,
+                        depth,
+                    });
+                    continue;
+                }
+                TAG_PARENS => {
+                    pending.push(Pending::Parens);
+                    depth += 1;
+                    continue;
+                }
+                tag => {
+                    return Err(SpdxExpressionError::Decode(format!(
+                        "unknown node tag {tag} at position {}",
+                        self.position - 1
+                    )))
+                }
+            };
+
+            // Complete the pending nodes until one still needs its right operand, whose depth
+            // follows from that of the operator.
+            loop {
+                match pending.pop() {
+                    None => return Ok(node),
+                    Some(Pending::Parens) => {
+                        node = ExpressionVariant::Parens(Arc::new(node));
+                    }
+                    Some(Pending::Operator {
+                        tag,
+                        left: None,
+                        depth: operator_depth,
+                    }) => {
+                        pending.push(Pending::Operator {
+                            tag,
+                            left: Some(node),
+                            depth: operator_depth,
+                        });
+                        depth = operator_depth + 1;
+                        break;
+                    }
+                    Some(Pending::Operator {
+                        tag,
+                        left: Some(left),
+                        ..
+                    }) => {
+                        node = if tag == TAG_AND {
+                            ExpressionVariant::And(Arc::new(left), Arc::new(node))
+                        } else {
+                            ExpressionVariant::Or(Arc::new(left), Arc::new(node))
+                        };
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Node whose operands are being decoded.
+enum Pending {
+    Parens,
+    Operator {
+        tag: u8,
+        left: Option<ExpressionVariant>,
+        depth: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_complex_expression() {
+        let expression = ExpressionVariant::parse(
+            "DocumentRef-doc:LicenseRef-a AND ((GPL-2.0-or-later WITH Classpath-exception-2.0) OR MIT+)",
+        )
+        .unwrap();
+
+        let decoded = decode(&encode(&expression)).unwrap();
+
+        assert_eq!(decoded, expression);
+        assert_eq!(decoded.to_string(), expression.to_string());
+    }
+
+    #[test]
+    fn encode_simple_expression_compactly() {
+        let expression = ExpressionVariant::parse("MIT").unwrap();
+
+        assert_eq!(
+            encode(&expression),
+            [FORMAT_VERSION, TAG_SIMPLE, 0, 3, b'M', b'I', b'T']
+        );
+    }
+
+    #[test]
+    fn encode_long_strings_with_multibyte_length() {
+        let identifier = "a".repeat(300);
+        let expression = ExpressionVariant::parse(&identifier).unwrap();
+
+        let encoded = encode(&expression);
+
+        assert_eq!(&encoded[3..5], &[0xac, 0x02]);
+        assert_eq!(decode(&encoded).unwrap(), expression);
+    }
+
+    #[test]
+    fn fail_on_unknown_version() {
+        assert!(decode(&[FORMAT_VERSION + 1, TAG_SIMPLE, 0, 1, b'a']).is_err());
+    }
+
+    #[test]
+    fn fail_on_truncated_input() {
+        let encoded = encode(&ExpressionVariant::parse("MIT OR ISC").unwrap());
+
+        for end in 0..encoded.len() {
+            assert!(decode(&encoded[..end]).is_err());
+        }
+    }
+
+    #[test]
+    fn fail_on_trailing_bytes() {
+        let mut encoded = encode(&ExpressionVariant::parse("MIT").unwrap());
+        encoded.push(0);
+
+        assert!(decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn fail_on_unknown_tag() {
+        assert!(decode(&[FORMAT_VERSION, 9]).is_err());
+    }
+
+    #[test]
+    fn fail_on_deep_nesting() {
+        let mut encoded = vec![FORMAT_VERSION];
+        encoded.resize(100_000, TAG_PARENS);
+
+        assert!(matches!(
+            decode(&encoded),
+            Err(SpdxExpressionError::Decode(message)) if message.contains("nests deeper")
+        ));
+
+        let mut encoded = vec![FORMAT_VERSION];
+        encoded.resize(MAX_DEPTH + 1, TAG_PARENS);
+        encoded.extend([TAG_SIMPLE, 0, 3, b'M', b'I', b'T']);
+        assert!(decode(&encoded).is_ok());
+    }
+
+    #[test]
+    fn fail_on_document_ref_of_listed_license() {
+        let mut encoded = encode(&ExpressionVariant::parse("DocumentRef-x:LicenseRef-a").unwrap());
+        assert_eq!(encoded[2], FLAG_LICENSE_REF | FLAG_DOCUMENT_REF);
+        assert!(decode(&encoded).is_ok());
+
+        encoded[2] = FLAG_DOCUMENT_REF;
+        assert!(decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn round_trip_long_chains() {
+        for operator in [" AND ", " OR "] {
+            let chain = (0..1000)
+                .map(|index| format!("LicenseRef-{index}"))
+                .collect::<Vec<_>>()
+                .join(operator);
+            let expression = ExpressionVariant::parse(&chain).unwrap();
+
+            assert_eq!(decode(&encode(&expression)).unwrap(), expression);
+        }
+    }
+
+    #[test]
+    fn fail_on_deeply_nested_right_operands() {
+        let nested = |levels| {
+            let mut encoded = vec![FORMAT_VERSION];
+            for _ in 0..levels {
+                encoded.extend([TAG_AND, TAG_SIMPLE, 0, 3, b'M', b'I', b'T']);
+            }
+            encoded.extend([TAG_SIMPLE, 0, 3, b'M', b'I', b'T']);
+            encoded
+        };
+
+        assert!(decode(&nested(100_000)).is_err());
+        assert!(decode(&nested(MAX_DEPTH)).is_ok());
+    }
+
+    #[test]
+    fn fail_on_invalid_identifiers() {
+        assert!(decode(&[FORMAT_VERSION, TAG_SIMPLE, 0, 0]).is_err());
+        assert!(decode(&[FORMAT_VERSION, TAG_SIMPLE, 0, 1, b'+']).is_err());
+        assert!(decode(&[FORMAT_VERSION, TAG_SIMPLE, 0, 3, b'a', b' ', b'b']).is_err());
+        assert!(decode(&[FORMAT_VERSION, TAG_SIMPLE, FLAG_DOCUMENT_REF, 0, 1, b'a']).is_err());
+        assert!(decode(&[FORMAT_VERSION, TAG_WITH, 0, 1, b'a', 1, b'(']).is_err());
+        assert!(decode(&[FORMAT_VERSION, TAG_WITH, 0, 1, b'a', 1, b'b']).is_ok());
+    }
+}
@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Validating construction of expressions.
+
+use crate::{
+    error::BuildError,
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser,
+    special::is_special,
+};
+
+/// Builder constructing an expression step by step, validating every step.
+///
+/// Every step returns a [`BuildError`] describing the problem if it would result in an invalid
+/// expression, e.g. when attaching an exception to a compound expression or combining `NONE`
+/// with other licenses. This lets user interfaces report problems as soon as they are made,
+/// instead of when the finished expression is parsed.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{BuildError, ExpressionBuilder};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let expression = ExpressionBuilder::license("GPL-2.0-only")?
+///     .with_exception("Classpath-exception-2.0")?
+///     .or(ExpressionBuilder::license("MIT")?.and(ExpressionBuilder::license("ISC")?)?)?
+///     .build();
+/// assert_eq!(
+///     expression.to_string(),
+///     "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT AND ISC"
+/// );
+///
+/// let compound = ExpressionBuilder::license("MIT")?.or(ExpressionBuilder::license("ISC")?)?;
+/// assert_eq!(
+///     compound.with_exception("exception").unwrap_err(),
+///     BuildError::ExceptionOnCompound("MIT OR ISC".to_string())
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionBuilder {
+    expression: ExpressionVariant,
+}
+
+impl ExpressionBuilder {
+    /// Start with a single license, e.g. `MIT`, `GPL-2.0+` or `LicenseRef-custom`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BuildError::InvalidLicense` if `identifier` is not a valid license identifier.
+    pub fn license(identifier: &str) -> Result<Self, BuildError> {
+        let license = SimpleExpression::parse(identifier)
+            .map_err(|_| BuildError::InvalidLicense(identifier.to_string()))?;
+
+        Ok(Self {
+            expression: ExpressionVariant::Simple(license),
+        })
+    }
+
+    /// Start with an existing expression, e.g. to extend a parsed expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BuildError::SpecialLicense` if `expression` combines `NONE` or `NOASSERTION`
+    /// with other licenses or exceptions.
+    pub fn from_expression(expression: &SpdxExpression) -> Result<Self, BuildError> {
+        let expression = expression.inner().clone();
+        let is_single = matches!(expression.unparenthesized(), ExpressionVariant::Simple(_));
+        if !is_single {
+            if let Some(special) = expression
+                .licenses()
+                .into_iter()
+                .find(|license| is_special(license))
+            {
+                return Err(BuildError::SpecialLicense(special.to_string()));
+            }
+        }
+
+        Ok(Self { expression })
+    }
+
+    /// Attach `exception` to the license.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BuildError::InvalidException` if `exception` is not a valid exception
+    /// identifier, `BuildError::ExceptionOnCompound` if the expression is not a single license,
+    /// `BuildError::DuplicateException` if the license already has an exception, and
+    /// `BuildError::SpecialLicense` if the license is `NONE` or `NOASSERTION`.
+    pub fn with_exception(self, exception: &str) -> Result<Self, BuildError> {
+        if !parser::is_idstring(exception) {
+            return Err(BuildError::InvalidException(exception.to_string()));
+        }
+
+        match self.expression.unparenthesized() {
+            ExpressionVariant::Simple(license) if is_special(license) => {
+                Err(BuildError::SpecialLicense(license.to_string()))
+            }
+            ExpressionVariant::Simple(license) => Ok(Self {
+                expression: ExpressionVariant::With(Box::new(WithExpression::new(
+                    license.clone(),
+                    exception.to_string(),
+                ))),
+            }),
+            ExpressionVariant::With(with) => Err(BuildError::DuplicateException(with.to_string())),
+            ExpressionVariant::And(..)
+            | ExpressionVariant::Or(..)
+            | ExpressionVariant::Parens(_) => Err(BuildError::ExceptionOnCompound(
+                self.expression.unparenthesized().to_string(),
+            )),
+        }
+    }
+
+    /// Require both the current expression and `other`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BuildError::SpecialLicense` if either expression is `NONE` or `NOASSERTION`.
+    pub fn and(self, other: Self) -> Result<Self, BuildError> {
+        let (left, right) = Self::operands(self, other)?;
+        Ok(Self {
+            expression: ExpressionVariant::and(left, right),
+        })
+    }
+
+    /// Allow a choice between the current expression and `other`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BuildError::SpecialLicense` if either expression is `NONE` or `NOASSERTION`.
+    pub fn or(self, other: Self) -> Result<Self, BuildError> {
+        let (left, right) = Self::operands(self, other)?;
+        Ok(Self {
+            expression: ExpressionVariant::or(left, right),
+        })
+    }
+
+    /// Finish the expression.
+    pub fn build(self) -> SpdxExpression {
+        SpdxExpression::from_inner(self.expression)
+    }
+
+    fn operands(
+        left: Self,
+        right: Self,
+    ) -> Result<(ExpressionVariant, ExpressionVariant), BuildError> {
+        for operand in [&left, &right] {
+            if let ExpressionVariant::Simple(license) = operand.expression.unparenthesized() {
+                if is_special(license) {
+                    return Err(BuildError::SpecialLicense(license.to_string()));
+                }
+            }
+        }
+
+        Ok((left.expression, right.expression))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn license(identifier: &str) -> ExpressionBuilder {
+        ExpressionBuilder::license(identifier).unwrap()
+    }
+
+    #[test]
+    fn build_expressions() {
+        let expression = license("MIT")
+            .or(license("ISC"))
+            .unwrap()
+            .and(license("GPL-2.0+").with_exception("exc").unwrap())
+            .unwrap()
+            .and(license("0BSD").and(license("Zlib")).unwrap())
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            expression.to_string(),
+            "(MIT OR ISC) AND GPL-2.0+ WITH exc AND (0BSD AND Zlib)"
+        );
+        assert_eq!(
+            SpdxExpression::parse(&expression.to_string()).unwrap(),
+            expression
+        );
+        assert_eq!(license("NONE").build().to_string(), "NONE");
+    }
+
+    #[test]
+    fn reject_invalid_steps() {
+        assert_eq!(
+            ExpressionBuilder::license("MIT OR ISC").unwrap_err(),
+            BuildError::InvalidLicense("MIT OR ISC".to_string())
+        );
+        assert_eq!(
+            license("MIT").with_exception("a b").unwrap_err(),
+            BuildError::InvalidException("a b".to_string())
+        );
+        assert_eq!(
+            license("MIT")
+                .with_exception("a")
+                .unwrap()
+                .with_exception("b")
+                .unwrap_err(),
+            BuildError::DuplicateException("MIT WITH a".to_string())
+        );
+        assert_eq!(
+            license("NOASSERTION").with_exception("a").unwrap_err(),
+            BuildError::SpecialLicense("NOASSERTION".to_string())
+        );
+        assert_eq!(
+            license("MIT").and(license("NONE")).unwrap_err(),
+            BuildError::SpecialLicense("NONE".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_existing_expressions() {
+        let parsed = SpdxExpression::parse("MIT OR NONE").unwrap();
+        assert_eq!(
+            ExpressionBuilder::from_expression(&parsed).unwrap_err(),
+            BuildError::SpecialLicense("NONE".to_string())
+        );
+
+        let parsed = SpdxExpression::parse("(MIT)").unwrap();
+        assert_eq!(
+            ExpressionBuilder::from_expression(&parsed)
+                .unwrap()
+                .with_exception("exc")
+                .unwrap()
+                .build()
+                .to_string(),
+            "MIT WITH exc"
+        );
+    }
+}
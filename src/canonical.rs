@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Canonical keys of expressions.
+
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser::Operator,
+    simplify::simplify,
+};
+
+/// Version of the canonical key format, included in the keys. Must be increased whenever the
+/// key of any expression would change.
+const VERSION: u32 = 1;
+
+pub fn canonical_key(expression: &ExpressionVariant) -> String {
+    let lowercase = expression
+        .map_leaves(&mut |leaf| match leaf {
+            ExpressionVariant::Simple(license) => {
+                Some(ExpressionVariant::Simple(lowercase(license)))
+            }
+            ExpressionVariant::With(with) => Some(ExpressionVariant::With(Box::new(
+                WithExpression::new(lowercase(&with.license), with.exception.to_lowercase()),
+            ))),
+            _ => None,
+        })
+        .unwrap_or_else(|| expression.clone());
+
+    format!("v{VERSION}:{}", key(&simplify(&lowercase)))
+}
+
+fn lowercase(license: &SimpleExpression) -> SimpleExpression {
+    SimpleExpression {
+        identifier: license.identifier.to_lowercase(),
+        document_ref: license.document_ref.as_deref().map(str::to_lowercase),
+        license_ref: license.license_ref,
+        or_later: license.or_later,
+    }
+}
+
+/// Key of `node` with sorted, deduplicated operands.
+fn key(node: &ExpressionVariant) -> String {
+    let Some((operator, operands)) = node.flatten() else {
+        return node.unparenthesized().to_string();
+    };
+
+    let mut operands = operands
+        .into_iter()
+        .map(|operand| {
+            let key = key(operand);
+            if operand.flatten().is_some() {
+                format!("({key})")
+            } else {
+                key
+            }
+        })
+        .collect::<Vec<_>>();
+    operands.sort_unstable();
+    operands.dedup();
+
+    let separator = match operator {
+        Operator::And => " AND ",
+        Operator::Or => " OR ",
+    };
+    operands.join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_of(expression: &str) -> String {
+        canonical_key(&ExpressionVariant::parse(expression).unwrap())
+    }
+
+    #[test]
+    fn keys_are_stable() {
+        assert_eq!(key_of("MIT"), "v1:mit");
+        assert_eq!(
+            key_of("MIT OR (GPL-2.0-or-later WITH Classpath-exception-2.0 AND Apache-2.0)"),
+            "v1:(apache-2.0 AND gpl-2.0-or-later WITH classpath-exception-2.0) OR mit"
+        );
+        assert_eq!(
+            key_of("DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2 OR GPL-2.0+"),
+            "v1:DocumentRef-spdx-tool-1.2:LicenseRef-mit-style-2 OR gpl-2.0+"
+        );
+    }
+
+    #[test]
+    fn equivalent_expressions_have_equal_keys() {
+        for (left, right) in [
+            ("MIT AND ISC", "ISC AND MIT"),
+            ("mit AND (ISC)", "MIT AND isc AND MIT"),
+            ("(MIT OR ISC) AND 0BSD", "0BSD AND (ISC OR MIT OR ISC)"),
+            ("MIT OR MIT AND ISC", "MIT"),
+        ] {
+            assert_eq!(key_of(left), key_of(right), "{left} = {right}");
+        }
+        assert_ne!(key_of("MIT AND ISC"), key_of("MIT OR ISC"));
+    }
+}
@@ -0,0 +1,115 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Helpers for the license fields of Cargo packages.
+
+#[cfg(feature = "serde_json")]
+use crate::{document::DocumentLicenses, expression::SpdxExpression};
+
+/// Convert the legacy `/` separators of Cargo license fields to `OR`, e.g. `MIT/Apache-2.0` to
+/// `MIT OR Apache-2.0`. `/` is not valid in SPDX expressions, so every `/` is a separator.
+pub fn normalize(license: &str) -> String {
+    license
+        .split('/')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+#[cfg(feature = "serde_json")]
+impl DocumentLicenses<String> {
+    /// Parse the license fields of the packages in the output of `cargo metadata`, keyed with the
+    /// package ids. The fields are parsed with [`SpdxExpression::parse_cargo`], so legacy `/`
+    /// separators are accepted. Packages without a license field, e.g. those with only a
+    /// `license-file`, are skipped.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::DocumentLicenses;
+    /// #
+    /// let metadata = serde_json::json!({
+    ///     "packages": [
+    ///         { "id": "serde 1.0.0", "license": "MIT/Apache-2.0" },
+    ///         { "id": "custom 0.1.0", "license": null, "license_file": "LICENSE" }
+    ///     ]
+    /// });
+    ///
+    /// let licenses = DocumentLicenses::from_cargo_metadata(&metadata);
+    ///
+    /// assert_eq!(licenses.expressions.len(), 1);
+    /// assert_eq!(licenses.expressions[0].0, "serde 1.0.0");
+    /// assert_eq!(licenses.expressions[0].1.to_string(), "MIT OR Apache-2.0");
+    /// ```
+    pub fn from_cargo_metadata(metadata: &serde_json::Value) -> Self {
+        let mut licenses = Self {
+            expressions: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let packages = metadata["packages"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice);
+        for package in packages {
+            let (Some(id), Some(license)) = (package["id"].as_str(), package["license"].as_str())
+            else {
+                continue;
+            };
+
+            match SpdxExpression::parse_cargo(license) {
+                Ok(expression) => licenses.expressions.push((id.to_string(), expression)),
+                Err(err) => licenses.errors.push((id.to_string(), err)),
+            }
+        }
+
+        licenses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_legacy_separators() {
+        assert_eq!(normalize("MIT/Apache-2.0"), "MIT OR Apache-2.0");
+        assert_eq!(
+            normalize("MIT / Apache-2.0/ISC"),
+            "MIT OR Apache-2.0 OR ISC"
+        );
+        assert_eq!(normalize("MIT OR Apache-2.0"), "MIT OR Apache-2.0");
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn parse_cargo_metadata() {
+        let metadata = serde_json::json!({
+            "packages": [
+                { "id": "a 1.0.0", "license": "MIT" },
+                { "id": "b 1.0.0", "license": "MIT/Apache-2.0 AND ISC" },
+                { "id": "c 1.0.0", "license": "MIT OR" },
+                { "id": "d 1.0.0", "license": null }
+            ],
+            "workspace_members": ["a 1.0.0"]
+        });
+
+        let licenses = DocumentLicenses::from_cargo_metadata(&metadata);
+
+        assert_eq!(
+            licenses
+                .expressions
+                .iter()
+                .map(|(id, expression)| (id.as_str(), expression.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                ("a 1.0.0", "MIT".to_string()),
+                ("b 1.0.0", "MIT OR Apache-2.0 AND ISC".to_string())
+            ]
+        );
+        assert_eq!(licenses.errors.len(), 1);
+        assert_eq!(licenses.errors[0].0, "c 1.0.0");
+    }
+}
@@ -0,0 +1,320 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Comparison of expressions that ignores some of their details.
+
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression},
+};
+
+/// Details of the licenses ignored when comparing expressions.
+#[derive(Debug, Clone, Copy)]
+struct Comparison {
+    ignore_document_refs: bool,
+    ignore_case: bool,
+}
+
+impl Comparison {
+    fn str_eq(self, left: &str, right: &str) -> bool {
+        if self.ignore_case {
+            left.eq_ignore_ascii_case(right)
+        } else {
+            left == right
+        }
+    }
+
+    fn license_eq(self, left: &SimpleExpression, right: &SimpleExpression) -> bool {
+        self.str_eq(&left.identifier, &right.identifier)
+            && left.license_ref == right.license_ref
+            && left.or_later == right.or_later
+            && (self.ignore_document_refs
+                || match (&left.document_ref, &right.document_ref) {
+                    (Some(left), Some(right)) => self.str_eq(left, right),
+                    (left, right) => left == right,
+                })
+    }
+
+    fn eq(self, left: &ExpressionVariant, right: &ExpressionVariant) -> bool {
+        match (left, right) {
+            (ExpressionVariant::Simple(left), ExpressionVariant::Simple(right)) => {
+                self.license_eq(left, right)
+            }
+            (ExpressionVariant::With(left), ExpressionVariant::With(right)) => {
+                self.license_eq(&left.license, &right.license)
+                    && self.str_eq(&left.exception, &right.exception)
+            }
+            (ExpressionVariant::And(left_1, left_2), ExpressionVariant::And(right_1, right_2))
+            | (ExpressionVariant::Or(left_1, left_2), ExpressionVariant::Or(right_1, right_2)) => {
+                self.eq(left_1, right_1) && self.eq(left_2, right_2)
+            }
+            (ExpressionVariant::Parens(left), ExpressionVariant::Parens(right)) => {
+                self.eq(left, right)
+            }
+            _ => false,
+        }
+    }
+
+    fn hash_str<H: Hasher>(self, value: &str, state: &mut H) {
+        if self.ignore_case {
+            for byte in value.bytes() {
+                state.write_u8(byte.to_ascii_lowercase());
+            }
+            // Terminate the string like `str::hash` does.
+            state.write_u8(0xff);
+        } else {
+            value.hash(state);
+        }
+    }
+
+    fn hash_license<H: Hasher>(self, license: &SimpleExpression, state: &mut H) {
+        self.hash_str(&license.identifier, state);
+        license.license_ref.hash(state);
+        license.or_later.hash(state);
+        if !self.ignore_document_refs {
+            license.document_ref.is_some().hash(state);
+            if let Some(document_ref) = &license.document_ref {
+                self.hash_str(document_ref, state);
+            }
+        }
+    }
+
+    fn hash<H: Hasher>(self, node: &ExpressionVariant, state: &mut H) {
+        std::mem::discriminant(node).hash(state);
+        match node {
+            ExpressionVariant::Simple(license) => self.hash_license(license, state),
+            ExpressionVariant::With(with) => {
+                self.hash_license(&with.license, state);
+                self.hash_str(&with.exception, state);
+            }
+            ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right) => {
+                self.hash(left, state);
+                self.hash(right, state);
+            }
+            ExpressionVariant::Parens(inner) => self.hash(inner, state),
+        }
+    }
+}
+
+const IGNORE_DOCUMENT_REFS: Comparison = Comparison {
+    ignore_document_refs: true,
+    ignore_case: false,
+};
+
+const IGNORE_CASE: Comparison = Comparison {
+    ignore_document_refs: false,
+    ignore_case: true,
+};
+
+/// Wrapper for comparing and hashing expressions without their `DocumentRef` prefixes.
+///
+/// Used e.g. for aggregating the licenses of several documents where the same extracted license
+/// is referenced from different documents.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use spdx_expression::{IgnoreDocumentRefs, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let licenses = [
+///     "MIT AND DocumentRef-a:LicenseRef-custom",
+///     "MIT AND DocumentRef-b:LicenseRef-custom",
+///     "MIT AND LicenseRef-custom",
+/// ]
+/// .iter()
+/// .map(|expression| SpdxExpression::parse(expression).map(IgnoreDocumentRefs))
+/// .collect::<Result<HashSet<_>, _>>()?;
+///
+/// assert_eq!(licenses.len(), 1);
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct IgnoreDocumentRefs(pub SpdxExpression);
+
+impl PartialEq for IgnoreDocumentRefs {
+    fn eq(&self, other: &Self) -> bool {
+        IGNORE_DOCUMENT_REFS.eq(self.0.inner(), other.0.inner())
+    }
+}
+
+impl Eq for IgnoreDocumentRefs {}
+
+impl Hash for IgnoreDocumentRefs {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        IGNORE_DOCUMENT_REFS.hash(self.0.inner(), state);
+    }
+}
+
+impl From<SpdxExpression> for IgnoreDocumentRefs {
+    fn from(expression: SpdxExpression) -> Self {
+        Self(expression)
+    }
+}
+
+impl Display for IgnoreDocumentRefs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Wrapper for comparing and hashing expressions with case-insensitive license identifiers,
+/// exceptions and `DocumentRef`s.
+///
+/// The wrapped expression keeps its original case, so it's displayed as it was parsed. Used e.g.
+/// for merging data from scanners that differ in the case of the identifiers.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use spdx_expression::{IgnoreCase, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut licenses = HashSet::new();
+/// for expression in ["Apache-2.0 OR MIT", "apache-2.0 OR mit", "APACHE-2.0 OR MIT"] {
+///     licenses.insert(IgnoreCase(SpdxExpression::parse(expression)?));
+/// }
+///
+/// assert_eq!(licenses.len(), 1);
+/// assert_eq!(
+///     licenses.iter().next().unwrap().to_string(),
+///     "Apache-2.0 OR MIT"
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct IgnoreCase(pub SpdxExpression);
+
+impl PartialEq for IgnoreCase {
+    fn eq(&self, other: &Self) -> bool {
+        IGNORE_CASE.eq(self.0.inner(), other.0.inner())
+    }
+}
+
+impl Eq for IgnoreCase {}
+
+impl Hash for IgnoreCase {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        IGNORE_CASE.hash(self.0.inner(), state);
+    }
+}
+
+impl From<SpdxExpression> for IgnoreCase {
+    fn from(expression: SpdxExpression) -> Self {
+        Self(expression)
+    }
+}
+
+impl Display for IgnoreCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+pub fn eq_ignoring_document_refs(left: &SpdxExpression, right: &SpdxExpression) -> bool {
+    IGNORE_DOCUMENT_REFS.eq(left.inner(), right.inner())
+}
+
+pub fn eq_ignoring_case(left: &SpdxExpression, right: &SpdxExpression) -> bool {
+    IGNORE_CASE.eq(left.inner(), right.inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    fn wrap(expression: &str) -> IgnoreDocumentRefs {
+        IgnoreDocumentRefs(SpdxExpression::parse(expression).unwrap())
+    }
+
+    fn hash_of<T: Hash>(expression: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        expression.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn document_refs_are_ignored() {
+        for (left, right) in [
+            ("DocumentRef-a:LicenseRef-x", "LicenseRef-x"),
+            (
+                "MIT OR DocumentRef-a:LicenseRef-x WITH exc",
+                "MIT OR DocumentRef-b:LicenseRef-x WITH exc",
+            ),
+            (
+                "(MIT AND LicenseRef-x)",
+                "(MIT AND DocumentRef-b:LicenseRef-x)",
+            ),
+        ] {
+            assert_eq!(wrap(left), wrap(right), "{left} = {right}");
+            assert_eq!(
+                hash_of(&wrap(left)),
+                hash_of(&wrap(right)),
+                "{left} = {right}"
+            );
+        }
+    }
+
+    #[test]
+    fn other_differences_are_not_ignored() {
+        for (left, right) in [
+            ("DocumentRef-a:LicenseRef-x", "DocumentRef-a:LicenseRef-y"),
+            ("MIT AND ISC", "MIT OR ISC"),
+            ("MIT AND ISC", "(MIT AND ISC)"),
+            ("GPL-2.0", "GPL-2.0+"),
+            ("MIT WITH a", "MIT WITH b"),
+        ] {
+            assert_ne!(wrap(left), wrap(right), "{left} != {right}");
+        }
+    }
+
+    fn ignore_case(expression: &str) -> IgnoreCase {
+        IgnoreCase(SpdxExpression::parse(expression).unwrap())
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        for (left, right) in [
+            ("MIT", "mit"),
+            (
+                "GPL-2.0+ WITH Classpath-exception-2.0",
+                "gpl-2.0+ WITH classpath-EXCEPTION-2.0",
+            ),
+            ("DocumentRef-A:LicenseRef-X", "DocumentRef-a:LicenseRef-x"),
+            ("(MIT OR ISC) AND 0bsd", "(mit OR isc) AND 0BSD"),
+        ] {
+            assert_eq!(ignore_case(left), ignore_case(right), "{left} = {right}");
+            assert_eq!(
+                hash_of(&ignore_case(left)),
+                hash_of(&ignore_case(right)),
+                "{left} = {right}"
+            );
+        }
+
+        for (left, right) in [
+            ("MIT", "MIT-0"),
+            ("DocumentRef-a:LicenseRef-x", "LicenseRef-x"),
+            ("mit AND isc", "ISC AND MIT"),
+        ] {
+            assert_ne!(ignore_case(left), ignore_case(right), "{left} != {right}");
+        }
+    }
+
+    #[test]
+    fn original_case_is_displayed() {
+        assert_eq!(
+            ignore_case("Apache-2.0 OR mit").to_string(),
+            "Apache-2.0 OR mit"
+        );
+    }
+}
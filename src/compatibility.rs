@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Compatibility checks of licenses combined in one work, with traces of failed checks.
+
+use std::fmt::Display;
+
+use crate::{expression_variant::SimpleExpression, options_report, SpdxExpression};
+
+/// Two licenses from different expressions that can't be combined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict<K> {
+    /// The license from the earlier expression.
+    pub license: SimpleExpression,
+
+    /// Key of the earlier expression.
+    pub from: K,
+
+    /// The license from the later expression.
+    pub other: SimpleExpression,
+
+    /// Key of the later expression.
+    pub other_from: K,
+}
+
+impl<K: Display> Display for Conflict<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (from {}) is incompatible with {} (from {})",
+            self.license, self.from, self.other, self.other_from
+        )
+    }
+}
+
+/// One combination of choices of the expressions that was explored, with its conflicts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt<K> {
+    /// The chosen licenses of each expression, as the `AND` of the licenses.
+    pub choices: Vec<(K, SpdxExpression)>,
+
+    /// The conflicting license pairs of the choices.
+    pub conflicts: Vec<Conflict<K>>,
+}
+
+/// Trace of a failed compatibility check, see [`SpdxExpression::check_compatibility`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatibilityTrace<K> {
+    /// Every combination of the choices of the `OR`s of the expressions, in the order they were
+    /// explored.
+    pub attempts: Vec<Attempt<K>>,
+}
+
+impl<K: Display> Display for CompatibilityTrace<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "No compatible choice of licenses:")?;
+        for attempt in &self.attempts {
+            let choices = attempt
+                .choices
+                .iter()
+                .map(|(key, choice)| format!("{choice} (from {key})"))
+                .collect::<Vec<_>>();
+            writeln!(f, "- choosing {}:", choices.join(", "))?;
+            for conflict in &attempt.conflicts {
+                writeln!(f, "  - {conflict}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn check_compatibility<'a, K, I, F>(
+    expressions: I,
+    mut is_compatible: F,
+) -> Result<Vec<(K, SpdxExpression)>, CompatibilityTrace<K>>
+where
+    K: Clone,
+    I: IntoIterator<Item = (K, &'a SpdxExpression)>,
+    F: FnMut(&SimpleExpression, &SimpleExpression) -> bool,
+{
+    let expressions = expressions
+        .into_iter()
+        .map(|(key, expression)| (key, options_report::choices(expression.inner())))
+        .collect::<Vec<_>>();
+
+    let mut attempts = Vec::new();
+    let mut indices = vec![0; expressions.len()];
+    loop {
+        let chosen = expressions
+            .iter()
+            .zip(&indices)
+            .map(|((key, choices), index)| (key, &choices[*index]))
+            .collect::<Vec<_>>();
+
+        let mut conflicts = Vec::new();
+        for (position, (from, terms)) in chosen.iter().enumerate() {
+            for (other_from, other_terms) in &chosen[position + 1..] {
+                for (license, _) in *terms {
+                    for (other, _) in *other_terms {
+                        if !is_compatible(license, other) {
+                            conflicts.push(Conflict {
+                                license: (*license).clone(),
+                                from: (*from).clone(),
+                                other: (*other).clone(),
+                                other_from: (*other_from).clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let choices = chosen
+            .iter()
+            .map(|(key, terms)| ((*key).clone(), options_report::to_expression(terms)))
+            .collect();
+        if conflicts.is_empty() {
+            return Ok(choices);
+        }
+        attempts.push(Attempt { choices, conflicts });
+
+        // Advance to the next combination of choices, or stop after the last one.
+        let Some(position) = indices
+            .iter()
+            .zip(&expressions)
+            .rposition(|(index, (_, choices))| index + 1 < choices.len())
+        else {
+            return Err(CompatibilityTrace { attempts });
+        };
+        indices[position] += 1;
+        for index in &mut indices[position + 1..] {
+            *index = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_compatible(license: &SimpleExpression, other: &SimpleExpression) -> bool {
+        let pair = (license.identifier.as_str(), other.identifier.as_str());
+        !matches!(
+            pair,
+            ("GPL-2.0-only", "Apache-2.0") | ("Apache-2.0", "GPL-2.0-only")
+        )
+    }
+
+    fn check(
+        expressions: &[&str],
+    ) -> Result<Vec<(usize, SpdxExpression)>, CompatibilityTrace<usize>> {
+        let expressions = expressions
+            .iter()
+            .map(|expression| SpdxExpression::parse(expression).unwrap())
+            .collect::<Vec<_>>();
+        check_compatibility(expressions.iter().enumerate(), is_compatible)
+    }
+
+    #[test]
+    fn find_compatible_choices() {
+        let choices = check(&["GPL-2.0-only OR MIT", "Apache-2.0", "MIT"]).unwrap();
+        assert_eq!(
+            choices
+                .iter()
+                .map(|(key, choice)| (*key, choice.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (0, "MIT".to_string()),
+                (1, "Apache-2.0".to_string()),
+                (2, "MIT".to_string())
+            ]
+        );
+        assert!(check(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn trace_explored_choices() {
+        let trace =
+            check(&["GPL-2.0-only", "MIT AND Apache-2.0 OR Apache-2.0 WITH exc"]).unwrap_err();
+
+        assert_eq!(trace.attempts.len(), 2);
+        assert_eq!(
+            trace.attempts[1].choices[1].1.to_string(),
+            "Apache-2.0 WITH exc"
+        );
+        assert_eq!(
+            trace.to_string(),
+            "No compatible choice of licenses:\n\
+             - choosing GPL-2.0-only (from 0), MIT AND Apache-2.0 (from 1):\n  \
+             - GPL-2.0-only (from 0) is incompatible with Apache-2.0 (from 1)\n\
+             - choosing GPL-2.0-only (from 0), Apache-2.0 WITH exc (from 1):\n  \
+             - GPL-2.0-only (from 0) is incompatible with Apache-2.0 (from 1)\n"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Completion suggestions for partially written expressions.
+
+use std::ops::Range;
+
+/// Kind of a [`Completion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    License,
+    Exception,
+
+    /// `AND`, `OR` or `WITH`.
+    Operator,
+
+    CloseParenthesis,
+}
+
+/// Suggested continuation of a partially written expression, see [`Completer::complete`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Completion {
+    pub kind: CompletionKind,
+
+    /// The suggested text.
+    pub text: String,
+
+    /// Byte range of the partially written word the text replaces. Empty at the end of the
+    /// input if the input doesn't end in a partial word.
+    pub range: Range<usize>,
+}
+
+/// Source of completion suggestions for expressions, e.g. for autocompletion in editors and web
+/// forms.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Completer, CompletionKind};
+/// #
+/// let completer = Completer {
+///     licenses: vec!["Apache-2.0".to_string(), "MIT".to_string(), "MIT-0".to_string()],
+///     exceptions: vec!["LLVM-exception".to_string()],
+/// };
+///
+/// let suggestions = completer.complete("Apache-2.0 OR mi");
+/// let texts = suggestions.iter().map(|s| s.text.as_str()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["MIT", "MIT-0"]);
+/// assert_eq!(suggestions[0].range, 14..16);
+///
+/// let suggestions = completer.complete("(Apache-2.0 ");
+/// let texts = suggestions.iter().map(|s| s.text.as_str()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["WITH", "AND", "OR", ")"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completer {
+    /// License identifiers suggested where a license is expected, in the order they're
+    /// suggested.
+    pub licenses: Vec<String>,
+
+    /// Exception identifiers suggested after `WITH`, in the order they're suggested.
+    pub exceptions: Vec<String>,
+}
+
+/// What the parser expects next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    /// A license or an opening parenthesis.
+    Operand,
+
+    /// An exception after `WITH`.
+    Exception,
+
+    /// `WITH` or an operator after a license.
+    AfterLicense,
+
+    /// An operator or a closing parenthesis after an exception or a parenthesized expression.
+    AfterTerm,
+}
+
+const fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':' | '+')
+}
+
+/// Get the state of the parser after `input`, or `None` if `input` is not the beginning of a
+/// valid expression. Returns what is expected next and the depth of the parentheses.
+fn state_after(input: &str) -> Option<(Expected, usize)> {
+    let mut expected = Expected::Operand;
+    let mut depth = 0_usize;
+    let mut rest = input.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let token = if c == '(' || c == ')' {
+            &rest[..1]
+        } else {
+            let end = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            &rest[..end]
+        };
+        rest = rest[token.len()..].trim_start();
+
+        expected = match (expected, token) {
+            (Expected::Operand, "(") => {
+                depth += 1;
+                Expected::Operand
+            }
+            (Expected::AfterLicense | Expected::AfterTerm, ")") if depth > 0 => {
+                depth -= 1;
+                Expected::AfterTerm
+            }
+            (_, "(" | ")") => return None,
+            (Expected::AfterLicense | Expected::AfterTerm, operator)
+                if operator.eq_ignore_ascii_case("AND") || operator.eq_ignore_ascii_case("OR") =>
+            {
+                Expected::Operand
+            }
+            (Expected::AfterLicense, with) if with.eq_ignore_ascii_case("WITH") => {
+                Expected::Exception
+            }
+            (Expected::Operand, _) => Expected::AfterLicense,
+            (Expected::Exception, _) => Expected::AfterTerm,
+            (Expected::AfterLicense | Expected::AfterTerm, _) => return None,
+        };
+    }
+
+    Some((expected, depth))
+}
+
+impl Completer {
+    /// Suggest continuations of `prefix`, the text before the cursor. If `prefix` ends in a
+    /// partial word, only the suggestions starting with the word, ignoring case, are returned.
+    /// Returns no suggestions if `prefix` is not the beginning of a valid expression.
+    pub fn complete(&self, prefix: &str) -> Vec<Completion> {
+        let word_start = prefix
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !is_word_char(*c))
+            .map_or(0, |(index, c)| index + c.len_utf8());
+        let word = &prefix[word_start..];
+
+        let Some((expected, depth)) = state_after(&prefix[..word_start]) else {
+            return Vec::new();
+        };
+
+        let mut candidates: Vec<(CompletionKind, &str)> = Vec::new();
+        match expected {
+            Expected::Operand => candidates.extend(
+                self.licenses
+                    .iter()
+                    .map(|license| (CompletionKind::License, license.as_str())),
+            ),
+            Expected::Exception => candidates.extend(
+                self.exceptions
+                    .iter()
+                    .map(|exception| (CompletionKind::Exception, exception.as_str())),
+            ),
+            Expected::AfterLicense | Expected::AfterTerm => {
+                if expected == Expected::AfterLicense {
+                    candidates.push((CompletionKind::Operator, "WITH"));
+                }
+                candidates.push((CompletionKind::Operator, "AND"));
+                candidates.push((CompletionKind::Operator, "OR"));
+                if depth > 0 {
+                    candidates.push((CompletionKind::CloseParenthesis, ")"));
+                }
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter(|(_, text)| {
+                text.get(..word.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(word))
+            })
+            .map(|(kind, text)| Completion {
+                kind,
+                text: text.to_string(),
+                range: word_start..prefix.len(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completer() -> Completer {
+        Completer {
+            licenses: vec![
+                "GPL-2.0-only".to_string(),
+                "GPL-2.0-or-later".to_string(),
+                "MIT".to_string(),
+            ],
+            exceptions: vec![
+                "Classpath-exception-2.0".to_string(),
+                "GCC-exception-3.1".to_string(),
+            ],
+        }
+    }
+
+    fn texts(prefix: &str) -> Vec<String> {
+        completer()
+            .complete(prefix)
+            .into_iter()
+            .map(|completion| completion.text)
+            .collect()
+    }
+
+    #[test]
+    fn complete_licenses() {
+        assert_eq!(texts(""), ["GPL-2.0-only", "GPL-2.0-or-later", "MIT"]);
+        assert_eq!(texts("gpl-2.0-o"), ["GPL-2.0-only", "GPL-2.0-or-later"]);
+        assert_eq!(
+            texts("MIT AND (("),
+            ["GPL-2.0-only", "GPL-2.0-or-later", "MIT"]
+        );
+        assert_eq!(texts("MIT OR m"), ["MIT"]);
+    }
+
+    #[test]
+    fn complete_operators_and_parentheses() {
+        assert_eq!(texts("MIT "), ["WITH", "AND", "OR"]);
+        assert_eq!(texts("(MIT OR ISC "), ["WITH", "AND", "OR", ")"]);
+        assert_eq!(texts("(MIT OR ISC) "), ["AND", "OR"]);
+        assert_eq!(texts("MIT WITH exc "), ["AND", "OR"]);
+        assert_eq!(texts("MIT a"), ["AND"]);
+        assert_eq!(texts("((MIT)"), ["AND", "OR", ")"]);
+    }
+
+    #[test]
+    fn complete_exceptions() {
+        assert_eq!(
+            texts("GPL-2.0-only WITH "),
+            ["Classpath-exception-2.0", "GCC-exception-3.1"]
+        );
+        assert_eq!(texts("GPL-2.0-only with c"), ["Classpath-exception-2.0"]);
+    }
+
+    #[test]
+    fn replaced_range_covers_partial_word() {
+        let completions = completer().complete("MIT OR gp");
+        assert_eq!(completions.len(), 2);
+        assert!(completions
+            .iter()
+            .all(|completion| completion.range == (7..9)));
+        assert_eq!(completer().complete("MIT ")[0].range, 4..4);
+        assert_eq!(
+            completer().complete("(MIT ")[3],
+            Completion {
+                kind: CompletionKind::CloseParenthesis,
+                text: ")".to_string(),
+                range: 5..5,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_prefixes_have_no_completions() {
+        for prefix in [
+            "MIT ISC ",
+            "MIT WITH a WITH ",
+            ")",
+            "MIT) ",
+            "MIT WITH (",
+            "MIT $",
+            "é",
+            "MIT OR é",
+            "MIT OR gplé",
+        ] {
+            assert!(texts(prefix).is_empty(), "{}", prefix);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Co-occurrence of licenses across many expressions.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{expression::SpdxExpression, expression_variant::ExpressionVariant, parser::Operator};
+
+/// Counts of pairs of licenses that occur together in a collection of expressions, e.g. for
+/// statistics of the licenses used in an organization.
+///
+/// Two licenses are required together if the operator joining them is `AND`, and alternatives if
+/// the operator is `OR`. The operator joining two licenses is the innermost operator with both
+/// licenses in its operands, so in `MIT AND (ISC OR 0BSD)` both `ISC` and `0BSD` are required
+/// together with `MIT`, but are alternatives to each other.
+///
+/// Licenses are compared without their exceptions. Pairs are stored in alphabetical order and
+/// counted once per expression.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{CoOccurrence, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let expressions = [
+///     SpdxExpression::parse("MIT AND (ISC OR Apache-2.0)")?,
+///     SpdxExpression::parse("Apache-2.0 AND MIT")?,
+/// ];
+///
+/// let cooccurrence = CoOccurrence::from_expressions(&expressions);
+/// assert_eq!(cooccurrence.conjunctive[&("Apache-2.0".into(), "MIT".into())], 2);
+/// assert_eq!(cooccurrence.alternative[&("Apache-2.0".into(), "ISC".into())], 1);
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoOccurrence {
+    /// Number of expressions in which the licenses are required together.
+    pub conjunctive: BTreeMap<(String, String), usize>,
+
+    /// Number of expressions in which the licenses are alternatives.
+    pub alternative: BTreeMap<(String, String), usize>,
+
+    /// Number of expressions added.
+    pub expressions: usize,
+}
+
+impl CoOccurrence {
+    /// Count the pairs of licenses in `expressions`.
+    pub fn from_expressions<'a, I>(expressions: I) -> Self
+    where
+        I: IntoIterator<Item = &'a SpdxExpression>,
+    {
+        let mut cooccurrence = Self::default();
+        for expression in expressions {
+            cooccurrence.add(expression);
+        }
+        cooccurrence
+    }
+
+    /// Count the pairs of licenses in `expression`.
+    pub fn add(&mut self, expression: &SpdxExpression) {
+        let mut pairs = Pairs::default();
+        collect(expression.inner(), &mut pairs);
+
+        for pair in pairs.conjunctive {
+            *self.conjunctive.entry(pair).or_default() += 1;
+        }
+        for pair in pairs.alternative {
+            *self.alternative.entry(pair).or_default() += 1;
+        }
+        self.expressions += 1;
+    }
+
+    /// Get the pairs of licenses sorted by the number of expressions they are required together
+    /// in, the most common first.
+    pub fn most_common_conjunctive(&self) -> Vec<(&(String, String), usize)> {
+        most_common(&self.conjunctive)
+    }
+
+    /// Get the pairs of licenses sorted by the number of expressions they are alternatives in,
+    /// the most common first.
+    pub fn most_common_alternative(&self) -> Vec<(&(String, String), usize)> {
+        most_common(&self.alternative)
+    }
+}
+
+/// Pairs of licenses of a single expression.
+#[derive(Default)]
+struct Pairs {
+    conjunctive: BTreeSet<(String, String)>,
+    alternative: BTreeSet<(String, String)>,
+}
+
+/// Collect the pairs of licenses joined by the operators of `expression`, and return the
+/// licenses of `expression`.
+fn collect(expression: &ExpressionVariant, pairs: &mut Pairs) -> BTreeSet<String> {
+    let Some((operator, operands)) = expression.flatten() else {
+        return expression
+            .unparenthesized()
+            .license()
+            .map(ToString::to_string)
+            .into_iter()
+            .collect();
+    };
+
+    let operand_licenses = operands
+        .into_iter()
+        .map(|operand| collect(operand, pairs))
+        .collect::<Vec<_>>();
+
+    let pairs = match operator {
+        Operator::And => &mut pairs.conjunctive,
+        Operator::Or => &mut pairs.alternative,
+    };
+    for (index, first) in operand_licenses.iter().enumerate() {
+        for second in &operand_licenses[index + 1..] {
+            for a in first {
+                for b in second {
+                    match a.cmp(b) {
+                        std::cmp::Ordering::Less => pairs.insert((a.clone(), b.clone())),
+                        std::cmp::Ordering::Greater => pairs.insert((b.clone(), a.clone())),
+                        std::cmp::Ordering::Equal => false,
+                    };
+                }
+            }
+        }
+    }
+
+    operand_licenses.into_iter().flatten().collect()
+}
+
+fn most_common(counts: &BTreeMap<(String, String), usize>) -> Vec<(&(String, String), usize)> {
+    let mut counts = counts
+        .iter()
+        .map(|(pair, &count)| (pair, count))
+        .collect::<Vec<_>>();
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(a: &str, b: &str) -> (String, String) {
+        (a.to_string(), b.to_string())
+    }
+
+    #[test]
+    fn pairs_are_counted_by_innermost_operator() {
+        let expressions = [
+            SpdxExpression::parse("MIT AND (ISC OR 0BSD WITH exc)").unwrap(),
+            SpdxExpression::parse("(ISC OR MIT) AND (ISC OR MIT)").unwrap(),
+            SpdxExpression::parse("MIT").unwrap(),
+        ];
+
+        let cooccurrence = CoOccurrence::from_expressions(&expressions);
+
+        assert_eq!(cooccurrence.expressions, 3);
+        assert_eq!(
+            cooccurrence.conjunctive,
+            BTreeMap::from([(pair("0BSD", "MIT"), 1), (pair("ISC", "MIT"), 2)])
+        );
+        assert_eq!(
+            cooccurrence.alternative,
+            BTreeMap::from([(pair("0BSD", "ISC"), 1), (pair("ISC", "MIT"), 1)])
+        );
+        assert_eq!(
+            cooccurrence.most_common_conjunctive(),
+            [(&pair("ISC", "MIT"), 2), (&pair("0BSD", "MIT"), 1)]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Copyleft categories of licenses.
+
+use serde::Serialize;
+
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    iter::Leaves,
+};
+
+/// Copyleft category of a license, ordered from the least to the most restrictive, see
+/// [`SpdxExpression::strongest_copyleft`].
+///
+/// [`SpdxExpression::strongest_copyleft`]: crate::SpdxExpression::strongest_copyleft
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Copyleft {
+    /// No copyleft, e.g. `MIT` or `Apache-2.0`.
+    Permissive,
+
+    /// Copyleft limited to the files or the library, e.g. `MPL-2.0` or `LGPL-2.1-only`.
+    Weak,
+
+    /// Copyleft covering the whole work, e.g. `GPL-2.0-only`.
+    Strong,
+
+    /// Strong copyleft that also applies to use over a network, e.g. `AGPL-3.0-only`.
+    Network,
+}
+
+impl Serialize for Copyleft {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let (index, name) = match self {
+            Self::Permissive => (0, "Permissive"),
+            Self::Weak => (1, "Weak"),
+            Self::Strong => (2, "Strong"),
+            Self::Network => (3, "Network"),
+        };
+        serializer.serialize_unit_variant("Copyleft", index, name)
+    }
+}
+
+/// Copyleft categories of the licenses with bundled obligations.
+#[cfg(feature = "data")]
+const CATEGORIES: &[(&str, Copyleft)] = &[
+    ("0BSD", Copyleft::Permissive),
+    ("AGPL-3.0", Copyleft::Network),
+    ("AGPL-3.0-only", Copyleft::Network),
+    ("AGPL-3.0-or-later", Copyleft::Network),
+    ("Apache-2.0", Copyleft::Permissive),
+    ("BSD-2-Clause", Copyleft::Permissive),
+    ("BSD-3-Clause", Copyleft::Permissive),
+    ("BSL-1.0", Copyleft::Permissive),
+    ("CC0-1.0", Copyleft::Permissive),
+    ("CDDL-1.0", Copyleft::Weak),
+    ("EPL-1.0", Copyleft::Weak),
+    ("EPL-2.0", Copyleft::Weak),
+    ("GPL-2.0", Copyleft::Strong),
+    ("GPL-2.0-only", Copyleft::Strong),
+    ("GPL-2.0-or-later", Copyleft::Strong),
+    ("GPL-3.0", Copyleft::Strong),
+    ("GPL-3.0-only", Copyleft::Strong),
+    ("GPL-3.0-or-later", Copyleft::Strong),
+    ("ISC", Copyleft::Permissive),
+    ("LGPL-2.0", Copyleft::Weak),
+    ("LGPL-2.0-only", Copyleft::Weak),
+    ("LGPL-2.0-or-later", Copyleft::Weak),
+    ("LGPL-2.1", Copyleft::Weak),
+    ("LGPL-2.1-only", Copyleft::Weak),
+    ("LGPL-2.1-or-later", Copyleft::Weak),
+    ("LGPL-3.0", Copyleft::Weak),
+    ("LGPL-3.0-only", Copyleft::Weak),
+    ("LGPL-3.0-or-later", Copyleft::Weak),
+    ("MIT", Copyleft::Permissive),
+    ("MIT-0", Copyleft::Permissive),
+    ("MPL-2.0", Copyleft::Weak),
+    ("Unlicense", Copyleft::Permissive),
+    ("Zlib", Copyleft::Permissive),
+];
+
+#[cfg(feature = "data")]
+impl Copyleft {
+    /// Get the copyleft category of common licenses, or `None` if the license is not known.
+    /// Identifiers are compared case-insensitively.
+    ///
+    /// Requires the `data` feature.
+    pub fn of_license(identifier: &str) -> Option<Self> {
+        CATEGORIES
+            .iter()
+            .find(|(license, _)| license.eq_ignore_ascii_case(identifier))
+            .map(|(_, category)| *category)
+    }
+}
+
+pub fn strongest_copyleft<F>(
+    expression: &ExpressionVariant,
+    mut category: F,
+) -> Option<(&SimpleExpression, Copyleft)>
+where
+    F: FnMut(&SimpleExpression) -> Option<Copyleft>,
+{
+    let mut strongest: Option<(&SimpleExpression, Copyleft)> = None;
+    let mut seen = Vec::new();
+
+    for license in Leaves::new(expression).map(|(license, _)| license) {
+        if seen.contains(&license) {
+            continue;
+        }
+        seen.push(license);
+
+        let Some(license_category) = category(license) else {
+            continue;
+        };
+        if strongest.is_some_and(|(_, strongest)| strongest >= license_category) {
+            continue;
+        }
+
+        // The license is unavoidable if the expression can't be satisfied without it.
+        if !expression.is_satisfied_by(&mut |other| other != license) {
+            strongest = Some((license, license_category));
+        }
+    }
+
+    strongest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strongest(expression: &str) -> Option<(String, Copyleft)> {
+        let expression = ExpressionVariant::parse(expression).unwrap();
+        strongest_copyleft(&expression, |license| match license.identifier.as_str() {
+            "MIT" | "Apache-2.0" => Some(Copyleft::Permissive),
+            "MPL-2.0" => Some(Copyleft::Weak),
+            "GPL-2.0-only" => Some(Copyleft::Strong),
+            "AGPL-3.0-only" => Some(Copyleft::Network),
+            _ => None,
+        })
+        .map(|(license, category)| (license.to_string(), category))
+    }
+
+    #[test]
+    fn find_unavoidable_strongest_copyleft() {
+        assert_eq!(
+            strongest("MIT AND (GPL-2.0-only OR AGPL-3.0-only) AND MPL-2.0"),
+            Some(("MPL-2.0".to_string(), Copyleft::Weak))
+        );
+        assert_eq!(
+            strongest("MIT AND GPL-2.0-only WITH exc AND (MPL-2.0 OR Apache-2.0)"),
+            Some(("GPL-2.0-only".to_string(), Copyleft::Strong))
+        );
+        assert_eq!(
+            strongest("(MIT OR AGPL-3.0-only) AND (AGPL-3.0-only OR Apache-2.0)"),
+            None
+        );
+        assert_eq!(strongest("LicenseRef-a AND MIT OR ISC"), None);
+    }
+
+    #[cfg(feature = "data")]
+    #[test]
+    fn categories_of_known_licenses() {
+        assert_eq!(Copyleft::of_license("mit"), Some(Copyleft::Permissive));
+        assert_eq!(
+            Copyleft::of_license("LGPL-2.1-or-later"),
+            Some(Copyleft::Weak)
+        );
+        assert_eq!(Copyleft::of_license("MPL-2.0"), Some(Copyleft::Weak));
+        assert_eq!(Copyleft::of_license("GPL-3.0"), Some(Copyleft::Strong));
+        assert_eq!(
+            Copyleft::of_license("AGPL-3.0-only"),
+            Some(Copyleft::Network)
+        );
+        assert_eq!(Copyleft::of_license("LicenseRef-a"), None);
+    }
+}
@@ -0,0 +1,202 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Comparison of two collections of expressions.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::expression::SpdxExpression;
+
+/// Differences between two collections of expressions, e.g. the license findings of two versions
+/// of a scanner or two releases of a product.
+///
+/// Expressions are compared as text and by their [canonical keys](SpdxExpression::canonical_key).
+/// An expression that only one of the collections has as text is either only in that collection
+/// or, if the other collection has an expression with the same canonical key, equivalent to that
+/// expression. Every expression is reported once, in the order of the collections.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{CorpusComparison, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let old = ["MIT", "MIT AND Apache-2.0", "GPL-2.0+"]
+///     .iter()
+///     .map(|expression| SpdxExpression::parse(expression))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// let new = ["MIT", "Apache-2.0 AND MIT", "GPL-2.0-or-later"]
+///     .iter()
+///     .map(|expression| SpdxExpression::parse(expression))
+///     .collect::<Result<Vec<_>, _>>()?;
+///
+/// let comparison = CorpusComparison::from_expressions(&old, &new);
+///
+/// assert_eq!(comparison.only_in_left[0].to_string(), "GPL-2.0+");
+/// assert_eq!(comparison.only_in_right[0].to_string(), "GPL-2.0-or-later");
+/// assert_eq!(comparison.equivalent[0].0.to_string(), "MIT AND Apache-2.0");
+/// assert_eq!(comparison.equivalent[0].1.to_string(), "Apache-2.0 AND MIT");
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusComparison {
+    /// Expressions of the left collection without an equivalent expression in the right
+    /// collection.
+    pub only_in_left: Vec<SpdxExpression>,
+
+    /// Expressions of the right collection without an equivalent expression in the left
+    /// collection.
+    pub only_in_right: Vec<SpdxExpression>,
+
+    /// Pairs of equivalent expressions from the left and right collections, where at least one
+    /// of the expressions is not in the other collection as text.
+    pub equivalent: Vec<(SpdxExpression, SpdxExpression)>,
+}
+
+impl CorpusComparison {
+    /// Compare the expressions of `left` and `right`.
+    pub fn from_expressions<'a, L, R>(left: L, right: R) -> Self
+    where
+        L: IntoIterator<Item = &'a SpdxExpression>,
+        R: IntoIterator<Item = &'a SpdxExpression>,
+    {
+        let left = Corpus::new(left);
+        let right = Corpus::new(right);
+
+        let mut comparison = Self::default();
+        let mut reported = HashSet::new();
+
+        for (expression, key) in &left.expressions {
+            if right.texts.contains(&expression.to_string()) {
+                continue;
+            }
+            match right.by_key.get(key) {
+                Some(other) => {
+                    reported.insert((expression.to_string(), other.to_string()));
+                    comparison
+                        .equivalent
+                        .push(((*expression).clone(), (*other).clone()));
+                }
+                None => comparison.only_in_left.push((*expression).clone()),
+            }
+        }
+
+        for (expression, key) in &right.expressions {
+            if left.texts.contains(&expression.to_string()) {
+                continue;
+            }
+            match left.by_key.get(key) {
+                Some(other) => {
+                    if reported.insert((other.to_string(), expression.to_string())) {
+                        comparison
+                            .equivalent
+                            .push(((*other).clone(), (*expression).clone()));
+                    }
+                }
+                None => comparison.only_in_right.push((*expression).clone()),
+            }
+        }
+
+        comparison
+    }
+
+    /// Check if the collections have no differences, i.e. they have the same expressions as
+    /// text.
+    pub const fn is_empty(&self) -> bool {
+        self.only_in_left.is_empty() && self.only_in_right.is_empty() && self.equivalent.is_empty()
+    }
+}
+
+/// Distinct expressions of a collection with their canonical keys.
+struct Corpus<'a> {
+    expressions: Vec<(&'a SpdxExpression, String)>,
+    texts: HashSet<String>,
+    by_key: HashMap<String, &'a SpdxExpression>,
+}
+
+impl<'a> Corpus<'a> {
+    fn new<I>(expressions: I) -> Self
+    where
+        I: IntoIterator<Item = &'a SpdxExpression>,
+    {
+        let mut corpus = Corpus {
+            expressions: Vec::new(),
+            texts: HashSet::new(),
+            by_key: HashMap::new(),
+        };
+
+        for expression in expressions {
+            if corpus.texts.insert(expression.to_string()) {
+                let key = expression.canonical_key();
+                corpus.by_key.entry(key.clone()).or_insert(expression);
+                corpus.expressions.push((expression, key));
+            }
+        }
+
+        corpus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(left: &[&str], right: &[&str]) -> CorpusComparison {
+        let parse = |expressions: &[&str]| {
+            expressions
+                .iter()
+                .map(|expression| SpdxExpression::parse(expression).unwrap())
+                .collect::<Vec<_>>()
+        };
+        CorpusComparison::from_expressions(&parse(left), &parse(right))
+    }
+
+    fn strings(expressions: &[SpdxExpression]) -> Vec<String> {
+        expressions.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn bucket_differences() {
+        let comparison = compare(
+            &["MIT", "ISC OR MIT", "0BSD", "0BSD", "Zlib"],
+            &["mit OR isc", "Zlib", "Apache-2.0", "MIT"],
+        );
+
+        assert_eq!(strings(&comparison.only_in_left), ["0BSD"]);
+        assert_eq!(strings(&comparison.only_in_right), ["Apache-2.0"]);
+        assert_eq!(
+            comparison
+                .equivalent
+                .iter()
+                .map(|(left, right)| (left.to_string(), right.to_string()))
+                .collect::<Vec<_>>(),
+            [("ISC OR MIT".to_string(), "mit OR isc".to_string())]
+        );
+        assert!(!comparison.is_empty());
+    }
+
+    #[test]
+    fn report_equivalent_expressions_once() {
+        let comparison = compare(
+            &["MIT AND ISC", "ISC AND MIT"],
+            &["MIT AND ISC", "(MIT AND ISC)"],
+        );
+
+        assert!(comparison.only_in_left.is_empty());
+        assert!(comparison.only_in_right.is_empty());
+        assert_eq!(
+            comparison
+                .equivalent
+                .iter()
+                .map(|(left, right)| (left.to_string(), right.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                ("ISC AND MIT".to_string(), "MIT AND ISC".to_string()),
+                ("MIT AND ISC".to_string(), "(MIT AND ISC)".to_string())
+            ]
+        );
+
+        assert!(compare(&["MIT", "ISC"], &["ISC", "MIT", "MIT"]).is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Cursor for navigating and editing expressions.
+
+use std::{ops::Range, sync::Arc};
+
+use crate::{expression::SpdxExpression, expression_variant::ExpressionVariant, span::NodeSpan};
+
+/// Cursor focused on a node of an expression, created with [`SpdxExpression::cursor`].
+///
+/// The cursor moves between the nodes of the expression tree and replaces the focused node
+/// without rebuilding the rest of the expression. The children of `AND` and `OR` are their left
+/// and right operands, and the child of parentheses is the expression in them. Moves return
+/// `None` if there's no node in that direction.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Cursor, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let expression = SpdxExpression::parse("MIT AND (GPL-2.0 OR ISC)")?;
+///
+/// let cursor = expression
+///     .cursor()
+///     .down()
+///     .and_then(Cursor::right)
+///     .and_then(Cursor::down)
+///     .and_then(Cursor::down)
+///     .expect("the node exists");
+/// assert_eq!(cursor.focus().to_string(), "GPL-2.0");
+/// assert_eq!(cursor.path(), [1, 0, 0]);
+///
+/// let edited = cursor
+///     .replace(&SpdxExpression::parse("GPL-2.0-only OR GPL-3.0-only")?)
+///     .into_expression();
+/// assert_eq!(
+///     edited.to_string(),
+///     "MIT AND (GPL-2.0-only OR GPL-3.0-only OR ISC)"
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Cursor {
+    root: ExpressionVariant,
+    path: Vec<usize>,
+
+    /// Locations of the nodes of `root` in its source text, until the expression is edited.
+    spans: Option<Arc<NodeSpan>>,
+}
+
+impl Cursor {
+    pub(crate) const fn new(root: ExpressionVariant, spans: Option<Arc<NodeSpan>>) -> Self {
+        Self {
+            root,
+            path: Vec::new(),
+            spans,
+        }
+    }
+
+    /// Get the focused node.
+    pub fn focus(&self) -> SpdxExpression {
+        SpdxExpression::from_inner(self.node().clone())
+    }
+
+    /// Get the path of the focused node, with the child indices of the nodes from the root.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Get the byte range of the focused node in the source of the expression, or `None` if the
+    /// expression has no spans or has been edited, see [`SpdxExpression::spans`].
+    pub fn span(&self) -> Option<Range<usize>> {
+        Some(self.spans.as_ref()?.get(&self.path)?.range.clone())
+    }
+
+    /// Check if the cursor is focused on the root of the expression.
+    pub const fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Move to the first child of the focused node, i.e. the left operand or the expression in
+    /// parentheses.
+    pub fn down(self) -> Option<Self> {
+        self.child(0)
+    }
+
+    /// Move to the parent of the focused node.
+    pub fn up(mut self) -> Option<Self> {
+        self.path.pop()?;
+        Some(self)
+    }
+
+    /// Move to the previous sibling of the focused node, i.e. from the right operand to the left.
+    pub fn left(mut self) -> Option<Self> {
+        let index = self.path.pop()?;
+        self.child(index.checked_sub(1)?)
+    }
+
+    /// Move to the next sibling of the focused node, i.e. from the left operand to the right.
+    pub fn right(mut self) -> Option<Self> {
+        let index = self.path.pop()?;
+        self.child(index + 1)
+    }
+
+    /// Replace the focused node with `replacement`. The cursor stays focused on the replaced
+    /// node, which is parenthesized if needed to keep the structure of `replacement`.
+    pub fn replace(mut self, replacement: &SpdxExpression) -> Self {
+        let replaced = self
+            .root
+            .replace_at(&self.path, replacement.inner().unparenthesized().clone());
+        // The cursor is always focused on an existing node, so the replacement always succeeds.
+        if let Some(root) = replaced {
+            self.root = root;
+            self.spans = None;
+        }
+        self
+    }
+
+    /// Get the whole expression, including the edits.
+    pub fn into_expression(self) -> SpdxExpression {
+        SpdxExpression::from_inner(self.root)
+    }
+
+    fn node(&self) -> &ExpressionVariant {
+        self.root
+            .get(&self.path)
+            .expect("the cursor is always focused on an existing node")
+    }
+
+    fn child(mut self, index: usize) -> Option<Self> {
+        self.path.push(index);
+        self.root.get(&self.path)?;
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(expression: &str) -> SpdxExpression {
+        SpdxExpression::parse(expression).unwrap()
+    }
+
+    #[test]
+    fn navigate_tree() {
+        let cursor = SpdxExpression::parse_preserving("MIT OR ISC AND 0BSD")
+            .unwrap()
+            .cursor();
+        assert!(cursor.is_root());
+        assert!(cursor.clone().up().is_none());
+        assert!(cursor.clone().right().is_none());
+
+        let left = cursor.down().unwrap();
+        assert_eq!(left.focus().to_string(), "MIT");
+        assert!(left.clone().down().is_none());
+        assert!(left.clone().left().is_none());
+
+        let right = left.right().unwrap();
+        assert_eq!(right.focus().to_string(), "ISC AND 0BSD");
+        assert!(right.clone().right().is_none());
+        assert_eq!(right.clone().left().unwrap().focus().to_string(), "MIT");
+
+        let nested = right.down().unwrap().right().unwrap();
+        assert_eq!(nested.focus().to_string(), "0BSD");
+        assert_eq!(nested.path(), [1, 1]);
+        assert_eq!(nested.span(), Some(15..19));
+        assert!(nested.up().unwrap().up().unwrap().is_root());
+    }
+
+    #[test]
+    fn replace_focused_nodes() {
+        let cursor = parse("MIT AND ISC")
+            .cursor()
+            .down()
+            .unwrap()
+            .replace(&parse("Apache-2.0 OR 0BSD"));
+        assert_eq!(cursor.focus().to_string(), "(Apache-2.0 OR 0BSD)");
+        assert_eq!(cursor.span(), None);
+
+        let cursor = cursor.right().unwrap().replace(&parse("(Zlib)"));
+        let expression = cursor.into_expression();
+        assert_eq!(expression.to_string(), "(Apache-2.0 OR 0BSD) AND Zlib");
+        assert_eq!(parse(&expression.to_string()), expression);
+
+        let original = SpdxExpression::parse_preserving("MIT").unwrap();
+        assert_eq!(original.cursor().span(), Some(0..3));
+        let replaced = original.cursor().replace(&parse("ISC")).into_expression();
+        assert_eq!(original.to_string(), "MIT");
+        assert_eq!(replaced.to_string(), "ISC");
+    }
+}
@@ -0,0 +1,358 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Structural differences between expressions.
+
+use crate::{expression::SpdxExpression, expression_variant::ExpressionVariant, parser::Operator};
+
+/// Difference between two expressions, see [`SpdxExpression::diff`].
+///
+/// Parts of the expressions are located with paths of child indices from the root of the
+/// expression. The operands of `AND` and `OR` are the children 0 and 1, and the expression in
+/// parentheses is the child 0, so in `MIT OR (ISC AND 0BSD)` the path of `ISC` is `[1, 0, 0]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// An operand of `other` that isn't in `self`.
+    Added {
+        /// Path of the operand in `other`.
+        path: Vec<usize>,
+        operand: SpdxExpression,
+    },
+
+    /// An operand of `self` that isn't in `other`.
+    Removed {
+        /// Path of the operand in `self`.
+        path: Vec<usize>,
+        operand: SpdxExpression,
+    },
+
+    /// A part of `self` that was replaced with a different part of `other`.
+    Changed {
+        /// Path of the part in `self`.
+        from_path: Vec<usize>,
+        /// Path of the part in `other`.
+        to_path: Vec<usize>,
+        from: SpdxExpression,
+        to: SpdxExpression,
+    },
+
+    /// An operand in both expressions at a different position, either reordered among the
+    /// operands of the same operator or moved to a different part of the expression.
+    Moved {
+        /// Path of the operand in `self`.
+        from_path: Vec<usize>,
+        /// Path of the operand in `other`.
+        to_path: Vec<usize>,
+        operand: SpdxExpression,
+    },
+}
+
+/// Part of an expression with its path.
+pub type Located<'a> = (Vec<usize>, &'a ExpressionVariant);
+
+pub fn diff(from: &ExpressionVariant, to: &ExpressionVariant) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_nodes(
+        strip_parens(from, Vec::new()),
+        strip_parens(to, Vec::new()),
+        &mut changes,
+    );
+    pair_moves(&mut changes);
+    changes
+}
+
+fn diff_nodes(from: Located<'_>, to: Located<'_>, changes: &mut Vec<Change>) {
+    if key(from.1) == key(to.1) {
+        return;
+    }
+
+    match (operands(&from), operands(&to)) {
+        (Some((from_operator, from_operands)), Some((to_operator, to_operands)))
+            if from_operator == to_operator =>
+        {
+            diff_operands(from_operands, to_operands, changes);
+        }
+        _ => changes.push(Change::Changed {
+            from_path: from.0,
+            to_path: to.0,
+            from: expression(from.1),
+            to: expression(to.1),
+        }),
+    }
+}
+
+/// Compare the operands of the same operator. Equal operands are matched first, and reported as
+/// moved if their order changed. The remaining compound operands are compared with operands of
+/// the same operator, then the rest are compared in order, and the extra operands of either side
+/// are added or removed.
+fn diff_operands(from: Vec<Located<'_>>, to: Vec<Located<'_>>, changes: &mut Vec<Change>) {
+    let from_keys = from.iter().map(|(_, node)| key(node)).collect::<Vec<_>>();
+    let to_keys = to.iter().map(|(_, node)| key(node)).collect::<Vec<_>>();
+
+    let mut matched = vec![None; from.len()];
+    let mut to_matched = vec![false; to.len()];
+    for (from_index, from_key) in from_keys.iter().enumerate() {
+        if let Some(to_index) =
+            (0..to.len()).find(|&to_index| !to_matched[to_index] && to_keys[to_index] == *from_key)
+        {
+            matched[from_index] = Some(to_index);
+            to_matched[to_index] = true;
+        }
+    }
+
+    let in_order = longest_increasing(&matched.iter().flatten().copied().collect::<Vec<_>>());
+    for (from_index, to_index) in matched.iter().enumerate() {
+        if let Some(to_index) = to_index {
+            if !in_order.contains(to_index) {
+                changes.push(Change::Moved {
+                    from_path: from[from_index].0.clone(),
+                    to_path: to[*to_index].0.clone(),
+                    operand: expression(from[from_index].1),
+                });
+            }
+        }
+    }
+
+    let mut from_rest = from
+        .into_iter()
+        .zip(&matched)
+        .filter(|(_, matched)| matched.is_none())
+        .map(|(located, _)| Some(located))
+        .collect::<Vec<_>>();
+    let mut to_rest = to
+        .into_iter()
+        .zip(to_matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(located, _)| Some(located))
+        .collect::<Vec<_>>();
+
+    // Compare compound operands with compound operands of the same operator.
+    for from in &mut from_rest {
+        let Some((from_operator, _)) = from.as_ref().and_then(|(_, node)| node.flatten()) else {
+            continue;
+        };
+        let same_operator = to_rest.iter_mut().find(|to| {
+            to.as_ref()
+                .and_then(|(_, node)| node.flatten())
+                .is_some_and(|(to_operator, _)| to_operator == from_operator)
+        });
+        if let Some(to) = same_operator {
+            if let (Some(from), Some(to)) = (from.take(), to.take()) {
+                diff_nodes(from, to, changes);
+            }
+        }
+    }
+
+    let mut from_rest = from_rest.into_iter().flatten();
+    let mut to_rest = to_rest.into_iter().flatten();
+    loop {
+        match (from_rest.next(), to_rest.next()) {
+            (Some(from), Some(to)) => diff_nodes(from, to, changes),
+            (Some((path, node)), None) => changes.push(Change::Removed {
+                path,
+                operand: expression(node),
+            }),
+            (None, Some((path, node))) => changes.push(Change::Added {
+                path,
+                operand: expression(node),
+            }),
+            (None, None) => break,
+        }
+    }
+}
+
+/// Replace operands removed from one part of the expression and added to another with moves.
+fn pair_moves(changes: &mut Vec<Change>) {
+    let mut index = 0;
+    while index < changes.len() {
+        if let Change::Removed { operand, .. } = &changes[index] {
+            let operand_key = key(operand.inner());
+            let added = changes.iter().position(|change| match change {
+                Change::Added { operand, .. } => key(operand.inner()) == operand_key,
+                _ => false,
+            });
+
+            if let Some(added) = added {
+                let Change::Added { path: to_path, .. } = changes.remove(added) else {
+                    unreachable!("the change was matched as added");
+                };
+                if added < index {
+                    index -= 1;
+                }
+                if let Change::Removed { path, operand } = changes[index].clone() {
+                    changes[index] = Change::Moved {
+                        from_path: path,
+                        to_path,
+                        operand,
+                    };
+                }
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Get the values of one longest increasing subsequence of `values`.
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    let mut lengths = vec![1; values.len()];
+    let mut previous = vec![None; values.len()];
+    for current in 0..values.len() {
+        for before in 0..current {
+            if values[before] < values[current] && lengths[before] + 1 > lengths[current] {
+                lengths[current] = lengths[before] + 1;
+                previous[current] = Some(before);
+            }
+        }
+    }
+
+    let mut subsequence = Vec::new();
+    let mut current = (0..values.len()).max_by_key(|&index| lengths[index]);
+    while let Some(index) = current {
+        subsequence.push(values[index]);
+        current = previous[index];
+    }
+    subsequence
+}
+
+/// Skip parentheses around `node`, extending its path.
+pub fn strip_parens(node: &ExpressionVariant, mut path: Vec<usize>) -> Located<'_> {
+    let mut node = node;
+    while let ExpressionVariant::Parens(inner) = node {
+        path.push(0);
+        node = inner;
+    }
+    (path, node)
+}
+
+/// Get the operator and the flattened operands of a compound expression with their paths, like
+/// [`ExpressionVariant::flatten`].
+pub fn operands<'a>(located: &Located<'a>) -> Option<(Operator, Vec<Located<'a>>)> {
+    let (operator, _) = located.1.flatten()?;
+
+    let mut operands = Vec::new();
+    let mut stack = vec![located.clone()];
+    while let Some((path, node)) = stack.pop() {
+        match (node, operator) {
+            (ExpressionVariant::And(left, right), Operator::And)
+            | (ExpressionVariant::Or(left, right), Operator::Or) => {
+                let mut right_path = path.clone();
+                right_path.push(1);
+                stack.push(strip_parens(right, right_path));
+                let mut left_path = path;
+                left_path.push(0);
+                stack.push(strip_parens(left, left_path));
+            }
+            _ => operands.push((path, node)),
+        }
+    }
+
+    Some((operator, operands))
+}
+
+/// Key of a node that ignores parentheses and the grouping of operands of the same operator.
+pub fn key(node: &ExpressionVariant) -> String {
+    match node.flatten() {
+        Some((operator, operands)) => {
+            let operands = operands.into_iter().map(key).collect::<Vec<_>>();
+            format!("{operator:?}({})", operands.join(","))
+        }
+        None => node.unparenthesized().to_string(),
+    }
+}
+
+fn expression(node: &ExpressionVariant) -> SpdxExpression {
+    SpdxExpression::from_inner(node.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(from: &str, to: &str) -> Vec<Change> {
+        SpdxExpression::parse(from)
+            .unwrap()
+            .diff(&SpdxExpression::parse(to).unwrap())
+    }
+
+    fn parse(expression: &str) -> SpdxExpression {
+        SpdxExpression::parse(expression).unwrap()
+    }
+
+    #[test]
+    fn no_changes_between_equivalent_groupings() {
+        assert!(changes("MIT AND (ISC AND 0BSD)", "(MIT AND ISC) AND 0BSD").is_empty());
+        assert!(changes("((MIT))", "MIT").is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_changed_operands() {
+        assert_eq!(
+            changes("MIT OR ISC OR 0BSD", "MIT OR Apache-2.0 OR 0BSD OR Zlib"),
+            [
+                Change::Changed {
+                    from_path: vec![0, 1],
+                    to_path: vec![0, 0, 1],
+                    from: parse("ISC"),
+                    to: parse("Apache-2.0"),
+                },
+                Change::Added {
+                    path: vec![1],
+                    operand: parse("Zlib"),
+                },
+            ]
+        );
+        assert_eq!(
+            changes("MIT AND ISC", "MIT"),
+            [Change::Changed {
+                from_path: vec![],
+                to_path: vec![],
+                from: parse("MIT AND ISC"),
+                to: parse("MIT"),
+            }]
+        );
+        assert_eq!(
+            changes("MIT AND ISC AND 0BSD", "MIT AND 0BSD"),
+            [Change::Removed {
+                path: vec![0, 1],
+                operand: parse("ISC"),
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_nested_operands() {
+        assert_eq!(
+            changes("MIT AND (ISC OR 0BSD)", "MIT AND (ISC OR Zlib)"),
+            [Change::Changed {
+                from_path: vec![1, 0, 1],
+                to_path: vec![1, 0, 1],
+                from: parse("0BSD"),
+                to: parse("Zlib"),
+            }]
+        );
+    }
+
+    #[test]
+    fn reordered_and_moved_operands() {
+        assert_eq!(
+            changes("MIT AND ISC AND 0BSD", "ISC AND 0BSD AND MIT"),
+            [Change::Moved {
+                from_path: vec![0, 0],
+                to_path: vec![1],
+                operand: parse("MIT"),
+            }]
+        );
+        assert_eq!(
+            changes(
+                "MIT AND ISC AND (0BSD OR Zlib)",
+                "ISC AND (0BSD OR Zlib OR MIT)"
+            ),
+            [Change::Moved {
+                from_path: vec![0, 0],
+                to_path: vec![1, 0, 1],
+                operand: parse("MIT"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Stable hashing of strings.
+//!
+//! The hashes of the standard library may change between Rust versions, so values that are
+//! persisted or shared, like anonymized identifiers, are hashed with 64-bit FNV-1a instead, or
+//! with SipHash-2-4 when the hashes need to be keyed.
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hash `bytes` with 64-bit FNV-1a.
+pub const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        index += 1;
+    }
+    hash
+}
+
+/// Hash `bytes` with SipHash-2-4 under `key`. Without the key, the hashes of guessed inputs
+/// can't be computed to compare them with known hashes.
+pub fn siphash(key: &[u8; 16], bytes: &[u8]) -> u64 {
+    let k0 = u64::from_le_bytes([
+        key[0], key[1], key[2], key[3], key[4], key[5], key[6], key[7],
+    ]);
+    let k1 = u64::from_le_bytes([
+        key[8], key[9], key[10], key[11], key[12], key[13], key[14], key[15],
+    ]);
+    let mut state = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        compress(&mut state, u64::from_le_bytes(word));
+    }
+    let mut last = [0; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = bytes.len().to_le_bytes()[0];
+    compress(&mut state, u64::from_le_bytes(last));
+
+    state[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut state);
+    }
+    state[0] ^ state[1] ^ state[2] ^ state[3]
+}
+
+const fn compress(state: &mut [u64; 4], word: u64) {
+    state[3] ^= word;
+    round(state);
+    round(state);
+    state[0] ^= word;
+}
+
+const fn round(state: &mut [u64; 4]) {
+    let [v0, v1, v2, v3] = state;
+    *v0 = v0.wrapping_add(*v1);
+    *v1 = v1.rotate_left(13) ^ *v0;
+    *v0 = v0.rotate_left(32);
+    *v2 = v2.wrapping_add(*v3);
+    *v3 = v3.rotate_left(16) ^ *v2;
+    *v0 = v0.wrapping_add(*v3);
+    *v3 = v3.rotate_left(21) ^ *v0;
+    *v2 = v2.wrapping_add(*v1);
+    *v1 = v1.rotate_left(17) ^ *v2;
+    *v2 = v2.rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn siphash_reference_values() {
+        // Test vectors of the reference implementation, with the key 00 01 .. 0f and the
+        // messages 00 01 .. (length - 1).
+        let mut key = [0; 16];
+        for (byte, value) in key.iter_mut().zip(0..) {
+            *byte = value;
+        }
+        let message = (0..64).collect::<Vec<u8>>();
+
+        assert_eq!(siphash(&key, &message[..0]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(siphash(&key, &message[..1]), 0x74f8_39c5_93dc_67fd);
+        assert_eq!(siphash(&key, &message[..8]), 0x93f5_f579_9a93_2462);
+        assert_eq!(siphash(&key, &message[..15]), 0xa129_ca61_49be_45e5);
+        assert_ne!(siphash(&[0; 16], b"MIT"), siphash(&key, b"MIT"));
+    }
+}
@@ -0,0 +1,437 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Helpers for the license fields of SPDX documents.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{error::SpdxExpressionError, expression::SpdxExpression};
+
+/// Parsed license fields of an SPDX document, e.g. the `license_concluded` and
+/// `license_declared` fields of the packages and files of a document from `spdx-rs`.
+///
+/// Every field is identified by a key of type `K` chosen by the caller, for example the SPDX
+/// identifier of the element the field belongs to and the name of the field. The keys are kept
+/// with both the parsed expressions and the errors, so problems can be traced back to the
+/// document.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::{BTreeSet, HashMap};
+/// # use spdx_expression::DocumentLicenses;
+/// #
+/// let licenses = DocumentLicenses::parse([
+///     ("SPDXRef-Package", "MIT OR LicenseRef-custom"),
+///     ("SPDXRef-File", "LicenseRef-other AND DocumentRef-ext:LicenseRef-external"),
+///     ("SPDXRef-Broken", "MIT OR"),
+/// ]);
+///
+/// assert_eq!(licenses.expressions.len(), 2);
+/// assert_eq!(licenses.errors[0].0, "SPDXRef-Broken");
+/// assert_eq!(
+///     licenses.undefined_license_refs(["LicenseRef-custom"]),
+///     BTreeSet::from(["LicenseRef-other".to_string()])
+/// );
+/// ```
+#[derive(Debug)]
+pub struct DocumentLicenses<K> {
+    /// Successfully parsed fields.
+    pub expressions: Vec<(K, SpdxExpression)>,
+
+    /// Fields that failed to parse.
+    pub errors: Vec<(K, SpdxExpressionError)>,
+}
+
+impl<K> DocumentLicenses<K> {
+    /// Parse the values of license fields.
+    pub fn parse<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, S)>,
+        S: AsRef<str>,
+    {
+        let mut licenses = Self {
+            expressions: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        for (key, value) in fields {
+            match SpdxExpression::parse(value.as_ref()) {
+                Ok(expression) => licenses.expressions.push((key, expression)),
+                Err(err) => licenses.errors.push((key, err)),
+            }
+        }
+
+        licenses
+    }
+
+    /// Get all `LicenseRef`s used in the parsed fields, including the `DocumentRef` prefix for
+    /// references to other documents.
+    pub fn license_refs(&self) -> BTreeSet<String> {
+        self.expressions
+            .iter()
+            .flat_map(|(_, expression)| expression.licenses())
+            .filter(|license| license.license_ref)
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Get the `LicenseRef`s used in the parsed fields that are not in `defined`, i.e. the
+    /// identifiers of the document's extracted licensing info. References to other documents
+    /// with a `DocumentRef` prefix are defined in those documents, so they are never reported.
+    pub fn undefined_license_refs<'a, D>(&self, defined: D) -> BTreeSet<String>
+    where
+        D: IntoIterator<Item = &'a str>,
+    {
+        let defined = defined.into_iter().collect::<BTreeSet<_>>();
+
+        self.expressions
+            .iter()
+            .flat_map(|(_, expression)| expression.licenses())
+            .filter(|license| license.license_ref && license.document_ref.is_none())
+            .map(ToString::to_string)
+            .filter(|license_ref| !defined.contains(license_ref.as_str()))
+            .collect()
+    }
+
+    /// Check the `LicenseRef`s of the parsed fields against `defined`, the identifiers of the
+    /// document's extracted licensing info. Reports the fields that use undefined
+    /// `LicenseRef`s and the defined `LicenseRef`s no field uses. References to other documents
+    /// with a `DocumentRef` prefix are defined in those documents, so they are never reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeSet;
+    /// # use spdx_expression::DocumentLicenses;
+    /// #
+    /// let licenses = DocumentLicenses::parse([
+    ///     ("SPDXRef-Package", "MIT OR LicenseRef-custom"),
+    ///     ("SPDXRef-File", "LicenseRef-other AND DocumentRef-ext:LicenseRef-external"),
+    /// ]);
+    ///
+    /// let report = licenses.license_ref_report(["LicenseRef-custom", "LicenseRef-unused"]);
+    /// assert!(!report.is_valid());
+    /// assert_eq!(
+    ///     report.undefined,
+    ///     [(&"SPDXRef-File", BTreeSet::from(["LicenseRef-other".to_string()]))]
+    /// );
+    /// assert_eq!(report.unused, BTreeSet::from(["LicenseRef-unused".to_string()]));
+    /// ```
+    pub fn license_ref_report<'a, D>(&self, defined: D) -> LicenseRefReport<'_, K>
+    where
+        D: IntoIterator<Item = &'a str>,
+    {
+        let defined = defined.into_iter().collect::<BTreeSet<_>>();
+        let mut used = BTreeSet::new();
+        let mut undefined = Vec::new();
+
+        for (key, expression) in &self.expressions {
+            let license_refs = expression
+                .licenses()
+                .into_iter()
+                .filter(|license| license.license_ref && license.document_ref.is_none())
+                .map(ToString::to_string)
+                .collect::<BTreeSet<_>>();
+            let missing = license_refs
+                .iter()
+                .filter(|license_ref| !defined.contains(license_ref.as_str()))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            if !missing.is_empty() {
+                undefined.push((key, missing));
+            }
+            used.extend(license_refs);
+        }
+
+        let unused = defined
+            .into_iter()
+            .filter(|license_ref| !used.contains(*license_ref))
+            .map(ToString::to_string)
+            .collect();
+        LicenseRefReport { undefined, unused }
+    }
+
+    /// Rename documents in the `DocumentRef` prefixes of the licenses in every parsed field,
+    /// with [`SpdxExpression::rename_document_refs`]. Used when merging documents changes the
+    /// names of the referenced documents.
+    pub fn rename_document_refs(&mut self, renames: &HashMap<String, String>) {
+        for (_, expression) in &mut self.expressions {
+            *expression = expression.rename_document_refs(renames);
+        }
+    }
+}
+
+/// Result of checking the `LicenseRef`s of a document, see
+/// [`DocumentLicenses::license_ref_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseRefReport<'a, K> {
+    /// Keys of the fields that use undefined `LicenseRef`s, with the undefined `LicenseRef`s.
+    pub undefined: Vec<(&'a K, BTreeSet<String>)>,
+
+    /// Defined `LicenseRef`s that no field uses.
+    pub unused: BTreeSet<String>,
+}
+
+impl<K> LicenseRefReport<'_, K> {
+    /// Check if every used `LicenseRef` is defined and every defined `LicenseRef` is used.
+    pub fn is_valid(&self) -> bool {
+        self.undefined.is_empty() && self.unused.is_empty()
+    }
+}
+
+/// Names of the license expression fields in SPDX JSON documents.
+#[cfg(feature = "serde_json")]
+const JSON_LICENSE_FIELDS: [&str; 4] = [
+    "licenseConcluded",
+    "licenseDeclared",
+    "licenseInfoFromFiles",
+    "licenseInfoInFiles",
+];
+
+#[cfg(feature = "serde_json")]
+impl DocumentLicenses<String> {
+    /// Find and parse the license fields of an SPDX JSON document, i.e. the `licenseConcluded`,
+    /// `licenseDeclared`, `licenseInfoFromFiles` and `licenseInfoInFiles` fields anywhere in the
+    /// document. The fields are keyed with their JSON Pointers, e.g.
+    /// `/packages/0/licenseConcluded` or `/files/2/licenseInfoInFiles/1`.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::DocumentLicenses;
+    /// #
+    /// let document = serde_json::json!({
+    ///     "packages": [{
+    ///         "SPDXID": "SPDXRef-Package",
+    ///         "licenseConcluded": "MIT",
+    ///         "licenseInfoFromFiles": ["MIT", "Apache-2.0 OR"]
+    ///     }]
+    /// });
+    ///
+    /// let licenses = DocumentLicenses::from_json(&document);
+    ///
+    /// assert_eq!(licenses.expressions[0].0, "/packages/0/licenseConcluded");
+    /// assert_eq!(licenses.errors[0].0, "/packages/0/licenseInfoFromFiles/1");
+    /// ```
+    pub fn from_json(document: &serde_json::Value) -> Self {
+        let mut fields = Vec::new();
+        collect_json_fields(document, &mut String::new(), &mut fields);
+        Self::parse(fields)
+    }
+}
+
+#[cfg(feature = "serde_json")]
+fn collect_json_fields<'a>(
+    value: &'a serde_json::Value,
+    path: &mut String,
+    fields: &mut Vec<(String, &'a str)>,
+) {
+    use serde_json::Value;
+
+    let length = path.len();
+
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                push_json_pointer_token(path, key);
+                if JSON_LICENSE_FIELDS.contains(&key.as_str()) {
+                    match value {
+                        Value::String(expression) => fields.push((path.clone(), expression)),
+                        Value::Array(values) => {
+                            for (index, value) in values.iter().enumerate() {
+                                if let Value::String(expression) = value {
+                                    fields.push((format!("{path}/{index}"), expression));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                } else {
+                    collect_json_fields(value, path, fields);
+                }
+                path.truncate(length);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                push_json_pointer_token(path, &index.to_string());
+                collect_json_fields(value, path, fields);
+                path.truncate(length);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Append `token` to a JSON Pointer, escaping it as specified in RFC 6901.
+#[cfg(feature = "serde_json")]
+fn push_json_pointer_token(path: &mut String, token: &str) {
+    path.push('/');
+    path.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fields_and_keep_keys() {
+        let licenses = DocumentLicenses::parse(vec![
+            (("SPDXRef-Package", "licenseConcluded"), "MIT".to_string()),
+            (("SPDXRef-Package", "licenseDeclared"), "((".to_string()),
+            (
+                ("SPDXRef-File", "licenseConcluded"),
+                "NOASSERTION".to_string(),
+            ),
+        ]);
+
+        assert_eq!(
+            licenses
+                .expressions
+                .iter()
+                .map(|(key, expression)| (*key, expression.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (("SPDXRef-Package", "licenseConcluded"), "MIT".to_string()),
+                (
+                    ("SPDXRef-File", "licenseConcluded"),
+                    "NOASSERTION".to_string()
+                )
+            ]
+        );
+        assert_eq!(licenses.errors.len(), 1);
+        assert_eq!(licenses.errors[0].0, ("SPDXRef-Package", "licenseDeclared"));
+    }
+
+    #[test]
+    fn report_license_refs_per_field() {
+        let licenses = DocumentLicenses::parse([
+            (1, "LicenseRef-a AND LicenseRef-b"),
+            (2, "LicenseRef-a OR DocumentRef-ext:LicenseRef-c"),
+            (3, "LicenseRef-b OR LicenseRef-d"),
+        ]);
+
+        let report = licenses.license_ref_report(["LicenseRef-a", "LicenseRef-e"]);
+        assert_eq!(
+            report.undefined,
+            [
+                (&1, BTreeSet::from(["LicenseRef-b".to_string()])),
+                (
+                    &3,
+                    BTreeSet::from(["LicenseRef-b".to_string(), "LicenseRef-d".to_string()])
+                ),
+            ]
+        );
+        assert_eq!(report.unused, BTreeSet::from(["LicenseRef-e".to_string()]));
+
+        let report = licenses.license_ref_report(["LicenseRef-a", "LicenseRef-b", "LicenseRef-d"]);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn collect_license_refs_across_fields() {
+        let licenses = DocumentLicenses::parse([
+            (1, "MIT OR LicenseRef-a"),
+            (2, "LicenseRef-b WITH exception AND LicenseRef-a"),
+            (3, "DocumentRef-ext:LicenseRef-c"),
+        ]);
+
+        assert_eq!(
+            licenses.license_refs(),
+            BTreeSet::from([
+                "DocumentRef-ext:LicenseRef-c".to_string(),
+                "LicenseRef-a".to_string(),
+                "LicenseRef-b".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn report_undefined_license_refs() {
+        let licenses = DocumentLicenses::parse([
+            (1, "MIT OR LicenseRef-a"),
+            (2, "LicenseRef-b AND DocumentRef-ext:LicenseRef-c"),
+        ]);
+
+        assert_eq!(
+            licenses.undefined_license_refs(["LicenseRef-a", "LicenseRef-unused"]),
+            BTreeSet::from(["LicenseRef-b".to_string()])
+        );
+    }
+
+    #[test]
+    fn rename_document_refs_in_all_fields() {
+        let mut licenses = DocumentLicenses::parse([
+            (1, "DocumentRef-old:LicenseRef-a"),
+            (2, "MIT AND DocumentRef-old:LicenseRef-b"),
+            (3, "DocumentRef-other:LicenseRef-c"),
+        ]);
+
+        licenses.rename_document_refs(&HashMap::from([(
+            "DocumentRef-old".to_string(),
+            "DocumentRef-new".to_string(),
+        )]));
+
+        assert_eq!(
+            licenses.license_refs(),
+            BTreeSet::from([
+                "DocumentRef-new:LicenseRef-a".to_string(),
+                "DocumentRef-new:LicenseRef-b".to_string(),
+                "DocumentRef-other:LicenseRef-c".to_string()
+            ])
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn parse_fields_of_json_document() {
+        let document = serde_json::json!({
+            "packages": [
+                {
+                    "SPDXID": "SPDXRef-Package",
+                    "licenseConcluded": "NOASSERTION",
+                    "licenseDeclared": "MIT OR LicenseRef-a",
+                    "licenseInfoFromFiles": ["MIT", "LicenseRef-a"]
+                }
+            ],
+            "files": [
+                {
+                    "SPDXID": "SPDXRef-File",
+                    "licenseConcluded": "((",
+                    "licenseInfoInFiles": ["MIT"]
+                }
+            ],
+            "odd/key": { "licenseDeclared": "ISC" }
+        });
+
+        let licenses = DocumentLicenses::from_json(&document);
+
+        assert_eq!(
+            licenses
+                .expressions
+                .iter()
+                .map(|(path, expression)| (path.as_str(), expression.to_string()))
+                .collect::<BTreeSet<_>>(),
+            BTreeSet::from([
+                ("/files/0/licenseInfoInFiles/0", "MIT".to_string()),
+                ("/odd~1key/licenseDeclared", "ISC".to_string()),
+                ("/packages/0/licenseConcluded", "NOASSERTION".to_string()),
+                (
+                    "/packages/0/licenseDeclared",
+                    "MIT OR LicenseRef-a".to_string()
+                ),
+                ("/packages/0/licenseInfoFromFiles/0", "MIT".to_string()),
+                (
+                    "/packages/0/licenseInfoFromFiles/1",
+                    "LicenseRef-a".to_string()
+                ),
+            ])
+        );
+        assert_eq!(licenses.errors.len(), 1);
+        assert_eq!(licenses.errors[0].0, "/files/0/licenseConcluded");
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rendering expressions as Graphviz DOT graphs.
+
+use std::fmt::Write;
+
+use crate::{
+    expression_variant::ExpressionVariant,
+    graph::{nodes, NodeKind},
+};
+
+pub fn render(expression: &ExpressionVariant) -> String {
+    let mut output = String::from("digraph expression {\n");
+
+    for node in nodes(expression) {
+        let shape = match node.kind {
+            NodeKind::Operator => "box",
+            NodeKind::License => "ellipse",
+            NodeKind::Exception => "note",
+        };
+
+        // Writing to a `String` can't fail.
+        let _ = writeln!(
+            output,
+            "    n{} [label=\"{}\", shape={shape}];",
+            node.id,
+            escape(&node.label)
+        );
+        if let Some(parent) = node.parent {
+            let _ = writeln!(output, "    n{parent} -> n{};", node.id);
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_flattened_tree() {
+        let expression = ExpressionVariant::parse(
+            "MIT OR (Apache-2.0 AND GPL-2.0-only WITH Classpath-exception-2.0) OR ISC",
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&expression),
+            r#"digraph expression {
+    n0 [label="OR", shape=box];
+    n1 [label="MIT", shape=ellipse];
+    n0 -> n1;
+    n2 [label="AND", shape=box];
+    n0 -> n2;
+    n3 [label="Apache-2.0", shape=ellipse];
+    n2 -> n3;
+    n4 [label="WITH", shape=box];
+    n2 -> n4;
+    n5 [label="GPL-2.0-only", shape=ellipse];
+    n4 -> n5;
+    n6 [label="Classpath-exception-2.0", shape=note];
+    n4 -> n6;
+    n7 [label="ISC", shape=ellipse];
+    n0 -> n7;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn escape_labels() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+}
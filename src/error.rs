@@ -4,14 +4,359 @@
 
 //! Errors of the library.
 
+use std::fmt::Display;
+
+use nom::error::{VerboseError, VerboseErrorKind};
+
+use crate::parser::{CLOSING_PARENTHESIS, EXCEPTION};
+
 /// Custom error struct.
 #[derive(thiserror::Error, Debug)]
 pub enum SpdxExpressionError {
-    #[error("Parsing for expression `{0}` failed.")]
-    Parse(String),
+    #[error("{0}")]
+    Parse(ParseError),
 
     #[error("Error parsing the SPDX Expression {0}.")]
     Nom(String),
+
+    #[error("Error reading expressions: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error decoding a binary encoded expression: {0}")]
+    Decode(String),
+
+    #[error("Error converting the expression: {0}")]
+    Conversion(String),
+
+    #[error("Removing `{0}` would leave the expression empty.")]
+    Empty(String),
+
+    #[error("Error rewriting the expression: {0}")]
+    Rewrite(String),
+
+    #[error("Error applying the patch: {0}")]
+    Patch(String),
+
+    #[error("Invalid text edit: {0}")]
+    Edit(String),
+
+    #[error("Invalid template: {0}")]
+    Template(String),
+
+    #[error("Invalid query: `{0}`")]
+    Query(String),
+
+    #[error(transparent)]
+    Build(#[from] BuildError),
+
+    #[error("Parsing for expression `{0}` exceeded the parse budget.")]
+    Budget(String),
+
+    #[error("Error loading the license list: {0}")]
+    LicenseList(String),
+
+    #[error("Invalid license policy: {0}")]
+    Policy(String),
+}
+
+impl SpdxExpressionError {
+    /// Get the stable code of the error, e.g. for aggregating or suppressing errors without
+    /// matching on the messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{ErrorCode, SpdxExpression};
+    /// #
+    /// let error = SpdxExpression::parse("MIT AND (ISC").unwrap_err();
+    /// assert_eq!(error.error_code(), ErrorCode::UnbalancedParenthesis);
+    /// assert_eq!(error.error_code().to_string(), "E0101 UnbalancedParenthesis");
+    /// ```
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::Parse(error) => error.error_code(),
+            Self::Nom(_) => ErrorCode::InvalidSyntax,
+            Self::Io(_) => ErrorCode::Io,
+            Self::Decode(_) => ErrorCode::InvalidEncoding,
+            Self::Conversion(_) => ErrorCode::Conversion,
+            Self::Empty(_) => ErrorCode::EmptyResult,
+            Self::Rewrite(_) => ErrorCode::Rewrite,
+            Self::Patch(_) => ErrorCode::Patch,
+            Self::Edit(_) => ErrorCode::InvalidEdit,
+            Self::Template(_) => ErrorCode::InvalidTemplate,
+            Self::Query(_) => ErrorCode::InvalidQuery,
+            Self::Build(error) => error.error_code(),
+            Self::Budget(_) => ErrorCode::BudgetExceeded,
+            Self::LicenseList(_) => ErrorCode::InvalidLicenseList,
+            Self::Policy(_) => ErrorCode::InvalidPolicy,
+        }
+    }
+}
+
+/// Stable code of an error, see [`SpdxExpressionError::error_code`].
+///
+/// The codes are grouped by the hundreds: `E01xx` for syntax errors, `E02xx` for errors reading
+/// and decoding, `E03xx` for errors transforming expressions, `E04xx` for invalid templates and
+/// queries, `E05xx` for invalid builder steps and `E06xx` for exceeded limits. A code is never
+/// reused for a different error, so it can be documented and matched on across crate versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// `E0100`: the input is not a valid expression.
+    InvalidSyntax,
+
+    /// `E0101`: a parenthesis is not closed, or closes a parenthesis that was not opened.
+    UnbalancedParenthesis,
+
+    /// `E0102`: a license or a parenthesized expression is missing, e.g. after an operator.
+    MissingOperand,
+
+    /// `E0103`: the exception is missing after `WITH`.
+    MissingException,
+
+    /// `E0104`: there is unexpected text after a valid expression.
+    UnexpectedText,
+
+    /// `E0105`: the input is empty.
+    EmptyExpression,
+
+    /// `E0201`: reading the input failed.
+    Io,
+
+    /// `E0202`: a binary encoded expression is invalid.
+    InvalidEncoding,
+
+    /// `E0203`: license list data is invalid.
+    InvalidLicenseList,
+
+    /// `E0204`: a license policy is invalid.
+    InvalidPolicy,
+
+    /// `E0301`: the expression can't be converted to or from another format.
+    Conversion,
+
+    /// `E0302`: the result of the operation would be empty.
+    EmptyResult,
+
+    /// `E0303`: rewriting the expression failed.
+    Rewrite,
+
+    /// `E0304`: applying a patch failed.
+    Patch,
+
+    /// `E0305`: a text edit doesn't fit the edited text.
+    InvalidEdit,
+
+    /// `E0401`: an expression template is invalid.
+    InvalidTemplate,
+
+    /// `E0402`: a query is invalid.
+    InvalidQuery,
+
+    /// `E0501`: a license identifier given to the builder is invalid.
+    InvalidLicense,
+
+    /// `E0502`: an exception identifier given to the builder is invalid.
+    InvalidException,
+
+    /// `E0503`: an exception is attached to a compound expression.
+    ExceptionOnCompound,
+
+    /// `E0504`: a license already has an exception.
+    DuplicateException,
+
+    /// `E0505`: `NONE` or `NOASSERTION` is combined with other licenses.
+    SpecialLicense,
+
+    /// `E0601`: parsing exceeded the budget of [`ParserOptions`](crate::ParserOptions).
+    BudgetExceeded,
+}
+
+impl ErrorCode {
+    /// Get the code, e.g. `E0101`.
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::InvalidSyntax => "E0100",
+            Self::UnbalancedParenthesis => "E0101",
+            Self::MissingOperand => "E0102",
+            Self::MissingException => "E0103",
+            Self::UnexpectedText => "E0104",
+            Self::EmptyExpression => "E0105",
+            Self::Io => "E0201",
+            Self::InvalidEncoding => "E0202",
+            Self::InvalidLicenseList => "E0203",
+            Self::InvalidPolicy => "E0204",
+            Self::Conversion => "E0301",
+            Self::EmptyResult => "E0302",
+            Self::Rewrite => "E0303",
+            Self::Patch => "E0304",
+            Self::InvalidEdit => "E0305",
+            Self::InvalidTemplate => "E0401",
+            Self::InvalidQuery => "E0402",
+            Self::InvalidLicense => "E0501",
+            Self::InvalidException => "E0502",
+            Self::ExceptionOnCompound => "E0503",
+            Self::DuplicateException => "E0504",
+            Self::SpecialLicense => "E0505",
+            Self::BudgetExceeded => "E0601",
+        }
+    }
+}
+
+/// Displays the code followed by the name of the error, e.g. `E0101 UnbalancedParenthesis`.
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {self:?}", self.code())
+    }
+}
+
+/// Details of an input that failed to parse, see [`SpdxExpressionError::Parse`].
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{SpdxExpression, SpdxExpressionError};
+/// #
+/// let Err(SpdxExpressionError::Parse(error)) = SpdxExpression::parse("MIT AND (ISC") else {
+///     panic!("parsing should fail");
+/// };
+/// assert_eq!(error.offset, Some(12));
+/// assert_eq!(error.context.first(), Some(&"closing parenthesis"));
+/// assert_eq!(
+///     error.to_string(),
+///     "Parsing for expression `MIT AND (ISC` failed at byte 12: expected closing parenthesis."
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The input that failed to parse.
+    pub input: String,
+
+    /// Byte offset in `input` where parsing failed, or `None` if not known.
+    pub offset: Option<usize>,
+
+    /// What was being parsed at `offset`, innermost first, e.g. `closing parenthesis` and
+    /// `license or parenthesized expression`. Empty if the input has unexpected text at
+    /// `offset` after a valid expression.
+    pub context: Vec<&'static str>,
+}
+
+impl ParseError {
+    /// Create an error for `input` without details of the failure.
+    pub fn new(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            offset: None,
+            context: Vec::new(),
+        }
+    }
+
+    /// Create an error for unexpected text at the end of `input`, where `remaining` is the
+    /// suffix of `input` left unparsed.
+    pub(crate) fn unexpected(input: &str, remaining: &str) -> Self {
+        let offset = input.len() - remaining.trim_start().len();
+        Self {
+            offset: Some(offset),
+            ..Self::new(input)
+        }
+    }
+
+    /// Create an error for `input` from the error of a parser applied to it.
+    pub(crate) fn from_nom(input: &str, err: &nom::Err<VerboseError<&str>>) -> Self {
+        let errors = match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => &err.errors,
+            nom::Err::Incomplete(_) => {
+                return Self {
+                    offset: Some(input.len()),
+                    ..Self::new(input)
+                }
+            }
+        };
+
+        Self {
+            input: input.to_string(),
+            offset: errors
+                .first()
+                .map(|(remaining, _)| input.len() - remaining.len()),
+            context: errors
+                .iter()
+                .filter_map(|(_, kind)| match kind {
+                    VerboseErrorKind::Context(context) => Some(*context),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ParseError {
+    /// Get the stable code of the error, see [`SpdxExpressionError::error_code`].
+    pub fn error_code(&self) -> ErrorCode {
+        if self.input.trim().is_empty() {
+            return ErrorCode::EmptyExpression;
+        }
+        let Some(offset) = self.offset else {
+            return ErrorCode::InvalidSyntax;
+        };
+
+        match self.context.first().copied() {
+            Some(CLOSING_PARENTHESIS) => ErrorCode::UnbalancedParenthesis,
+            Some(EXCEPTION) => ErrorCode::MissingException,
+            Some(_) => ErrorCode::MissingOperand,
+            None if self.input[offset..].starts_with(')') => ErrorCode::UnbalancedParenthesis,
+            None => ErrorCode::UnexpectedText,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Parsing for expression `{}` failed", self.input)?;
+
+        let Some(offset) = self.offset else {
+            return write!(f, ".");
+        };
+        write!(f, " at byte {offset}")?;
+        if let Some(context) = self.context.first() {
+            return write!(f, ": expected {context}.");
+        }
+        match self.input.get(offset..).filter(|rest| !rest.is_empty()) {
+            Some(rest) => write!(f, ": unexpected `{rest}`."),
+            None => write!(f, ": unexpected end of input."),
+        }
+    }
+}
+
+/// Invalid step when building an expression with [`crate::ExpressionBuilder`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    #[error("`{0}` is not a valid license identifier.")]
+    InvalidLicense(String),
+
+    #[error("`{0}` is not a valid exception identifier.")]
+    InvalidException(String),
+
+    #[error("Exceptions can only be attached to a single license, not to `{0}`.")]
+    ExceptionOnCompound(String),
+
+    #[error("`{0}` already has an exception.")]
+    DuplicateException(String),
+
+    #[error("`{0}` can't be combined with other licenses or exceptions.")]
+    SpecialLicense(String),
+}
+
+impl BuildError {
+    /// Get the stable code of the error, see [`SpdxExpressionError::error_code`].
+    pub const fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidLicense(_) => ErrorCode::InvalidLicense,
+            Self::InvalidException(_) => ErrorCode::InvalidException,
+            Self::ExceptionOnCompound(_) => ErrorCode::ExceptionOnCompound,
+            Self::DuplicateException(_) => ErrorCode::DuplicateException,
+            Self::SpecialLicense(_) => ErrorCode::SpecialLicense,
+        }
+    }
 }
 
 impl From<nom::Err<nom::error::Error<&str>>> for SpdxExpressionError {
@@ -19,3 +364,48 @@ impl From<nom::Err<nom::error::Error<&str>>> for SpdxExpressionError {
         Self::Nom(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExpressionBuilder, SpdxExpression};
+
+    #[test]
+    fn parse_failures_have_codes() {
+        for (input, code) in [
+            ("", ErrorCode::EmptyExpression),
+            ("  ", ErrorCode::EmptyExpression),
+            ("(MIT OR ISC", ErrorCode::UnbalancedParenthesis),
+            ("MIT OR ISC)", ErrorCode::UnbalancedParenthesis),
+            ("MIT AND", ErrorCode::MissingOperand),
+            ("MIT AND ()", ErrorCode::MissingOperand),
+            ("MIT WITH", ErrorCode::UnexpectedText),
+            ("MIT WITH ", ErrorCode::MissingException),
+            ("MIT WITH $", ErrorCode::MissingException),
+            ("MIT ISC", ErrorCode::UnexpectedText),
+        ] {
+            assert_eq!(
+                SpdxExpression::parse(input).unwrap_err().error_code(),
+                code,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(ErrorCode::InvalidSyntax.code(), "E0100");
+        assert_eq!(ErrorCode::SpecialLicense.code(), "E0505");
+        assert_eq!(ErrorCode::BudgetExceeded.code(), "E0601");
+        assert_eq!(
+            ErrorCode::MissingException.to_string(),
+            "E0103 MissingException"
+        );
+        assert_eq!(
+            ExpressionBuilder::license("MIT AND")
+                .unwrap_err()
+                .error_code(),
+            ErrorCode::InvalidLicense
+        );
+    }
+}
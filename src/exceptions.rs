@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Permissions granted by common license exceptions.
+
+use std::collections::BTreeSet;
+
+use crate::{copyleft::Copyleft, expression_variant::SimpleExpression};
+
+/// Additional permission granted by a license exception, see
+/// [`SpdxExpression::effective_terms`].
+///
+/// [`SpdxExpression::effective_terms`]: crate::SpdxExpression::effective_terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ExceptionGrant {
+    /// The copyleft doesn't extend to code linked with the licensed code, e.g.
+    /// `Classpath-exception-2.0`.
+    LinkingPermitted,
+
+    /// The copyleft doesn't extend to programs compiled with a runtime library, e.g.
+    /// `GCC-exception-3.1`.
+    RuntimeLibrary,
+
+    /// The copyleft doesn't extend to documents embedding the licensed fonts, e.g.
+    /// `Font-exception-2.0`.
+    FontEmbedding,
+
+    /// The copyleft doesn't extend to the output generated by the licensed tool, e.g.
+    /// `Bison-exception-2.2`.
+    GeneratedOutput,
+}
+
+use ExceptionGrant::{FontEmbedding, GeneratedOutput, LinkingPermitted, RuntimeLibrary};
+
+/// Grants of common exceptions.
+pub const GRANTS: &[(&str, &[ExceptionGrant])] = &[
+    ("Autoconf-exception-2.0", &[GeneratedOutput]),
+    ("Autoconf-exception-3.0", &[GeneratedOutput]),
+    ("Bison-exception-2.2", &[GeneratedOutput]),
+    ("Classpath-exception-2.0", &[LinkingPermitted]),
+    ("eCos-exception-2.0", &[LinkingPermitted]),
+    ("Font-exception-2.0", &[FontEmbedding]),
+    ("GCC-exception-2.0", &[RuntimeLibrary]),
+    ("GCC-exception-3.1", &[RuntimeLibrary]),
+    ("LGPL-3.0-linking-exception", &[LinkingPermitted]),
+    ("Linux-syscall-note", &[LinkingPermitted]),
+    ("LLVM-exception", &[LinkingPermitted]),
+    ("OpenJDK-assembly-exception-1.0", &[LinkingPermitted]),
+    ("Qt-LGPL-exception-1.1", &[LinkingPermitted]),
+    ("WxWindows-exception-3.1", &[LinkingPermitted]),
+];
+
+impl ExceptionGrant {
+    /// Get the grants of the exception `identifier`, or `None` if the exception is not known.
+    /// Identifiers are compared case-insensitively.
+    pub fn of_exception(identifier: &str) -> Option<&'static [Self]> {
+        GRANTS
+            .iter()
+            .find(|(exception, _)| exception.eq_ignore_ascii_case(identifier))
+            .map(|(_, grants)| *grants)
+    }
+}
+
+/// License of an expression with the permissions granted by its exception, see
+/// [`SpdxExpression::effective_terms`].
+///
+/// [`SpdxExpression::effective_terms`]: crate::SpdxExpression::effective_terms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveTerm {
+    pub license: SimpleExpression,
+
+    pub exception: Option<String>,
+
+    /// Permissions granted by the exception.
+    pub grants: BTreeSet<ExceptionGrant>,
+
+    /// `true` if the term has an exception whose grants are not known.
+    pub unknown_exception: bool,
+}
+
+impl EffectiveTerm {
+    pub(crate) fn new(license: &SimpleExpression, exception: Option<&str>) -> Self {
+        let grants = exception.and_then(ExceptionGrant::of_exception);
+        Self {
+            license: license.clone(),
+            exception: exception.map(ToString::to_string),
+            grants: grants.unwrap_or_default().iter().copied().collect(),
+            unknown_exception: exception.is_some() && grants.is_none(),
+        }
+    }
+
+    /// Get the copyleft category of the license with the exception applied, or `None` if the
+    /// license is not known. Exceptions permitting linking, runtime libraries or embedding
+    /// fonts limit a strong copyleft to the licensed code itself.
+    pub fn copyleft(&self) -> Option<Copyleft> {
+        if self.license.license_ref {
+            return None;
+        }
+
+        let copyleft = Copyleft::of_license(&self.license.identifier)?;
+        let limits_copyleft = self
+            .grants
+            .iter()
+            .any(|grant| matches!(grant, LinkingPermitted | RuntimeLibrary | FontEmbedding));
+
+        Some(if limits_copyleft {
+            copyleft.min(Copyleft::Weak)
+        } else {
+            copyleft
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(license: &str, exception: Option<&str>) -> EffectiveTerm {
+        EffectiveTerm::new(&SimpleExpression::parse(license).unwrap(), exception)
+    }
+
+    #[test]
+    fn exceptions_limit_copyleft() {
+        let classpath = term("GPL-2.0-only", Some("classpath-exception-2.0"));
+        assert_eq!(classpath.grants, BTreeSet::from([LinkingPermitted]));
+        assert_eq!(classpath.copyleft(), Some(Copyleft::Weak));
+
+        assert_eq!(
+            term("GPL-2.0-only", None).copyleft(),
+            Some(Copyleft::Strong)
+        );
+        assert_eq!(
+            term("GPL-3.0-or-later", Some("Bison-exception-2.2")).copyleft(),
+            Some(Copyleft::Strong)
+        );
+        assert_eq!(
+            term("MIT", Some("LLVM-exception")).copyleft(),
+            Some(Copyleft::Permissive)
+        );
+    }
+
+    #[test]
+    fn unknown_exceptions_are_flagged() {
+        let unknown = term("GPL-2.0-only", Some("custom-exception"));
+        assert!(unknown.unknown_exception);
+        assert!(unknown.grants.is_empty());
+        assert_eq!(unknown.copyleft(), Some(Copyleft::Strong));
+
+        assert!(!term("MIT", None).unknown_exception);
+        assert_eq!(term("LicenseRef-MIT", None).copyleft(), None);
+    }
+}
@@ -4,23 +4,145 @@
 
 //! The main struct of the library.
 
-use std::{collections::HashSet, fmt::Display, string::ToString};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    hash::{Hash, Hasher},
+    io::BufRead,
+    ops::Range,
+    str::FromStr,
+    string::ToString,
+    sync::{Arc, OnceLock},
+};
 
 use serde::{de::Visitor, Deserialize, Serialize};
 
 use crate::{
+    alias::AliasTable,
+    binary, canonical, cargo, compare,
+    compatibility::{self, CompatibilityTrace},
+    copyleft::{self, Copyleft},
+    cursor::Cursor,
+    diff::{self, Change},
+    digest, dot,
     error::SpdxExpressionError,
-    expression_variant::{ExpressionVariant, SimpleExpression},
+    expression_variant::{ExpressionVariant, Pruned, SimpleExpression, WithExpression},
+    format::{FormatOptions, Formatted},
+    html::{self, HtmlOptions},
+    incremental::TextEdit,
+    index::LicenseIndex,
+    iter::{Leaves, Operators},
+    lint::{self, Lint},
+    markdown, mermaid,
+    options_report::OptionsReport,
+    parser::{self, Operator},
+    parser_options::ParserOptions,
+    postfix::{self, PostfixToken},
+    preference::{self, LicenseWeights},
+    pretty::{self, PrettyOptions},
+    prose,
+    rewrite::RewriteRules,
+    sexpr,
+    simplify::{self, SimplifyOptions},
+    sort,
+    span::{self, NodeSpan},
+    special::{self, SpecialLicenses},
+    stream::ParseMany,
+    structure::{self, StructureKind},
+    trove,
+    versions::{self, LicenseVersions},
+    visit::VisitorMut,
+};
+
+#[cfg(feature = "sat")]
+use crate::sat;
+#[cfg(feature = "data")]
+use crate::{
+    exceptions::EffectiveTerm,
+    obligations::{self, Obligations},
 };
 
 /// Main struct for SPDX License Expressions.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct SpdxExpression {
     /// The parsed expression.
     inner: ExpressionVariant,
+
+    /// Hash of `inner`, computed on first use. Needs to be reset whenever `inner` is modified.
+    hash: OnceLock<u64>,
+
+    /// Data only some expressions have, allocated on first use to keep `SpdxExpression` small.
+    /// Needs to be reset whenever `inner` is modified.
+    extras: OnceLock<Box<Extras>>,
+}
+
+/// Data of an [`SpdxExpression`] that is only kept or computed on request.
+#[derive(Clone, Default)]
+struct Extras {
+    /// Locations of the licenses in `inner`, only built on request.
+    index: OnceLock<Arc<LicenseIndex>>,
+
+    /// Source text displayed instead of `inner`, see [`SpdxExpression::parse_preserving`].
+    source: Option<Arc<str>>,
+
+    /// Locations of the nodes of `inner` in `source`.
+    spans: Option<Arc<NodeSpan>>,
+
+    /// Simplified `inner`, computed on first use.
+    simplified: OnceLock<Arc<ExpressionVariant>>,
+
+    /// Canonical key of `inner`, computed on first use.
+    canonical_key: OnceLock<Arc<str>>,
 }
 
 impl SpdxExpression {
+    /// Create `Self` from a parsed expression.
+    pub(crate) const fn from_inner(inner: ExpressionVariant) -> Self {
+        Self {
+            inner,
+            hash: OnceLock::new(),
+            extras: OnceLock::new(),
+        }
+    }
+
+    /// Create `Self` from an expression parsed from `source`, keeping the source and the
+    /// locations of the nodes in it.
+    fn from_source(inner: ExpressionVariant, source: &str) -> Self {
+        let extras = Extras {
+            source: Some(source.into()),
+            spans: span::locate(&inner, source).map(Arc::new),
+            ..Extras::default()
+        };
+        Self {
+            extras: OnceLock::from(Box::new(extras)),
+            ..Self::from_inner(inner)
+        }
+    }
+
+    /// Get the extras of `Self`, allocating them on first use.
+    fn extras(&self) -> &Extras {
+        self.extras.get_or_init(Box::default)
+    }
+
+    /// Get the license index of `Self` if it has been built.
+    fn index(&self) -> Option<&LicenseIndex> {
+        self.extras.get()?.index.get().map(Arc::as_ref)
+    }
+
+    /// Get the parsed expression.
+    pub(crate) const fn inner(&self) -> &ExpressionVariant {
+        &self.inner
+    }
+
+    /// Get the hash of the expression tree, computing it only on first call.
+    fn tree_hash(&self) -> u64 {
+        *self.hash.get_or_init(|| {
+            let mut hasher = DefaultHasher::new();
+            self.inner.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+
     /// Parse `Self` from a string. The input expression needs to be a syntactically valid SPDX
     /// expression, `NONE` or `NOASSERTION`. The parser accepts license identifiers that are not
     /// valid SPDX.
@@ -50,111 +172,2633 @@ impl SpdxExpression {
     ///
     /// Returns `SpdxExpressionError` if the license expression is not syntactically valid.
     pub fn parse(expression: &str) -> Result<Self, SpdxExpressionError> {
-        Ok(Self {
-            inner: ExpressionVariant::parse(expression)
-                .map_err(|err| SpdxExpressionError::Parse(err.to_string()))?,
-        })
+        Ok(Self::from_inner(ExpressionVariant::parse(expression)?))
     }
 
-    /// Get all license and exception identifiers from the `SpdxExpression`.
+    /// Parse `Self` from a string like [`SpdxExpression::parse`], with the limits of `options`.
+    /// Use this to bound the work spent on untrusted input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use spdx_expression::{ErrorCode, ParserOptions, SpdxExpression, SpdxExpressionError};
+    /// #
+    /// let options = ParserOptions {
+    ///     max_steps: Some(100),
+    ///     ..ParserOptions::default()
+    /// };
+    /// let expression = SpdxExpression::parse_with("MIT OR Apache-2.0", &options)?;
+    ///
+    /// let nested = format!("{}MIT{}", "(".repeat(200), ")".repeat(200));
+    /// let error = SpdxExpression::parse_with(&nested, &options).unwrap_err();
+    /// assert_eq!(error.error_code(), ErrorCode::BudgetExceeded);
+    ///
+    /// let options = ParserOptions {
+    ///     timeout: Some(Duration::ZERO),
+    ///     ..ParserOptions::default()
+    /// };
+    /// assert!(SpdxExpression::parse_with("MIT", &options).is_err());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the license expression is not syntactically valid, or
+    /// [`SpdxExpressionError::Budget`] if parsing exceeds the limits of `options`.
+    pub fn parse_with(
+        expression: &str,
+        options: &ParserOptions,
+    ) -> Result<Self, SpdxExpressionError> {
+        match ExpressionVariant::parse_with(expression, options) {
+            Ok(inner) => {
+                let parsed = Self::from_inner(inner);
+                Ok(match &options.aliases {
+                    Some(aliases) => parsed.translate_aliases(aliases),
+                    None => parsed,
+                })
+            }
+            Err(SpdxExpressionError::Parse(error)) => options.aliases.as_ref().map_or_else(
+                || Err(SpdxExpressionError::Parse(error)),
+                |aliases| aliases.translate_lenient(expression),
+            ),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Parse `Self` from a string like [`SpdxExpression::parse`], and build an index of the
+    /// locations of the licenses in the expression. With the index, license queries like
+    /// [`SpdxExpression::contains_license`] and [`SpdxExpression::licenses`] don't need to
+    /// traverse the expression, which is useful when the same expression is analyzed repeatedly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse_indexed("MIT OR Apache-2.0 AND ISC")?;
+    /// assert!(expression.contains_license("ISC"));
+    /// assert_eq!(expression, SpdxExpression::parse("MIT OR Apache-2.0 AND ISC")?);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the license expression is not syntactically valid.
+    pub fn parse_indexed(expression: &str) -> Result<Self, SpdxExpressionError> {
+        let expression = Self::parse(expression)?;
+        expression
+            .extras()
+            .index
+            .get_or_init(|| Arc::new(LicenseIndex::build(&expression.inner)));
+        Ok(expression)
+    }
+
+    /// Parse `Self` from a string like [`SpdxExpression::parse`], keeping the string so that
+    /// `Self` is displayed exactly as it was written, including whitespace, the case of the
+    /// operators and redundant parentheses. Used by tools that edit files in place and shouldn't
+    /// change expressions they don't modify.
+    ///
+    /// The source is only kept while the expression is unchanged. Methods returning modified
+    /// expressions, and [`SpdxExpression::walk_mut`], drop it, and the modified expressions are
+    /// displayed in the normal form. The source doesn't affect comparisons or hashing. The
+    /// locations of the nodes in the source are recorded too, see [`SpdxExpression::spans`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse_preserving("mit  or ((Apache-2.0))")?;
+    /// assert_eq!(expression.to_string(), "mit  or ((Apache-2.0))");
+    /// assert_eq!(expression, SpdxExpression::parse("mit OR ((Apache-2.0))")?);
+    ///
+    /// let modified = expression.substitute("mit", &SpdxExpression::parse("MIT")?);
+    /// assert_eq!(modified.to_string(), "MIT OR ((Apache-2.0))");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the license expression is not syntactically valid.
+    pub fn parse_preserving(expression: &str) -> Result<Self, SpdxExpressionError> {
+        Ok(Self::from_source(
+            ExpressionVariant::parse(expression)?,
+            expression,
+        ))
+    }
+
+    /// Get the source text `Self` was parsed from with [`SpdxExpression::parse_preserving`], or
+    /// `None` if it was parsed otherwise or has been modified.
+    pub fn source(&self) -> Option<&str> {
+        self.extras.get()?.source.as_deref()
+    }
+
+    /// Get the locations of the nodes of `Self` in its [source](SpdxExpression::source), or
+    /// `None` if `Self` was not parsed with [`SpdxExpression::parse_preserving`] or has been
+    /// modified. The locations are only recorded when the source is kept, so other parsing
+    /// doesn't pay for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let source = "MIT or (Apache-2.0 WITH LLVM-exception)";
+    /// let expression = SpdxExpression::parse_preserving(source)?;
+    ///
+    /// let spans = expression.spans().unwrap();
+    /// assert_eq!(&source[spans.operator.clone().unwrap()], "or");
+    /// assert_eq!(&source[spans.children[1].range.clone()], "(Apache-2.0 WITH LLVM-exception)");
+    ///
+    /// for (path, (license, _)) in expression.leaves().with_paths() {
+    ///     let range = expression.span(&path).unwrap();
+    ///     assert!(source[range].starts_with(&license.identifier));
+    /// }
+    ///
+    /// assert_eq!(expression.simplify().spans(), None);
+    /// assert_eq!(SpdxExpression::parse(source)?.spans(), None);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn spans(&self) -> Option<&NodeSpan> {
+        self.extras.get()?.spans.as_deref()
+    }
+
+    /// Get the byte range of the node at `path` in the text `Self` was parsed from, see
+    /// [`SpdxExpression::spans`] and [`SpdxExpression::get`].
+    pub fn span(&self, path: &[usize]) -> Option<Range<usize>> {
+        Some(self.spans()?.get(path)?.range.clone())
+    }
+
+    /// Parse `Self` from the license field of a Cargo package. Older packages separate
+    /// alternative licenses with `/` instead of `OR`, so `/` is accepted as `OR`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse_cargo("MIT/Apache-2.0")?;
+    /// assert_eq!(expression, SpdxExpression::parse("MIT OR Apache-2.0")?);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the license field is not syntactically valid.
+    pub fn parse_cargo(license: &str) -> Result<Self, SpdxExpressionError> {
+        Self::parse(&cargo::normalize(license))
+    }
+
+    /// Parse `Self` from a string that may use aliases from `aliases` in place of license
+    /// identifiers, e.g. `ASL 2.0 or MIT` with [`AliasTable::fedora`]. Operators may be in any
+    /// case. Licenses that are aliases are translated also when the string is a valid SPDX
+    /// expression, e.g. `Expat` with [`AliasTable::debian`], and other licenses are kept as they
+    /// are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{AliasTable, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let mut aliases = AliasTable::new();
+    /// aliases.insert("Acme EULA", SpdxExpression::parse("LicenseRef-Acme-EULA")?);
+    ///
+    /// let expression = SpdxExpression::parse_lenient("MIT and Acme EULA", &aliases)?;
+    /// assert_eq!(expression.to_string(), "MIT AND LicenseRef-Acme-EULA");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the string contains names that are neither aliases nor
+    /// license identifiers, or is otherwise not a valid expression.
+    pub fn parse_lenient(
+        expression: &str,
+        aliases: &AliasTable,
+    ) -> Result<Self, SpdxExpressionError> {
+        Self::parse(expression).map_or_else(
+            |_| aliases.translate_lenient(expression),
+            |parsed| Ok(parsed.translate_aliases(aliases)),
+        )
+    }
+
+    /// Lazily parse expressions separated by newlines or commas from a reader. Each item of the
+    /// returned iterator is the result of parsing one expression, so a single invalid entry
+    /// doesn't stop the iteration. Use [`std::io::BufReader`] to read from an [`std::io::Read`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let input = "MIT OR Apache-2.0\nISC, BSD-3-Clause\n";
+    /// let expressions = SpdxExpression::parse_many(input.as_bytes())
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(expressions.len(), 3);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub const fn parse_many<R: BufRead>(reader: R) -> ParseMany<R> {
+        ParseMany::new(reader)
+    }
+
+    /// Encode `Self` in a compact binary format. The result can be turned back into an
+    /// expression with [`SpdxExpression::from_bytes`], which is considerably faster than parsing
+    /// the expression again. The format is versioned and stays stable across crate versions, so
+    /// it's suitable for caching parsed expressions between runs.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use std::collections::HashSet;
-    /// # use std::iter::FromIterator;
     /// # use spdx_expression::SpdxExpression;
     /// # use spdx_expression::SpdxExpressionError;
     /// #
     /// let expression = SpdxExpression::parse("MIT OR Apache-2.0")?;
-    /// let licenses = expression.identifiers();
-    /// assert_eq!(licenses, HashSet::from_iter(["Apache-2.0".to_string(), "MIT".to_string()]));
+    /// let bytes = expression.to_bytes();
+    /// assert_eq!(SpdxExpression::from_bytes(&bytes)?, expression);
     /// # Ok::<(), SpdxExpressionError>(())
     /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(&self.inner)
+    }
+
+    /// Decode an expression encoded with [`SpdxExpression::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the input is not a valid encoded expression.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SpdxExpressionError> {
+        Ok(Self::from_inner(binary::decode(bytes)?))
+    }
+
+    /// Render `Self` as a Graphviz DOT graph. Operators are rendered as box nodes with their
+    /// operands as children, and licenses as the leaves of the graph. Operands joined with the
+    /// same operator are children of a single operator node, regardless of parentheses.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// # use std::collections::HashSet;
-    /// # use std::iter::FromIterator;
     /// # use spdx_expression::SpdxExpression;
     /// # use spdx_expression::SpdxExpressionError;
     /// #
-    /// let expression = SpdxExpression::parse("MIT OR GPL-2.0-only WITH Classpath-exception-2.0")?;
-    /// let licenses = expression.identifiers();
+    /// let expression = SpdxExpression::parse("MIT OR ISC")?;
     /// assert_eq!(
-    ///     licenses,
-    ///     HashSet::from_iter([
-    ///         "MIT".to_string(),
-    ///         "GPL-2.0-only".to_string(),
-    ///         "Classpath-exception-2.0".to_string()
-    ///     ])
+    ///     expression.to_dot(),
+    ///     r#"digraph expression {
+    ///     n0 [label="OR", shape=box];
+    ///     n1 [label="MIT", shape=ellipse];
+    ///     n0 -> n1;
+    ///     n2 [label="ISC", shape=ellipse];
+    ///     n0 -> n2;
+    /// }
+    /// "#
     /// );
     /// # Ok::<(), SpdxExpressionError>(())
     /// ```
-    pub fn identifiers(&self) -> HashSet<String> {
-        let mut identifiers = self
-            .licenses()
-            .iter()
-            .map(ToString::to_string)
-            .collect::<HashSet<_>>();
-
-        identifiers.extend(self.exceptions().iter().map(ToString::to_string));
+    pub fn to_dot(&self) -> String {
+        dot::render(self.inner())
+    }
 
-        identifiers
+    /// Render `Self` as HTML, with the licenses and exceptions linked to their pages on the SPDX
+    /// License List and `LicenseRef`s linked as configured in `options`. The structure and
+    /// parentheses of the expression are kept as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{HtmlOptions, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR LicenseRef-custom")?;
+    /// assert_eq!(
+    ///     expression.to_html(&HtmlOptions::default()),
+    ///     "<a href=\"https://spdx.org/licenses/MIT.html\">MIT</a> OR \
+    ///      <a href=\"#LicenseRef-custom\">LicenseRef-custom</a>"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        html::render(self.inner(), options)
     }
 
-    /// Get all simple license expressions in `Self`. For licenses with exceptions, returns the
-    /// license without the exception
+    /// Render `Self` as a Mermaid flowchart, e.g. for embedding in Markdown documents. Operators
+    /// are rendered as rhombus nodes with their operands as children, licenses as rectangles and
+    /// exceptions as rounded nodes. Operands joined with the same operator are children of a
+    /// single operator node, regardless of parentheses.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use std::collections::HashSet;
-    /// # use std::iter::FromIterator;
     /// # use spdx_expression::SpdxExpression;
     /// # use spdx_expression::SpdxExpressionError;
     /// #
+    /// let expression = SpdxExpression::parse("MIT OR ISC")?;
+    /// assert_eq!(
+    ///     expression.to_mermaid(),
+    ///     r#"flowchart TD
+    ///     n0{"OR"}
+    ///     n1["MIT"]
+    ///     n0 --> n1
+    ///     n2["ISC"]
+    ///     n0 --> n2
+    /// "#
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        mermaid::render(self.inner())
+    }
+
+    /// Find redundant parentheses and style issues in `Self`, like lowercase operators,
+    /// duplicate operands and a mix of `+` and `-or-later` licenses. The ranges of the findings
+    /// refer to the text of `Self`, i.e. the source if it was parsed with
+    /// [`SpdxExpression::parse_preserving`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LintKind, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse_preserving("(MIT) or ISC")?;
+    /// let lints = expression.lint();
+    /// assert_eq!(lints[0].kind, LintKind::RedundantParentheses);
+    /// assert_eq!((lints[0].range.clone(), lints[0].replacement.as_str()), (0..5, "MIT"));
+    /// assert_eq!(lints[1].kind, LintKind::LowercaseOperator);
+    /// assert_eq!((lints[1].range.clone(), lints[1].replacement.as_str()), (6..8, "OR"));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn lint(&self) -> Vec<Lint> {
+        lint::lint(self.inner(), &self.to_string())
+    }
+
+    /// Apply the fixes suggested by [`SpdxExpression::lint`] until there are no lints left.
+    /// Returns the fixed expression, which keeps its source like expressions parsed with
+    /// [`SpdxExpression::parse_preserving`], and the edits applied to the text of `Self`. The
+    /// edits are in the order they were applied, each to the text resulting from the previous
+    /// edits.
+    ///
+    /// The fixes don't change the meaning of the expression, apart from replacing licenses with
+    /// a `+` with the equivalent `-or-later` identifiers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SpdxExpression, TextEdit};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse_preserving("(MIT)  or ISC")?;
+    /// let (fixed, edits) = expression.autofix();
+    /// assert_eq!(fixed.to_string(), "MIT  OR ISC");
+    /// assert_eq!(
+    ///     edits,
+    ///     [
+    ///         TextEdit::new(7..9, "OR".to_string()),
+    ///         TextEdit::new(0..5, "MIT".to_string()),
+    ///     ]
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn autofix(&self) -> (Self, Vec<TextEdit>) {
+        let (fixed, source, edits) = lint::autofix(self.inner(), &self.to_string());
+        (Self::from_source(fixed, &source), edits)
+    }
+
+    /// Display `Self` in the style configured with `options`, e.g. with lowercase operators.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{FormatOptions, OperatorCase, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR (Apache-2.0 WITH LLVM-exception)")?;
+    /// let options = FormatOptions {
+    ///     operator_case: OperatorCase::Lower,
+    ///     ..FormatOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     expression.display_with(&options).to_string(),
+    ///     "MIT or (Apache-2.0 with LLVM-exception)"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub const fn display_with<'a>(&'a self, options: &'a FormatOptions) -> Formatted<'a> {
+        Formatted::new(self.inner(), options)
+    }
+
+    /// Render `Self` on multiple lines with indentation reflecting the nesting, breaking only the
+    /// parts that don't fit on a line of `options.width`. Each operand of a broken sequence of
+    /// `AND` or `OR` starts a line beginning with the operator, and the contents of broken
+    /// parentheses are indented. The structure and parentheses of the expression are kept as
+    /// they are, so the result parses to the same expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{PrettyOptions, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
     /// let expression = SpdxExpression::parse(
-    ///     "(MIT OR Apache-2.0 AND (GPL-2.0-only WITH Classpath-exception-2.0 OR ISC))",
+    ///     "MIT OR (Apache-2.0 AND BSD-3-Clause AND ISC) OR GPL-2.0-or-later",
+    /// )?;
+    /// let options = PrettyOptions {
+    ///     width: 30,
+    ///     ..PrettyOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     expression.to_pretty(&options),
+    ///     "\
+    /// MIT
+    /// OR (
+    ///   Apache-2.0
+    ///   AND BSD-3-Clause
+    ///   AND ISC
     /// )
-    /// .unwrap();
+    /// OR GPL-2.0-or-later"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn to_pretty(&self, options: &PrettyOptions) -> String {
+        pretty::render(self.inner(), options)
+    }
+
+    /// Render `Self` as an s-expression, e.g. `(or MIT (with GPL-2.0-only exception))`.
+    /// Operands joined with the same operator are flattened into a single list, regardless of
+    /// parentheses.
     ///
-    /// let licenses = expression.licenses();
+    /// # Examples
     ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse(
+    ///     "MIT OR GPL-2.0-only AND LGPL-2.1 WITH exception OR ISC",
+    /// )?;
     /// assert_eq!(
-    ///     licenses
-    ///         .iter()
-    ///         .map(|&license| license.identifier.as_str())
-    ///         .collect::<HashSet<_>>(),
-    ///     HashSet::from_iter(["Apache-2.0", "GPL-2.0-only", "ISC", "MIT"])
+    ///     expression.to_sexpr(),
+    ///     "(or MIT (and GPL-2.0-only (with LGPL-2.1 exception)) ISC)"
     /// );
     /// # Ok::<(), SpdxExpressionError>(())
     /// ```
-    pub fn licenses(&self) -> HashSet<&SimpleExpression> {
-        self.inner.licenses()
+    pub fn to_sexpr(&self) -> String {
+        sexpr::render(self.inner())
     }
 
-    /// Get all exception identifiers for `Self`.
+    /// Parse `Self` from an s-expression rendered with [`SpdxExpression::to_sexpr`]. The
+    /// operators `and` and `or` take two or more operands, and `with` takes a license and an
+    /// exception. Operators are case-insensitive.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use std::collections::HashSet;
-    /// # use std::iter::FromIterator;
     /// # use spdx_expression::SpdxExpression;
     /// # use spdx_expression::SpdxExpressionError;
     /// #
-    /// let expression = SpdxExpression::parse(
-    ///     "(MIT OR Apache-2.0 AND (GPL-2.0-only WITH Classpath-exception-2.0 OR ISC))",
-    /// )
-    /// .unwrap();
+    /// let expression = SpdxExpression::parse_sexpr("(and (or MIT ISC) Apache-2.0)")?;
+    /// assert_eq!(expression.to_string(), "(MIT OR ISC) AND Apache-2.0");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError` if the input is not a valid s-expression of a license
+    /// expression.
+    pub fn parse_sexpr(expression: &str) -> Result<Self, SpdxExpressionError> {
+        sexpr::parse(expression).map(Self::from_inner)
+    }
+
+    /// Get all license and exception identifiers from the `SpdxExpression`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use std::iter::FromIterator;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR Apache-2.0")?;
+    /// let licenses = expression.identifiers();
+    /// assert_eq!(licenses, HashSet::from_iter(["Apache-2.0".to_string(), "MIT".to_string()]));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use std::iter::FromIterator;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR GPL-2.0-only WITH Classpath-exception-2.0")?;
+    /// let licenses = expression.identifiers();
+    /// assert_eq!(
+    ///     licenses,
+    ///     HashSet::from_iter([
+    ///         "MIT".to_string(),
+    ///         "GPL-2.0-only".to_string(),
+    ///         "Classpath-exception-2.0".to_string()
+    ///     ])
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn identifiers(&self) -> HashSet<String> {
+        let mut identifiers = self
+            .licenses()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<HashSet<_>>();
+
+        identifiers.extend(self.exceptions().iter().map(ToString::to_string));
+
+        identifiers
+    }
+
+    /// Check whether `Self` contains the license `identifier`, either on its own or with an
+    /// exception. The identifier needs to include possible `LicenseRef-` and `DocumentRef-`
+    /// prefixes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR GPL-2.0-only WITH Classpath-exception-2.0")?;
+    /// assert!(expression.contains_license("GPL-2.0-only"));
+    /// assert!(!expression.contains_license("Classpath-exception-2.0"));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn contains_license(&self, identifier: &str) -> bool {
+        SimpleExpression::parse(identifier).is_ok_and(|license| {
+            self.index().map_or_else(
+                || self.inner.contains_license(&license),
+                |index| index.contains(&license),
+            )
+        })
+    }
+
+    /// Iterate over the licenses of `Self` with their optional exceptions, in the order they
+    /// appear in the expression. Unlike [`SpdxExpression::licenses`], licenses that appear more
+    /// than once are returned every time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (ISC OR MIT WITH exc)")?;
+    /// let leaves = expression
+    ///     .leaves()
+    ///     .map(|(license, exception)| (license.identifier.as_str(), exception))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(leaves, [("MIT", None), ("ISC", None), ("MIT", Some("exc"))]);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn leaves(&self) -> Leaves<'_> {
+        Leaves::new(&self.inner)
+    }
+
+    /// Iterate over the operators of `Self` with the number of their operands. Operands of the
+    /// same operator are flattened, and parentheses are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Operator, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (ISC OR 0BSD) AND Zlib")?;
+    /// let operators = expression.operators().collect::<Vec<_>>();
+    /// assert_eq!(operators, [(Operator::And, 3), (Operator::Or, 2)]);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn operators(&self) -> Operators<'_> {
+        Operators::new(&self.inner)
+    }
+
+    /// Get the part of `Self` at `path`, a sequence of child indices from the root of the
+    /// expression. The operands of `AND` and `OR` are the children 0 and 1, and the expression in
+    /// parentheses is the child 0, like in [`Change`] and [`Cursor::path`]. Paths of the leaves
+    /// and operators are available from [`Leaves::with_paths`] and [`Operators::with_paths`].
+    /// Returns `None` if there's no part at `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR (ISC AND 0BSD)")?;
+    /// assert_eq!(expression.get(&[1, 0]).unwrap().to_string(), "ISC AND 0BSD");
+    /// assert_eq!(expression.get(&[1, 0, 1]).unwrap().to_string(), "0BSD");
+    /// assert!(expression.get(&[0, 0]).is_none());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn get(&self, path: &[usize]) -> Option<Self> {
+        self.inner
+            .get(path)
+            .map(|node| Self::from_inner(node.clone()))
+    }
+
+    /// Replace the part of `Self` at `path` with `replacement`, see [`SpdxExpression::get`] for
+    /// the paths. The replacement is parenthesized where needed to keep its structure. Returns
+    /// `None` if there's no part at `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND ISC")?;
+    /// let replaced = expression.replace_at(&[1], &SpdxExpression::parse("0BSD OR Zlib")?);
+    /// assert_eq!(replaced.unwrap().to_string(), "MIT AND (0BSD OR Zlib)");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn replace_at(&self, path: &[usize], replacement: &Self) -> Option<Self> {
+        self.inner
+            .replace_at(path, replacement.inner.unparenthesized().clone())
+            .map(Self::from_inner)
+    }
+
+    /// Classify the overall structure of `Self` as a single term, a choice between terms, a
+    /// conjunction of terms or a mix of `AND` and `OR`. A term is a license with an optional
+    /// exception.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SpdxExpression, StructureKind};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR Apache-2.0 OR GPL-2.0-only WITH exc")?;
+    /// assert_eq!(expression.structure_kind(), StructureKind::Choice);
+    ///
+    /// let expression = SpdxExpression::parse("MIT AND (Apache-2.0 OR ISC)")?;
+    /// assert_eq!(expression.structure_kind(), StructureKind::Mixed);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn structure_kind(&self) -> StructureKind {
+        structure::structure_kind(&self.inner)
+    }
+
+    /// Get the two alternatives if `Self` is a choice between exactly two different terms, like
+    /// `MIT OR Apache-2.0` or `(GPL-2.0-only WITH Classpath-exception-2.0 OR MIT)`. A term is a
+    /// license with an optional exception.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("(MIT OR Apache-2.0)")?;
+    /// let (first, second) = expression.dual_license_pair().unwrap();
+    /// assert_eq!((first.to_string(), second.to_string()), ("MIT".into(), "Apache-2.0".into()));
+    ///
+    /// assert!(SpdxExpression::parse("MIT OR ISC OR 0BSD")?.dual_license_pair().is_none());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn dual_license_pair(&self) -> Option<(Self, Self)> {
+        match self.inner.flatten()? {
+            (Operator::Or, operands) => match operands.as_slice() {
+                [first, second]
+                    if first.flatten().is_none()
+                        && second.flatten().is_none()
+                        && first != second =>
+                {
+                    Some((
+                        Self::from_inner((*first).clone()),
+                        Self::from_inner((*second).clone()),
+                    ))
+                }
+                _ => None,
+            },
+            (Operator::And, _) => None,
+        }
+    }
+
+    /// Check if `Self` is a choice between exactly two different terms, see
+    /// [`SpdxExpression::dual_license_pair`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// assert!(SpdxExpression::parse("MIT OR Apache-2.0")?.is_pure_dual_license());
+    /// assert!(!SpdxExpression::parse("MIT OR (Apache-2.0 AND ISC)")?.is_pure_dual_license());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn is_pure_dual_license(&self) -> bool {
+        self.dual_license_pair().is_some()
+    }
+
+    /// Get the license if `Self` is a single license without an exception, e.g. `MIT` or
+    /// `(LicenseRef-custom)`. Most packages have a single license, so this is a cheap way to
+    /// handle them without collecting the licenses of the expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT")?;
+    /// assert_eq!(expression.is_single_license().unwrap().identifier, "MIT");
+    ///
+    /// assert!(SpdxExpression::parse("MIT OR ISC")?.is_single_license().is_none());
+    /// assert!(SpdxExpression::parse("MIT WITH exc")?.is_single_license().is_none());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn is_single_license(&self) -> Option<&SimpleExpression> {
+        match self.inner.unparenthesized() {
+            ExpressionVariant::Simple(license) => Some(license),
+            _ => None,
+        }
+    }
+
+    /// Check whether `Self` contains any `LicenseRef`, including references to licenses in other
+    /// documents. A document with such expressions needs the extracted licensing info of the
+    /// referenced licenses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// assert!(SpdxExpression::parse("MIT OR LicenseRef-custom")?.has_license_ref());
+    /// assert!(!SpdxExpression::parse("MIT OR Apache-2.0")?.has_license_ref());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn has_license_ref(&self) -> bool {
+        self.licenses().iter().any(|license| license.license_ref)
+    }
+
+    /// Check whether `Self` contains any license from another document, i.e. a `LicenseRef` with a
+    /// `DocumentRef` prefix. A document with such expressions needs external document references
+    /// for the referenced documents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR DocumentRef-other:LicenseRef-custom")?;
+    /// assert!(expression.has_document_ref());
+    /// assert!(!SpdxExpression::parse("LicenseRef-custom")?.has_document_ref());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn has_document_ref(&self) -> bool {
+        self.licenses()
+            .iter()
+            .any(|license| license.document_ref.is_some())
+    }
+
+    /// Replace licenses in `Self` with the result of `f`. Licenses for which `f` returns `None` are
+    /// kept as is. Exceptions attached to replaced licenses are kept.
+    ///
+    /// Cloning an expression shares its structure with the original, and the result of this
+    /// function only copies the nodes on the paths to the replaced licenses. This keeps editing
+    /// large shared expressions cheap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SimpleExpression, SpdxExpression, SpdxExpressionError};
+    /// #
+    /// let expression = SpdxExpression::parse("GPL-2.0 WITH Classpath-exception-2.0 OR MIT")?;
+    /// let mapped = expression.map_licenses(|license| {
+    ///     (license.identifier == "GPL-2.0")
+    ///         .then(|| SimpleExpression::new("GPL-2.0-only".to_string(), None, false))
+    /// });
+    /// assert_eq!(
+    ///     mapped.to_string(),
+    ///     "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn map_licenses<F>(&self, mut f: F) -> Self
+    where
+        F: FnMut(&SimpleExpression) -> Option<SimpleExpression>,
+    {
+        self.inner
+            .map_licenses(&mut f)
+            .map_or_else(|| self.clone(), Self::from_inner)
+    }
+
+    /// Replace every occurrence of the license `target` in `Self` with `replacement`. The
+    /// identifier needs to include possible `LicenseRef-` and `DocumentRef-` prefixes.
+    /// Replacements with operators are parenthesized where needed to keep their structure.
+    ///
+    /// If `target` has an exception, the exception is attached to every license of the
+    /// replacement. Licenses in the replacement that already have an exception keep their own.
+    ///
+    /// Expressions parsed with [`SpdxExpression::parse_indexed`] only visit the occurrences of
+    /// `target` instead of the whole expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND LicenseRef-unknown-gpl")?;
+    /// let replacement = SpdxExpression::parse("GPL-2.0-only OR GPL-3.0-only")?;
+    /// assert_eq!(
+    ///     expression
+    ///         .substitute("LicenseRef-unknown-gpl", &replacement)
+    ///         .to_string(),
+    ///     "MIT AND (GPL-2.0-only OR GPL-3.0-only)"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn substitute(&self, target: &str, replacement: &Self) -> Self {
+        let Ok(target) = SimpleExpression::parse(target) else {
+            return self.clone();
+        };
+        let replacement = replacement.inner.unparenthesized();
+        let substitute = |leaf: &ExpressionVariant| match leaf {
+            ExpressionVariant::Simple(license) if *license == target => Some(replacement.clone()),
+            ExpressionVariant::With(with) if with.license == target => {
+                Some(replacement.with_exception(&with.exception))
+            }
+            _ => None,
+        };
+
+        let Some(index) = self.index() else {
+            return self.map_leaves(substitute);
+        };
+        let paths = index.paths_of(&target);
+        if paths.is_empty() {
+            return self.clone();
+        }
+
+        // Replacing a leaf doesn't move the other leaves, so the paths stay valid.
+        let mut substituted = self.inner.clone();
+        for path in paths {
+            let replaced = substituted
+                .get(path)
+                .and_then(substitute)
+                .map(|leaf| {
+                    if path.is_empty() {
+                        leaf
+                    } else {
+                        leaf.parenthesized()
+                    }
+                })
+                .and_then(|leaf| substituted.replace_at(path, leaf));
+            match replaced {
+                Some(replaced) => substituted = replaced,
+                None => return self.map_leaves(substitute),
+            }
+        }
+        Self::from_inner(substituted)
+    }
+
+    /// Remove every occurrence of the license `identifier` from `Self`, with or without an
+    /// exception. The identifier needs to include possible `LicenseRef-` and `DocumentRef-`
+    /// prefixes. Removing an operand of `AND` or `OR` leaves just the other operand in place of
+    /// the operator, so removing an alternative drops the branch and removing one of two
+    /// required licenses leaves the other. Returns `Self` unchanged if it doesn't contain the
+    /// license.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (ISC OR GPL-2.0-only)")?;
+    /// assert_eq!(
+    ///     expression.remove_license("GPL-2.0-only")?.to_string(),
+    ///     "MIT AND ISC"
+    /// );
+    /// assert!(SpdxExpression::parse("MIT")?.remove_license("MIT").is_err());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Empty` if `Self` contains no other licenses.
+    pub fn remove_license(&self, identifier: &str) -> Result<Self, SpdxExpressionError> {
+        let Ok(license) = SimpleExpression::parse(identifier) else {
+            return Ok(self.clone());
+        };
+
+        match self
+            .inner
+            .prune(&mut |leaf| leaf.license() != Some(&license))
+        {
+            Pruned::Unchanged => Ok(self.clone()),
+            Pruned::Removed => Err(SpdxExpressionError::Empty(identifier.to_string())),
+            Pruned::Replaced(inner) => Ok(Self::from_inner(inner)),
+        }
+    }
+
+    /// Keep only the licenses of `Self` for which `keep` returns `true`, with or without their
+    /// exceptions, pruning the expression like [`SpdxExpression::remove_license`]. Returns `None`
+    /// if no license is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let shipped = ["MIT", "Apache-2.0"];
+    /// let expression = SpdxExpression::parse("(MIT OR GPL-2.0-only) AND (Apache-2.0 OR ISC)")?;
+    ///
+    /// let filtered = expression.filter(|license| shipped.contains(&license.identifier.as_str()));
+    ///
+    /// assert_eq!(filtered.unwrap().to_string(), "MIT AND Apache-2.0");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn filter<F>(&self, mut keep: F) -> Option<Self>
+    where
+        F: FnMut(&SimpleExpression) -> bool,
+    {
+        match self
+            .inner
+            .prune(&mut |leaf| leaf.license().is_none_or(&mut keep))
+        {
+            Pruned::Unchanged => Some(self.clone()),
+            Pruned::Removed => None,
+            Pruned::Replaced(inner) => Some(Self::from_inner(inner)),
+        }
+    }
+
+    /// Get all simple license expressions in `Self`. For licenses with exceptions, returns the
+    /// license without the exception
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use std::iter::FromIterator;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse(
+    ///     "(MIT OR Apache-2.0 AND (GPL-2.0-only WITH Classpath-exception-2.0 OR ISC))",
+    /// )
+    /// .unwrap();
+    ///
+    /// let licenses = expression.licenses();
+    ///
+    /// assert_eq!(
+    ///     licenses
+    ///         .iter()
+    ///         .map(|&license| license.identifier.as_str())
+    ///         .collect::<HashSet<_>>(),
+    ///     HashSet::from_iter(["Apache-2.0", "GPL-2.0-only", "ISC", "MIT"])
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn licenses(&self) -> HashSet<&SimpleExpression> {
+        self.index().map_or_else(
+            || self.inner.licenses(),
+            |index| {
+                index
+                    .first_paths()
+                    .filter_map(|path| self.inner.get(path))
+                    .filter_map(ExpressionVariant::license)
+                    .collect()
+            },
+        )
+    }
+
+    /// Get the obligations of the licenses in `chosen`, a choice of licenses that satisfies
+    /// `Self`, e.g. one of the alternatives of an `OR`. Licenses are given with their
+    /// `LicenseRef-` and `DocumentRef-` prefixes, and compared case-insensitively. Returns `None`
+    /// if the chosen licenses don't satisfy `Self`.
+    ///
+    /// Licenses without known obligations, like `LicenseRef`s, are listed in
+    /// [`Obligations::unknown`]. Exceptions are not taken into account.
+    ///
+    /// Requires the `data` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeSet;
+    /// # use spdx_expression::{Obligation, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (Apache-2.0 OR GPL-2.0-only)")?;
+    ///
+    /// let obligations = expression.obligations(["MIT", "Apache-2.0"]).unwrap();
+    /// assert_eq!(
+    ///     obligations.obligations,
+    ///     BTreeSet::from([Obligation::Attribution, Obligation::PatentGrant])
+    /// );
+    ///
+    /// assert!(expression.obligations(["Apache-2.0"]).is_none());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[cfg(feature = "data")]
+    pub fn obligations<'a, I>(&self, chosen: I) -> Option<Obligations>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let chosen = chosen
+            .into_iter()
+            .map(str::to_lowercase)
+            .collect::<HashSet<_>>();
+        obligations::obligations(&self.inner, |license| {
+            chosen.contains(&license.to_string().to_lowercase())
+        })
+    }
+
+    /// Get the terms of `Self`, i.e. the licenses with their exceptions, with the permissions
+    /// granted by the exceptions, in the order they appear in the expression. Terms that appear
+    /// more than once are returned once. This allows treating
+    /// `GPL-2.0-only WITH Classpath-exception-2.0` differently from `GPL-2.0-only`, e.g. by
+    /// comparing their [`EffectiveTerm::copyleft`].
+    ///
+    /// Requires the `data` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Copyleft, ExceptionGrant, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("GPL-2.0-only WITH Classpath-exception-2.0")?;
+    ///
+    /// let terms = expression.effective_terms();
+    /// assert!(terms[0].grants.contains(&ExceptionGrant::LinkingPermitted));
+    /// assert_eq!(terms[0].copyleft(), Some(Copyleft::Weak));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[cfg(feature = "data")]
+    pub fn effective_terms(&self) -> Vec<EffectiveTerm> {
+        let mut terms: Vec<EffectiveTerm> = Vec::new();
+        for (license, exception) in self.leaves() {
+            let is_new = !terms
+                .iter()
+                .any(|term| &term.license == license && term.exception.as_deref() == exception);
+            if is_new {
+                terms.push(EffectiveTerm::new(license, exception));
+            }
+        }
+        terms
+    }
+
+    /// Get the license with the strongest copyleft that can't be avoided by any choice of
+    /// licenses satisfying `Self`, with its category from `category`. Licenses for which
+    /// `category` returns `None` are ignored. Returns `None` if every license with a category can
+    /// be avoided.
+    ///
+    /// With the `data` feature, [`Copyleft::of_license`] provides the categories of common
+    /// licenses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Copyleft, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (GPL-2.0-only OR MIT) AND MPL-2.0")?;
+    ///
+    /// let (license, category) = expression
+    ///     .strongest_copyleft(|license| match license.identifier.as_str() {
+    ///         "MIT" => Some(Copyleft::Permissive),
+    ///         "MPL-2.0" => Some(Copyleft::Weak),
+    ///         "GPL-2.0-only" => Some(Copyleft::Strong),
+    ///         _ => None,
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!((license.to_string(), category), ("MPL-2.0".to_string(), Copyleft::Weak));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// [`Copyleft::of_license`]: crate::Copyleft
+    pub fn strongest_copyleft<F>(&self, category: F) -> Option<(&SimpleExpression, Copyleft)>
+    where
+        F: FnMut(&SimpleExpression) -> Option<Copyleft>,
+    {
+        copyleft::strongest_copyleft(&self.inner, category)
+    }
+
+    /// Get a report of the ways to satisfy `Self` for legal review. Each option of the report is
+    /// a minimal set of licenses that together comply with the expression, with the exceptions it
+    /// relies on and its licenses grouped by their categories from `category`. The report can be
+    /// serialized, e.g. to JSON with `serde_json`.
+    ///
+    /// The number of options can grow exponentially with the number of `AND`ed choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Copyleft, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse(
+    ///     "MIT AND (GPL-2.0-only WITH Classpath-exception-2.0 OR MPL-2.0)",
+    /// )?;
+    /// let report = expression.options_report(|license| match license.identifier.as_str() {
+    ///     "MIT" => Some(Copyleft::Permissive),
+    ///     "MPL-2.0" => Some(Copyleft::Weak),
+    ///     "GPL-2.0-only" => Some(Copyleft::Strong),
+    ///     _ => None,
+    /// });
+    ///
+    /// let [first, second] = &report.options[..] else { unreachable!() };
+    /// assert_eq!(
+    ///     first.expression.to_string(),
+    ///     "MIT AND GPL-2.0-only WITH Classpath-exception-2.0"
+    /// );
+    /// assert_eq!(first.exceptions, ["Classpath-exception-2.0"]);
+    /// assert_eq!(first.strongest_copyleft(), Some(Copyleft::Strong));
+    /// assert_eq!(second.expression.to_string(), "MIT AND MPL-2.0");
+    /// assert_eq!(second.strongest_copyleft(), Some(Copyleft::Weak));
+    ///
+    /// let json = serde_json::to_value(&report).unwrap();
+    /// assert_eq!(json["options"][1]["categories"]["Weak"][0], "MPL-2.0");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn options_report<F>(&self, category: F) -> OptionsReport
+    where
+        F: FnMut(&SimpleExpression) -> Option<Copyleft>,
+    {
+        OptionsReport::new(self, category)
+    }
+
+    /// Choose the licenses to comply with `Self` by their `weights`. Every minimal choice of
+    /// licenses satisfying the expression is considered, also across nested `OR`s, and the one
+    /// with the lowest total weight is returned as the `AND` of its licenses with their
+    /// exceptions. Ties are broken by the order of the choices in the expression. Returns `None`
+    /// if every choice has a forbidden license.
+    ///
+    /// The number of choices can grow exponentially with the number of `AND`ed choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LicenseWeights, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let mut weights = LicenseWeights::new();
+    /// weights.insert("MIT", 1);
+    /// weights.insert("Apache-2.0", 1);
+    /// weights.insert("GPL-2.0-only", 5);
+    ///
+    /// // Choosing the first alternative of each `OR` would need `GPL-2.0-only` and `Apache-2.0`.
+    /// let expression = SpdxExpression::parse("(GPL-2.0-only OR MIT) AND (Apache-2.0 OR MIT)")?;
+    /// assert_eq!(expression.resolve(&weights).unwrap().to_string(), "MIT");
+    ///
+    /// weights.forbid("MIT");
+    /// assert_eq!(
+    ///     expression.resolve(&weights).unwrap().to_string(),
+    ///     "GPL-2.0-only AND Apache-2.0"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn resolve(&self, weights: &LicenseWeights) -> Option<Self> {
+        preference::resolve(&self.inner, weights)
+    }
+
+    /// Check if `Self` and `other` are satisfied by exactly the same choices of licenses, e.g.
+    /// `MIT AND (ISC OR 0BSD)` and `MIT AND ISC OR 0BSD AND MIT`. Licenses are compared
+    /// case-insensitively, and a license with an exception is distinct from the license without
+    /// it.
+    ///
+    /// The check uses a SAT solver, so it stays feasible for expressions with hundreds of
+    /// licenses. Requires the `sat` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (ISC OR 0BSD)")?;
+    /// assert!(expression.is_equivalent(&SpdxExpression::parse("mit AND ISC OR 0BSD AND MIT")?));
+    /// assert!(!expression.is_equivalent(&SpdxExpression::parse("MIT AND ISC")?));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[cfg(feature = "sat")]
+    pub fn is_equivalent(&self, other: &Self) -> bool {
+        sat::is_equivalent(&self.inner, &other.inner)
+    }
+
+    /// Get the choice of licenses satisfying `Self` with the fewest licenses, as the `AND` of the
+    /// licenses with their exceptions. Only the licenses for which `is_allowed` returns `true`
+    /// are chosen. Returns `None` if `Self` can't be satisfied with the allowed licenses.
+    ///
+    /// The choice is found with a SAT solver instead of enumerating the choices, so it stays
+    /// feasible for expressions with hundreds of licenses. Requires the `sat` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("ISC AND (MIT OR Apache-2.0 AND 0BSD)")?;
+    ///
+    /// let smallest = expression.smallest_choice(|_| true).unwrap();
+    /// assert_eq!(smallest.to_string(), "ISC AND MIT");
+    ///
+    /// let smallest = expression.smallest_choice(|license| license.identifier != "MIT").unwrap();
+    /// assert_eq!(smallest.to_string(), "ISC AND Apache-2.0 AND 0BSD");
+    ///
+    /// assert!(expression.smallest_choice(|license| license.identifier != "ISC").is_none());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[cfg(feature = "sat")]
+    pub fn smallest_choice<F>(&self, is_allowed: F) -> Option<Self>
+    where
+        F: FnMut(&SimpleExpression) -> bool,
+    {
+        sat::smallest_choice(&self.inner, is_allowed)
+    }
+
+    /// Get all exception identifiers for `Self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use std::iter::FromIterator;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse(
+    ///     "(MIT OR Apache-2.0 AND (GPL-2.0-only WITH Classpath-exception-2.0 OR ISC))",
+    /// )
+    /// .unwrap();
+    ///
+    /// let exceptions = expression.exceptions();
+    ///
+    /// assert_eq!(exceptions, HashSet::from_iter(["Classpath-exception-2.0"]));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn exceptions(&self) -> HashSet<&str> {
+        self.inner.exceptions()
+    }
+
+    /// Describe `Self` in natural language, e.g. for NOTICE files and end user documentation.
+    /// Licenses and exceptions are referred to by their identifiers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR GPL-2.0-only WITH Classpath-exception-2.0")?;
+    /// assert_eq!(
+    ///     expression.to_prose(),
+    ///     "Licensed under MIT, or alternatively under GPL-2.0-only with Classpath-exception-2.0"
+    /// );
+    ///
+    /// let expression = SpdxExpression::parse("Apache-2.0 AND (MIT OR ISC)")?;
+    /// assert_eq!(
+    ///     expression.to_prose(),
+    ///     "Licensed under both Apache-2.0 and either MIT or ISC"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn to_prose(&self) -> String {
+        prose::render(self.inner())
+    }
+
+    /// Render `Self` as a Markdown list. The alternatives of the top level `OR` are the items of
+    /// the list, and operands of nested operators are nested lists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR Apache-2.0 AND ISC")?;
+    /// assert_eq!(
+    ///     expression.to_markdown(),
+    ///     "- `MIT`
+    /// - All of:
+    ///   - `Apache-2.0`
+    ///   - `ISC`
+    /// "
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        markdown::render(self.inner())
+    }
+
+    /// Get the expression as a sequence of tokens in postfix order, i.e. with every operator
+    /// after its operands. The sequence can be evaluated with a stack and turned back to an
+    /// expression with [`SpdxExpression::from_postfix`]. Parentheses are implied by the order of
+    /// the tokens, so redundant parentheses are not kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{PostfixToken, SimpleExpression, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR ISC")?;
+    /// assert_eq!(
+    ///     expression.to_postfix(),
+    ///     [
+    ///         PostfixToken::License(SimpleExpression::parse("MIT")?),
+    ///         PostfixToken::License(SimpleExpression::parse("ISC")?),
+    ///         PostfixToken::Or,
+    ///     ]
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn to_postfix(&self) -> Vec<PostfixToken> {
+        postfix::to_postfix(self.inner())
+    }
+
+    /// Build an expression from a sequence of tokens in postfix order, see
+    /// [`SpdxExpression::to_postfix`]. Parentheses are added where needed to keep the structure
+    /// when the expression is displayed and parsed again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Conversion` if an operator doesn't have the operands it
+    /// needs or the tokens don't form exactly one expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{PostfixToken, SimpleExpression, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::from_postfix([
+    ///     PostfixToken::License(SimpleExpression::parse("MIT")?),
+    ///     PostfixToken::License(SimpleExpression::parse("ISC")?),
+    ///     PostfixToken::License(SimpleExpression::parse("0BSD")?),
+    ///     PostfixToken::Or,
+    ///     PostfixToken::And,
+    /// ])?;
+    /// assert_eq!(expression.to_string(), "MIT AND (ISC OR 0BSD)");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn from_postfix<I>(tokens: I) -> Result<Self, SpdxExpressionError>
+    where
+        I: IntoIterator<Item = PostfixToken>,
+    {
+        postfix::from_postfix(tokens).map(Self::from_inner)
+    }
+
+    /// Convert the license classifiers of a Python package to an expression. Classifiers don't
+    /// tell whether several licenses are alternatives, so the licenses of all classifiers are
+    /// joined with `AND`. Classifiers other than license classifiers are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::from_trove_classifiers([
+    ///     "License :: OSI Approved :: MIT License",
+    ///     "Programming Language :: Python :: 3",
+    /// ])?;
+    /// assert_eq!(expression.to_string(), "MIT");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Conversion` if there are no license classifiers, or a
+    /// classifier doesn't identify a single license, like
+    /// `License :: OSI Approved :: BSD License`.
+    pub fn from_trove_classifiers<I, S>(classifiers: I) -> Result<Self, SpdxExpressionError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        trove::from_classifiers(classifiers, AliasTable::bundled_trove())
+    }
+
+    /// Convert the license classifiers of a Python package to an expression like
+    /// [`SpdxExpression::from_trove_classifiers`], looking up the classifiers in `aliases`. Start
+    /// from [`AliasTable::trove`] to add classifiers to the bundled ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{AliasTable, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let mut aliases = AliasTable::trove();
+    /// aliases.insert(
+    ///     "License :: OSI Approved :: Apache Software License",
+    ///     SpdxExpression::parse("Apache-2.0")?,
+    /// );
+    ///
+    /// let expression = SpdxExpression::from_trove_classifiers_with(
+    ///     ["License :: OSI Approved :: Apache Software License"],
+    ///     &aliases,
+    /// )?;
+    /// assert_eq!(expression.to_string(), "Apache-2.0");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Conversion` if there are no license classifiers, or a
+    /// classifier is not in `aliases`.
+    pub fn from_trove_classifiers_with<I, S>(
+        classifiers: I,
+        aliases: &AliasTable,
+    ) -> Result<Self, SpdxExpressionError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        trove::from_classifiers(classifiers, aliases)
+    }
+
+    /// Get the Python license classifiers of the licenses in the expression. Classifiers can't
+    /// express the structure of the expression or exceptions, so only the licenses are
+    /// converted. Licenses like `BSD-3-Clause` that only have a classifier for the family of
+    /// licenses get the classifier of the family.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeSet;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR Apache-2.0")?;
+    /// assert_eq!(
+    ///     expression.trove_classifiers()?,
+    ///     BTreeSet::from([
+    ///         "License :: OSI Approved :: Apache Software License",
+    ///         "License :: OSI Approved :: MIT License",
+    ///     ])
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Conversion` if a license has no classifier.
+    pub fn trove_classifiers(&self) -> Result<BTreeSet<&'static str>, SpdxExpressionError> {
+        trove::to_classifiers(self)
+    }
+
+    /// Simplify `Self` without changing its meaning. Redundant parentheses and duplicate
+    /// operands are removed, and operands absorbed by other operands are dropped, so
+    /// `(MIT OR ISC) AND MIT` becomes `MIT`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("((MIT AND ISC) AND MIT) OR (MIT AND ISC)")?;
+    /// assert_eq!(expression.simplify().to_string(), "MIT AND ISC");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// The result is computed on the first call and reused by later calls, also on clones of
+    /// `Self`.
+    #[must_use]
+    pub fn simplify(&self) -> Self {
+        let simplified = self
+            .extras()
+            .simplified
+            .get_or_init(|| Arc::new(simplify::simplify(&self.inner)));
+        Self::from_inner(ExpressionVariant::clone(simplified))
+    }
+
+    /// Simplify `Self` like [`SpdxExpression::simplify`], with `options` for the simplifications
+    /// that depend on how the expression is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{ExceptionOverlap, SimplifyOptions, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression =
+    ///     SpdxExpression::parse("GPL-2.0-only AND GPL-2.0-only WITH Classpath-exception-2.0")?;
+    /// let options = SimplifyOptions {
+    ///     exception_overlap: ExceptionOverlap::Effective,
+    ///     ..SimplifyOptions::default()
+    /// };
+    /// assert_eq!(expression.simplify_with(options).to_string(), "GPL-2.0-only");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn simplify_with(&self, options: SimplifyOptions) -> Self {
+        Self::from_inner(simplify::simplify_with(&self.inner, options))
+    }
+
+    /// Simplify `Self` like [`SpdxExpression::simplify_with`], failing if `NONE` or
+    /// `NOASSERTION` is combined with other licenses and `options.special_licenses` is
+    /// [`SpecialLicenses::Error`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SimplifyOptions, SpdxExpression, SpecialLicenses};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (NOASSERTION OR ISC) AND MIT")?;
+    ///
+    /// let options = SimplifyOptions {
+    ///     special_licenses: SpecialLicenses::Satisfied,
+    ///     ..SimplifyOptions::default()
+    /// };
+    /// assert_eq!(expression.try_simplify_with(options)?.to_string(), "MIT");
+    ///
+    /// let options = SimplifyOptions {
+    ///     special_licenses: SpecialLicenses::Error,
+    ///     ..SimplifyOptions::default()
+    /// };
+    /// assert!(expression.try_simplify_with(options).is_err());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Build` with `BuildError::SpecialLicense` if a special
+    /// license is combined with other licenses and the special licenses are errors.
+    pub fn try_simplify_with(&self, options: SimplifyOptions) -> Result<Self, SpdxExpressionError> {
+        Ok(Self::from_inner(simplify::try_simplify_with(
+            &self.inner,
+            options,
+        )?))
+    }
+
+    /// Check if `Self` is satisfied when the licenses for which `is_chosen` returns `true` are
+    /// chosen, with `NONE` and `NOASSERTION` interpreted according to `special_licenses`.
+    /// Returns `None` if the result is unknown, which is only possible with
+    /// [`SpecialLicenses::Unknown`]. `is_chosen` is not called for the special licenses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SimpleExpression, SpdxExpression, SpecialLicenses};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (ISC OR NOASSERTION)")?;
+    ///
+    /// let mit_and_isc = |license: &SimpleExpression| license.identifier != "0BSD";
+    /// assert_eq!(expression.evaluate(mit_and_isc, SpecialLicenses::Unknown)?, Some(true));
+    ///
+    /// let mit = |license: &SimpleExpression| license.identifier == "MIT";
+    /// assert_eq!(expression.evaluate(mit, SpecialLicenses::Unknown)?, None);
+    /// assert_eq!(expression.evaluate(mit, SpecialLicenses::Satisfied)?, Some(true));
+    /// assert!(expression.evaluate(mit, SpecialLicenses::Error).is_err());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Build` with `BuildError::SpecialLicense` if `Self`
+    /// contains a special license and the special licenses are errors.
+    pub fn evaluate<F>(
+        &self,
+        mut is_chosen: F,
+        special_licenses: SpecialLicenses,
+    ) -> Result<Option<bool>, SpdxExpressionError>
+    where
+        F: FnMut(&SimpleExpression) -> bool,
+    {
+        Ok(special::evaluate(
+            &self.inner,
+            &mut is_chosen,
+            special_licenses,
+        )?)
+    }
+
+    /// Add `term` as an additional requirement of `Self`, i.e. join them with `AND`. `Self` is
+    /// parenthesized if needed. Call [`SpdxExpression::simplify`] on the result to drop the term
+    /// if it is already required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT OR Apache-2.0")?;
+    /// let term = SpdxExpression::parse("LicenseRef-Company-Terms")?;
+    /// assert_eq!(
+    ///     expression.constrain(&term).to_string(),
+    ///     "(MIT OR Apache-2.0) AND LicenseRef-Company-Terms"
+    /// );
+    ///
+    /// let expression = SpdxExpression::parse("MIT AND ISC")?;
+    /// let term = SpdxExpression::parse("MIT")?;
+    /// assert_eq!(expression.constrain(&term).simplify().to_string(), "MIT AND ISC");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn constrain(&self, term: &Self) -> Self {
+        Self::from_inner(ExpressionVariant::and(
+            self.inner.clone(),
+            term.inner.clone(),
+        ))
+    }
+
+    /// Join `expressions` with `operator`. Operands that are joined with the same operator are
+    /// spliced in, so joining `MIT AND ISC` and `0BSD` with `AND` gives `MIT AND ISC AND 0BSD`,
+    /// and operands are parenthesized where needed. Returns `None` if there are no expressions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Operator, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expressions = ["MIT OR ISC", "Apache-2.0", "0BSD AND Zlib"]
+    ///     .iter()
+    ///     .map(|expression| SpdxExpression::parse(expression))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// let joined = SpdxExpression::join(Operator::And, &expressions).unwrap();
+    /// assert_eq!(
+    ///     joined.to_string(),
+    ///     "(MIT OR ISC) AND Apache-2.0 AND 0BSD AND Zlib"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn join<'a, I>(operator: Operator, expressions: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        let mut joined: Option<ExpressionVariant> = None;
+
+        for expression in expressions {
+            let operands = match expression.inner.flatten() {
+                Some((nested_operator, operands)) if nested_operator == operator => operands,
+                _ => vec![expression.inner.unparenthesized()],
+            };
+
+            for operand in operands {
+                let operand = operand.clone();
+                joined = Some(match joined {
+                    Some(previous) => match operator {
+                        Operator::And => ExpressionVariant::and(previous, operand),
+                        Operator::Or => ExpressionVariant::or(previous, operand),
+                    },
+                    None => operand,
+                });
+            }
+        }
+
+        joined.map(Self::from_inner)
+    }
+
+    /// Aggregate `expressions` into an expression requiring all of them, like
+    /// [`SpdxExpression::join`] with `AND`, with `NONE` and `NOASSERTION` interpreted according
+    /// to `special_licenses`. With [`SpecialLicenses::Satisfied`] they are dropped unless every
+    /// expression is a special license. Returns `None` if there are no expressions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SpdxExpression, SpecialLicenses};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expressions = ["MIT", "NOASSERTION", "ISC OR 0BSD"]
+    ///     .iter()
+    ///     .map(|expression| SpdxExpression::parse(expression))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// let aggregated = SpdxExpression::aggregate(&expressions, SpecialLicenses::Unknown)?;
+    /// assert_eq!(aggregated.unwrap().to_string(), "MIT AND NOASSERTION AND (ISC OR 0BSD)");
+    ///
+    /// let aggregated = SpdxExpression::aggregate(&expressions, SpecialLicenses::Satisfied)?;
+    /// assert_eq!(aggregated.unwrap().to_string(), "MIT AND (ISC OR 0BSD)");
+    ///
+    /// assert!(SpdxExpression::aggregate(&expressions, SpecialLicenses::Error).is_err());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Build` with `BuildError::SpecialLicense` if a special
+    /// license is aggregated with other licenses and the special licenses are errors.
+    pub fn aggregate<'a, I>(
+        expressions: I,
+        special_licenses: SpecialLicenses,
+    ) -> Result<Option<Self>, SpdxExpressionError>
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        let Some(joined) = Self::join(Operator::And, expressions) else {
+            return Ok(None);
+        };
+        let Some((Operator::And, operands)) = joined.inner.flatten() else {
+            return Ok(Some(joined));
+        };
+
+        let mut operands = operands.into_iter().cloned().collect();
+        special::resolve(Operator::And, &mut operands, special_licenses)?;
+        Ok(operands
+            .into_iter()
+            .reduce(ExpressionVariant::and)
+            .map(Self::from_inner))
+    }
+
+    /// Check if the licenses of `expressions` can be combined in one work, e.g. the expressions
+    /// of a package and its dependencies. `is_compatible` tells if a license of an earlier
+    /// expression can be combined with a license of a later one, so it can encode a one-way
+    /// compatibility matrix.
+    ///
+    /// Every combination of the choices of the expressions is explored until one without
+    /// conflicts is found, and the chosen licenses of each expression are returned with their
+    /// keys. The number of combinations can grow exponentially with the number of expressions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SimpleExpression, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let package = SpdxExpression::parse("GPL-2.0-only")?;
+    /// let dependency = SpdxExpression::parse("Apache-2.0 OR Apache-2.0 WITH LLVM-exception")?;
+    ///
+    /// let is_compatible = |license: &SimpleExpression, other: &SimpleExpression| {
+    ///     !(license.identifier == "GPL-2.0-only" && other.identifier == "Apache-2.0")
+    /// };
+    /// let trace = SpdxExpression::check_compatibility(
+    ///     [("package", &package), ("dependency", &dependency)],
+    ///     is_compatible,
+    /// )
+    /// .unwrap_err();
+    ///
+    /// assert_eq!(trace.attempts.len(), 2);
+    /// assert_eq!(
+    ///     trace.attempts[0].conflicts[0].to_string(),
+    ///     "GPL-2.0-only (from package) is incompatible with Apache-2.0 (from dependency)"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CompatibilityTrace`] of the explored choices and their conflicting licenses
+    /// if no combination of choices is compatible.
+    pub fn check_compatibility<'a, K, I, F>(
+        expressions: I,
+        is_compatible: F,
+    ) -> Result<Vec<(K, Self)>, CompatibilityTrace<K>>
+    where
+        K: Clone,
+        I: IntoIterator<Item = (K, &'a Self)>,
+        F: FnMut(&SimpleExpression, &SimpleExpression) -> bool,
+    {
+        compatibility::check_compatibility(expressions, is_compatible)
+    }
+
+    /// Aggregate the expressions of the files of a package into the expression of the whole
+    /// package, e.g. for the declared license of a package in an SBOM.
+    ///
+    /// The licenses of every file apply to the package, so the result requires all of the
+    /// expressions:
+    ///
+    /// - The result is the `AND` of the [conjuncts](SpdxExpression::conjuncts) of the
+    ///   expressions. Choices within a file, like `MIT OR Apache-2.0`, stay choices, but
+    ///   there is no choice between files.
+    /// - Conjuncts repeated in several files are included once, in the order they first
+    ///   appear. Duplicates are detected by their [canonical keys](SpdxExpression::canonical_key)
+    ///   while the expressions are read, so only the distinct conjuncts are kept in memory.
+    /// - `NONE` adds no licenses, so it's dropped. The result is `NONE` only if there are no
+    ///   other licenses, including when there are no expressions.
+    /// - `NOASSERTION` means the licenses of a file are unknown, so it's kept once.
+    /// - The result is [simplified](SpdxExpression::simplify), so conjuncts implied by other
+    ///   conjuncts are removed, like `MIT OR ISC` when `MIT` is required anyway.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let files = ["MIT", "NONE", "Apache-2.0 AND mit", "MIT OR ISC", "ISC OR 0BSD"]
+    ///     .iter()
+    ///     .map(|expression| SpdxExpression::parse(expression))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// assert_eq!(
+    ///     SpdxExpression::aggregate_files(&files).to_string(),
+    ///     "MIT AND Apache-2.0 AND (ISC OR 0BSD)"
+    /// );
+    /// assert_eq!(SpdxExpression::aggregate_files(&[]).to_string(), "NONE");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn aggregate_files<'a, I>(expressions: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        let mut keys = HashSet::new();
+        let mut conjuncts = Vec::new();
+        for expression in expressions {
+            for conjunct in expression.conjuncts() {
+                let is_none = matches!(
+                    conjunct.inner.unparenthesized(),
+                    ExpressionVariant::Simple(license)
+                        if special::is_special(license) && license.identifier == "NONE"
+                );
+                if !is_none && keys.insert(conjunct.canonical_key()) {
+                    conjuncts.push(conjunct.inner);
+                }
+            }
+        }
+
+        conjuncts
+            .into_iter()
+            .reduce(ExpressionVariant::and)
+            .map_or_else(
+                || {
+                    Self::from_inner(ExpressionVariant::Simple(SimpleExpression::new(
+                        "NONE".to_string(),
+                        None,
+                        false,
+                    )))
+                },
+                |aggregated| Self::from_inner(aggregated).simplify(),
+            )
+    }
+
+    /// Split `Self` into the operands of its top level `AND`, looking through parentheses and
+    /// nested `AND`s. Every operand is a term that is required on its own. Expressions without
+    /// a top level `AND` have themselves as the only conjunct.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (ISC OR 0BSD) AND (Apache-2.0 AND Zlib)")?;
+    ///
+    /// let conjuncts = expression
+    ///     .conjuncts()
+    ///     .iter()
+    ///     .map(ToString::to_string)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(conjuncts, ["MIT", "ISC OR 0BSD", "Apache-2.0", "Zlib"]);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn conjuncts(&self) -> Vec<Self> {
+        match self.inner.flatten() {
+            Some((Operator::And, operands)) => operands
+                .into_iter()
+                .map(|operand| Self::from_inner(operand.clone()))
+                .collect(),
+            _ => vec![Self::from_inner(self.inner.unparenthesized().clone())],
+        }
+    }
+
+    /// Set the exception of every occurrence of the license `identifier` to `exception`,
+    /// replacing any exception the license had. The identifier needs to include possible
+    /// `LicenseRef-` and `DocumentRef-` prefixes. Returns the expression unchanged if
+    /// `identifier` is not a license or `exception` is not a valid exception identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("GPL-2.0-only OR MIT")?;
+    /// assert_eq!(
+    ///     expression
+    ///         .set_exception("GPL-2.0-only", "Classpath-exception-2.0")
+    ///         .to_string(),
+    ///     "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn set_exception(&self, identifier: &str, exception: &str) -> Self {
+        let Ok(target) = SimpleExpression::parse(identifier) else {
+            return self.clone();
+        };
+        if !parser::is_idstring(exception) {
+            return self.clone();
+        }
+
+        self.map_leaves(|leaf| {
+            let license = leaf.license().filter(|license| **license == target)?;
+            Some(ExpressionVariant::With(Box::new(WithExpression::new(
+                license.clone(),
+                exception.to_string(),
+            ))))
+        })
+    }
+
+    /// Replace the exception `from` with `to` everywhere in `Self`. Returns the expression
+    /// unchanged if `to` is not a valid exception identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("GPL-2.0-only WITH Classpath-exception")?;
+    /// assert_eq!(
+    ///     expression
+    ///         .replace_exception("Classpath-exception", "Classpath-exception-2.0")
+    ///         .to_string(),
+    ///     "GPL-2.0-only WITH Classpath-exception-2.0"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn replace_exception(&self, from: &str, to: &str) -> Self {
+        if !parser::is_idstring(to) {
+            return self.clone();
+        }
+
+        self.map_leaves(|leaf| match leaf {
+            ExpressionVariant::With(with) if with.exception == from => {
+                Some(ExpressionVariant::With(Box::new(WithExpression::new(
+                    with.license.clone(),
+                    to.to_string(),
+                ))))
+            }
+            _ => None,
+        })
+    }
+
+    /// Remove all exceptions from `Self`, keeping the licenses they were attached to. Returns
+    /// the expression without exceptions and the exceptions that were removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression =
+    ///     SpdxExpression::parse("GPL-2.0-only WITH Classpath-exception-2.0 OR MIT")?;
+    ///
+    /// let (stripped, exceptions) = expression.strip_exceptions();
+    ///
+    /// assert_eq!(stripped.to_string(), "GPL-2.0-only OR MIT");
+    /// assert_eq!(
+    ///     exceptions,
+    ///     HashSet::from(["Classpath-exception-2.0".to_string()])
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn strip_exceptions(&self) -> (Self, HashSet<String>) {
+        let mut exceptions = HashSet::new();
+
+        let stripped = self.map_leaves(|leaf| match leaf {
+            ExpressionVariant::With(with) => {
+                exceptions.insert(with.exception.clone());
+                Some(ExpressionVariant::Simple(with.license.clone()))
+            }
+            _ => None,
+        });
+
+        (stripped, exceptions)
+    }
+
+    /// Remove the `DocumentRef` prefixes of all licenses, turning references to licenses in
+    /// other documents into references to licenses in the same document. Used when merging
+    /// external documents into a single document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND DocumentRef-ext:LicenseRef-custom")?;
+    /// assert_eq!(
+    ///     expression.strip_document_refs().to_string(),
+    ///     "MIT AND LicenseRef-custom"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn strip_document_refs(&self) -> Self {
+        self.map_licenses(|license| {
+            license.document_ref.as_ref().map(|_| SimpleExpression {
+                document_ref: None,
+                ..license.clone()
+            })
+        })
+    }
+
+    /// Remove the `DocumentRef` prefixes of the licenses in `documents` like
+    /// [`SpdxExpression::strip_document_refs`], keeping references to other documents. The
+    /// documents can be given with or without the `DocumentRef-` prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse(
+    ///     "DocumentRef-merged:LicenseRef-a AND DocumentRef-other:LicenseRef-b",
+    /// )?;
+    /// assert_eq!(
+    ///     expression
+    ///         .strip_document_refs_of(&["DocumentRef-merged"])
+    ///         .to_string(),
+    ///     "LicenseRef-a AND DocumentRef-other:LicenseRef-b"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn strip_document_refs_of(&self, documents: &[&str]) -> Self {
+        let documents = documents
+            .iter()
+            .map(|document| document.strip_prefix("DocumentRef-").unwrap_or(document))
+            .collect::<HashSet<_>>();
+
+        self.map_licenses(|license| {
+            license
+                .document_ref
+                .as_deref()
+                .filter(|document| documents.contains(document))
+                .map(|_| SimpleExpression {
+                    document_ref: None,
+                    ..license.clone()
+                })
+        })
+    }
+
+    /// Rename the document `old` to `new` in the `DocumentRef` prefixes of the licenses. The
+    /// documents can be given with or without the `DocumentRef-` prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND DocumentRef-old:LicenseRef-custom")?;
+    /// assert_eq!(
+    ///     expression
+    ///         .rename_document_ref("DocumentRef-old", "DocumentRef-new")
+    ///         .to_string(),
+    ///     "MIT AND DocumentRef-new:LicenseRef-custom"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn rename_document_ref(&self, old: &str, new: &str) -> Self {
+        self.rename_document_refs(&HashMap::from([(old.to_string(), new.to_string())]))
+    }
+
+    /// Rename the documents in the `DocumentRef` prefixes of the licenses with the renames from
+    /// old to new names in `renames`. Every prefix is renamed at most once, so renames can swap
+    /// names. The documents can be given with or without the `DocumentRef-` prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression =
+    ///     SpdxExpression::parse("DocumentRef-a:LicenseRef-x OR DocumentRef-b:LicenseRef-y")?;
+    /// let renames = HashMap::from([
+    ///     ("a".to_string(), "b".to_string()),
+    ///     ("b".to_string(), "a".to_string()),
+    /// ]);
+    /// assert_eq!(
+    ///     expression.rename_document_refs(&renames).to_string(),
+    ///     "DocumentRef-b:LicenseRef-x OR DocumentRef-a:LicenseRef-y"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn rename_document_refs(&self, renames: &HashMap<String, String>) -> Self {
+        let without_prefix = |document: &'_ str| {
+            document
+                .strip_prefix("DocumentRef-")
+                .unwrap_or(document)
+                .to_string()
+        };
+        let renames = renames
+            .iter()
+            .map(|(old, new)| (without_prefix(old), without_prefix(new)))
+            .collect::<HashMap<_, _>>();
+
+        self.map_licenses(|license| {
+            let new = renames.get(license.document_ref.as_ref()?)?;
+            Some(SimpleExpression {
+                document_ref: Some(new.clone()),
+                ..license.clone()
+            })
+        })
+    }
+
+    /// Check if the expressions are equal when the `DocumentRef` prefixes of the licenses are
+    /// ignored. See [`IgnoreDocumentRefs`] for using the same comparison in collections.
+    ///
+    /// [`IgnoreDocumentRefs`]: crate::IgnoreDocumentRefs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND DocumentRef-a:LicenseRef-custom")?;
+    /// assert!(expression.eq_ignoring_document_refs(&SpdxExpression::parse(
+    ///     "MIT AND DocumentRef-b:LicenseRef-custom"
+    /// )?));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn eq_ignoring_document_refs(&self, other: &Self) -> bool {
+        compare::eq_ignoring_document_refs(self, other)
+    }
+
+    /// Check if the expressions are equal when license identifiers, exceptions and
+    /// `DocumentRef`s are compared case-insensitively. See [`IgnoreCase`] for using the same
+    /// comparison in collections.
+    ///
+    /// [`IgnoreCase`]: crate::IgnoreCase
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("Apache-2.0 WITH LLVM-exception")?;
+    /// assert!(expression.eq_ignoring_case(&SpdxExpression::parse("apache-2.0 WITH llvm-exception")?));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn eq_ignoring_case(&self, other: &Self) -> bool {
+        compare::eq_ignoring_case(self, other)
+    }
+
+    /// Replace the names of `LicenseRef`s and the documents they are from with opaque tokens,
+    /// keeping the structure of the expression and the licenses on the SPDX License List. The
+    /// same name always gets the same token, also between runs and crate versions, so
+    /// anonymized expressions can be compared with each other. Used to share expressions with
+    /// internal license names e.g. in bug reports.
+    ///
+    /// The tokens are hashes of the names, so names can be recovered by guessing. Don't rely on
+    /// anonymization to hide names that are easy to guess.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND LicenseRef-Acme-Customer-EULA")?;
+    /// assert_eq!(
+    ///     expression.anonymize_license_refs().to_string(),
+    ///     "MIT AND LicenseRef-anon-dfdcb98b67ccd124"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn anonymize_license_refs(&self) -> Self {
+        let anonymize = |name: &str| format!("anon-{:016x}", digest::fnv1a(name.as_bytes()));
+
+        self.map_licenses(|license| {
+            license.license_ref.then(|| SimpleExpression {
+                identifier: anonymize(&license.identifier),
+                document_ref: license.document_ref.as_deref().map(anonymize),
+                license_ref: true,
+                or_later: license.or_later,
+            })
+        })
+    }
+
+    /// Replace every license, document and exception name with a hash keyed with `key`, keeping
+    /// the structure of the expression: the operators, parentheses, exceptions, `+`s and which
+    /// licenses are `LicenseRef`s. Used to collect statistics of expressions, like their
+    /// [structures](SpdxExpression::structure_kind) and [complexity](crate::Stats), without
+    /// revealing the licenses.
+    ///
+    /// The same name always gets the same hash with the same key, so the fingerprints of
+    /// expressions can be compared and aggregated. Unlike
+    /// [`anonymize_license_refs`](SpdxExpression::anonymize_license_refs), names can't be
+    /// recovered by guessing without the key, so keep the key secret and use different keys for
+    /// data that shouldn't be linkable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{SpdxExpression, StructureKind};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let key = [7; 16];
+    /// let expression = SpdxExpression::parse("MIT AND (LicenseRef-Acme-EULA OR MIT)")?;
+    /// let fingerprint = expression.keyed_fingerprint(&key);
+    ///
+    /// assert_eq!(
+    ///     fingerprint.to_string(),
+    ///     "anon-4176dcd2ed177ad3 AND (LicenseRef-anon-e77691eb5fab2980 OR anon-4176dcd2ed177ad3)"
+    /// );
+    /// assert_eq!(fingerprint.structure_kind(), StructureKind::Mixed);
+    /// assert_ne!(expression.keyed_fingerprint(&[8; 16]), fingerprint);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn keyed_fingerprint(&self, key: &[u8; 16]) -> Self {
+        struct Anonymizer<'a>(&'a [u8; 16]);
+
+        impl Anonymizer<'_> {
+            fn anonymize(&self, name: &mut String) {
+                *name = format!("anon-{:016x}", digest::siphash(self.0, name.as_bytes()));
+            }
+        }
+
+        impl VisitorMut for Anonymizer<'_> {
+            fn visit_license(&mut self, license: &mut SimpleExpression) {
+                self.anonymize(&mut license.identifier);
+                if let Some(document_ref) = &mut license.document_ref {
+                    self.anonymize(document_ref);
+                }
+            }
+
+            fn visit_exception(&mut self, exception: &mut String) {
+                self.anonymize(exception);
+            }
+        }
+
+        let mut fingerprint = self.clone();
+        fingerprint.walk_mut(&mut Anonymizer(key));
+        fingerprint
+    }
+
+    /// Rewrite `Self` with `rules`, see [`RewriteRules`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Rewrite` if the rules rewrite the expression in a cycle or
+    /// it still changes after the maximum number of passes.
+    pub fn rewrite(&self, rules: &RewriteRules) -> Result<Self, SpdxExpressionError> {
+        rules.rewrite(self)
+    }
+
+    /// Edit the licenses, exceptions and operators of `Self` in place with `visitor` in a single
+    /// pass, see [`VisitorMut`]. Operands are parenthesized where needed to keep the structure of
+    /// the edited expression when it's displayed and parsed again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Operator, SpdxExpression, VisitorMut};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// struct Conjunctive;
+    ///
+    /// impl VisitorMut for Conjunctive {
+    ///     fn visit_operator(&mut self, operator: &mut Operator) {
+    ///         *operator = Operator::And;
+    ///     }
+    /// }
+    ///
+    /// let mut expression = SpdxExpression::parse("MIT AND ISC OR 0BSD")?;
+    /// expression.walk_mut(&mut Conjunctive);
+    ///
+    /// assert_eq!(expression.to_string(), "MIT AND ISC AND 0BSD");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn walk_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitorMut + ?Sized,
+    {
+        self.inner.walk_mut(visitor);
+        self.hash = OnceLock::new();
+        self.extras = OnceLock::new();
+    }
+
+    /// Get the structural differences from `Self` to `other`, i.e. the operands added, removed,
+    /// changed or moved, see [`Change`]. Differences in parentheses and in the grouping of
+    /// operands of the same operator are ignored, but differences in the order of operands are
+    /// reported as moves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Change, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let suggested = SpdxExpression::parse("MIT AND (GPL-2.0-only OR ISC)")?;
+    /// let curated = SpdxExpression::parse("MIT AND (GPL-2.0-or-later OR ISC)")?;
+    ///
+    /// assert_eq!(
+    ///     suggested.diff(&curated),
+    ///     [Change::Changed {
+    ///         from_path: vec![1, 0, 0],
+    ///         to_path: vec![1, 0, 0],
+    ///         from: SpdxExpression::parse("GPL-2.0-only")?,
+    ///         to: SpdxExpression::parse("GPL-2.0-or-later")?,
+    ///     }]
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        diff::diff(self.inner(), other.inner())
+    }
+
+    /// Get a canonical key of `Self`, for use e.g. as a database key for deduplicating
+    /// expressions. Expressions that only differ in the case of the identifiers, the order,
+    /// grouping or duplicates of operands, absorbed operands or parentheses have the same key.
+    ///
+    /// The key is the [simplified](SpdxExpression::simplify) expression with lowercase
+    /// identifiers and exceptions and sorted operands, prefixed with the version of the key
+    /// format, e.g. `v1:isc AND mit`. The key of an expression only changes in new crate versions
+    /// together with the version prefix, so stored keys of different formats are never compared
+    /// with each other by accident.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (Apache-2.0 OR mit) AND isc")?;
+    /// assert_eq!(expression.canonical_key(), "v1:isc AND mit");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// The key is computed on the first call and reused by later calls, also on clones of `Self`.
+    pub fn canonical_key(&self) -> String {
+        self.extras()
+            .canonical_key
+            .get_or_init(|| canonical::canonical_key(self.inner()).into())
+            .to_string()
+    }
+
+    /// Get a 64-bit fingerprint of the [canonical key](SpdxExpression::canonical_key) of `Self`,
+    /// for use e.g. in database index columns and bloom filters. Equivalent expressions with the
+    /// same canonical key have the same fingerprint.
+    ///
+    /// The fingerprint is the 64-bit FNV-1a hash of the UTF-8 bytes of the canonical key, so it
+    /// can be computed by other tools from the key, and it only changes when the version of the
+    /// key format changes. Unlike the [`Hash`] implementation, which may change between releases
+    /// like the hashers of the standard library, fingerprints can be stored. Different keys can
+    /// have the same fingerprint, so compare the canonical keys of expressions with the same
+    /// fingerprint to check if they are equivalent. The fingerprint is not a cryptographic hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (Apache-2.0 OR mit) AND isc")?;
+    /// assert_eq!(expression.fingerprint(), 0xf201_71aa_f37a_d51c);
+    ///
+    /// let other = SpdxExpression::parse("ISC AND MIT")?;
+    /// assert_eq!(other.fingerprint(), expression.fingerprint());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let key = self
+            .extras()
+            .canonical_key
+            .get_or_init(|| canonical::canonical_key(self.inner()).into());
+        digest::fnv1a(key.as_bytes())
+    }
+
+    /// Get a [`Cursor`] focused on the root of `Self`, for navigating the expression and
+    /// replacing parts of it.
+    pub fn cursor(&self) -> Cursor {
+        let spans = self.extras.get().and_then(|extras| extras.spans.clone());
+        Cursor::new(self.inner().clone(), spans)
+    }
+
+    /// Sort the operands of every `AND` and `OR` in `Self` into a deterministic order, keeping
+    /// everything else, e.g. duplicate operands and the case of identifiers, unchanged. Used to
+    /// keep the differences between stored versions of an expression small.
+    ///
+    /// Licenses on the SPDX License List come first, then `LicenseRef`s, then `LicenseRef`s of
+    /// other documents, and compound expressions last. Operands in the same group are sorted
+    /// alphabetically ignoring case, and by case if they only differ in case. Licenses with an
+    /// exception are sorted by the license.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("(MIT OR ISC) AND LicenseRef-a AND Apache-2.0")?;
+    /// assert_eq!(
+    ///     expression.sort_operands().to_string(),
+    ///     "Apache-2.0 AND LicenseRef-a AND (ISC OR MIT)"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn sort_operands(&self) -> Self {
+        sort::sort_operands(self.inner()).map_or_else(|| self.clone(), Self::from_inner)
+    }
+
+    /// Remove parentheses that don't change the meaning of `Self`, without the other
+    /// simplifications of [`SpdxExpression::simplify`]. Parentheses around licenses, around the
+    /// whole expression, around operands with a higher precedence than their operator and around
+    /// operands with the same operator are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("((MIT)) AND (ISC AND (MIT OR 0BSD))")?;
+    /// assert_eq!(
+    ///     expression.remove_redundant_parentheses().to_string(),
+    ///     "MIT AND ISC AND (MIT OR 0BSD)"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn remove_redundant_parentheses(&self) -> Self {
+        let removed = simplify::remove_redundant_parentheses(self.inner());
+        if removed == *self.inner() {
+            self.clone()
+        } else {
+            Self::from_inner(removed)
+        }
+    }
+
+    /// Replace "or later" licenses with the alternatives of their single versions in `versions`,
+    /// e.g. `GPL-2.0-or-later` with `GPL-2.0-only OR GPL-3.0-only`. Exceptions are attached to
+    /// every alternative. Used for analysis that only understands single versions of licenses.
+    /// Licenses that are not in any family of `versions` are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LicenseVersions, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND LGPL-2.1+ WITH exception")?;
+    /// assert_eq!(
+    ///     expression.expand_or_later(&LicenseVersions::spdx()).to_string(),
+    ///     "MIT AND (LGPL-2.1-only WITH exception OR LGPL-3.0-only WITH exception)"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub fn expand_or_later(&self, versions: &LicenseVersions) -> Self {
+        self.map_leaves(|leaf| {
+            let license = leaf.license()?;
+            let alternatives = versions.or_later_versions(license)?;
+            alternatives
+                .into_iter()
+                .map(|version| match leaf {
+                    ExpressionVariant::With(with) => ExpressionVariant::With(Box::new(
+                        WithExpression::new(version, with.exception.clone()),
+                    )),
+                    _ => ExpressionVariant::Simple(version),
+                })
+                .reduce(ExpressionVariant::or)
+        })
+    }
+
+    /// Replace alternatives of every version of a license from some version on with the "or
+    /// later" license of that version, e.g. `GPL-2.0-only OR GPL-3.0-only` with
+    /// `GPL-2.0-or-later`. This is the inverse of [`SpdxExpression::expand_or_later`], and
+    /// produces the most compact expression for reports. Only alternatives with the same
+    /// exception are collapsed.
     ///
-    /// let exceptions = expression.exceptions();
+    /// # Examples
     ///
-    /// assert_eq!(exceptions, HashSet::from_iter(["Classpath-exception-2.0"]));
+    /// ```
+    /// # use spdx_expression::{LicenseVersions, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("MIT AND (LGPL-2.1-only OR LGPL-3.0-only)")?;
+    /// assert_eq!(
+    ///     expression.collapse_or_later(&LicenseVersions::spdx()).to_string(),
+    ///     "MIT AND LGPL-2.1-or-later"
+    /// );
     /// # Ok::<(), SpdxExpressionError>(())
     /// ```
-    pub fn exceptions(&self) -> HashSet<&str> {
-        self.inner.exceptions()
+    #[must_use]
+    pub fn collapse_or_later(&self, versions: &LicenseVersions) -> Self {
+        versions::collapse_or_later(self.inner(), versions)
+            .map_or_else(|| self.clone(), Self::from_inner)
+    }
+
+    /// Replace the licenses of `Self` that are aliases in `aliases` with their expressions,
+    /// keeping the exceptions of the licenses.
+    fn translate_aliases(&self, aliases: &AliasTable) -> Self {
+        self.map_leaves(|leaf| match leaf {
+            ExpressionVariant::Simple(license) => aliases
+                .get(&license.to_string())
+                .map(|expression| expression.inner.unparenthesized().clone()),
+            ExpressionVariant::With(with) => aliases
+                .get(&with.license.to_string())
+                .map(|expression| expression.inner.with_exception(&with.exception)),
+            _ => None,
+        })
+    }
+
+    /// Apply `f` to the leaves of the expression, keeping `Self` if nothing changed.
+    fn map_leaves<F>(&self, mut f: F) -> Self
+    where
+        F: FnMut(&ExpressionVariant) -> Option<ExpressionVariant>,
+    {
+        self.inner
+            .map_leaves(&mut f)
+            .map_or_else(|| self.clone(), Self::from_inner)
+    }
+}
+
+impl std::fmt::Debug for SpdxExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpdxExpression")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for SpdxExpression {
+    fn eq(&self, other: &Self) -> bool {
+        // Expressions with different cached hashes can't be equal, which avoids walking the trees.
+        if let (Some(left), Some(right)) = (self.hash.get(), other.hash.get()) {
+            if left != right {
+                return false;
+            }
+        }
+
+        self.inner == other.inner
+    }
+}
+
+impl Eq for SpdxExpression {}
+
+/// The hash of the expression tree is computed once and cached, so hashing the same expression
+/// repeatedly, e.g. as a `HashMap` key, doesn't traverse the tree every time. The cache is never
+/// observable through the public API, so `clippy::mutable_key_type` warnings for `SpdxExpression`
+/// keys are false positives.
+impl Hash for SpdxExpression {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.tree_hash());
+    }
+}
+
+/// Expressions are ordered by their trees, so sorting gives the same order on every run and
+/// platform: single licenses, with or without an exception, come before compound expressions,
+/// licenses are ordered like [`SimpleExpression`]s, and compound expressions are ordered `AND`
+/// before `OR`, then by their operands. The order is consistent with [`PartialEq`], so
+/// expressions that differ only in case or parentheses are ordered next to each other but are
+/// not equal.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::SpdxExpression;
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut expressions = ["MIT OR ISC", "LicenseRef-a", "MIT WITH exc", "Apache-2.0", "MIT"]
+///     .iter()
+///     .map(|expression| SpdxExpression::parse(expression))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// expressions.sort();
+///
+/// assert_eq!(
+///     expressions.iter().map(ToString::to_string).collect::<Vec<_>>(),
+///     ["Apache-2.0", "MIT", "MIT WITH exc", "LicenseRef-a", "MIT OR ISC"]
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+impl Ord for SpdxExpression {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
+impl PartialOrd for SpdxExpression {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -164,15 +2808,30 @@ impl Default for SpdxExpression {
     }
 }
 
+/// Serializes as a string in human-readable formats like JSON. See
+/// [`structured`](crate::structured) for serializing the tree of the expression instead.
+///
+/// In formats that are not human-readable, like bincode or postcard, expressions serialize as
+/// bytes in the format of [`SpdxExpression::to_bytes`]. The format is versioned and stays stable
+/// across crate versions, and deserializing doesn't depend on the default [`ParserOptions`], so
+/// expressions can be kept in binary caches and messages between processes. Expressions stored
+/// as strings by earlier versions of the crate still deserialize.
 impl Serialize for SpdxExpression {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.collect_str(self)
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
     }
 }
 
+/// Maximum number of bytes preallocated for an expression encoded as a sequence.
+const MAX_PREALLOCATED_BYTES: usize = 1024 * 1024;
+
 struct SpdxExpressionVisitor;
 
 impl<'de> Visitor<'de> for SpdxExpressionVisitor {
@@ -186,7 +2845,7 @@ impl<'de> Visitor<'de> for SpdxExpressionVisitor {
     where
         E: serde::de::Error,
     {
-        SpdxExpression::parse(v)
+        SpdxExpression::parse_with(v, &ParserOptions::current())
             .map_err(|err| E::custom(format!("error parsing the expression: {err}")))
     }
 
@@ -203,6 +2862,39 @@ impl<'de> Visitor<'de> for SpdxExpressionVisitor {
     {
         self.visit_str(&v)
     }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        // Formats like bincode and postcard encode strings and bytes the same way, so expressions
+        // stored as strings by earlier versions arrive here as their UTF-8 text. The binary
+        // encoding starts with a version byte that can't start an expression.
+        if v.first() != Some(&binary::FORMAT_VERSION) {
+            if let Ok(text) = std::str::from_utf8(v) {
+                return self.visit_str(text);
+            }
+        }
+        SpdxExpression::from_bytes(v)
+            .map_err(|err| E::custom(format!("error decoding the expression: {err}")))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        // Some formats encode bytes as sequences of integers. Don't trust the size hint for more
+        // than a preallocation of reasonable size, like serde does.
+        let capacity = seq
+            .size_hint()
+            .unwrap_or_default()
+            .min(MAX_PREALLOCATED_BYTES);
+        let mut bytes = Vec::with_capacity(capacity);
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
 }
 
 impl<'de> Deserialize<'de> for SpdxExpression {
@@ -210,13 +2902,29 @@ impl<'de> Deserialize<'de> for SpdxExpression {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_str(SpdxExpressionVisitor)
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(SpdxExpressionVisitor)
+        } else {
+            deserializer.deserialize_bytes(SpdxExpressionVisitor)
+        }
+    }
+}
+
+/// Parses with the default options of the current thread, see [`ParserOptions::current`].
+impl FromStr for SpdxExpression {
+    type Err = SpdxExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &ParserOptions::current())
     }
 }
 
 impl Display for SpdxExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.inner)
+        match self.source() {
+            Some(source) => f.write_str(source),
+            None => write!(f, "{}", self.inner),
+        }
     }
 }
 
@@ -227,6 +2935,7 @@ mod tests {
     use serde_json::Value;
 
     use super::*;
+    use crate::{error::ErrorCode, Patch};
 
     #[test]
     fn test_parsing_works() {
@@ -234,6 +2943,26 @@ mod tests {
         assert_eq!(expression.to_string(), "MIT AND (Apache-2.0 OR ISC)");
     }
 
+    #[test]
+    fn preserve_source_until_modified() {
+        struct Unchanged;
+        impl VisitorMut for Unchanged {}
+
+        let source = " MIT\tand (isc OR ((0BSD)))  ";
+        let expression = SpdxExpression::parse_preserving(source).unwrap();
+        assert_eq!(expression.to_string(), source);
+        assert_eq!(expression.source(), Some(source));
+        assert_eq!(
+            serde_json::to_string(&expression).unwrap(),
+            serde_json::to_string(source).unwrap()
+        );
+        assert_eq!(expression.simplify().source(), None);
+
+        let mut walked = expression;
+        walked.walk_mut(&mut Unchanged);
+        assert_eq!(walked.to_string(), "MIT AND (isc OR ((0BSD)))");
+    }
+
     #[test]
     fn test_identifiers_from_simple_expression() {
         let expression = SpdxExpression::parse("MIT").unwrap();
@@ -300,6 +3029,394 @@ mod tests {
         assert_eq!(exceptions, HashSet::from_iter(["Classpath-exception-2.0"]));
     }
 
+    #[test]
+    fn hash_is_cached_and_consistent_with_eq() {
+        let expression = SpdxExpression::parse("MIT AND (Apache-2.0 OR ISC)").unwrap();
+        let same = SpdxExpression::parse("MIT AND (Apache-2.0 OR ISC)").unwrap();
+        let other = SpdxExpression::parse("MIT AND (Apache-2.0 OR BSD-3-Clause)").unwrap();
+
+        assert!(expression.hash.get().is_none());
+
+        let mut set = HashSet::new();
+        set.insert(expression.clone());
+
+        assert!(set.contains(&same));
+        assert!(!set.contains(&other));
+        assert_eq!(same.hash.get(), Some(&expression.tree_hash()));
+        assert_ne!(same, other);
+    }
+
+    #[test]
+    fn expression_is_not_much_larger_than_its_tree() {
+        assert!(
+            std::mem::size_of::<SpdxExpression>() <= std::mem::size_of::<ExpressionVariant>() + 32
+        );
+        assert!(SpdxExpression::parse("MIT OR ISC")
+            .unwrap()
+            .extras
+            .get()
+            .is_none());
+    }
+
+    #[test]
+    fn normalization_is_cached_until_mutated() {
+        struct Rename;
+
+        impl VisitorMut for Rename {
+            fn visit_license(&mut self, license: &mut SimpleExpression) {
+                license.identifier = license.identifier.replace("ISC", "0BSD");
+            }
+        }
+
+        let mut expression = SpdxExpression::parse("(MIT OR ISC) AND MIT AND ISC").unwrap();
+        assert!(expression.extras().simplified.get().is_none());
+
+        assert_eq!(expression.simplify().to_string(), "MIT AND ISC");
+        assert_eq!(expression.canonical_key(), "v1:isc AND mit");
+        let clone = expression.clone();
+        assert!(clone.extras().simplified.get().is_some());
+        assert!(clone.extras().canonical_key.get().is_some());
+
+        expression.walk_mut(&mut Rename);
+        assert!(expression.extras().simplified.get().is_none());
+        assert!(expression.extras().canonical_key.get().is_none());
+        assert_eq!(expression.simplify().to_string(), "MIT AND 0BSD");
+        assert_eq!(expression.canonical_key(), "v1:0bsd AND mit");
+        assert_eq!(clone.simplify().to_string(), "MIT AND ISC");
+    }
+
+    #[test]
+    fn indexed_expression_answers_queries_like_unindexed() {
+        let input = "(MIT OR Apache-2.0 AND (GPL-2.0-only WITH Classpath-exception-2.0 OR ISC))";
+        let indexed = SpdxExpression::parse_indexed(input).unwrap();
+        let unindexed = SpdxExpression::parse(input).unwrap();
+
+        assert!(indexed.index().is_some());
+        assert!(unindexed.index().is_none());
+        assert_eq!(indexed.licenses(), unindexed.licenses());
+        assert_eq!(indexed.identifiers(), unindexed.identifiers());
+        for identifier in [
+            "MIT",
+            "GPL-2.0-only",
+            "Classpath-exception-2.0",
+            "BSD-3-Clause",
+        ] {
+            assert_eq!(
+                indexed.contains_license(identifier),
+                unindexed.contains_license(identifier)
+            );
+        }
+    }
+
+    #[test]
+    fn substitute_licenses_with_expressions() {
+        let expression = SpdxExpression::parse_indexed(
+            "LicenseRef-gpl OR MIT AND LicenseRef-gpl WITH Classpath-exception-2.0",
+        )
+        .unwrap();
+        let replacement = SpdxExpression::parse("GPL-2.0-only OR GPL-3.0-only").unwrap();
+
+        let substituted = expression.substitute("LicenseRef-gpl", &replacement);
+
+        assert_eq!(
+            substituted.to_string(),
+            "(GPL-2.0-only OR GPL-3.0-only) OR MIT AND (GPL-2.0-only WITH Classpath-exception-2.0 \
+             OR GPL-3.0-only WITH Classpath-exception-2.0)"
+        );
+        assert_eq!(
+            substituted.to_string(),
+            SpdxExpression::parse(&expression.to_string())
+                .unwrap()
+                .substitute("LicenseRef-gpl", &replacement)
+                .to_string()
+        );
+        assert!(substituted.index().is_none());
+        assert!(!substituted.contains_license("LicenseRef-gpl"));
+        assert_eq!(
+            SpdxExpression::parse(&substituted.to_string()).unwrap(),
+            substituted
+        );
+        assert_eq!(expression.substitute("ISC", &replacement), expression);
+        assert_eq!(expression.substitute("OR", &replacement), expression);
+    }
+
+    #[test]
+    fn remove_licenses_with_and_without_exceptions() {
+        let expression = SpdxExpression::parse(
+            "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT AND GPL-2.0-only",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expression
+                .remove_license("GPL-2.0-only")
+                .unwrap()
+                .to_string(),
+            "MIT"
+        );
+        assert_eq!(expression.remove_license("ISC").unwrap(), expression);
+        assert!(matches!(
+            SpdxExpression::parse("MIT AND MIT")
+                .unwrap()
+                .remove_license("MIT"),
+            Err(SpdxExpressionError::Empty(_))
+        ));
+    }
+
+    #[test]
+    fn filter_licenses() {
+        let expression =
+            SpdxExpression::parse("MIT AND (ISC OR LicenseRef-a) OR LicenseRef-b").unwrap();
+
+        assert_eq!(
+            expression
+                .filter(|license| !license.license_ref)
+                .unwrap()
+                .to_string(),
+            "MIT AND ISC"
+        );
+        assert_eq!(expression.filter(|_| true).unwrap(), expression);
+        assert!(expression.filter(|_| false).is_none());
+    }
+
+    #[test]
+    fn join_expressions() {
+        let expressions = ["MIT OR ISC", "(Apache-2.0 OR 0BSD)", "Zlib AND Unlicense"]
+            .iter()
+            .map(|expression| SpdxExpression::parse(expression).unwrap())
+            .collect::<Vec<_>>();
+
+        let or = SpdxExpression::join(Operator::Or, &expressions).unwrap();
+        let and = SpdxExpression::join(Operator::And, &expressions).unwrap();
+
+        assert_eq!(
+            or.to_string(),
+            "MIT OR ISC OR Apache-2.0 OR 0BSD OR Zlib AND Unlicense"
+        );
+        assert_eq!(
+            and.to_string(),
+            "(MIT OR ISC) AND (Apache-2.0 OR 0BSD) AND Zlib AND Unlicense"
+        );
+        for joined in [or, and] {
+            assert_eq!(SpdxExpression::parse(&joined.to_string()).unwrap(), joined);
+        }
+        assert!(SpdxExpression::join(Operator::And, &[]).is_none());
+    }
+
+    #[test]
+    fn split_conjuncts() {
+        let conjuncts = |expression: &str| {
+            SpdxExpression::parse(expression)
+                .unwrap()
+                .conjuncts()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(conjuncts("(MIT)"), ["MIT"]);
+        assert_eq!(conjuncts("MIT OR ISC AND 0BSD"), ["MIT OR ISC AND 0BSD"]);
+        assert_eq!(
+            conjuncts("((MIT OR ISC) AND (0BSD WITH exc AND Zlib))"),
+            ["MIT OR ISC", "0BSD WITH exc", "Zlib"]
+        );
+    }
+
+    #[test]
+    fn set_and_replace_exceptions() {
+        let expression = SpdxExpression::parse(
+            "GPL-2.0-only OR (MIT AND GPL-2.0-only WITH exc) OR GPL-3.0-only WITH exc",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expression
+                .set_exception("GPL-2.0-only", "Classpath-exception-2.0")
+                .to_string(),
+            "GPL-2.0-only WITH Classpath-exception-2.0 OR (MIT AND GPL-2.0-only WITH \
+             Classpath-exception-2.0) OR GPL-3.0-only WITH exc"
+        );
+        assert_eq!(
+            expression.replace_exception("exc", "other").to_string(),
+            "GPL-2.0-only OR (MIT AND GPL-2.0-only WITH other) OR GPL-3.0-only WITH other"
+        );
+        assert_eq!(expression.replace_exception("missing", "other"), expression);
+        assert_eq!(expression.set_exception("ISC", "other"), expression);
+
+        for invalid in ["", "exc OR GPL-3.0-only", "exc+"] {
+            assert_eq!(
+                expression.set_exception("GPL-2.0-only", invalid),
+                expression
+            );
+            assert_eq!(expression.replace_exception("exc", invalid), expression);
+        }
+    }
+
+    #[test]
+    fn strip_all_exceptions() {
+        let expression =
+            SpdxExpression::parse("MIT WITH a OR (ISC AND 0BSD WITH b) OR MIT WITH b").unwrap();
+
+        let (stripped, exceptions) = expression.strip_exceptions();
+
+        assert_eq!(stripped.to_string(), "MIT OR (ISC AND 0BSD) OR MIT");
+        assert_eq!(
+            exceptions,
+            HashSet::from_iter(["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(stripped.strip_exceptions(), (stripped, HashSet::new()));
+    }
+
+    #[test]
+    fn strip_document_refs_of_all_or_some_documents() {
+        let expression = SpdxExpression::parse(
+            "DocumentRef-a:LicenseRef-x WITH exc OR (MIT AND DocumentRef-b:LicenseRef-y)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expression.strip_document_refs().to_string(),
+            "LicenseRef-x WITH exc OR (MIT AND LicenseRef-y)"
+        );
+        assert_eq!(
+            expression.strip_document_refs_of(&["b"]).to_string(),
+            "DocumentRef-a:LicenseRef-x WITH exc OR (MIT AND LicenseRef-y)"
+        );
+        assert_eq!(expression.strip_document_refs_of(&["c"]), expression);
+    }
+
+    #[test]
+    fn detect_dual_licenses() {
+        for (expression, pair) in [
+            ("MIT OR Apache-2.0", Some(("MIT", "Apache-2.0"))),
+            (
+                "((GPL-2.0-only WITH exc) OR (MIT))",
+                Some(("GPL-2.0-only WITH exc", "MIT")),
+            ),
+            ("MIT OR MIT", None),
+            ("MIT OR ISC OR 0BSD", None),
+            ("MIT AND ISC", None),
+            ("MIT OR ISC AND 0BSD", None),
+            ("MIT", None),
+        ] {
+            let expression = SpdxExpression::parse(expression).unwrap();
+            assert_eq!(
+                expression
+                    .dual_license_pair()
+                    .map(|(first, second)| (first.to_string(), second.to_string())),
+                pair.map(|(first, second)| (first.to_string(), second.to_string())),
+                "{expression}"
+            );
+            assert_eq!(expression.is_pure_dual_license(), pair.is_some());
+        }
+    }
+
+    #[test]
+    fn get_single_license() {
+        assert_eq!(
+            SpdxExpression::parse("((LicenseRef-a))")
+                .unwrap()
+                .is_single_license(),
+            Some(&SimpleExpression::new("a".to_string(), None, true))
+        );
+        for expression in ["MIT AND MIT", "(MIT WITH exc)", "MIT OR ISC"] {
+            let expression = SpdxExpression::parse(expression).unwrap();
+            assert!(expression.is_single_license().is_none(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn detect_license_and_document_refs() {
+        for (expression, license_ref, document_ref) in [
+            ("MIT AND (Apache-2.0 OR ISC)", false, false),
+            ("MIT AND (Apache-2.0 OR LicenseRef-a WITH exc)", true, false),
+            ("MIT OR DocumentRef-doc:LicenseRef-a", true, true),
+        ] {
+            let expression = SpdxExpression::parse(expression).unwrap();
+            assert_eq!(expression.has_license_ref(), license_ref);
+            assert_eq!(expression.has_document_ref(), document_ref);
+        }
+    }
+
+    #[test]
+    fn anonymize_license_refs_consistently() {
+        let expression = SpdxExpression::parse(
+            "LicenseRef-a WITH exc OR MIT AND (DocumentRef-doc:LicenseRef-a OR LicenseRef-b)",
+        )
+        .unwrap();
+
+        let anonymized = expression.anonymize_license_refs();
+
+        let licenses = anonymized
+            .to_postfix()
+            .into_iter()
+            .filter_map(|token| match token {
+                PostfixToken::License(license) => Some(license),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let [first, _, second, third] = &licenses[..] else {
+            panic!("unexpected licenses: {:?}", licenses);
+        };
+        assert_eq!(anonymized.exceptions(), HashSet::from_iter(["exc"]));
+        assert!(anonymized.contains_license("MIT"));
+        assert!(first.license_ref && first.identifier.starts_with("anon-"));
+        assert_eq!(second.identifier, first.identifier);
+        assert!(second.document_ref.as_ref().unwrap().starts_with("anon-"));
+        assert_ne!(third.identifier, first.identifier);
+        assert_eq!(expression.anonymize_license_refs(), anonymized);
+    }
+
+    #[test]
+    fn fingerprint_canonical_key() {
+        let expression = SpdxExpression::parse("(Apache-2.0 OR MIT) AND MIT").unwrap();
+        assert_eq!(expression.canonical_key(), "v1:mit");
+        assert_eq!(expression.fingerprint(), digest::fnv1a(b"v1:mit"));
+
+        let other = SpdxExpression::parse("MIT AND (ISC OR mit)").unwrap();
+        assert_eq!(other.fingerprint(), expression.fingerprint());
+        assert_ne!(
+            SpdxExpression::parse("MIT OR ISC").unwrap().fingerprint(),
+            expression.fingerprint()
+        );
+    }
+
+    #[test]
+    fn keyed_fingerprint_keeps_structure() {
+        let expression = SpdxExpression::parse(
+            "GPL-2.0+ WITH exc OR (DocumentRef-doc:LicenseRef-a AND LicenseRef-a)",
+        )
+        .unwrap();
+
+        let fingerprint = expression.keyed_fingerprint(&[1; 16]);
+
+        let licenses = fingerprint
+            .to_postfix()
+            .into_iter()
+            .filter_map(|token| match token {
+                PostfixToken::License(license) => Some(license),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(licenses.len(), 3);
+        assert!(licenses
+            .iter()
+            .all(|license| license.identifier.starts_with("anon-")));
+        assert!(licenses[0].or_later && !licenses[0].license_ref);
+        assert!(licenses[1]
+            .document_ref
+            .as_ref()
+            .unwrap()
+            .starts_with("anon-"));
+        assert_eq!(licenses[1].identifier, licenses[2].identifier);
+        assert!(fingerprint
+            .exceptions()
+            .iter()
+            .all(|exception| exception.starts_with("anon-")));
+        assert_eq!(fingerprint.structure_kind(), expression.structure_kind());
+        assert_eq!(expression.keyed_fingerprint(&[1; 16]), fingerprint);
+    }
+
     #[test]
     fn serialize_expression_correctly() {
         let expression = SpdxExpression::parse("MIT OR ISC").unwrap();
@@ -319,4 +3436,233 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn round_trip_in_binary_formats() {
+        for expression in [
+            "MIT",
+            "GPL-2.0+ WITH Classpath-exception-2.0",
+            "DocumentRef-x:LicenseRef-a OR (MIT AND ISC)",
+            "((MIT)) AND (ISC OR 0BSD)",
+        ] {
+            let expression = SpdxExpression::parse(expression).unwrap();
+
+            let bincode: SpdxExpression =
+                bincode::deserialize(&bincode::serialize(&expression).unwrap()).unwrap();
+            assert_eq!(bincode, expression);
+            assert_eq!(bincode.to_string(), expression.to_string());
+
+            let postcard: SpdxExpression =
+                postcard::from_bytes(&postcard::to_allocvec(&expression).unwrap()).unwrap();
+            assert_eq!(postcard, expression);
+            assert_eq!(postcard.to_string(), expression.to_string());
+        }
+
+        let expression = SpdxExpression::parse("MIT AND (ISC OR 0BSD)").unwrap();
+        let encoded = postcard::to_allocvec(&expression).unwrap();
+        assert_eq!(encoded[1..], expression.to_bytes()[..]);
+        assert!(postcard::from_bytes::<SpdxExpression>(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn round_trip_long_chains_in_binary_formats() {
+        let chain = (0..300)
+            .map(|index| format!("LicenseRef-{index}"))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let expression = SpdxExpression::parse(&chain).unwrap();
+
+        let bincode: SpdxExpression =
+            bincode::deserialize(&bincode::serialize(&expression).unwrap()).unwrap();
+        assert_eq!(bincode, expression);
+
+        let postcard: SpdxExpression =
+            postcard::from_bytes(&postcard::to_allocvec(&expression).unwrap()).unwrap();
+        assert_eq!(postcard, expression);
+    }
+
+    #[test]
+    fn round_trip_collections_in_binary_formats() {
+        let from = SpdxExpression::parse("MIT OR Apache-2.0").unwrap();
+        let to = SpdxExpression::parse("MIT OR Apache-2.0 WITH exc").unwrap();
+
+        let patch = Patch::new(&from, &to);
+        let decoded: Patch = bincode::deserialize(&bincode::serialize(&patch).unwrap()).unwrap();
+        assert_eq!(decoded.apply(&from).unwrap(), to);
+
+        let aliases = AliasTable::from_iter([("GPLv2".to_string(), to.clone())]);
+        let decoded: AliasTable =
+            postcard::from_bytes(&postcard::to_allocvec(&aliases).unwrap()).unwrap();
+        assert_eq!(decoded, aliases);
+
+        let mut weights = LicenseWeights::new();
+        weights.insert("MIT", 1);
+        weights.forbid("GPL-2.0-only");
+        let decoded: LicenseWeights =
+            bincode::deserialize(&bincode::serialize(&weights).unwrap()).unwrap();
+        assert_eq!(decoded, weights);
+
+        let map = HashMap::from([(from, vec![to])]);
+        let decoded: HashMap<SpdxExpression, Vec<SpdxExpression>> =
+            postcard::from_bytes(&postcard::to_allocvec(&map).unwrap()).unwrap();
+        assert_eq!(decoded, map);
+    }
+
+    #[test]
+    fn deserialize_expressions_stored_as_strings_in_binary_formats() {
+        let expected = SpdxExpression::parse("MIT OR ISC").unwrap();
+
+        let bincode: SpdxExpression =
+            bincode::deserialize(&bincode::serialize("MIT OR ISC").unwrap()).unwrap();
+        assert_eq!(bincode, expected);
+
+        let postcard: SpdxExpression =
+            postcard::from_bytes(&postcard::to_allocvec("MIT OR ISC").unwrap()).unwrap();
+        assert_eq!(postcard, expected);
+
+        assert!(
+            bincode::deserialize::<SpdxExpression>(&bincode::serialize("MIT OR").unwrap()).is_err()
+        );
+    }
+
+    #[test]
+    fn preallocate_cautiously_for_sequences() {
+        use serde::de::IntoDeserializer;
+
+        struct Lying(std::vec::IntoIter<u8>);
+        impl<'de> serde::de::SeqAccess<'de> for Lying {
+            type Error = serde::de::value::Error;
+
+            fn next_element_seed<T: serde::de::DeserializeSeed<'de>>(
+                &mut self,
+                seed: T,
+            ) -> Result<Option<T::Value>, Self::Error> {
+                self.0
+                    .next()
+                    .map(|byte| seed.deserialize(byte.into_deserializer()))
+                    .transpose()
+            }
+
+            fn size_hint(&self) -> Option<usize> {
+                Some(usize::MAX)
+            }
+        }
+
+        let bytes = SpdxExpression::parse("MIT").unwrap().to_bytes();
+        let expression = SpdxExpressionVisitor
+            .visit_seq(Lying(bytes.into_iter()))
+            .unwrap();
+        assert_eq!(expression.to_string(), "MIT");
+    }
+
+    #[test]
+    fn aggregate_files_with_special_licenses() {
+        let aggregate = |files: &[&str]| {
+            let files = files
+                .iter()
+                .map(|file| SpdxExpression::parse(file).unwrap())
+                .collect::<Vec<_>>();
+            SpdxExpression::aggregate_files(&files).to_string()
+        };
+        assert_eq!(aggregate(&["NONE", "NONE"]), "NONE");
+        assert_eq!(
+            aggregate(&["NOASSERTION", "MIT", "(NOASSERTION)", "NONE"]),
+            "NOASSERTION AND MIT"
+        );
+        assert_eq!(
+            aggregate(&["(MIT AND ISC)", "ISC AND MIT", "MIT OR GPL-2.0-only"]),
+            "MIT AND ISC"
+        );
+    }
+
+    #[test]
+    fn parse_lenient_translates_single_word_aliases() {
+        let debian = AliasTable::debian();
+        assert_eq!(
+            SpdxExpression::parse_lenient("Expat or GPL-2+", &debian)
+                .unwrap()
+                .to_string(),
+            "MIT OR GPL-2.0-or-later"
+        );
+
+        let fedora = AliasTable::fedora();
+        for (alias, translated) in [
+            ("GPLv2", "GPL-2.0-only"),
+            ("AGPLv3", "AGPL-3.0-only"),
+            ("Boost", "BSL-1.0"),
+            ("CC0", "CC0-1.0"),
+            ("CDDL", "CDDL-1.0"),
+            ("OFL", "OFL-1.1"),
+        ] {
+            assert_eq!(
+                SpdxExpression::parse_lenient(alias, &fedora)
+                    .unwrap()
+                    .to_string(),
+                translated
+            );
+        }
+        assert_eq!(
+            SpdxExpression::parse_lenient("(GPLv2 WITH exc OR MIT) AND ISC", &fedora)
+                .unwrap()
+                .to_string(),
+            "(GPL-2.0-only WITH exc OR MIT) AND ISC"
+        );
+        assert_eq!(
+            SpdxExpression::parse_lenient("Apache-2.0 OR LicenseRef-a", &fedora)
+                .unwrap()
+                .to_string(),
+            "Apache-2.0 OR LicenseRef-a"
+        );
+    }
+
+    #[test]
+    fn parse_with_strict_and_lenient_options() {
+        let strict = ParserOptions {
+            uppercase_operators: true,
+            ..ParserOptions::default()
+        };
+        let error = SpdxExpression::parse_with("MIT OR (ISC and 0BSD)", &strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parsing for expression `MIT OR (ISC and 0BSD)` failed at byte 12: unexpected \
+             `and 0BSD)`."
+        );
+        assert!(SpdxExpression::parse_with("MIT OR (ISC AND 0BSD)", &strict).is_ok());
+
+        let lenient = ParserOptions {
+            aliases: Some(Arc::new(AliasTable::fedora())),
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            SpdxExpression::parse_with("ASL 2.0 or GPLv2+", &lenient).unwrap(),
+            SpdxExpression::parse("Apache-2.0 OR GPL-2.0-or-later").unwrap()
+        );
+        assert!(SpdxExpression::parse_with("ASL 2.0 or GPLv2+", &strict).is_err());
+        assert_eq!(
+            SpdxExpression::parse_with("GPLv2 AND CC0 OR MIT", &lenient)
+                .unwrap()
+                .to_string(),
+            "GPL-2.0-only AND CC0-1.0 OR MIT"
+        );
+    }
+
+    #[test]
+    fn from_str_and_deserialize_use_thread_default_options() {
+        ParserOptions::set_thread_default(Some(ParserOptions {
+            max_steps: Some(1),
+            ..ParserOptions::default()
+        }));
+        assert!("MIT".parse::<SpdxExpression>().is_ok());
+        assert_eq!(
+            "MIT OR ISC"
+                .parse::<SpdxExpression>()
+                .unwrap_err()
+                .error_code(),
+            ErrorCode::BudgetExceeded
+        );
+        assert!(serde_json::from_value::<SpdxExpression>(Value::from("MIT OR ISC")).is_err());
+
+        ParserOptions::set_thread_default(None);
+        assert!("MIT OR ISC".parse::<SpdxExpression>().is_ok());
+    }
 }
@@ -4,27 +4,38 @@
 
 //! Private inner structs for [`crate::SpdxExpression`].
 
-use std::{collections::HashSet, fmt::Display};
+use std::{cmp::Ordering, collections::HashSet, fmt::Display, sync::Arc};
 
-use nom::Finish;
 use serde::{de::Visitor, Deserialize, Serialize};
 
 use crate::{
-    error::SpdxExpressionError,
-    parser::{parse_expression, simple_expression},
+    error::{ParseError, SpdxExpressionError},
+    lint::{self, LintKind},
+    parser::{parse_expression, simple_expression, Operator},
+    parser_options::{self, ParserOptions},
+    visit::VisitorMut,
 };
 
 /// Simple SPDX license expression.
+///
+/// Licenses are ordered by group: licenses on the SPDX License List first, then `LicenseRef`s and
+/// last `LicenseRef`s of other documents. Licenses in the same group are ordered by their
+/// `DocumentRef`s and identifiers ignoring case, then by case, then without `+` before with `+`.
+/// The order doesn't depend on the platform or on anything but the licenses themselves.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SimpleExpression {
     /// The license identifier.
     pub identifier: String,
 
-    /// Optional DocumentRef for the expression.
+    /// Optional `DocumentRef` for the expression.
     pub document_ref: Option<String>,
 
     /// `true` if the expression is a user defined license reference.
     pub license_ref: bool,
+
+    /// `true` if the identifier is followed by `+`, allowing later versions of the license.
+    /// The `+` is not included in `identifier`.
+    pub or_later: bool,
 }
 
 impl Serialize for SimpleExpression {
@@ -87,24 +98,77 @@ impl Display for SimpleExpression {
             });
 
         let license_ref = if self.license_ref { "LicenseRef-" } else { "" };
+        let or_later = if self.or_later { "+" } else { "" };
         write!(
             f,
-            "{document_ref}{license_ref}{identifier}",
+            "{document_ref}{license_ref}{identifier}{or_later}",
             identifier = self.identifier
         )
     }
 }
 
+impl Ord for SimpleExpression {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let group = |license: &Self| match license {
+            Self {
+                document_ref: Some(_),
+                ..
+            } => 2,
+            Self {
+                license_ref: true, ..
+            } => 1,
+            _ => 0,
+        };
+
+        group(self)
+            .cmp(&group(other))
+            .then_with(|| match (&self.document_ref, &other.document_ref) {
+                (Some(left), Some(right)) => cmp_ignoring_case(left, right),
+                (left, right) => left.cmp(right),
+            })
+            .then_with(|| cmp_ignoring_case(&self.identifier, &other.identifier))
+            .then_with(|| self.or_later.cmp(&other.or_later))
+            .then_with(|| self.license_ref.cmp(&other.license_ref))
+    }
+}
+
+impl PartialOrd for SimpleExpression {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl SimpleExpression {
-    /// Create a new simple expression.
+    /// Create a new simple expression without the "or later" `+`, see
+    /// [`SimpleExpression::with_or_later`].
     pub const fn new(identifier: String, document_ref: Option<String>, license_ref: bool) -> Self {
         Self {
             identifier,
             document_ref,
             license_ref,
+            or_later: false,
         }
     }
 
+    /// Set whether the license is followed by `+`, allowing later versions of the license.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SimpleExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let license = SimpleExpression::new("GPL-2.0".to_string(), None, false).with_or_later(true);
+    /// assert_eq!(license, SimpleExpression::parse("GPL-2.0+")?);
+    /// assert_eq!(license.to_string(), "GPL-2.0+");
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    #[must_use]
+    pub const fn with_or_later(mut self, or_later: bool) -> Self {
+        self.or_later = or_later;
+        self
+    }
+
     /// Parse a simple expression.
     ///
     /// # Examples
@@ -130,17 +194,20 @@ impl SimpleExpression {
     ///
     /// Fails if parsing fails.
     pub fn parse(expression: &str) -> Result<Self, SpdxExpressionError> {
-        let (remaining, result) = simple_expression(expression)?;
+        let (remaining, result) = simple_expression(expression)
+            .map_err(|err| SpdxExpressionError::Parse(ParseError::from_nom(expression, &err)))?;
 
         if remaining.is_empty() {
             Ok(result)
         } else {
-            Err(SpdxExpressionError::Parse(expression.to_string()))
+            Err(SpdxExpressionError::Parse(ParseError::unexpected(
+                expression, remaining,
+            )))
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WithExpression {
     pub license: SimpleExpression,
     pub exception: String,
@@ -152,6 +219,21 @@ impl WithExpression {
     }
 }
 
+/// Ordered by the license, then by the exception ignoring case, then by case.
+impl Ord for WithExpression {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.license
+            .cmp(&other.license)
+            .then_with(|| cmp_ignoring_case(&self.exception, &other.exception))
+    }
+}
+
+impl PartialOrd for WithExpression {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Display for WithExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -163,13 +245,31 @@ impl Display for WithExpression {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq)]
+/// Node of a parsed expression. Child nodes are reference counted, so cloning an expression is
+/// cheap and edits only need to copy the nodes on the path to the edited node.
+///
+/// The size of every node is the size of its largest variant, so the less common `With` variant
+/// is boxed to keep the nodes no larger than a `SimpleExpression`.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub enum ExpressionVariant {
     Simple(SimpleExpression),
-    With(WithExpression),
-    And(Box<Self>, Box<Self>),
-    Or(Box<Self>, Box<Self>),
-    Parens(Box<Self>),
+    With(Box<WithExpression>),
+    And(Arc<Self>, Arc<Self>),
+    Or(Arc<Self>, Arc<Self>),
+    Parens(Arc<Self>),
+}
+
+/// Result of [`ExpressionVariant::prune`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Pruned {
+    /// Nothing was removed.
+    Unchanged,
+
+    /// Everything was removed.
+    Removed,
+
+    /// Some nodes were removed, leaving the expression.
+    Replaced(ExpressionVariant),
 }
 
 impl Display for ExpressionVariant {
@@ -186,16 +286,488 @@ impl Display for ExpressionVariant {
     }
 }
 
+/// Leaves are ordered before compound expressions. Leaves are ordered by their licenses, and a
+/// license without an exception before the license with exceptions. Compound expressions are
+/// ordered `AND` before `OR`, then by their left and right operands. Parentheses are only
+/// compared if the expressions are otherwise equal, from the root down, with fewer parentheses
+/// first.
+impl Ord for ExpressionVariant {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_ignoring_parentheses(other)
+            .then_with(|| self.cmp_parentheses(other))
+    }
+}
+
+impl PartialOrd for ExpressionVariant {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare strings ignoring ASCII case, and by case if they only differ in case.
+fn cmp_ignoring_case(left: &str, right: &str) -> Ordering {
+    left.bytes()
+        .map(|byte| byte.to_ascii_lowercase())
+        .cmp(right.bytes().map(|byte| byte.to_ascii_lowercase()))
+        .then_with(|| left.cmp(right))
+}
+
 impl ExpressionVariant {
+    /// Get the node inside any parentheses around `self`, with the number of parentheses.
+    fn strip_parentheses(&self) -> (&Self, usize) {
+        let mut node = self;
+        let mut depth = 0;
+        while let Self::Parens(inner) = node {
+            node = inner;
+            depth += 1;
+        }
+        (node, depth)
+    }
+
+    /// Compare the trees of `self` and `other` like [`Ord`], as if they had no parentheses.
+    fn cmp_ignoring_parentheses(&self, other: &Self) -> Ordering {
+        match (self.strip_parentheses().0, other.strip_parentheses().0) {
+            (Self::Simple(left), Self::Simple(right)) => left.cmp(right),
+            (Self::With(left), Self::With(right)) => left.cmp(right),
+            (Self::Simple(left), Self::With(right)) => {
+                left.cmp(&right.license).then(Ordering::Less)
+            }
+            (Self::With(left), Self::Simple(right)) => {
+                left.license.cmp(right).then(Ordering::Greater)
+            }
+            (Self::Simple(_) | Self::With(_), _) => Ordering::Less,
+            (_, Self::Simple(_) | Self::With(_)) => Ordering::Greater,
+            (Self::And(left, left_right), Self::And(right, right_right))
+            | (Self::Or(left, left_right), Self::Or(right, right_right)) => left
+                .cmp_ignoring_parentheses(right)
+                .then_with(|| left_right.cmp_ignoring_parentheses(right_right)),
+            (Self::And(..), _) => Ordering::Less,
+            (_, Self::And(..)) => Ordering::Greater,
+            (Self::Or(..) | Self::Parens(_), _) => {
+                unreachable!("parentheses are stripped and other pairs are compared above")
+            }
+        }
+    }
+
+    /// Compare the parentheses of `self` and `other`, which are equal ignoring parentheses.
+    fn cmp_parentheses(&self, other: &Self) -> Ordering {
+        let (left, left_depth) = self.strip_parentheses();
+        let (right, right_depth) = other.strip_parentheses();
+
+        left_depth
+            .cmp(&right_depth)
+            .then_with(|| match (left, right) {
+                (Self::And(left, left_right), Self::And(right, right_right))
+                | (Self::Or(left, left_right), Self::Or(right, right_right)) => left
+                    .cmp_parentheses(right)
+                    .then_with(|| left_right.cmp_parentheses(right_right)),
+                _ => Ordering::Equal,
+            })
+    }
+
     pub fn parse(i: &str) -> Result<Self, SpdxExpressionError> {
+        Self::parse_with(i, &ParserOptions::default())
+    }
+
+    pub fn parse_with(i: &str, options: &ParserOptions) -> Result<Self, SpdxExpressionError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse", input_length = i.len()).entered();
+
+        let (result, exceeded) = parser_options::with_budget(options, || Self::parse_untraced(i));
+        let result = match result {
+            _ if exceeded => Err(SpdxExpressionError::Budget(i.to_string())),
+            Ok(expression) if options.uppercase_operators => {
+                Self::check_uppercase_operators(i, &expression).map(|()| expression)
+            }
+            result => result,
+        };
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(_) => tracing::debug!(outcome = "parsed", "parsed expression"),
+            Err(error) => tracing::debug!(
+                outcome = "failed",
+                code = error.error_code().code(),
+                "failed to parse expression"
+            ),
+        }
+
+        result
+    }
+
+    /// Fail at the first operator of `expression` that is not in uppercase in `i`, the text it
+    /// was parsed from.
+    fn check_uppercase_operators(i: &str, expression: &Self) -> Result<(), SpdxExpressionError> {
+        let lowercase = lint::lint(expression, i)
+            .into_iter()
+            .find(|lint| lint.kind == LintKind::LowercaseOperator);
+        match lowercase {
+            Some(lint) => Err(SpdxExpressionError::Parse(ParseError::unexpected(
+                i,
+                &i[lint.range.start..],
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_untraced(i: &str) -> Result<Self, SpdxExpressionError> {
         let (remaining, expression) = parse_expression(i)
-            .finish()
-            .map_err(|_| SpdxExpressionError::Parse(i.to_string()))?;
+            .map_err(|err| SpdxExpressionError::Parse(ParseError::from_nom(i, &err)))?;
 
         if remaining.is_empty() {
             Ok(expression)
         } else {
-            Err(SpdxExpressionError::Parse(i.to_string()))
+            Err(SpdxExpressionError::Parse(ParseError::unexpected(
+                i, remaining,
+            )))
+        }
+    }
+
+    /// Join `left` and `right` with `AND`, adding parentheses where needed to keep the structure
+    /// when the result is displayed and parsed again.
+    pub fn and(left: Self, right: Self) -> Self {
+        let left = match left {
+            Self::Or(..) => Self::Parens(Arc::new(left)),
+            _ => left,
+        };
+        let right = match right {
+            Self::And(..) | Self::Or(..) => Self::Parens(Arc::new(right)),
+            _ => right,
+        };
+
+        Self::And(Arc::new(left), Arc::new(right))
+    }
+
+    /// Join `left` and `right` with `OR`, adding parentheses where needed to keep the structure
+    /// when the result is displayed and parsed again.
+    pub fn or(left: Self, right: Self) -> Self {
+        let right = match right {
+            Self::Or(..) => Self::Parens(Arc::new(right)),
+            _ => right,
+        };
+
+        Self::Or(Arc::new(left), Arc::new(right))
+    }
+
+    /// Get `self` without any parentheses around it.
+    pub fn unparenthesized(&self) -> &Self {
+        match self {
+            Self::Parens(inner) => inner.unparenthesized(),
+            _ => self,
+        }
+    }
+
+    /// Get the operator of a compound expression and its operands, looking through parentheses.
+    /// Operands combined with the same operator are flattened, so `MIT AND (ISC AND 0BSD)` has
+    /// three operands. The operands are returned without parentheses. Returns `None` for
+    /// licenses and licenses with exceptions.
+    pub fn flatten(&self) -> Option<(Operator, Vec<&Self>)> {
+        let operator = match self.unparenthesized() {
+            Self::And(..) => Operator::And,
+            Self::Or(..) => Operator::Or,
+            Self::Simple(_) | Self::With(_) | Self::Parens(_) => return None,
+        };
+
+        let mut operands = Vec::new();
+        let mut stack = vec![self.unparenthesized()];
+        while let Some(node) = stack.pop() {
+            match (node, operator) {
+                (Self::And(left, right), Operator::And) | (Self::Or(left, right), Operator::Or) => {
+                    stack.push(right.unparenthesized());
+                    stack.push(left.unparenthesized());
+                }
+                _ => operands.push(node),
+            }
+        }
+
+        Some((operator, operands))
+    }
+
+    /// Get the node at `path`, a sequence of child indices from `self`.
+    pub fn get(&self, path: &[usize]) -> Option<&Self> {
+        let Some((&first, rest)) = path.split_first() else {
+            return Some(self);
+        };
+
+        match (self, first) {
+            (Self::And(left, _) | Self::Or(left, _) | Self::Parens(left), 0) => left.get(rest),
+            (Self::And(_, right) | Self::Or(_, right), 1) => right.get(rest),
+            _ => None,
+        }
+    }
+
+    /// Replace the node at `path` with `replacement`, parenthesizing it where needed to keep its
+    /// structure. Returns `None` if there's no node at `path`. Subtrees outside `path` are shared
+    /// with `self` instead of copied.
+    pub fn replace_at(&self, path: &[usize], replacement: Self) -> Option<Self> {
+        let Some((&first, rest)) = path.split_first() else {
+            return Some(replacement);
+        };
+
+        match (self, first) {
+            (Self::And(left, right), 0) => Some(Self::and(
+                left.replace_at(rest, replacement)?,
+                (**right).clone(),
+            )),
+            (Self::And(left, right), 1) => Some(Self::and(
+                (**left).clone(),
+                right.replace_at(rest, replacement)?,
+            )),
+            (Self::Or(left, right), 0) => Some(Self::or(
+                left.replace_at(rest, replacement)?,
+                (**right).clone(),
+            )),
+            (Self::Or(left, right), 1) => Some(Self::or(
+                (**left).clone(),
+                right.replace_at(rest, replacement)?,
+            )),
+            (Self::Parens(inner), 0) => {
+                Some(Self::Parens(Arc::new(inner.replace_at(rest, replacement)?)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the license of a `Simple` or `With` node.
+    pub const fn license(&self) -> Option<&SimpleExpression> {
+        match self {
+            Self::Simple(license) => Some(license),
+            Self::With(with) => Some(&with.license),
+            Self::And(..) | Self::Or(..) | Self::Parens(_) => None,
+        }
+    }
+
+    /// Replace licenses with the result of `f`, or keep them if `f` returns `None`. Returns `None`
+    /// if no license was replaced. Unchanged subtrees are shared with `self` instead of copied.
+    pub fn map_licenses<F>(&self, f: &mut F) -> Option<Self>
+    where
+        F: FnMut(&SimpleExpression) -> Option<SimpleExpression>,
+    {
+        self.map_leaves(&mut |leaf| match leaf {
+            Self::Simple(license) => f(license).map(Self::Simple),
+            Self::With(with) => f(&with.license).map(|license| {
+                Self::With(Box::new(WithExpression::new(
+                    license,
+                    with.exception.clone(),
+                )))
+            }),
+            Self::And(..) | Self::Or(..) | Self::Parens(_) => None,
+        })
+    }
+
+    /// Replace `Simple` and `With` nodes with the result of `f`, or keep them if `f` returns
+    /// `None`. Returns `None` if no node was replaced. Replacements with operators are
+    /// parenthesized when they become operands, so the structure of the replacement is kept.
+    /// Unchanged subtrees are shared with `self` instead of copied.
+    pub fn map_leaves<F>(&self, f: &mut F) -> Option<Self>
+    where
+        F: FnMut(&Self) -> Option<Self>,
+    {
+        match self {
+            Self::Simple(_) | Self::With(_) => f(self),
+            Self::And(left, right) => {
+                Self::map_children(left, right, f).map(|(left, right)| Self::And(left, right))
+            }
+            Self::Or(left, right) => {
+                Self::map_children(left, right, f).map(|(left, right)| Self::Or(left, right))
+            }
+            Self::Parens(inner) => inner
+                .map_leaves(f)
+                .map(|inner| Self::Parens(Arc::new(inner))),
+        }
+    }
+
+    fn map_children<F>(
+        left: &Arc<Self>,
+        right: &Arc<Self>,
+        f: &mut F,
+    ) -> Option<(Arc<Self>, Arc<Self>)>
+    where
+        F: FnMut(&Self) -> Option<Self>,
+    {
+        let new_left = left.map_operand(f);
+        let new_right = right.map_operand(f);
+
+        if new_left.is_none() && new_right.is_none() {
+            None
+        } else {
+            Some((
+                new_left.map_or_else(|| Arc::clone(left), Arc::new),
+                new_right.map_or_else(|| Arc::clone(right), Arc::new),
+            ))
+        }
+    }
+
+    /// Map the leaves of an operand, parenthesizing the operand if it was a leaf replaced with an
+    /// expression with an operator.
+    fn map_operand<F>(&self, f: &mut F) -> Option<Self>
+    where
+        F: FnMut(&Self) -> Option<Self>,
+    {
+        let mapped = self.map_leaves(f)?;
+        Some(match self {
+            Self::Simple(_) | Self::With(_) => mapped.parenthesized(),
+            _ => mapped,
+        })
+    }
+
+    /// Visit the parts of `self` with `visitor`, see [`VisitorMut`]. Shared nodes are copied
+    /// before they are visited. Operands are parenthesized where a changed operator would
+    /// otherwise change the structure of the displayed expression.
+    pub fn walk_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitorMut + ?Sized,
+    {
+        let (operator, left, right) = match self {
+            Self::Simple(license) => return visitor.visit_license(license),
+            Self::With(with) => {
+                visitor.visit_license(&mut with.license);
+                return visitor.visit_exception(&mut with.exception);
+            }
+            Self::Parens(inner) => return Arc::make_mut(inner).walk_mut(visitor),
+            Self::And(left, right) => (Operator::And, left, right),
+            Self::Or(left, right) => (Operator::Or, left, right),
+        };
+
+        Arc::make_mut(left).walk_mut(visitor);
+        let mut new_operator = operator;
+        visitor.visit_operator(&mut new_operator);
+        Arc::make_mut(right).walk_mut(visitor);
+
+        if new_operator != operator {
+            let (left, right) = (Arc::clone(left), Arc::clone(right));
+            *self = match new_operator {
+                Operator::And => Self::And(left, right),
+                Operator::Or => Self::Or(left, right),
+            };
+        }
+
+        match self {
+            Self::And(left, right) => {
+                if matches!(**left, Self::Or(..)) {
+                    Self::parenthesize(left);
+                }
+                if matches!(**right, Self::And(..) | Self::Or(..)) {
+                    Self::parenthesize(right);
+                }
+            }
+            Self::Or(_, right) => {
+                if matches!(**right, Self::Or(..)) {
+                    Self::parenthesize(right);
+                }
+            }
+            Self::Simple(_) | Self::With(_) | Self::Parens(_) => {}
+        }
+    }
+
+    fn parenthesize(node: &mut Arc<Self>) {
+        *node = Arc::new(Self::Parens(Arc::clone(node)));
+    }
+
+    /// Attach `exception` to every license in `self` that doesn't have an exception yet.
+    pub fn with_exception(&self, exception: &str) -> Self {
+        self.map_leaves(&mut |leaf| match leaf {
+            Self::Simple(license) => Some(Self::With(Box::new(WithExpression::new(
+                license.clone(),
+                exception.to_string(),
+            )))),
+            _ => None,
+        })
+        .unwrap_or_else(|| self.clone())
+    }
+
+    /// Wrap `self` in parentheses if it has a top level operator.
+    pub fn parenthesized(self) -> Self {
+        match self {
+            Self::And(..) | Self::Or(..) => Self::Parens(Arc::new(self)),
+            _ => self,
+        }
+    }
+
+    /// Remove the `Simple` and `With` nodes for which `keep` returns `false`. Removing an operand
+    /// of `AND` or `OR` replaces the operator with the other operand, and parentheses left
+    /// around a single license are removed.
+    pub fn prune<F>(&self, keep: &mut F) -> Pruned
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        match self {
+            Self::Simple(_) | Self::With(_) => {
+                if keep(self) {
+                    Pruned::Unchanged
+                } else {
+                    Pruned::Removed
+                }
+            }
+            Self::And(left, right) | Self::Or(left, right) => {
+                let (left, right) = match (left.prune(keep), right.prune(keep)) {
+                    (Pruned::Unchanged, Pruned::Unchanged) => return Pruned::Unchanged,
+                    (Pruned::Removed, Pruned::Removed) => return Pruned::Removed,
+                    (Pruned::Removed, Pruned::Unchanged) => {
+                        return Pruned::Replaced(right.as_ref().clone())
+                    }
+                    (Pruned::Unchanged, Pruned::Removed) => {
+                        return Pruned::Replaced(left.as_ref().clone())
+                    }
+                    (Pruned::Removed, Pruned::Replaced(remaining))
+                    | (Pruned::Replaced(remaining), Pruned::Removed) => {
+                        return Pruned::Replaced(remaining)
+                    }
+                    (Pruned::Replaced(new_left), Pruned::Unchanged) => {
+                        (Arc::new(new_left), Arc::clone(right))
+                    }
+                    (Pruned::Unchanged, Pruned::Replaced(new_right)) => {
+                        (Arc::clone(left), Arc::new(new_right))
+                    }
+                    (Pruned::Replaced(new_left), Pruned::Replaced(new_right)) => {
+                        (Arc::new(new_left), Arc::new(new_right))
+                    }
+                };
+
+                Pruned::Replaced(if matches!(self, Self::And(..)) {
+                    Self::And(left, right)
+                } else {
+                    Self::Or(left, right)
+                })
+            }
+            Self::Parens(inner) => match inner.prune(keep) {
+                // Parentheses around a single license are redundant.
+                Pruned::Replaced(inner @ (Self::Simple(_) | Self::With(_))) => {
+                    Pruned::Replaced(inner)
+                }
+                Pruned::Replaced(inner) => Pruned::Replaced(Self::Parens(Arc::new(inner))),
+                pruned => pruned,
+            },
+        }
+    }
+
+    /// Check if the expression is satisfied when the licenses for which `is_chosen` returns `true`
+    /// are chosen.
+    pub fn is_satisfied_by<F>(&self, is_chosen: &mut F) -> bool
+    where
+        F: FnMut(&SimpleExpression) -> bool,
+    {
+        match self {
+            Self::Simple(license) => is_chosen(license),
+            Self::With(with) => is_chosen(&with.license),
+            Self::And(left, right) => {
+                left.is_satisfied_by(is_chosen) && right.is_satisfied_by(is_chosen)
+            }
+            Self::Or(left, right) => {
+                left.is_satisfied_by(is_chosen) || right.is_satisfied_by(is_chosen)
+            }
+            Self::Parens(expression) => expression.is_satisfied_by(is_chosen),
+        }
+    }
+
+    pub fn contains_license(&self, license: &SimpleExpression) -> bool {
+        match self {
+            Self::Simple(simple) => simple == license,
+            Self::With(with) => &with.license == license,
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.contains_license(license) || right.contains_license(license)
+            }
+            Self::Parens(expression) => expression.contains_license(license),
         }
     }
 
@@ -250,6 +822,85 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn order_mixed_nodes() {
+        let mut nodes = [
+            "(MIT OR ISC)",
+            "MIT AND ISC",
+            "DocumentRef-a:LicenseRef-x",
+            "LicenseRef-b",
+            "GPL-2.0+",
+            "mit",
+            "GPL-2.0",
+            "MIT WITH exc",
+            "((MIT OR ISC))",
+            "MIT OR ISC",
+            "MIT",
+            "LicenseRef-a",
+            "Apache-2.0 WITH exc",
+            "(MIT) AND ISC",
+            "MIT AND Zlib",
+            "MIT AND (ISC)",
+        ]
+        .map(|node| ExpressionVariant::parse(node).unwrap());
+        nodes.sort();
+
+        assert_eq!(
+            nodes.map(|node| node.to_string()),
+            [
+                "Apache-2.0 WITH exc",
+                "GPL-2.0",
+                "GPL-2.0+",
+                "MIT",
+                "MIT WITH exc",
+                "mit",
+                "LicenseRef-a",
+                "LicenseRef-b",
+                "DocumentRef-a:LicenseRef-x",
+                "MIT AND ISC",
+                "MIT AND (ISC)",
+                "(MIT) AND ISC",
+                "MIT AND Zlib",
+                "MIT OR ISC",
+                "(MIT OR ISC)",
+                "((MIT OR ISC))",
+            ]
+        );
+    }
+
+    #[test]
+    fn order_compares_parentheses_last() {
+        let parse = |node| ExpressionVariant::parse(node).unwrap();
+
+        assert!(parse("(MIT) AND ISC") < parse("MIT AND Zlib"));
+        assert!(parse("MIT OR ((ISC))") < parse("(MIT OR Zlib)"));
+        assert!(parse("MIT AND (ISC)") < parse("(MIT) AND ISC"));
+    }
+
+    #[test]
+    fn order_is_consistent_with_eq() {
+        let nodes = [
+            "MIT",
+            "mit",
+            "MIT+",
+            "LicenseRef-MIT",
+            "MIT WITH exc",
+            "MIT WITH EXC",
+            "(MIT) AND ISC",
+            "MIT AND (ISC)",
+            "MIT AND ((ISC))",
+            "MIT AND Zlib",
+            "(MIT AND ISC)",
+        ]
+        .map(|node| ExpressionVariant::parse(node).unwrap());
+        for left in &nodes {
+            for right in &nodes {
+                assert_eq!(left.cmp(right) == Ordering::Equal, left == right);
+                assert_eq!(left.cmp(right), right.cmp(left).reverse());
+            }
+        }
+    }
+
     #[test]
     fn display_simple_correctly() {
         let expression =
@@ -279,29 +930,29 @@ mod tests {
 
     #[test]
     fn display_with_expression_correctly() {
-        let expression = ExpressionVariant::With(WithExpression::new(
+        let expression = ExpressionVariant::With(Box::new(WithExpression::new(
             SimpleExpression::new("license".to_string(), None, false),
             "exception".to_string(),
-        ));
+        )));
         assert_eq!(expression.to_string(), "license WITH exception".to_string());
     }
 
     #[test]
     fn display_and_expression_correctly() {
         let expression = ExpressionVariant::And(
-            Box::new(ExpressionVariant::And(
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+            Arc::new(ExpressionVariant::And(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license1".to_string(),
                     None,
                     false,
                 ))),
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license2".to_string(),
                     None,
                     false,
                 ))),
             )),
-            Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+            Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                 "license3".to_string(),
                 None,
                 false,
@@ -316,19 +967,19 @@ mod tests {
     #[test]
     fn display_or_expression_correctly() {
         let expression = ExpressionVariant::Or(
-            Box::new(ExpressionVariant::Or(
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+            Arc::new(ExpressionVariant::Or(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license1".to_string(),
                     None,
                     false,
                 ))),
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license2".to_string(),
                     None,
                     false,
                 ))),
             )),
-            Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+            Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                 "license3".to_string(),
                 None,
                 false,
@@ -343,28 +994,28 @@ mod tests {
     #[test]
     fn get_licenses_correctly() {
         let expression = ExpressionVariant::And(
-            Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+            Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                 "license1+".to_string(),
                 None,
                 false,
             ))),
-            Box::new(ExpressionVariant::Parens(Box::new(ExpressionVariant::Or(
-                Box::new(ExpressionVariant::Parens(Box::new(
-                    ExpressionVariant::With(WithExpression::new(
+            Arc::new(ExpressionVariant::Parens(Arc::new(ExpressionVariant::Or(
+                Arc::new(ExpressionVariant::Parens(Arc::new(
+                    ExpressionVariant::With(Box::new(WithExpression::new(
                         SimpleExpression::new("license2".to_string(), None, false),
                         "exception1".to_string(),
-                    )),
+                    ))),
                 ))),
-                Box::new(ExpressionVariant::And(
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::And(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license3+".to_string(),
                         None,
                         false,
                     ))),
-                    Box::new(ExpressionVariant::With(WithExpression::new(
+                    Arc::new(ExpressionVariant::With(Box::new(WithExpression::new(
                         SimpleExpression::new("license4".to_string(), None, false),
                         "exception2".to_string(),
-                    ))),
+                    )))),
                 )),
             )))),
         );
@@ -382,28 +1033,28 @@ mod tests {
     #[test]
     fn get_exceptions_correctly() {
         let expression = ExpressionVariant::And(
-            Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+            Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                 "license1+".to_string(),
                 None,
                 false,
             ))),
-            Box::new(ExpressionVariant::Parens(Box::new(ExpressionVariant::Or(
-                Box::new(ExpressionVariant::Parens(Box::new(
-                    ExpressionVariant::With(WithExpression::new(
+            Arc::new(ExpressionVariant::Parens(Arc::new(ExpressionVariant::Or(
+                Arc::new(ExpressionVariant::Parens(Arc::new(
+                    ExpressionVariant::With(Box::new(WithExpression::new(
                         SimpleExpression::new("license2".to_string(), None, false),
                         "exception1".to_string(),
-                    )),
+                    ))),
                 ))),
-                Box::new(ExpressionVariant::And(
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::And(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license3+".to_string(),
                         None,
                         false,
                     ))),
-                    Box::new(ExpressionVariant::With(WithExpression::new(
+                    Arc::new(ExpressionVariant::With(Box::new(WithExpression::new(
                         SimpleExpression::new("license4".to_string(), None, false),
                         "exception2".to_string(),
-                    ))),
+                    )))),
                 )),
             )))),
         );
@@ -414,6 +1065,153 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parenthesize_replaced_leaves() {
+        let expression = ExpressionVariant::parse("MIT AND (ISC OR 0BSD)").unwrap();
+        let replacement = ExpressionVariant::parse("Apache-2.0 OR MIT").unwrap();
+
+        let mapped = expression
+            .map_leaves(&mut |leaf| {
+                (leaf.license()?.identifier == "MIT" || leaf.license()?.identifier == "0BSD")
+                    .then(|| replacement.clone())
+            })
+            .unwrap();
+
+        assert_eq!(
+            mapped.to_string(),
+            "(Apache-2.0 OR MIT) AND (ISC OR (Apache-2.0 OR MIT))"
+        );
+        assert_eq!(
+            ExpressionVariant::parse("MIT")
+                .unwrap()
+                .map_leaves(&mut |_| Some(replacement.clone()))
+                .unwrap(),
+            replacement
+        );
+    }
+
+    #[test]
+    fn attach_exception_to_licenses() {
+        let expression = ExpressionVariant::parse("MIT OR (ISC AND GPL-2.0 WITH exc)").unwrap();
+
+        assert_eq!(
+            expression.with_exception("other").to_string(),
+            "MIT WITH other OR (ISC WITH other AND GPL-2.0 WITH exc)"
+        );
+    }
+
+    #[test]
+    fn prune_operands() {
+        let expression = ExpressionVariant::parse("MIT AND (ISC OR 0BSD) OR Apache-2.0").unwrap();
+        let prune = |removed: &[&str]| {
+            expression
+                .prune(&mut |leaf| !removed.contains(&leaf.license().unwrap().identifier.as_str()))
+        };
+
+        assert_eq!(prune(&[]), Pruned::Unchanged);
+        assert_eq!(
+            prune(&["MIT", "ISC", "0BSD", "Apache-2.0"]),
+            Pruned::Removed
+        );
+        for (removed, expected) in [
+            (&["MIT"][..], "(ISC OR 0BSD) OR Apache-2.0"),
+            (&["ISC"][..], "MIT AND 0BSD OR Apache-2.0"),
+            (&["ISC", "0BSD"][..], "MIT OR Apache-2.0"),
+            (&["Apache-2.0"][..], "MIT AND (ISC OR 0BSD)"),
+            (&["MIT", "Apache-2.0"][..], "(ISC OR 0BSD)"),
+        ] {
+            let Pruned::Replaced(pruned) = prune(removed) else {
+                panic!("{:?} should replace the expression", removed);
+            };
+            assert_eq!(pruned.to_string(), expected);
+            assert_eq!(ExpressionVariant::parse(expected).unwrap(), pruned);
+        }
+    }
+
+    #[test]
+    fn map_licenses_shares_unchanged_subtrees() {
+        let expression =
+            ExpressionVariant::parse("(MIT OR ISC) AND (GPL-2.0 WITH exception OR BSD)").unwrap();
+
+        let mapped = expression
+            .map_licenses(&mut |license| {
+                (license.identifier == "GPL-2.0")
+                    .then(|| SimpleExpression::new("GPL-2.0-only".to_string(), None, false))
+            })
+            .unwrap();
+
+        assert_eq!(
+            mapped.to_string(),
+            "(MIT OR ISC) AND (GPL-2.0-only WITH exception OR BSD)"
+        );
+        match (&expression, &mapped) {
+            (
+                ExpressionVariant::And(left, right),
+                ExpressionVariant::And(mapped_left, mapped_right),
+            ) => {
+                assert!(Arc::ptr_eq(left, mapped_left));
+                assert!(!Arc::ptr_eq(right, mapped_right));
+            }
+            _ => unreachable!(),
+        }
+        assert!(expression.map_licenses(&mut |_| None).is_none());
+    }
+
+    #[test]
+    fn and_and_or_keep_structure_through_display() {
+        let simple = |id: &str| {
+            ExpressionVariant::Simple(SimpleExpression::new(id.to_string(), None, false))
+        };
+
+        let expressions = [
+            ExpressionVariant::and(
+                ExpressionVariant::or(simple("a"), simple("b")),
+                ExpressionVariant::and(simple("c"), simple("d")),
+            ),
+            ExpressionVariant::or(
+                simple("a"),
+                ExpressionVariant::or(
+                    simple("b"),
+                    ExpressionVariant::and(simple("c"), simple("d")),
+                ),
+            ),
+        ];
+
+        assert_eq!(expressions[0].to_string(), "(a OR b) AND (c AND d)");
+        assert_eq!(expressions[1].to_string(), "a OR (b OR c AND d)");
+        for expression in expressions {
+            assert_eq!(
+                ExpressionVariant::parse(&expression.to_string()).unwrap(),
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn flatten_operands_of_same_operator() {
+        let expression =
+            ExpressionVariant::parse("((MIT AND (ISC AND 0BSD)) AND (X OR Y))").unwrap();
+
+        let (operator, operands) = expression.flatten().unwrap();
+
+        assert_eq!(operator, Operator::And);
+        assert_eq!(
+            operands.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["MIT", "ISC", "0BSD", "X OR Y"]
+        );
+        assert!(ExpressionVariant::parse("(MIT)")
+            .unwrap()
+            .flatten()
+            .is_none());
+    }
+
+    #[test]
+    fn node_is_no_larger_than_simple_expression() {
+        assert!(
+            std::mem::size_of::<ExpressionVariant>() <= std::mem::size_of::<SimpleExpression>()
+        );
+    }
+
     #[test]
     fn parse_simple_expression() {
         let expression = SimpleExpression::parse("MIT").unwrap();
@@ -448,4 +1246,17 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn round_trip_simple_expression_in_binary_formats() {
+        let expression = SimpleExpression::parse("DocumentRef-x:LicenseRef-a").unwrap();
+
+        let bincode: SimpleExpression =
+            bincode::deserialize(&bincode::serialize(&expression).unwrap()).unwrap();
+        assert_eq!(bincode, expression);
+
+        let postcard: SimpleExpression =
+            postcard::from_bytes(&postcard::to_allocvec(&expression).unwrap()).unwrap();
+        assert_eq!(postcard, expression);
+    }
 }
@@ -0,0 +1,301 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Collections of license findings with set operations.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    hash::Hash,
+    iter::FromIterator,
+};
+
+use crate::{error::SpdxExpressionError, expression::SpdxExpression};
+
+/// Set of license findings, i.e. expressions found for keys of type `K` chosen by the caller,
+/// for example the paths of the files the expressions were found in.
+///
+/// A key can have many expressions, but equivalent expressions are only kept once per key.
+/// Expressions are equivalent if they have the same [canonical
+/// key](SpdxExpression::canonical_key), so e.g. `MIT AND ISC` and `isc AND mit` are the same
+/// finding. The set operations compare findings the same way, so they can be used to compare the
+/// findings of different scans or versions of an SBOM. Findings are kept in the order they were
+/// added.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{LicenseFindings, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let old = LicenseFindings::parse([("src/a.rs", "MIT"), ("src/b.rs", "MIT AND ISC")])?;
+/// let new = LicenseFindings::parse([
+///     ("src/a.rs", "MIT"),
+///     ("src/b.rs", "ISC AND MIT"),
+///     ("src/c.rs", "GPL-2.0-only"),
+/// ])?;
+///
+/// let added = new.difference(&old);
+/// assert_eq!(added.len(), 1);
+/// assert!(added.contains(&"src/c.rs", &SpdxExpression::parse("GPL-2.0-only")?));
+/// assert!(old.difference(&new).is_empty());
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct LicenseFindings<K> {
+    findings: Vec<(K, SpdxExpression)>,
+    seen: HashSet<(K, String)>,
+}
+
+impl<K> Default for LicenseFindings<K> {
+    fn default() -> Self {
+        Self {
+            findings: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> LicenseFindings<K> {
+    /// Create an empty set of findings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the expressions of findings.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first expression that fails to parse.
+    pub fn parse<I, S>(findings: I) -> Result<Self, SpdxExpressionError>
+    where
+        I: IntoIterator<Item = (K, S)>,
+        S: AsRef<str>,
+    {
+        findings
+            .into_iter()
+            .map(|(key, expression)| Ok((key, SpdxExpression::parse(expression.as_ref())?)))
+            .collect()
+    }
+
+    /// Add a finding. Returns `false` if `key` already has an equivalent expression, in which
+    /// case the finding isn't added.
+    pub fn insert(&mut self, key: K, expression: SpdxExpression) -> bool {
+        let added = self.seen.insert((key.clone(), expression.canonical_key()));
+        if added {
+            self.findings.push((key, expression));
+        }
+        added
+    }
+
+    /// Check if `key` has an expression equivalent to `expression`.
+    pub fn contains(&self, key: &K, expression: &SpdxExpression) -> bool {
+        self.seen
+            .contains(&(key.clone(), expression.canonical_key()))
+    }
+
+    /// Get the number of findings.
+    pub const fn len(&self) -> usize {
+        self.findings.len()
+    }
+
+    /// Check if there are no findings.
+    pub const fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Iterate over the findings in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &SpdxExpression)> {
+        self.findings
+            .iter()
+            .map(|(key, expression)| (key, expression))
+    }
+
+    /// Get the findings of `self` and `other`, with the findings of `self` first.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        union.extend(other.findings.iter().cloned());
+        union
+    }
+
+    /// Get the findings of `self` that are also in `other`.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.filter(|key, expression| other.contains(key, expression))
+    }
+
+    /// Get the findings of `self` that are not in `other`.
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.filter(|key, expression| !other.contains(key, expression))
+    }
+
+    /// Group the findings by the canonical keys of their expressions, e.g. to list the files with
+    /// each distinct license expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::LicenseFindings;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let findings = LicenseFindings::parse([
+    ///     ("a.rs", "MIT OR Apache-2.0"),
+    ///     ("b.rs", "ISC"),
+    ///     ("c.rs", "Apache-2.0 OR MIT"),
+    /// ])?;
+    ///
+    /// let groups = findings.group_by_canonical();
+    /// assert_eq!(groups.len(), 2);
+    /// assert_eq!(
+    ///     groups["v1:apache-2.0 OR mit"]
+    ///         .iter()
+    ///         .map(|(key, _)| **key)
+    ///         .collect::<Vec<_>>(),
+    ///     ["a.rs", "c.rs"]
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn group_by_canonical(&self) -> BTreeMap<String, Vec<(&K, &SpdxExpression)>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (key, expression) in self.iter() {
+            groups
+                .entry(expression.canonical_key())
+                .or_default()
+                .push((key, expression));
+        }
+        groups
+    }
+
+    fn filter<F>(&self, mut predicate: F) -> Self
+    where
+        F: FnMut(&K, &SpdxExpression) -> bool,
+    {
+        self.iter()
+            .filter(|(key, expression)| predicate(key, expression))
+            .map(|(key, expression)| (key.clone(), expression.clone()))
+            .collect()
+    }
+}
+
+/// Findings are equal if they have the same findings, regardless of their order.
+impl<K: Eq + Hash> PartialEq for LicenseFindings<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.seen == other.seen
+    }
+}
+
+impl<K: Eq + Hash> Eq for LicenseFindings<K> {}
+
+impl<K: Clone + Eq + Hash> Extend<(K, SpdxExpression)> for LicenseFindings<K> {
+    fn extend<T: IntoIterator<Item = (K, SpdxExpression)>>(&mut self, iter: T) {
+        for (key, expression) in iter {
+            self.insert(key, expression);
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> FromIterator<(K, SpdxExpression)> for LicenseFindings<K> {
+    fn from_iter<T: IntoIterator<Item = (K, SpdxExpression)>>(iter: T) -> Self {
+        let mut findings = Self::new();
+        findings.extend(iter);
+        findings
+    }
+}
+
+impl<K> IntoIterator for LicenseFindings<K> {
+    type Item = (K, SpdxExpression);
+    type IntoIter = std::vec::IntoIter<(K, SpdxExpression)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.findings.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn findings(findings: &[(u32, &str)]) -> LicenseFindings<u32> {
+        LicenseFindings::parse(findings.iter().copied()).unwrap()
+    }
+
+    fn strings(findings: &LicenseFindings<u32>) -> Vec<(u32, String)> {
+        findings
+            .iter()
+            .map(|(key, expression)| (*key, expression.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn deduplicate_equivalent_expressions() {
+        let mut findings =
+            findings(&[(1, "MIT AND ISC"), (1, "isc AND (MIT)"), (2, "ISC AND MIT")]);
+        assert_eq!(
+            strings(&findings),
+            [
+                (1, "MIT AND ISC".to_string()),
+                (2, "ISC AND MIT".to_string())
+            ]
+        );
+
+        assert!(findings.insert(1, SpdxExpression::parse("MIT").unwrap()));
+        assert!(!findings.insert(1, SpdxExpression::parse("MIT OR MIT").unwrap()));
+        assert_eq!(findings.len(), 3);
+    }
+
+    #[test]
+    fn combine_findings() {
+        let left = findings(&[(1, "MIT"), (2, "ISC"), (3, "0BSD")]);
+        let right = findings(&[(3, "0bsd"), (2, "MIT"), (4, "Zlib")]);
+
+        assert_eq!(
+            strings(&left.union(&right)),
+            [
+                (1, "MIT".to_string()),
+                (2, "ISC".to_string()),
+                (3, "0BSD".to_string()),
+                (2, "MIT".to_string()),
+                (4, "Zlib".to_string())
+            ]
+        );
+        assert_eq!(
+            strings(&left.intersection(&right)),
+            [(3, "0BSD".to_string())]
+        );
+        assert_eq!(
+            strings(&right.intersection(&left)),
+            [(3, "0bsd".to_string())]
+        );
+        assert_eq!(
+            strings(&left.difference(&right)),
+            [(1, "MIT".to_string()), (2, "ISC".to_string())]
+        );
+        assert_eq!(left.union(&right), right.union(&left));
+        assert!(left.difference(&left).is_empty());
+    }
+
+    #[test]
+    fn group_equivalent_expressions() {
+        let findings = findings(&[(1, "MIT OR ISC"), (2, "MIT"), (3, "(ISC OR MIT)")]);
+
+        let groups = findings
+            .group_by_canonical()
+            .into_iter()
+            .map(|(canonical, group)| {
+                (
+                    canonical,
+                    group.into_iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                ("v1:isc OR mit".to_string(), vec![1, 3]),
+                ("v1:mit".to_string(), vec![2])
+            ]
+        );
+    }
+}
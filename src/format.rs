@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Displaying expressions in a configurable style.
+
+use std::fmt::Display;
+
+use crate::expression_variant::ExpressionVariant;
+
+/// Case of the `AND`, `OR` and `WITH` operators in [`FormatOptions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OperatorCase {
+    /// `AND`, `OR` and `WITH`, as recommended by the SPDX specification.
+    #[default]
+    Upper,
+
+    /// `and`, `or` and `with`.
+    Lower,
+}
+
+/// Options for displaying expressions with [`SpdxExpression::display_with`]. The defaults
+/// display expressions like [`Display`] of [`SpdxExpression`].
+///
+/// Operators are case-insensitive and whitespace inside parentheses is ignored when parsing, so
+/// expressions displayed in any style parse to the same expression.
+///
+/// [`SpdxExpression`]: crate::SpdxExpression
+/// [`SpdxExpression::display_with`]: crate::SpdxExpression::display_with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FormatOptions {
+    /// Case of the operators.
+    pub operator_case: OperatorCase,
+
+    /// `true` to add a space after opening and before closing parentheses, e.g.
+    /// `( MIT OR ISC )`.
+    pub space_inside_parentheses: bool,
+}
+
+/// Expression displayed with [`FormatOptions`], see [`SpdxExpression::display_with`].
+///
+/// [`SpdxExpression::display_with`]: crate::SpdxExpression::display_with
+#[derive(Debug, Clone, Copy)]
+pub struct Formatted<'a> {
+    expression: &'a ExpressionVariant,
+    options: &'a FormatOptions,
+}
+
+impl<'a> Formatted<'a> {
+    pub(crate) const fn new(expression: &'a ExpressionVariant, options: &'a FormatOptions) -> Self {
+        Self {
+            expression,
+            options,
+        }
+    }
+
+    const fn operator(&self, upper: &'static str, lower: &'static str) -> &'static str {
+        match self.options.operator_case {
+            OperatorCase::Upper => upper,
+            OperatorCase::Lower => lower,
+        }
+    }
+
+    fn fmt_node(
+        &self,
+        node: &ExpressionVariant,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match node {
+            ExpressionVariant::Simple(license) => write!(f, "{license}"),
+            ExpressionVariant::With(with) => write!(
+                f,
+                "{} {} {}",
+                with.license,
+                self.operator("WITH", "with"),
+                with.exception
+            ),
+            ExpressionVariant::And(left, right) => {
+                self.fmt_node(left, f)?;
+                write!(f, " {} ", self.operator("AND", "and"))?;
+                self.fmt_node(right, f)
+            }
+            ExpressionVariant::Or(left, right) => {
+                self.fmt_node(left, f)?;
+                write!(f, " {} ", self.operator("OR", "or"))?;
+                self.fmt_node(right, f)
+            }
+            ExpressionVariant::Parens(inner) => {
+                let padding = if self.options.space_inside_parentheses {
+                    " "
+                } else {
+                    ""
+                };
+                write!(f, "({padding}")?;
+                self.fmt_node(inner, f)?;
+                write!(f, "{padding})")
+            }
+        }
+    }
+}
+
+impl Display for Formatted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_node(self.expression, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(expression: &str, options: &FormatOptions) -> String {
+        Formatted::new(&ExpressionVariant::parse(expression).unwrap(), options).to_string()
+    }
+
+    #[test]
+    fn default_options_match_display() {
+        let expression = "MIT OR (GPL-2.0+ WITH exc AND DocumentRef-a:LicenseRef-b)";
+        assert_eq!(format(expression, &FormatOptions::default()), expression);
+    }
+
+    #[test]
+    fn format_in_configured_style() {
+        let options = FormatOptions {
+            operator_case: OperatorCase::Lower,
+            space_inside_parentheses: true,
+        };
+        assert_eq!(
+            format("MIT OR ((GPL-2.0-only WITH exc) AND ISC)", &options),
+            "MIT or ( ( GPL-2.0-only with exc ) and ISC )"
+        );
+    }
+
+    #[test]
+    fn formatted_expressions_parse_to_the_same_expression() {
+        let expression =
+            ExpressionVariant::parse("MIT OR ((GPL-2.0-only WITH exc) AND ISC)").unwrap();
+        let options = FormatOptions {
+            operator_case: OperatorCase::Lower,
+            space_inside_parentheses: true,
+        };
+        let formatted = Formatted::new(&expression, &options).to_string();
+        assert_eq!(ExpressionVariant::parse(&formatted).unwrap(), expression);
+    }
+}
@@ -0,0 +1,308 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Seedable generator of random valid expressions, e.g. for load testing, fuzzing corpora and
+//! demo data.
+//!
+//! Unlike the [`proptest`] strategies and the [`quickcheck`] implementations, the generator has
+//! no dependencies, and the same seed and options always generate the same expressions.
+//!
+//! # Examples
+//!
+//! ```
+//! # use spdx_expression::generator::{Generator, GeneratorOptions};
+//! # use spdx_expression::SpdxExpression;
+//! #
+//! let options = GeneratorOptions {
+//!     licenses: vec!["MIT".to_string(), "Apache-2.0".to_string()],
+//!     ..GeneratorOptions::default()
+//! };
+//!
+//! let expressions = Generator::new(42, options.clone()).take(10).collect::<Vec<_>>();
+//! assert_eq!(expressions, Generator::new(42, options).take(10).collect::<Vec<_>>());
+//!
+//! for expression in expressions {
+//!     assert_eq!(SpdxExpression::parse(&expression.to_string()).unwrap(), expression);
+//! }
+//! ```
+//!
+//! [`proptest`]: https://docs.rs/proptest
+//! [`quickcheck`]: https://docs.rs/quickcheck
+
+use crate::{
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+};
+
+/// Options of a [`Generator`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorOptions {
+    /// Maximum nesting depth of the operators. Expressions with depth 0 are single licenses.
+    /// Defaults to 3.
+    pub max_depth: usize,
+
+    /// Maximum number of operands of an operator, at least 2. Defaults to 4.
+    pub max_operands: usize,
+
+    /// Probability of an operand being a license instead of a nested operator, between 0 and 1.
+    /// Defaults to 0.5.
+    pub license_probability: f64,
+
+    /// Probability of a license having an exception, between 0 and 1. Defaults to 0.1.
+    pub exception_probability: f64,
+
+    /// License identifiers to draw from. If empty, random identifiers and `LicenseRef`s are
+    /// generated.
+    pub licenses: Vec<String>,
+
+    /// Exception identifiers to draw from. If empty, random identifiers are generated.
+    pub exceptions: Vec<String>,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_operands: 4,
+            license_probability: 0.5,
+            exception_probability: 0.1,
+            licenses: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+}
+
+impl GeneratorOptions {
+    /// Create options drawing the licenses and exceptions from the bundled data about common
+    /// licenses and exceptions.
+    #[cfg(feature = "data")]
+    pub fn bundled() -> Self {
+        Self {
+            licenses: crate::obligations::OBLIGATIONS
+                .iter()
+                .map(|(license, _)| (*license).to_string())
+                .collect(),
+            exceptions: crate::exceptions::GRANTS
+                .iter()
+                .map(|(exception, _)| (*exception).to_string())
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Generator of random valid expressions, see the [module documentation](self). Generates an
+/// endless sequence of expressions as an [`Iterator`].
+#[derive(Debug, Clone)]
+pub struct Generator {
+    state: u64,
+    options: GeneratorOptions,
+}
+
+const ID_START: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const ID_CONTINUE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789.-";
+
+impl Generator {
+    /// Create a generator with `seed`.
+    pub const fn new(seed: u64, options: GeneratorOptions) -> Self {
+        Self {
+            state: seed,
+            options,
+        }
+    }
+
+    /// Generate an expression.
+    pub fn generate(&mut self) -> SpdxExpression {
+        SpdxExpression::from_inner(self.node(self.options.max_depth))
+    }
+
+    /// Next pseudorandom number, with `SplitMix64`.
+    const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `0..bound`, or 0 if `bound` is 0.
+    const fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+        // The modulo bias is irrelevant for the small bounds used here.
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (self.next_u64() % bound as u64) as usize;
+        index
+    }
+
+    /// Return `true` with `probability`.
+    fn chance(&mut self, probability: f64) -> bool {
+        #[allow(clippy::cast_precision_loss)]
+        let sample = (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+        sample < probability
+    }
+
+    fn node(&mut self, depth: usize) -> ExpressionVariant {
+        if depth == 0 || self.chance(self.options.license_probability) {
+            return self.leaf();
+        }
+
+        let operands = 2 + self.below(self.options.max_operands.max(2) - 1);
+        let is_and = self.chance(0.5);
+        (0..operands)
+            .map(|_| self.node(depth - 1))
+            .reduce(|left, right| {
+                if is_and {
+                    ExpressionVariant::and(left, right)
+                } else {
+                    ExpressionVariant::or(left, right)
+                }
+            })
+            .expect("operators have at least two operands")
+    }
+
+    fn leaf(&mut self) -> ExpressionVariant {
+        let license = self.license();
+        if self.chance(self.options.exception_probability) {
+            let exception = self.pick(|options| &options.exceptions);
+            ExpressionVariant::With(Box::new(WithExpression::new(license, exception)))
+        } else {
+            ExpressionVariant::Simple(license)
+        }
+    }
+
+    fn license(&mut self) -> SimpleExpression {
+        if !self.options.licenses.is_empty() {
+            let identifier = self.pick(|options| &options.licenses);
+            return SimpleExpression::parse(&identifier)
+                .unwrap_or_else(|_| SimpleExpression::new(identifier, None, false));
+        }
+
+        match self.below(8) {
+            0 => SimpleExpression::new(self.identifier(), None, true),
+            1 => SimpleExpression::new(self.identifier(), Some(self.identifier()), true),
+            2 => SimpleExpression::new(self.identifier(), None, false).with_or_later(true),
+            _ => SimpleExpression::new(self.identifier(), None, false),
+        }
+    }
+
+    /// Pick an identifier from the list selected by `list`, or generate one if it's empty.
+    fn pick<F>(&mut self, list: F) -> String
+    where
+        F: Fn(&GeneratorOptions) -> &Vec<String>,
+    {
+        let length = list(&self.options).len();
+        if length == 0 {
+            return self.identifier();
+        }
+        let index = self.below(length);
+        list(&self.options)[index].clone()
+    }
+
+    /// Generate a random identifier that is not an operator or a reference.
+    fn identifier(&mut self) -> String {
+        loop {
+            let length = self.below(12);
+            let mut identifier = String::new();
+            identifier.push(char::from(ID_START[self.below(ID_START.len())]));
+            for _ in 0..length {
+                identifier.push(char::from(ID_CONTINUE[self.below(ID_CONTINUE.len())]));
+            }
+
+            let is_reserved = ["AND", "OR", "WITH"]
+                .iter()
+                .any(|reserved| reserved.eq_ignore_ascii_case(&identifier));
+            let is_reference =
+                identifier.starts_with("LicenseRef-") || identifier.starts_with("DocumentRef-");
+            if !is_reserved && !is_reference {
+                return identifier;
+            }
+        }
+    }
+}
+
+impl Iterator for Generator {
+    type Item = SpdxExpression;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.generate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(node: &ExpressionVariant) -> usize {
+        match node.flatten() {
+            Some((_, operands)) => 1 + operands.into_iter().map(depth).max().unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    #[test]
+    fn generated_expressions_are_valid() {
+        for expression in Generator::new(1, GeneratorOptions::default()).take(500) {
+            assert_eq!(
+                SpdxExpression::parse(&expression.to_string()).unwrap(),
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_generates_same_expressions() {
+        let generate = |seed| {
+            Generator::new(seed, GeneratorOptions::default())
+                .take(20)
+                .map(|expression| expression.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generate(7), generate(7));
+        assert_ne!(generate(7), generate(8));
+    }
+
+    #[test]
+    fn respect_bounds() {
+        let options = GeneratorOptions {
+            max_depth: 2,
+            max_operands: 3,
+            license_probability: 0.0,
+            ..GeneratorOptions::default()
+        };
+        for expression in Generator::new(3, options).take(100) {
+            assert!(depth(expression.inner()) <= 2);
+            let (_, operands) = expression.inner().flatten().unwrap();
+            assert!(operands.len() <= 3 * 3);
+        }
+    }
+
+    #[test]
+    fn draw_identifiers_from_lists() {
+        let options = GeneratorOptions {
+            licenses: vec!["MIT".to_string(), "GPL-2.0+".to_string()],
+            exceptions: vec!["Classpath-exception-2.0".to_string()],
+            exception_probability: 0.5,
+            ..GeneratorOptions::default()
+        };
+        for expression in Generator::new(5, options).take(100) {
+            for (license, exception) in expression.leaves() {
+                assert!(["MIT", "GPL-2.0+"].contains(&license.to_string().as_str()));
+                assert!(exception.is_none_or(|exception| exception == "Classpath-exception-2.0"));
+            }
+        }
+    }
+
+    #[cfg(feature = "data")]
+    #[test]
+    fn draw_identifiers_from_bundled_data() {
+        let options = GeneratorOptions::bundled();
+        for expression in Generator::new(9, options.clone()).take(50) {
+            for (license, _) in expression.leaves() {
+                assert!(options.licenses.contains(&license.to_string()));
+            }
+        }
+    }
+}
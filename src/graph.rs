@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Traversal of expressions as graphs for the graph renderers.
+
+use crate::{expression_variant::ExpressionVariant, parser::Operator};
+
+/// Kind of a node in the graph of an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Operator,
+    License,
+    Exception,
+}
+
+/// Node in the graph of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// Sequential number of the node in preorder.
+    pub id: usize,
+
+    /// Number of the parent node, `None` for the root.
+    pub parent: Option<usize>,
+
+    pub label: String,
+
+    pub kind: NodeKind,
+}
+
+/// Get the nodes of the graph of `expression` in preorder. Operators, including `WITH`, are
+/// nodes with their operands as children. Operands joined with the same operator are children of
+/// a single operator node, regardless of parentheses.
+pub fn nodes(expression: &ExpressionVariant) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    collect(expression, None, &mut nodes);
+    nodes
+}
+
+fn push(nodes: &mut Vec<Node>, parent: Option<usize>, label: String, kind: NodeKind) -> usize {
+    let id = nodes.len();
+    nodes.push(Node {
+        id,
+        parent,
+        label,
+        kind,
+    });
+    id
+}
+
+fn collect(expression: &ExpressionVariant, parent: Option<usize>, nodes: &mut Vec<Node>) {
+    if let Some((operator, operands)) = expression.flatten() {
+        let label = match operator {
+            Operator::And => "AND",
+            Operator::Or => "OR",
+        };
+        let id = push(nodes, parent, label.to_string(), NodeKind::Operator);
+        for operand in operands {
+            collect(operand, Some(id), nodes);
+        }
+        return;
+    }
+
+    match expression.unparenthesized() {
+        ExpressionVariant::Simple(license) => {
+            push(nodes, parent, license.to_string(), NodeKind::License);
+        }
+        ExpressionVariant::With(with) => {
+            let id = push(nodes, parent, "WITH".to_string(), NodeKind::Operator);
+            push(nodes, Some(id), with.license.to_string(), NodeKind::License);
+            push(nodes, Some(id), with.exception.clone(), NodeKind::Exception);
+        }
+        ExpressionVariant::And(..) | ExpressionVariant::Or(..) | ExpressionVariant::Parens(_) => {
+            unreachable!("compound expressions are flattened")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_nodes_in_preorder() {
+        let expression = ExpressionVariant::parse("(MIT OR (ISC)) AND GPL WITH exc").unwrap();
+
+        let nodes = nodes(&expression);
+
+        assert_eq!(
+            nodes
+                .iter()
+                .map(|node| (node.parent, node.label.as_str(), node.kind))
+                .collect::<Vec<_>>(),
+            [
+                (None, "AND", NodeKind::Operator),
+                (Some(0), "OR", NodeKind::Operator),
+                (Some(1), "MIT", NodeKind::License),
+                (Some(1), "ISC", NodeKind::License),
+                (Some(0), "WITH", NodeKind::Operator),
+                (Some(4), "GPL", NodeKind::License),
+                (Some(4), "exc", NodeKind::Exception),
+            ]
+        );
+    }
+}
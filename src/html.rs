@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rendering expressions as HTML.
+
+use std::fmt::Write;
+
+use crate::expression_variant::{ExpressionVariant, SimpleExpression};
+
+/// Options for rendering expressions as HTML with [`SpdxExpression::to_html`].
+///
+/// [`SpdxExpression::to_html`]: crate::SpdxExpression::to_html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// Prefix of the links to licenses and exceptions on the SPDX License List. The identifier
+    /// and `.html` are appended to it. Defaults to `https://spdx.org/licenses/`.
+    pub license_list_url: String,
+
+    /// Prefix of the links to `LicenseRef`s. The full reference, e.g.
+    /// `DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2`, is appended to it. Defaults to `#`,
+    /// i.e. anchors on the same page.
+    pub license_ref_url: String,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            license_list_url: "https://spdx.org/licenses/".to_string(),
+            license_ref_url: "#".to_string(),
+        }
+    }
+}
+
+pub fn render(expression: &ExpressionVariant, options: &HtmlOptions) -> String {
+    let mut output = String::new();
+    write_expression(&mut output, expression, options);
+    output
+}
+
+fn write_expression(output: &mut String, expression: &ExpressionVariant, options: &HtmlOptions) {
+    match expression {
+        ExpressionVariant::Simple(license) => write_license(output, license, options),
+        ExpressionVariant::With(with) => {
+            write_license(output, &with.license, options);
+            output.push_str(" WITH ");
+            write_list_link(output, &with.exception, &with.exception, options);
+        }
+        ExpressionVariant::And(left, right) => {
+            write_expression(output, left, options);
+            output.push_str(" AND ");
+            write_expression(output, right, options);
+        }
+        ExpressionVariant::Or(left, right) => {
+            write_expression(output, left, options);
+            output.push_str(" OR ");
+            write_expression(output, right, options);
+        }
+        ExpressionVariant::Parens(inner) => {
+            output.push('(');
+            write_expression(output, inner, options);
+            output.push(')');
+        }
+    }
+}
+
+fn write_license(output: &mut String, license: &SimpleExpression, options: &HtmlOptions) {
+    let text = license.to_string();
+    if license.license_ref {
+        write_link(output, &format!("{}{text}", options.license_ref_url), &text);
+    } else {
+        // The or later versions are described on the page of the license.
+        write_list_link(output, &license.identifier, &text, options);
+    }
+}
+
+fn write_list_link(output: &mut String, identifier: &str, text: &str, options: &HtmlOptions) {
+    let url = format!("{}{identifier}.html", options.license_list_url);
+    write_link(output, &url, text);
+}
+
+fn write_link(output: &mut String, url: &str, text: &str) {
+    // Writing to a `String` can't fail.
+    let _ = write!(output, "<a href=\"{}\">{}</a>", escape(url), escape(text));
+}
+
+/// Escape characters with a special meaning in HTML text and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_with_links() {
+        let expression = ExpressionVariant::parse(
+            "(GPL-2.0+ WITH Classpath-exception-2.0 OR MIT) AND LicenseRef-a",
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&expression, &HtmlOptions::default()),
+            "(<a href=\"https://spdx.org/licenses/GPL-2.0.html\">GPL-2.0+</a> WITH \
+             <a href=\"https://spdx.org/licenses/Classpath-exception-2.0.html\">Classpath-exception-2.0</a> OR \
+             <a href=\"https://spdx.org/licenses/MIT.html\">MIT</a>) AND \
+             <a href=\"#LicenseRef-a\">LicenseRef-a</a>"
+        );
+    }
+
+    #[test]
+    fn render_with_custom_urls() {
+        let expression = ExpressionVariant::parse("MIT OR DocumentRef-doc:LicenseRef-a").unwrap();
+        let options = HtmlOptions {
+            license_list_url: "https://example.com/list/".to_string(),
+            license_ref_url: "https://example.com/refs?id=".to_string(),
+        };
+
+        assert_eq!(
+            render(&expression, &options),
+            "<a href=\"https://example.com/list/MIT.html\">MIT</a> OR \
+             <a href=\"https://example.com/refs?id=DocumentRef-doc:LicenseRef-a\">DocumentRef-doc:LicenseRef-a</a>"
+        );
+    }
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(
+            escape(r#"<a href="x">&'"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Incremental re-parsing of edited expressions.
+
+use std::ops::Range;
+
+use crate::{
+    error::{ParseError, SpdxExpressionError},
+    expression::SpdxExpression,
+    expression_variant::ExpressionVariant,
+    parser::{fold_terms, top_level_terms, Term},
+};
+
+/// Replacement of a byte range of text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    /// Byte range of the replaced text.
+    pub range: Range<usize>,
+
+    /// Text inserted in place of the range.
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Create a new text edit.
+    pub const fn new(range: Range<usize>, replacement: String) -> Self {
+        Self { range, replacement }
+    }
+}
+
+/// SPDX expression that keeps its source text, so that edits to the text can be re-parsed
+/// incrementally. Intended for editors, where the text is modified a little at a time.
+///
+/// The source is kept as a sequence of top-level terms, i.e. the operands outside of any
+/// parentheses. When an edit falls inside a single term, only the text of that term is parsed
+/// again and the other terms are reused. Other edits fall back to parsing the whole source.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{IncrementalExpression, SpdxExpression, SpdxExpressionError, TextEdit};
+/// #
+/// let mut expression = IncrementalExpression::new("MIT OR Apache-2.0 AND ISC".to_string());
+///
+/// expression.apply_edit(&TextEdit::new(7..17, "(GPL-2.0-only OR BSD-3-Clause)".to_string()))?;
+///
+/// assert_eq!(expression.source(), "MIT OR (GPL-2.0-only OR BSD-3-Clause) AND ISC");
+/// assert_eq!(
+///     expression.expression(),
+///     Some(&SpdxExpression::parse("MIT OR (GPL-2.0-only OR BSD-3-Clause) AND ISC")?)
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct IncrementalExpression {
+    source: String,
+
+    /// Top-level terms of the source, empty if the source is not a valid expression.
+    terms: Vec<Term>,
+
+    /// The parsed source, `None` if the source is not a valid expression.
+    expression: Option<SpdxExpression>,
+
+    /// Why the source is not a valid expression, `None` if it is.
+    error: Option<ParseError>,
+}
+
+impl IncrementalExpression {
+    /// Parse `source`. The source doesn't need to be a valid expression, in which case
+    /// [`IncrementalExpression::expression`] returns `None` until the source is edited to be
+    /// valid.
+    pub fn new(source: String) -> Self {
+        let mut expression = Self {
+            source,
+            terms: Vec::new(),
+            expression: None,
+            error: None,
+        };
+        expression.parse_full();
+        expression
+    }
+
+    /// Get the current source text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the parsed expression, or `None` if the current source is not a valid expression.
+    pub const fn expression(&self) -> Option<&SpdxExpression> {
+        self.expression.as_ref()
+    }
+
+    /// Apply `edit` to the source and update the parsed expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Edit` if the range of the edit is out of bounds of the
+    /// source or doesn't lie on `char` boundaries, in which case the source is left unchanged.
+    ///
+    /// Returns `SpdxExpressionError::Parse` if the edited source is not a valid expression. The
+    /// edit is still applied to the source, so following edits are relative to the edited text.
+    pub fn apply_edit(&mut self, edit: &TextEdit) -> Result<&SpdxExpression, SpdxExpressionError> {
+        let Range { start, end } = edit.range;
+        if start > end || !self.source.is_char_boundary(start) || !self.source.is_char_boundary(end)
+        {
+            return Err(SpdxExpressionError::Edit(format!(
+                "range {start}..{end} is not a range of characters in `{}`",
+                self.source
+            )));
+        }
+
+        self.source
+            .replace_range(edit.range.clone(), &edit.replacement);
+
+        if !self.reparse_term(edit) {
+            self.parse_full();
+        }
+
+        match (&self.expression, &self.error) {
+            (Some(expression), _) => Ok(expression),
+            (None, error) => Err(SpdxExpressionError::Parse(
+                error
+                    .clone()
+                    .unwrap_or_else(|| ParseError::new(&self.source)),
+            )),
+        }
+    }
+
+    fn parse_full(&mut self) {
+        if let Ok(("", terms)) = top_level_terms(&self.source) {
+            self.terms = terms;
+            self.error = None;
+        } else {
+            self.terms = Vec::new();
+            // Failures are reported like when parsing the source in one go, which doesn't stop
+            // at the top-level terms.
+            self.error = match ExpressionVariant::parse(&self.source) {
+                Err(SpdxExpressionError::Parse(error)) => Some(error),
+                _ => None,
+            };
+        }
+        self.rebuild();
+    }
+
+    /// Try to re-parse only the term containing the edit, which has already been applied to the
+    /// source. Returns `false` if the whole source needs to be parsed instead.
+    fn reparse_term(&mut self, edit: &TextEdit) -> bool {
+        let Some(index) = self.terms.iter().position(|term| {
+            term.range.start <= edit.range.start && edit.range.end <= term.range.end
+        }) else {
+            return false;
+        };
+
+        let old_range = self.terms[index].range.clone();
+        let new_end = old_range.end - edit.range.len() + edit.replacement.len();
+        let text = &self.source[old_range.start..new_end];
+
+        let Ok(("", mut new_terms)) = top_level_terms(text) else {
+            return false;
+        };
+
+        // A term ending in an identifier would merge with an operator directly after it.
+        let ends_in_identifier = text
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '+'));
+        let followed_by_text = self.source[new_end..]
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace());
+        if ends_in_identifier && followed_by_text {
+            return false;
+        }
+
+        new_terms[0].operator = self.terms[index].operator;
+        for term in &mut new_terms {
+            term.range = term.range.start + old_range.start..term.range.end + old_range.start;
+        }
+        for term in &mut self.terms[index + 1..] {
+            term.range = term.range.start - old_range.end + new_end
+                ..term.range.end - old_range.end + new_end;
+        }
+
+        self.terms.splice(index..=index, new_terms);
+        self.rebuild();
+
+        true
+    }
+
+    fn rebuild(&mut self) {
+        self.expression = fold_terms(
+            self.terms
+                .iter()
+                .map(|term| (term.operator, term.expression.clone())),
+        )
+        .map(SpdxExpression::from_inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matches_full_parse(expression: &IncrementalExpression) {
+        let parsed = SpdxExpression::parse(expression.source());
+        assert_eq!(
+            expression.expression(),
+            parsed.as_ref().ok(),
+            "source: {}",
+            expression.source()
+        );
+        if let Err(SpdxExpressionError::Parse(error)) = parsed {
+            assert_eq!(
+                expression.error.as_ref(),
+                Some(&error),
+                "source: {}",
+                expression.source()
+            );
+        }
+    }
+
+    #[test]
+    fn edit_inside_a_term_is_reparsed_locally() {
+        let mut expression = IncrementalExpression::new("MIT AND (ISC OR X) OR Y".to_string());
+
+        expression
+            .apply_edit(&TextEdit::new(16..17, "Apache-2.0".to_string()))
+            .unwrap();
+
+        assert_eq!(expression.source(), "MIT AND (ISC OR Apache-2.0) OR Y");
+        assert_eq!(
+            expression
+                .terms
+                .iter()
+                .map(|term| &expression.source[term.range.clone()])
+                .collect::<Vec<_>>(),
+            ["MIT", "(ISC OR Apache-2.0)", "Y"]
+        );
+        assert_matches_full_parse(&expression);
+    }
+
+    #[test]
+    fn edit_splitting_a_term_keeps_precedence() {
+        let mut expression = IncrementalExpression::new("A AND B".to_string());
+
+        expression
+            .apply_edit(&TextEdit::new(0..1, "C OR D".to_string()))
+            .unwrap();
+
+        assert_eq!(expression.source(), "C OR D AND B");
+        assert_matches_full_parse(&expression);
+    }
+
+    #[test]
+    fn invalid_edit_returns_error_and_recovers() {
+        let mut expression = IncrementalExpression::new("MIT OR ISC".to_string());
+
+        assert!(expression
+            .apply_edit(&TextEdit::new(7..7, "(".to_string()))
+            .is_err());
+        assert!(expression.expression().is_none());
+
+        expression
+            .apply_edit(&TextEdit::new(7..8, String::new()))
+            .unwrap();
+        assert_eq!(
+            expression.expression(),
+            Some(&SpdxExpression::parse("MIT OR ISC").unwrap())
+        );
+    }
+
+    #[test]
+    fn invalid_edit_returns_the_parse_error() {
+        let mut expression = IncrementalExpression::new("MIT OR ISC".to_string());
+
+        let Err(SpdxExpressionError::Parse(error)) =
+            expression.apply_edit(&TextEdit::new(7..7, "(".to_string()))
+        else {
+            panic!("the edit should fail to parse");
+        };
+        assert_eq!(error.input, "MIT OR (ISC");
+        assert_eq!(error.offset, Some(11));
+        assert_eq!(error.context.first(), Some(&"closing parenthesis"));
+    }
+
+    #[test]
+    fn edit_outside_of_the_source_is_rejected() {
+        let mut expression = IncrementalExpression::new("MIT OR Müller".to_string());
+
+        #[allow(clippy::reversed_empty_ranges)]
+        for range in [0..15, 16..16, 9..9, 3..2] {
+            assert!(matches!(
+                expression.apply_edit(&TextEdit::new(range.clone(), String::new())),
+                Err(SpdxExpressionError::Edit(_))
+            ));
+            assert_eq!(expression.source(), "MIT OR Müller");
+        }
+
+        expression
+            .apply_edit(&TextEdit::new(7..14, "ISC".to_string()))
+            .unwrap();
+        assert_eq!(expression.source(), "MIT OR ISC");
+    }
+
+    #[test]
+    fn term_merging_with_following_operator_is_detected() {
+        let mut expression = IncrementalExpression::new("(A)AND B".to_string());
+
+        assert!(expression
+            .apply_edit(&TextEdit::new(0..3, "A".to_string()))
+            .is_err());
+        assert_matches_full_parse(&expression);
+    }
+
+    #[test]
+    fn all_single_character_edits_match_full_parse() {
+        let sources = [
+            "MIT AND (ISC OR X) OR Y WITH Z",
+            "(A)AND(B) or C",
+            "LicenseRef-a OR GPL-2.0+ AND B",
+        ];
+        let replacements = ["", "a", " ", "(", ")", "+", "AND", " OR "];
+
+        for source in sources {
+            for start in 0..=source.len() {
+                for end in start..=(start + 1).min(source.len()) {
+                    for replacement in replacements {
+                        let mut expression = IncrementalExpression::new(source.to_string());
+                        let _ = expression
+                            .apply_edit(&TextEdit::new(start..end, replacement.to_string()));
+                        assert_matches_full_parse(&expression);
+                    }
+                }
+            }
+        }
+    }
+}
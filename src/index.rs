@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Index from licenses to their locations in an expression.
+
+use std::collections::HashMap;
+
+use crate::expression_variant::{ExpressionVariant, SimpleExpression};
+
+/// Locations of every license in an expression. Each location is the path of child indices
+/// from the root of the expression to the `Simple` or `With` node containing the license.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LicenseIndex {
+    paths: HashMap<SimpleExpression, Vec<Vec<usize>>>,
+}
+
+impl LicenseIndex {
+    pub fn build(expression: &ExpressionVariant) -> Self {
+        let mut index = Self::default();
+        index.insert(expression, &mut Vec::new());
+        index
+    }
+
+    fn insert(&mut self, expression: &ExpressionVariant, path: &mut Vec<usize>) {
+        match expression {
+            ExpressionVariant::Simple(license) => self.push(license, path),
+            ExpressionVariant::With(with) => self.push(&with.license, path),
+            ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right) => {
+                for (child_index, child) in [left, right].iter().enumerate() {
+                    path.push(child_index);
+                    self.insert(child, path);
+                    path.pop();
+                }
+            }
+            ExpressionVariant::Parens(inner) => {
+                path.push(0);
+                self.insert(inner, path);
+                path.pop();
+            }
+        }
+    }
+
+    fn push(&mut self, license: &SimpleExpression, path: &[usize]) {
+        self.paths
+            .entry(license.clone())
+            .or_default()
+            .push(path.to_vec());
+    }
+
+    pub fn contains(&self, license: &SimpleExpression) -> bool {
+        self.paths.contains_key(license)
+    }
+
+    /// Get the paths to every occurrence of `license`, in the order they appear.
+    pub fn paths_of(&self, license: &SimpleExpression) -> &[Vec<usize>] {
+        self.paths.get(license).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get a path to one occurrence of every distinct license.
+    pub fn first_paths(&self) -> impl Iterator<Item = &[usize]> {
+        self.paths
+            .values()
+            .filter_map(|paths| paths.first())
+            .map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_paths_of_all_occurrences() {
+        let expression = ExpressionVariant::parse("MIT AND (ISC OR MIT WITH exception)").unwrap();
+
+        let index = LicenseIndex::build(&expression);
+
+        let mit = SimpleExpression::parse("MIT").unwrap();
+        assert_eq!(index.paths_of(&mit), [vec![0], vec![1, 0, 1]]);
+        assert!(index.contains(&SimpleExpression::parse("ISC").unwrap()));
+        assert!(!index.contains(&SimpleExpression::parse("Apache-2.0").unwrap()));
+        assert_eq!(index.first_paths().count(), 2);
+    }
+}
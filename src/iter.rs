@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Iterators over the parts of expressions.
+
+use crate::{
+    diff::{operands, strip_parens, Located},
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    parser::Operator,
+};
+
+/// License with its optional exception.
+pub type Leaf<'a> = (&'a SimpleExpression, Option<&'a str>);
+
+/// Iterator over the leaves of an expression, i.e. the licenses with their optional exceptions,
+/// in the order they appear in the expression. Created with [`SpdxExpression::leaves`].
+///
+/// Licenses that appear more than once are returned every time.
+///
+/// [`SpdxExpression::leaves`]: crate::SpdxExpression::leaves
+#[derive(Debug, Clone)]
+pub struct Leaves<'a> {
+    stack: Vec<Located<'a>>,
+}
+
+impl<'a> Leaves<'a> {
+    pub(crate) fn new(expression: &'a ExpressionVariant) -> Self {
+        Self {
+            stack: vec![(Vec::new(), expression)],
+        }
+    }
+
+    /// Include the path of every leaf, which can be used with [`SpdxExpression::get`] and
+    /// [`SpdxExpression::replace_at`].
+    ///
+    /// [`SpdxExpression::get`]: crate::SpdxExpression::get
+    /// [`SpdxExpression::replace_at`]: crate::SpdxExpression::replace_at
+    pub fn with_paths(mut self) -> impl Iterator<Item = (Vec<usize>, Leaf<'a>)> {
+        std::iter::from_fn(move || self.next_located())
+    }
+
+    fn next_located(&mut self) -> Option<(Vec<usize>, Leaf<'a>)> {
+        while let Some((path, node)) = self.stack.pop() {
+            match node {
+                ExpressionVariant::Simple(license) => return Some((path, (license, None))),
+                ExpressionVariant::With(with) => {
+                    return Some((path, (&with.license, Some(with.exception.as_str()))))
+                }
+                ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right) => {
+                    self.stack.push((child_path(&path, 1), right));
+                    self.stack.push((child_path(&path, 0), left));
+                }
+                ExpressionVariant::Parens(inner) => self.stack.push((child_path(&path, 0), inner)),
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = Leaf<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_located().map(|(_, leaf)| leaf)
+    }
+}
+
+/// Iterator over the operators of an expression with the number of their operands. Created with
+/// [`SpdxExpression::operators`].
+///
+/// Operands of the same operator are flattened like in [`SpdxExpression::conjuncts`], so
+/// `MIT AND (ISC AND 0BSD)` has one `AND` with three operands. Operators are returned before the
+/// operators of their operands, in the order they appear in the expression.
+///
+/// [`SpdxExpression::operators`]: crate::SpdxExpression::operators
+/// [`SpdxExpression::conjuncts`]: crate::SpdxExpression::conjuncts
+#[derive(Debug, Clone)]
+pub struct Operators<'a> {
+    stack: Vec<Located<'a>>,
+}
+
+impl<'a> Operators<'a> {
+    pub(crate) fn new(expression: &'a ExpressionVariant) -> Self {
+        Self {
+            stack: vec![(Vec::new(), expression)],
+        }
+    }
+
+    /// Include the path of every operator. The path is the path of the outermost node of the
+    /// flattened operator, without parentheses around it.
+    pub fn with_paths(mut self) -> impl Iterator<Item = (Vec<usize>, (Operator, usize))> + 'a {
+        std::iter::from_fn(move || self.next_located())
+    }
+
+    fn next_located(&mut self) -> Option<(Vec<usize>, (Operator, usize))> {
+        while let Some((path, node)) = self.stack.pop() {
+            let located = strip_parens(node, path);
+            if let Some((operator, operands)) = operands(&located) {
+                let arity = operands.len();
+                self.stack.extend(operands.into_iter().rev());
+                return Some((located.0, (operator, arity)));
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for Operators<'_> {
+    type Item = (Operator, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_located().map(|(_, operator)| operator)
+    }
+}
+
+fn child_path(path: &[usize], index: usize) -> Vec<usize> {
+    let mut child = path.to_vec();
+    child.push(index);
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterate_leaves_in_order() {
+        let expression =
+            ExpressionVariant::parse("MIT AND (ISC OR MIT WITH exc) AND ((LicenseRef-a))").unwrap();
+
+        assert_eq!(
+            Leaves::new(&expression)
+                .map(|(license, exception)| (license.to_string(), exception))
+                .collect::<Vec<_>>(),
+            [
+                ("MIT".to_string(), None),
+                ("ISC".to_string(), None),
+                ("MIT".to_string(), Some("exc")),
+                ("LicenseRef-a".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn iterate_flattened_operators() {
+        let expression =
+            ExpressionVariant::parse("MIT AND (ISC OR (0BSD AND Zlib) OR Apache-2.0) AND ISC")
+                .unwrap();
+
+        assert_eq!(
+            Operators::new(&expression).collect::<Vec<_>>(),
+            [(Operator::And, 3), (Operator::Or, 3), (Operator::And, 2)]
+        );
+        assert_eq!(
+            Operators::new(&ExpressionVariant::parse("(MIT)").unwrap()).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn paths_locate_nodes() {
+        let expression = ExpressionVariant::parse("MIT OR ((ISC AND 0BSD) AND Zlib)").unwrap();
+
+        let leaves = Leaves::new(&expression).with_paths().collect::<Vec<_>>();
+        assert_eq!(
+            leaves
+                .iter()
+                .map(|(path, _)| path.as_slice())
+                .collect::<Vec<_>>(),
+            [&[0][..], &[1, 0, 0, 0, 0], &[1, 0, 0, 0, 1], &[1, 0, 1]]
+        );
+        for (path, (license, _)) in leaves {
+            assert_eq!(
+                expression.get(&path).and_then(ExpressionVariant::license),
+                Some(license)
+            );
+        }
+
+        assert_eq!(
+            Operators::new(&expression).with_paths().collect::<Vec<_>>(),
+            [
+                (vec![], (Operator::Or, 2)),
+                (vec![1, 0], (Operator::And, 3))
+            ]
+        );
+    }
+}
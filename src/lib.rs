@@ -4,13 +4,128 @@
 
 #![doc = include_str!("../README.md")]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
-#![allow(clippy::module_name_repetitions, clippy::must_use_candidate)]
+#![allow(
+    clippy::module_name_repetitions,
+    clippy::must_use_candidate,
+    clippy::multiple_crate_versions
+)]
 
+mod alias;
+#[cfg(feature = "quickcheck")]
+mod arbitrary;
+mod binary;
+mod builder;
+mod canonical;
+mod cargo;
+mod compare;
+mod compatibility;
+mod complete;
+mod cooccurrence;
+mod copyleft;
+mod corpus;
+mod cursor;
+mod diff;
+mod digest;
+mod document;
+mod dot;
 mod error;
+#[cfg(feature = "data")]
+mod exceptions;
 mod expression;
 mod expression_variant;
+mod findings;
+mod format;
+pub mod generator;
+mod graph;
+mod html;
+mod incremental;
+mod index;
+mod iter;
+mod license_list;
+mod lint;
+mod markdown;
+mod mermaid;
+mod messages;
+mod migrate;
+#[cfg(feature = "data")]
+mod obligations;
+mod options_report;
 mod parser;
+mod parser_options;
+mod patch;
+mod policy;
+mod postfix;
+mod preference;
+mod pretty;
+pub mod properties;
+mod prose;
+mod query;
+mod rewrite;
+#[cfg(feature = "sat")]
+mod sat;
+mod sexpr;
+mod simplify;
+mod sort;
+mod span;
+#[cfg(feature = "spdx")]
+mod spdx_crate;
+mod special;
+mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+mod stream;
+mod structure;
+pub mod structured;
+mod template;
+mod trove;
+mod versions;
+mod visit;
 
-pub use error::SpdxExpressionError;
+pub use alias::AliasTable;
+pub use builder::ExpressionBuilder;
+pub use compare::{IgnoreCase, IgnoreDocumentRefs};
+pub use compatibility::{Attempt, CompatibilityTrace, Conflict};
+pub use complete::{Completer, Completion, CompletionKind};
+pub use cooccurrence::CoOccurrence;
+pub use copyleft::Copyleft;
+pub use corpus::CorpusComparison;
+pub use cursor::Cursor;
+pub use diff::Change;
+pub use document::{DocumentLicenses, LicenseRefReport};
+pub use error::{BuildError, ErrorCode, ParseError, SpdxExpressionError};
+#[cfg(feature = "data")]
+pub use exceptions::{EffectiveTerm, ExceptionGrant};
 pub use expression::SpdxExpression;
 pub use expression_variant::SimpleExpression;
+pub use findings::LicenseFindings;
+pub use format::{FormatOptions, Formatted, OperatorCase};
+pub use html::HtmlOptions;
+pub use incremental::{IncrementalExpression, TextEdit};
+pub use iter::{Leaf, Leaves, Operators};
+#[cfg(feature = "serde_json")]
+pub use license_list::LATEST_LIST_URL;
+pub use license_list::{Invalidated, LicenseList, ListDiff, ListEntry, SharedLicenseList};
+pub use lint::{Lint, LintKind};
+pub use messages::{ErrorDetails, ErrorTemplates};
+pub use migrate::{ExceptionRename, Migration, Migrations, Rename};
+#[cfg(feature = "data")]
+pub use obligations::{Obligation, Obligations};
+pub use options_report::{LicenseOption, OptionsReport};
+pub use parser::Operator;
+pub use parser_options::ParserOptions;
+pub use patch::{Patch, Replacement};
+pub use policy::Policy;
+pub use postfix::PostfixToken;
+pub use preference::LicenseWeights;
+pub use pretty::PrettyOptions;
+pub use query::{LeafPattern, Query};
+pub use rewrite::{Pattern, RewriteRules};
+pub use simplify::{ExceptionOverlap, SimplifyOptions};
+pub use span::NodeSpan;
+pub use special::SpecialLicenses;
+pub use stats::Stats;
+pub use stream::ParseMany;
+pub use structure::StructureKind;
+pub use template::ExpressionTemplate;
+pub use versions::{LicenseVersions, VersionSuffix, VersionedIdentifier};
+pub use visit::VisitorMut;
@@ -0,0 +1,843 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Versions of the SPDX License List, for validating identifiers.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+use crate::{
+    error::SpdxExpressionError, expression::SpdxExpression, migrate::Migrations,
+    special::is_special,
+};
+
+/// License or exception on a [`LicenseList`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListEntry {
+    /// The identifier, e.g. `MIT` or `Classpath-exception-2.0`.
+    pub id: String,
+
+    /// The full name, e.g. `MIT License`.
+    pub name: String,
+
+    /// `true` if the identifier is deprecated and shouldn't be used in new expressions.
+    pub deprecated: bool,
+}
+
+impl ListEntry {
+    /// Create an entry that is not deprecated.
+    pub fn new(id: &str, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            deprecated: false,
+        }
+    }
+}
+
+/// A version of the SPDX License List: the license and exception identifiers that are valid in
+/// expressions.
+///
+/// Identifiers are looked up ignoring case, as specified for SPDX expressions. Lists can be
+/// built entry by entry, or loaded from the JSON files of the official
+/// [license list data](https://github.com/spdx/license-list-data) with the `serde_json` feature.
+/// Use [`SharedLicenseList`] to share a list that can be refreshed while it's in use.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{LicenseList, ListEntry, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut list = LicenseList::new("3.21");
+/// list.insert_license(ListEntry::new("MIT", "MIT License"));
+/// list.insert_license(ListEntry::new("GPL-2.0-only", "GNU General Public License v2.0 only"));
+/// list.insert_exception(ListEntry::new("Classpath-exception-2.0", "Classpath exception 2.0"));
+///
+/// assert!(list.contains_license("mit"));
+///
+/// let expression = SpdxExpression::parse(
+///     "MIT OR GPL-2.0-only WITH Classpath-exception-2.0 OR Acme OR LicenseRef-Acme",
+/// )?;
+/// assert_eq!(list.unknown_identifiers(&expression), ["Acme".to_string()].into());
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LicenseList {
+    version: String,
+    licenses: BTreeMap<String, ListEntry>,
+    exceptions: BTreeMap<String, ListEntry>,
+}
+
+impl LicenseList {
+    /// Create an empty list with `version`, e.g. `3.21`.
+    pub fn new(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Get the version of the list.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Add a license, replacing any license with the same identifier.
+    pub fn insert_license(&mut self, license: ListEntry) {
+        self.licenses.insert(license.id.to_lowercase(), license);
+    }
+
+    /// Add an exception, replacing any exception with the same identifier.
+    pub fn insert_exception(&mut self, exception: ListEntry) {
+        self.exceptions
+            .insert(exception.id.to_lowercase(), exception);
+    }
+
+    /// Get the license with `id`, ignoring case.
+    pub fn license(&self, id: &str) -> Option<&ListEntry> {
+        self.licenses.get(&id.to_lowercase())
+    }
+
+    /// Get the exception with `id`, ignoring case.
+    pub fn exception(&self, id: &str) -> Option<&ListEntry> {
+        self.exceptions.get(&id.to_lowercase())
+    }
+
+    /// Check if the list has a license with `id`, ignoring case.
+    pub fn contains_license(&self, id: &str) -> bool {
+        self.license(id).is_some()
+    }
+
+    /// Check if the list has an exception with `id`, ignoring case.
+    pub fn contains_exception(&self, id: &str) -> bool {
+        self.exception(id).is_some()
+    }
+
+    /// Iterate over the licenses, ordered by their identifiers ignoring case.
+    pub fn licenses(&self) -> impl Iterator<Item = &ListEntry> {
+        self.licenses.values()
+    }
+
+    /// Iterate over the exceptions, ordered by their identifiers ignoring case.
+    pub fn exceptions(&self) -> impl Iterator<Item = &ListEntry> {
+        self.exceptions.values()
+    }
+
+    /// Get the license and exception identifiers of `expression` that are not on the list.
+    /// `LicenseRef`s, `NONE` and `NOASSERTION` are always valid.
+    pub fn unknown_identifiers(&self, expression: &SpdxExpression) -> BTreeSet<String> {
+        let mut unknown = BTreeSet::new();
+        for (license, exception) in expression.leaves() {
+            if !license.license_ref
+                && !is_special(license)
+                && !self.contains_license(&license.identifier)
+            {
+                unknown.insert(license.identifier.clone());
+            }
+            if let Some(exception) = exception {
+                if !self.contains_exception(exception) {
+                    unknown.insert(exception.to_string());
+                }
+            }
+        }
+        unknown
+    }
+
+    /// Get the license and exception identifiers of `expression` that are deprecated on the
+    /// list.
+    pub fn deprecated_identifiers(&self, expression: &SpdxExpression) -> BTreeSet<String> {
+        let mut deprecated = BTreeSet::new();
+        for (license, exception) in expression.leaves() {
+            if !license.license_ref
+                && self
+                    .license(&license.identifier)
+                    .is_some_and(|entry| entry.deprecated)
+            {
+                deprecated.insert(license.identifier.clone());
+            }
+            if let Some(exception) = exception {
+                if self
+                    .exception(exception)
+                    .is_some_and(|entry| entry.deprecated)
+                {
+                    deprecated.insert(exception.to_string());
+                }
+            }
+        }
+        deprecated
+    }
+
+    /// Check if `expression` passes strict validation against the list, i.e. all its
+    /// identifiers are on the list and none of them is deprecated.
+    pub fn is_valid_strict(&self, expression: &SpdxExpression) -> bool {
+        self.unknown_identifiers(expression).is_empty()
+            && self.deprecated_identifiers(expression).is_empty()
+    }
+
+    /// Find the expressions of a corpus that pass strict validation against the list but fail
+    /// it against a `newer` version of the list, e.g. to plan remediation of stored expressions
+    /// before upgrading. The expressions are identified by keys of type `K` chosen by the
+    /// caller, like the primary keys of a database.
+    ///
+    /// Where migrating an expression with the replacements documented between the versions,
+    /// see [`Migrations`](crate::Migrations), makes it valid again, the migrated expression is
+    /// suggested as remediation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LicenseList, ListEntry, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let mut current = LicenseList::new("2.6");
+    /// current.insert_license(ListEntry::new("MIT", "MIT License"));
+    /// current.insert_license(ListEntry::new("GPL-2.0", "GNU General Public License v2.0"));
+    /// current.insert_license(ListEntry::new("Acme", "Acme License"));
+    ///
+    /// let mut newer = LicenseList::new("3.0");
+    /// newer.insert_license(ListEntry::new("MIT", "MIT License"));
+    /// newer.insert_license(ListEntry {
+    ///     deprecated: true,
+    ///     ..ListEntry::new("GPL-2.0", "GNU General Public License v2.0")
+    /// });
+    /// newer.insert_license(ListEntry::new("GPL-2.0-only", "GNU General Public License v2.0 only"));
+    ///
+    /// let corpus = [
+    ///     (1, SpdxExpression::parse("MIT")?),
+    ///     (2, SpdxExpression::parse("MIT OR GPL-2.0")?),
+    ///     (3, SpdxExpression::parse("Acme")?),
+    /// ];
+    /// let invalidated = current.invalidated_by(&newer, corpus.iter().map(|(id, e)| (*id, e)));
+    ///
+    /// assert_eq!(invalidated.len(), 2);
+    /// assert_eq!(invalidated[0].key, 2);
+    /// assert_eq!(invalidated[0].deprecated, ["GPL-2.0".to_string()].into());
+    /// assert_eq!(
+    ///     invalidated[0].remediation,
+    ///     Some(SpdxExpression::parse("MIT OR GPL-2.0-only")?)
+    /// );
+    /// assert_eq!(invalidated[1].unknown, ["Acme".to_string()].into());
+    /// assert_eq!(invalidated[1].remediation, None);
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn invalidated_by<'a, K, I>(&self, newer: &Self, expressions: I) -> Vec<Invalidated<K>>
+    where
+        I: IntoIterator<Item = (K, &'a SpdxExpression)>,
+    {
+        let migrations = Migrations::spdx();
+        expressions
+            .into_iter()
+            .filter(|(_, expression)| {
+                self.is_valid_strict(expression) && !newer.is_valid_strict(expression)
+            })
+            .map(|(key, expression)| {
+                let migrated = migrations
+                    .migrate(expression, &self.version, &newer.version)
+                    .expression;
+                Invalidated {
+                    key,
+                    unknown: newer.unknown_identifiers(expression),
+                    deprecated: newer.deprecated_identifiers(expression),
+                    remediation: newer.is_valid_strict(&migrated).then_some(migrated),
+                }
+            })
+            .collect()
+    }
+
+    /// Compare the list with a `newer` version of it, e.g. to audit what changes before
+    /// upgrading the list used for validation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LicenseList, ListEntry};
+    /// #
+    /// let mut old = LicenseList::new("3.20");
+    /// old.insert_license(ListEntry::new("GPL-2.0", "GNU General Public License v2.0 only"));
+    /// old.insert_license(ListEntry::new("Acme", "Acme License"));
+    ///
+    /// let mut new = LicenseList::new("3.21");
+    /// new.insert_license(ListEntry {
+    ///     deprecated: true,
+    ///     ..ListEntry::new("GPL-2.0", "GNU General Public License v2.0 only")
+    /// });
+    /// new.insert_license(ListEntry::new("GPL-2.0-only", "GNU General Public License v2.0 only"));
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added_licenses, ["GPL-2.0-only".to_string()].into());
+    /// assert_eq!(diff.removed_licenses, ["Acme".to_string()].into());
+    /// assert_eq!(diff.deprecated_licenses, ["GPL-2.0".to_string()].into());
+    /// assert!(diff.added_exceptions.is_empty());
+    /// ```
+    pub fn diff(&self, newer: &Self) -> ListDiff {
+        let (added_licenses, removed_licenses, deprecated_licenses) =
+            diff_entries(&self.licenses, &newer.licenses);
+        let (added_exceptions, removed_exceptions, deprecated_exceptions) =
+            diff_entries(&self.exceptions, &newer.exceptions);
+        ListDiff {
+            added_licenses,
+            removed_licenses,
+            deprecated_licenses,
+            added_exceptions,
+            removed_exceptions,
+            deprecated_exceptions,
+        }
+    }
+}
+
+/// Expression that fails strict validation against a newer version of a [`LicenseList`], see
+/// [`LicenseList::invalidated_by`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invalidated<K> {
+    /// Key of the expression.
+    pub key: K,
+
+    /// Identifiers of the expression that are not on the newer list.
+    pub unknown: BTreeSet<String>,
+
+    /// Identifiers of the expression that are deprecated on the newer list.
+    pub deprecated: BTreeSet<String>,
+
+    /// The expression migrated to the newer list, if the migration makes it valid.
+    pub remediation: Option<SpdxExpression>,
+}
+
+/// Changes between two versions of a [`LicenseList`], see [`LicenseList::diff`].
+///
+/// Identifiers are spelled as on the list they are on, i.e. removed identifiers as on the older
+/// list and the others as on the newer list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListDiff {
+    /// Licenses only on the newer list.
+    pub added_licenses: BTreeSet<String>,
+
+    /// Licenses only on the older list.
+    pub removed_licenses: BTreeSet<String>,
+
+    /// Licenses on both lists that are deprecated only on the newer list.
+    pub deprecated_licenses: BTreeSet<String>,
+
+    /// Exceptions only on the newer list.
+    pub added_exceptions: BTreeSet<String>,
+
+    /// Exceptions only on the older list.
+    pub removed_exceptions: BTreeSet<String>,
+
+    /// Exceptions on both lists that are deprecated only on the newer list.
+    pub deprecated_exceptions: BTreeSet<String>,
+}
+
+impl ListDiff {
+    /// Check if the lists have the same identifiers and deprecations.
+    pub fn is_empty(&self) -> bool {
+        self.added_licenses.is_empty()
+            && self.removed_licenses.is_empty()
+            && self.deprecated_licenses.is_empty()
+            && self.added_exceptions.is_empty()
+            && self.removed_exceptions.is_empty()
+            && self.deprecated_exceptions.is_empty()
+    }
+}
+
+/// Get the added, removed and newly deprecated identifiers of `newer` compared to `older`.
+fn diff_entries(
+    older: &BTreeMap<String, ListEntry>,
+    newer: &BTreeMap<String, ListEntry>,
+) -> (BTreeSet<String>, BTreeSet<String>, BTreeSet<String>) {
+    let mut added = BTreeSet::new();
+    let mut deprecated = BTreeSet::new();
+    for (key, entry) in newer {
+        match older.get(key) {
+            None => {
+                added.insert(entry.id.clone());
+            }
+            Some(old) if entry.deprecated && !old.deprecated => {
+                deprecated.insert(entry.id.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    let removed = older
+        .iter()
+        .filter(|(key, _)| !newer.contains_key(*key))
+        .map(|(_, entry)| entry.id.clone())
+        .collect();
+    (added, removed, deprecated)
+}
+
+#[cfg(feature = "serde_json")]
+impl LicenseList {
+    /// Create a list from the contents of the `licenses.json` and `exceptions.json` files of the
+    /// SPDX license list data. The version is read from `licenses`.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::LicenseList;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let licenses = serde_json::json!({
+    ///     "licenseListVersion": "3.21",
+    ///     "licenses": [
+    ///         { "licenseId": "MIT", "name": "MIT License", "isDeprecatedLicenseId": false },
+    ///         { "licenseId": "GPL-2.0", "name": "GNU GPL v2.0", "isDeprecatedLicenseId": true }
+    ///     ]
+    /// });
+    /// let exceptions = serde_json::json!({
+    ///     "licenseListVersion": "3.21",
+    ///     "exceptions": [{
+    ///         "licenseExceptionId": "LLVM-exception",
+    ///         "name": "LLVM Exception",
+    ///         "isDeprecatedLicenseId": false
+    ///     }]
+    /// });
+    ///
+    /// let list = LicenseList::from_json(&licenses, &exceptions)?;
+    /// assert_eq!(list.version(), "3.21");
+    /// assert!(list.license("GPL-2.0").unwrap().deprecated);
+    /// assert!(list.contains_exception("LLVM-exception"));
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpdxExpressionError::LicenseList`] if the files don't have the expected
+    /// structure.
+    pub fn from_json(
+        licenses: &serde_json::Value,
+        exceptions: &serde_json::Value,
+    ) -> Result<Self, SpdxExpressionError> {
+        let version = licenses["licenseListVersion"].as_str().ok_or_else(|| {
+            SpdxExpressionError::LicenseList("missing `licenseListVersion`".to_string())
+        })?;
+
+        let mut list = Self::new(version);
+        for license in json_entries(licenses, "licenses", "licenseId")? {
+            list.insert_license(license);
+        }
+        for exception in json_entries(exceptions, "exceptions", "licenseExceptionId")? {
+            list.insert_exception(exception);
+        }
+        Ok(list)
+    }
+
+    /// Load a list from the `licenses.json` and `exceptions.json` files in `directory`, e.g. the
+    /// `json` directory of a checkout of the SPDX license list data.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpdxExpressionError::Io`] if reading the files fails, and
+    /// [`SpdxExpressionError::LicenseList`] if they are not valid license list data.
+    pub fn load<P>(directory: P) -> Result<Self, SpdxExpressionError>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let read = |file: &str| -> Result<serde_json::Value, SpdxExpressionError> {
+            let reader =
+                std::io::BufReader::new(std::fs::File::open(directory.as_ref().join(file))?);
+            serde_json::from_reader(reader)
+                .map_err(|error| SpdxExpressionError::LicenseList(format!("{file}: {error}")))
+        };
+        Self::from_json(&read("licenses.json")?, &read("exceptions.json")?)
+    }
+}
+
+/// Base URL of the JSON files of the latest version of the SPDX License List.
+#[cfg(feature = "serde_json")]
+pub const LATEST_LIST_URL: &str = "https://spdx.org/licenses/";
+
+#[cfg(feature = "serde_json")]
+impl LicenseList {
+    /// Download the latest version of the SPDX License List from [`LATEST_LIST_URL`].
+    ///
+    /// The files are downloaded with `get`, which is called with the URL of each file and
+    /// returns the body of the response. This way any HTTP client and async runtime can be used,
+    /// e.g. `reqwest`, and no client is included in the crate.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LicenseList, SharedLicenseList, SpdxExpressionError};
+    /// #
+    /// # async fn http_get(url: String) -> Result<Vec<u8>, std::io::Error> {
+    /// #     unimplemented!()
+    /// # }
+    /// #
+    /// async fn update(shared: &SharedLicenseList) -> Result<(), SpdxExpressionError> {
+    ///     shared.set(LicenseList::fetch_latest(http_get).await?);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpdxExpressionError::LicenseList`] if downloading the files fails or they are
+    /// not valid license list data.
+    pub async fn fetch_latest<F, Fut, E>(get: F) -> Result<Self, SpdxExpressionError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<u8>, E>>,
+        E: std::fmt::Display,
+    {
+        Self::fetch(LATEST_LIST_URL, get).await
+    }
+
+    /// Download the `licenses.json` and `exceptions.json` files of the SPDX License List from
+    /// `base_url`, e.g. a mirror or the `json` directory of a version of the license list data,
+    /// like [`LicenseList::fetch_latest`].
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SpdxExpressionError::LicenseList`] if downloading the files fails or they are
+    /// not valid license list data.
+    pub async fn fetch<F, Fut, E>(base_url: &str, get: F) -> Result<Self, SpdxExpressionError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<u8>, E>>,
+        E: std::fmt::Display,
+    {
+        let mut files = Vec::new();
+        for file in ["licenses.json", "exceptions.json"] {
+            let url = format!("{}/{file}", base_url.trim_end_matches('/'));
+            let body = get(url.clone())
+                .await
+                .map_err(|error| SpdxExpressionError::LicenseList(format!("{url}: {error}")))?;
+            let value = serde_json::from_slice::<serde_json::Value>(&body)
+                .map_err(|error| SpdxExpressionError::LicenseList(format!("{url}: {error}")))?;
+            files.push(value);
+        }
+        Self::from_json(&files[0], &files[1])
+    }
+}
+
+/// Read the entries of the array `field` of license list data, with identifiers in `id_field`.
+#[cfg(feature = "serde_json")]
+fn json_entries(
+    data: &serde_json::Value,
+    field: &str,
+    id_field: &str,
+) -> Result<Vec<ListEntry>, SpdxExpressionError> {
+    let entries = data[field]
+        .as_array()
+        .ok_or_else(|| SpdxExpressionError::LicenseList(format!("missing `{field}`")))?;
+    entries
+        .iter()
+        .map(|entry| {
+            let id = entry[id_field].as_str().ok_or_else(|| {
+                SpdxExpressionError::LicenseList(format!("entry of `{field}` without `{id_field}`"))
+            })?;
+            Ok(ListEntry {
+                id: id.to_string(),
+                name: entry["name"].as_str().unwrap_or_default().to_string(),
+                deprecated: entry["isDeprecatedLicenseId"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// Source of the data of a [`SharedLicenseList`].
+type Source = Arc<dyn Fn() -> Result<LicenseList, SpdxExpressionError> + Send + Sync>;
+
+struct Shared {
+    list: RwLock<Arc<LicenseList>>,
+    source: RwLock<Option<Source>>,
+}
+
+/// Shared, cached [`LicenseList`] that can be replaced or refreshed while it's in use, e.g. by
+/// long-running services that update their validation data without restarting.
+///
+/// Clones of the handle share the same list. Readers get the list as an [`Arc`], so a refresh
+/// doesn't affect lookups in progress, and the list is only read from its source when it's
+/// refreshed. [`SharedLicenseList::global`] is shared by the whole process, and independent
+/// handles can be created and passed to the code that needs them.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{LicenseList, ListEntry, SharedLicenseList};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let shared = SharedLicenseList::with_source(|| {
+///     let mut list = LicenseList::new("3.21");
+///     list.insert_license(ListEntry::new("MIT", "MIT License"));
+///     Ok(list)
+/// })?;
+/// let list = shared.get();
+/// assert!(list.contains_license("MIT"));
+///
+/// shared.refresh()?;
+/// assert_eq!(shared.get().version(), "3.21");
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Clone)]
+pub struct SharedLicenseList {
+    shared: Arc<Shared>,
+}
+
+impl SharedLicenseList {
+    /// Create a handle for `list`, without a source to refresh it from.
+    pub fn new(list: LicenseList) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                list: RwLock::new(Arc::new(list)),
+                source: RwLock::new(None),
+            }),
+        }
+    }
+
+    /// Create a handle that reads the list from `source`, e.g. from files or the network, and
+    /// reads it again on [`SharedLicenseList::refresh`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `source` if reading the list fails.
+    pub fn with_source<F>(source: F) -> Result<Self, SpdxExpressionError>
+    where
+        F: Fn() -> Result<LicenseList, SpdxExpressionError> + Send + Sync + 'static,
+    {
+        let shared = Self::new(source()?);
+        shared.set_source(source);
+        Ok(shared)
+    }
+
+    /// Get the handle shared by the whole process. The list is empty until it's set or
+    /// refreshed from a source.
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<SharedLicenseList> = OnceLock::new();
+        GLOBAL.get_or_init(|| Self::new(LicenseList::default()))
+    }
+
+    /// Get the current list.
+    pub fn get(&self) -> Arc<LicenseList> {
+        self.shared
+            .list
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replace the current list with `list`.
+    pub fn set(&self, list: LicenseList) {
+        *self
+            .shared
+            .list
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::new(list);
+    }
+
+    /// Set the source [`SharedLicenseList::refresh`] reads the list from. The current list is
+    /// kept until the next refresh.
+    pub fn set_source<F>(&self, source: F)
+    where
+        F: Fn() -> Result<LicenseList, SpdxExpressionError> + Send + Sync + 'static,
+    {
+        *self
+            .shared
+            .source
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(source));
+    }
+
+    /// Read the list from the source again and replace the current list with it. Returns the
+    /// new list, or the current list if there's no source.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the source if reading the list fails. The current list is kept.
+    pub fn refresh(&self) -> Result<Arc<LicenseList>, SpdxExpressionError> {
+        let source = self
+            .shared
+            .source
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some(source) = source {
+            self.set(source()?);
+        }
+        Ok(self.get())
+    }
+}
+
+impl Debug for SharedLicenseList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedLicenseList")
+            .field("list", &self.get())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn list(version: &str, licenses: &[&str]) -> LicenseList {
+        let mut list = LicenseList::new(version);
+        for license in licenses {
+            list.insert_license(ListEntry::new(license, license));
+        }
+        list
+    }
+
+    #[test]
+    fn find_unknown_identifiers() {
+        let list = list("3.21", &["MIT", "GPL-2.0"]);
+        let expression = SpdxExpression::parse(
+            "mit AND GPL-2.0+ AND NOASSERTION AND DocumentRef-a:LicenseRef-b AND ISC WITH exc",
+        )
+        .unwrap();
+        assert_eq!(
+            list.unknown_identifiers(&expression),
+            BTreeSet::from(["ISC".to_string(), "exc".to_string()])
+        );
+    }
+
+    #[test]
+    fn find_expressions_invalidated_by_upgrade() {
+        let current = list("2.6", &["MIT", "GPL-3.0", "Dropped"]);
+        let mut newer = list("3.0", &["MIT", "GPL-3.0-only"]);
+        newer.insert_license(ListEntry {
+            deprecated: true,
+            ..ListEntry::new("GPL-3.0", "")
+        });
+
+        let corpus = ["MIT", "GPL-3.0 AND Dropped", "Unknown", "LicenseRef-a"]
+            .map(|expression| SpdxExpression::parse(expression).unwrap());
+        let invalidated = current.invalidated_by(&newer, corpus.iter().enumerate());
+
+        assert_eq!(invalidated.len(), 1);
+        let Invalidated {
+            key,
+            unknown,
+            deprecated,
+            remediation,
+        } = &invalidated[0];
+        assert_eq!(*key, 1);
+        assert_eq!(unknown, &BTreeSet::from(["Dropped".to_string()]));
+        assert_eq!(deprecated, &BTreeSet::from(["GPL-3.0".to_string()]));
+        assert_eq!(remediation, &None);
+    }
+
+    #[test]
+    fn diff_exceptions_and_ignore_case() {
+        let mut old = list("3.20", &["mit"]);
+        old.insert_exception(ListEntry::new("Old-exception", ""));
+        old.insert_exception(ListEntry::new("Nokia-Qt-exception-1.1", ""));
+
+        let mut new = list("3.21", &["MIT"]);
+        new.insert_exception(ListEntry {
+            deprecated: true,
+            ..ListEntry::new("Nokia-Qt-exception-1.1", "")
+        });
+        new.insert_exception(ListEntry::new("New-exception", ""));
+
+        let diff = old.diff(&new);
+        assert!(diff.added_licenses.is_empty() && diff.removed_licenses.is_empty());
+        assert_eq!(
+            diff.added_exceptions,
+            BTreeSet::from(["New-exception".to_string()])
+        );
+        assert_eq!(
+            diff.removed_exceptions,
+            BTreeSet::from(["Old-exception".to_string()])
+        );
+        assert_eq!(
+            diff.deprecated_exceptions,
+            BTreeSet::from(["Nokia-Qt-exception-1.1".to_string()])
+        );
+        assert!(new.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn refresh_shared_list_from_source() {
+        let loads = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&loads);
+        let shared = SharedLicenseList::with_source(move || {
+            let version = counter.fetch_add(1, Ordering::SeqCst).to_string();
+            Ok(list(&version, &["MIT"]))
+        })
+        .unwrap();
+
+        let before = shared.get();
+        let clone = shared.clone();
+        assert_eq!(clone.refresh().unwrap().version(), "1");
+        assert_eq!(before.version(), "0");
+        assert_eq!(shared.get().version(), "1");
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn keep_list_when_refresh_fails() {
+        let shared = SharedLicenseList::new(list("3.20", &["MIT"]));
+        assert_eq!(shared.refresh().unwrap().version(), "3.20");
+
+        shared.set_source(|| Err(SpdxExpressionError::LicenseList("offline".to_string())));
+        assert!(shared.refresh().is_err());
+        assert_eq!(shared.get().version(), "3.20");
+    }
+
+    /// Run `future` to completion. The futures in the tests never wait, so they don't need a
+    /// runtime.
+    #[cfg(feature = "serde_json")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        loop {
+            if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn fetch_list_files() {
+        let get = |url: String| async move {
+            match url.as_str() {
+                "https://example.com/json/licenses.json" => Ok(br#"{
+                    "licenseListVersion": "3.22",
+                    "licenses": [{ "licenseId": "MIT", "name": "MIT License" }]
+                }"#
+                .to_vec()),
+                "https://example.com/json/exceptions.json" => {
+                    Ok(br#"{ "exceptions": [] }"#.to_vec())
+                }
+                _ => Err("not found"),
+            }
+        };
+
+        let list = block_on(LicenseList::fetch("https://example.com/json/", get)).unwrap();
+        assert_eq!(list.version(), "3.22");
+        assert!(list.contains_license("MIT"));
+
+        let error = block_on(LicenseList::fetch("https://example.com", get)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error loading the license list: https://example.com/licenses.json: not found"
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn reject_invalid_json() {
+        let licenses = serde_json::json!({ "licenseListVersion": "3.21", "licenses": [{}] });
+        let exceptions = serde_json::json!({ "exceptions": [] });
+        assert!(LicenseList::from_json(&licenses, &exceptions).is_err());
+        assert!(LicenseList::from_json(&exceptions, &exceptions).is_err());
+        assert!(LicenseList::load("/nonexistent").is_err());
+    }
+}
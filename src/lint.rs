@@ -0,0 +1,428 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Findings about redundant parts and style of expressions.
+
+use std::ops::Range;
+
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    incremental::TextEdit,
+    parser::Operator,
+    span::{self, NodeSpan},
+    versions::LicenseVersions,
+};
+
+/// Kind of a [`Lint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    /// Parentheses that don't change the meaning of the expression, like in `(MIT)` or
+    /// `MIT OR (ISC AND 0BSD)`.
+    RedundantParentheses,
+
+    /// `AND`, `OR` or `WITH` not in uppercase, as recommended by the SPDX specification.
+    LowercaseOperator,
+
+    /// Operand repeated in the operands of the same operator, like the second `MIT` in
+    /// `MIT AND ISC AND MIT`.
+    DuplicateOperand,
+
+    /// License with a `+` in an expression that also uses `-or-later` identifiers, like
+    /// `GPL-2.0+` in `GPL-2.0+ OR LGPL-2.1-or-later`.
+    MixedOrLaterStyles,
+}
+
+/// Finding in an expression, see [`SpdxExpression::lint`].
+///
+/// [`SpdxExpression::lint`]: crate::SpdxExpression::lint
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lint {
+    pub kind: LintKind,
+
+    /// Byte range of the finding in the text of the expression.
+    pub range: Range<usize>,
+
+    /// Suggested text to replace the range with. Empty if the range should be removed.
+    pub replacement: String,
+}
+
+/// Find the lints of `expression` in `source`, the text it was parsed from. Sorted by their
+/// ranges.
+pub fn lint(expression: &ExpressionVariant, source: &str) -> Vec<Lint> {
+    let Some(span) = span::locate(expression, source) else {
+        return Vec::new();
+    };
+
+    let mut linter = Linter {
+        source,
+        lints: Vec::new(),
+        plus_licenses: Vec::new(),
+        uses_or_later_identifiers: false,
+    };
+    linter.node(expression, &span, None, None);
+    linter.mixed_or_later_styles();
+
+    let mut lints = linter.lints;
+    lints.sort_by_key(|lint| (lint.range.start, lint.range.end));
+    lints
+}
+
+/// Apply the fixes of the lints of `expression` to `source` until there are no lints left.
+/// Returns the fixed expression, its source and the edits in the order they were applied, each to the text
+/// resulting from the previous edits.
+pub fn autofix(
+    expression: &ExpressionVariant,
+    source: &str,
+) -> (ExpressionVariant, String, Vec<TextEdit>) {
+    let mut source = source.to_string();
+    let mut expression = expression.clone();
+    let mut edits = Vec::new();
+
+    loop {
+        // Overlapping fixes are left for the next round.
+        let mut round: Vec<TextEdit> = Vec::new();
+        for lint in lint(&expression, &source) {
+            if round
+                .last()
+                .is_none_or(|previous| previous.range.end <= lint.range.start)
+            {
+                round.push(TextEdit::new(lint.range, lint.replacement));
+            }
+        }
+        if round.is_empty() {
+            return (expression, source, edits);
+        }
+
+        // Applying the edits from the end keeps the ranges of the rest valid.
+        let mut fixed = source.clone();
+        for edit in round.iter().rev() {
+            fixed.replace_range(edit.range.clone(), &edit.replacement);
+        }
+        let Ok(fixed_expression) = ExpressionVariant::parse(&fixed) else {
+            return (expression, source, edits);
+        };
+
+        source = fixed;
+        expression = fixed_expression;
+        edits.extend(round.into_iter().rev());
+    }
+}
+
+const fn operator_of(node: &ExpressionVariant) -> Option<Operator> {
+    match node {
+        ExpressionVariant::And(..) => Some(Operator::And),
+        ExpressionVariant::Or(..) => Some(Operator::Or),
+        _ => None,
+    }
+}
+
+/// Check if parentheses around `inner` with `parent` as the parent of the parentheses can be
+/// removed without changing the meaning of the expression.
+const fn is_redundant(inner: &ExpressionVariant, parent: Option<&ExpressionVariant>) -> bool {
+    let Some(inner) = operator_of(inner) else {
+        return true;
+    };
+
+    match parent {
+        None | Some(ExpressionVariant::Parens(_)) => true,
+        Some(parent) => matches!(
+            (operator_of(parent), inner),
+            (Some(Operator::Or), _) | (Some(Operator::And), Operator::And)
+        ),
+    }
+}
+
+struct Linter<'a> {
+    source: &'a str,
+    lints: Vec<Lint>,
+
+    /// Ranges of the licenses with a `+` that have an `-or-later` identifier, and the
+    /// identifiers.
+    plus_licenses: Vec<(Range<usize>, String)>,
+
+    uses_or_later_identifiers: bool,
+}
+
+impl Linter<'_> {
+    /// Lint `node` at `span`. `parent` is the parent of the node and `group` the operator of the
+    /// closest ancestor that is not in parentheses.
+    fn node(
+        &mut self,
+        node: &ExpressionVariant,
+        span: &NodeSpan,
+        parent: Option<&ExpressionVariant>,
+        group: Option<Operator>,
+    ) {
+        if let Some(operator) = &span.operator {
+            let text = &self.source[operator.clone()];
+            let uppercase = text.to_ascii_uppercase();
+            if text != uppercase {
+                self.lints.push(Lint {
+                    kind: LintKind::LowercaseOperator,
+                    range: operator.clone(),
+                    replacement: uppercase,
+                });
+            }
+        }
+
+        match node {
+            ExpressionVariant::Simple(license) => self.license(license, span.range.start),
+            ExpressionVariant::With(with) => self.license(&with.license, span.range.start),
+            ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right) => {
+                let operator = operator_of(node);
+                if group != operator {
+                    self.duplicates(node, span);
+                }
+                self.node(left, &span.children[0], Some(node), operator);
+                self.node(right, &span.children[1], Some(node), operator);
+            }
+            ExpressionVariant::Parens(inner) => {
+                let inner_span = &span.children[0];
+                if is_redundant(inner, parent) {
+                    self.lints.push(Lint {
+                        kind: LintKind::RedundantParentheses,
+                        range: span.range.clone(),
+                        replacement: self.padded(&span.range, inner_span.range.clone()),
+                    });
+                }
+                self.node(inner, inner_span, Some(node), group);
+            }
+        }
+    }
+
+    fn license(&mut self, license: &SimpleExpression, start: usize) {
+        if license.identifier.ends_with("-or-later") {
+            self.uses_or_later_identifiers = true;
+        }
+
+        if license.or_later && !license.license_ref {
+            let only = format!("{}-only", license.identifier);
+            if LicenseVersions::spdx().later_versions(&only).is_some() {
+                self.plus_licenses.push((
+                    start..start + license.to_string().len(),
+                    format!("{}-or-later", license.identifier),
+                ));
+            }
+        }
+    }
+
+    fn mixed_or_later_styles(&mut self) {
+        if !self.uses_or_later_identifiers {
+            return;
+        }
+
+        for (range, replacement) in self.plus_licenses.drain(..) {
+            self.lints.push(Lint {
+                kind: LintKind::MixedOrLaterStyles,
+                range,
+                replacement,
+            });
+        }
+    }
+
+    /// Text of `inner` for replacing `range` with, separated from the surrounding text with
+    /// spaces where needed.
+    fn padded(&self, range: &Range<usize>, inner: Range<usize>) -> String {
+        let before = self.source[..range.start].chars().next_back();
+        let after = self.source[range.end..].chars().next();
+
+        let mut text = String::new();
+        if before.is_some_and(|c| !c.is_whitespace() && c != '(') {
+            text.push(' ');
+        }
+        text.push_str(&self.source[inner]);
+        if after.is_some_and(|c| !c.is_whitespace() && c != ')') {
+            text.push(' ');
+        }
+        text
+    }
+
+    /// Find the operands of the operator of `node` that are repeated, looking through
+    /// parentheses like [`ExpressionVariant::flatten`].
+    fn duplicates(&mut self, node: &ExpressionVariant, span: &NodeSpan) {
+        let mut operands = Vec::new();
+        collect_operands(node, span, &mut operands);
+
+        for (index, (operand, removed)) in operands.iter().enumerate() {
+            if operands[..index].iter().any(|(other, _)| other == operand) {
+                self.lints.push(Lint {
+                    kind: LintKind::DuplicateOperand,
+                    range: removed.clone(),
+                    replacement: String::new(),
+                });
+            }
+        }
+    }
+}
+
+/// Collect the operands of the operator of `node` without parentheses, with the ranges to
+/// remove to remove the operands along with their operators.
+fn collect_operands<'a>(
+    node: &'a ExpressionVariant,
+    span: &NodeSpan,
+    operands: &mut Vec<(&'a ExpressionVariant, Range<usize>)>,
+) {
+    let (ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right)) = node else {
+        return;
+    };
+    let (left_span, right_span) = (&span.children[0], &span.children[1]);
+
+    for (child, child_span, removed) in [
+        (
+            left,
+            left_span,
+            left_span.range.start..right_span.range.start,
+        ),
+        (right, right_span, left_span.range.end..right_span.range.end),
+    ] {
+        let mut child: &ExpressionVariant = child;
+        let mut child_span = child_span;
+        while let ExpressionVariant::Parens(inner) = child {
+            child = inner;
+            child_span = &child_span.children[0];
+        }
+
+        if operator_of(child) == operator_of(node) {
+            collect_operands(child, child_span, operands);
+        } else {
+            operands.push((child, removed));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lints(source: &str) -> Vec<(LintKind, &str, String)> {
+        lint(&ExpressionVariant::parse(source).unwrap(), source)
+            .into_iter()
+            .map(|lint| (lint.kind, &source[lint.range], lint.replacement))
+            .collect()
+    }
+
+    fn autofix_str(source: &str) -> String {
+        autofix(&ExpressionVariant::parse(source).unwrap(), source).1
+    }
+
+    #[test]
+    fn clean_expressions_have_no_lints() {
+        assert!(lints("MIT AND (ISC OR 0BSD)").is_empty());
+        assert!(lints("(MIT OR ISC) AND GPL-2.0-or-later WITH exc").is_empty());
+        assert!(lints("GPL-2.0+ OR Apache-2.0").is_empty());
+    }
+
+    #[test]
+    fn find_redundant_parentheses() {
+        assert_eq!(
+            lints("((MIT))"),
+            [
+                (
+                    LintKind::RedundantParentheses,
+                    "((MIT))",
+                    "(MIT)".to_string()
+                ),
+                (LintKind::RedundantParentheses, "(MIT)", "MIT".to_string()),
+            ]
+        );
+        assert_eq!(
+            lints("MIT OR ( ISC AND 0BSD ) AND (0BSD OR ISC)"),
+            [(
+                LintKind::RedundantParentheses,
+                "( ISC AND 0BSD )",
+                "ISC AND 0BSD".to_string()
+            )]
+        );
+        assert_eq!(
+            lints("MIT AND(ISC AND 0BSD)"),
+            [(
+                LintKind::RedundantParentheses,
+                "(ISC AND 0BSD)",
+                " ISC AND 0BSD".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn find_lowercase_operators() {
+        assert_eq!(
+            lints("MIT and ISC Or GPL-2.0-only with exc"),
+            [
+                (LintKind::LowercaseOperator, "and", "AND".to_string()),
+                (LintKind::LowercaseOperator, "Or", "OR".to_string()),
+                (LintKind::LowercaseOperator, "with", "WITH".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_duplicate_operands() {
+        assert_eq!(
+            lints("MIT AND ISC AND MIT"),
+            [(LintKind::DuplicateOperand, " AND MIT", String::new())]
+        );
+        assert_eq!(
+            lints("(MIT OR ISC) AND ISC AND (MIT OR ISC)"),
+            [(
+                LintKind::DuplicateOperand,
+                " AND (MIT OR ISC)",
+                String::new()
+            )]
+        );
+        assert_eq!(
+            lints("MIT OR (ISC OR MIT)"),
+            [
+                (
+                    LintKind::RedundantParentheses,
+                    "(ISC OR MIT)",
+                    "ISC OR MIT".to_string()
+                ),
+                (LintKind::DuplicateOperand, " OR MIT", String::new()),
+            ]
+        );
+        assert!(lints("MIT AND (ISC OR MIT)").is_empty());
+    }
+
+    #[test]
+    fn find_mixed_or_later_styles() {
+        assert_eq!(
+            lints("GPL-2.0+ WITH exc OR LGPL-2.1-or-later OR Apache-2.0+"),
+            [(
+                LintKind::MixedOrLaterStyles,
+                "GPL-2.0+",
+                "GPL-2.0-or-later".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn autofix_until_no_lints_are_left() {
+        assert_eq!(autofix_str("((MIT))"), "MIT");
+        assert_eq!(
+            autofix_str("mit and (ISC and mit) or (GPL-2.0+ OR GPL-3.0-or-later)"),
+            "mit AND ISC OR GPL-2.0-or-later OR GPL-3.0-or-later"
+        );
+        assert_eq!(
+            autofix_str("MIT AND(ISC AND MIT)OR 0BSD"),
+            "MIT AND ISC OR 0BSD"
+        );
+        assert_eq!(
+            autofix_str("MIT AND (ISC OR 0BSD)"),
+            "MIT AND (ISC OR 0BSD)"
+        );
+    }
+
+    #[test]
+    fn autofix_edits_reproduce_the_fixed_source() {
+        let source = "(MIT and MIT) OR ((ISC))";
+        let (_, fixed, edits) = autofix(&ExpressionVariant::parse(source).unwrap(), source);
+
+        let mut text = source.to_string();
+        for edit in &edits {
+            text.replace_range(edit.range.clone(), &edit.replacement);
+        }
+        assert_eq!(text, fixed);
+        assert_eq!(fixed, "MIT OR ISC");
+    }
+}
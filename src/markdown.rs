@@ -0,0 +1,91 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rendering expressions as Markdown lists.
+
+use std::fmt::Write;
+
+use crate::{expression_variant::ExpressionVariant, parser::Operator};
+
+pub fn render(expression: &ExpressionVariant) -> String {
+    let mut output = String::new();
+
+    match expression.flatten() {
+        Some((Operator::Or, alternatives)) => {
+            for alternative in alternatives {
+                write_item(&mut output, alternative, 0);
+            }
+        }
+        _ => write_item(&mut output, expression, 0),
+    }
+
+    output
+}
+
+fn write_item(output: &mut String, expression: &ExpressionVariant, depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    // Writing to a `String` can't fail.
+    if let Some((operator, operands)) = expression.flatten() {
+        let heading = match operator {
+            Operator::And => "All of:",
+            Operator::Or => "One of:",
+        };
+        let _ = writeln!(output, "{indent}- {heading}");
+        for operand in operands {
+            write_item(output, operand, depth + 1);
+        }
+        return;
+    }
+
+    let _ = match expression.unparenthesized() {
+        ExpressionVariant::With(with) => writeln!(
+            output,
+            "{indent}- `{}` with `{}`",
+            with.license, with.exception
+        ),
+        other => writeln!(output, "{indent}- `{other}`"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_str(expression: &str) -> String {
+        render(&ExpressionVariant::parse(expression).unwrap())
+    }
+
+    #[test]
+    fn render_single_license() {
+        assert_eq!(render_str("(MIT)"), "- `MIT`\n");
+        assert_eq!(
+            render_str("GPL-2.0-or-later WITH Classpath-exception-2.0"),
+            "- `GPL-2.0-or-later` with `Classpath-exception-2.0`\n"
+        );
+    }
+
+    #[test]
+    fn render_conjunction() {
+        assert_eq!(
+            render_str("MIT AND ISC"),
+            "- All of:\n  - `MIT`\n  - `ISC`\n"
+        );
+    }
+
+    #[test]
+    fn render_nested() {
+        assert_eq!(
+            render_str("MIT OR Apache-2.0 AND (ISC OR 0BSD) OR LicenseRef-a"),
+            "- `MIT`
+- All of:
+  - `Apache-2.0`
+  - One of:
+    - `ISC`
+    - `0BSD`
+- `LicenseRef-a`
+"
+        );
+    }
+}
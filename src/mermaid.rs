@@ -0,0 +1,75 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rendering expressions as Mermaid flowcharts.
+
+use std::fmt::Write;
+
+use crate::{
+    expression_variant::ExpressionVariant,
+    graph::{nodes, NodeKind},
+};
+
+pub fn render(expression: &ExpressionVariant) -> String {
+    let mut output = String::from("flowchart TD\n");
+
+    for node in nodes(expression) {
+        let label = escape(&node.label);
+        let id = node.id;
+
+        // Writing to a `String` can't fail.
+        let _ = match node.kind {
+            NodeKind::Operator => writeln!(output, "    n{id}{{\"{label}\"}}"),
+            NodeKind::License => writeln!(output, "    n{id}[\"{label}\"]"),
+            NodeKind::Exception => writeln!(output, "    n{id}([\"{label}\"])"),
+        };
+        if let Some(parent) = node.parent {
+            let _ = writeln!(output, "    n{parent} --> n{id}");
+        }
+    }
+
+    output
+}
+
+/// Escape characters that would end a quoted Mermaid label.
+fn escape(label: &str) -> String {
+    label.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_flowchart() {
+        let expression = ExpressionVariant::parse(
+            "MIT OR Apache-2.0 AND GPL-2.0-only WITH Classpath-exception-2.0",
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&expression),
+            r#"flowchart TD
+    n0{"OR"}
+    n1["MIT"]
+    n0 --> n1
+    n2{"AND"}
+    n0 --> n2
+    n3["Apache-2.0"]
+    n2 --> n3
+    n4{"WITH"}
+    n2 --> n4
+    n5["GPL-2.0-only"]
+    n4 --> n5
+    n6(["Classpath-exception-2.0"])
+    n4 --> n6
+"#
+        );
+    }
+
+    #[test]
+    fn escape_quotes() {
+        assert_eq!(escape(r#"a"b"#), "a#quot;b");
+    }
+}
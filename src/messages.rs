@@ -0,0 +1,229 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Customizable rendering of error messages.
+
+use std::collections::HashMap;
+
+use crate::error::{BuildError, ErrorCode, SpdxExpressionError};
+
+/// Structured data of an error for rendering custom messages, see
+/// [`SpdxExpressionError::render_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDetails {
+    pub code: ErrorCode,
+
+    /// What the error is about, e.g. the input that failed to parse or the invalid license
+    /// identifier.
+    pub subject: String,
+
+    /// Byte offset in `subject` where parsing failed, for parse errors.
+    pub offset: Option<usize>,
+
+    /// What the parser expected at `offset`, for parse errors.
+    pub expected: Option<&'static str>,
+
+    /// The default message of the error.
+    pub message: String,
+}
+
+impl SpdxExpressionError {
+    /// Get the structured data of the error.
+    pub fn details(&self) -> ErrorDetails {
+        let (subject, offset, expected) = match self {
+            Self::Parse(error) => (
+                error.input.clone(),
+                error.offset,
+                error.context.first().copied(),
+            ),
+            Self::Io(error) => (error.to_string(), None, None),
+            Self::Build(
+                BuildError::InvalidLicense(subject)
+                | BuildError::InvalidException(subject)
+                | BuildError::ExceptionOnCompound(subject)
+                | BuildError::DuplicateException(subject)
+                | BuildError::SpecialLicense(subject),
+            )
+            | Self::Nom(subject)
+            | Self::Decode(subject)
+            | Self::LicenseList(subject)
+            | Self::Policy(subject)
+            | Self::Conversion(subject)
+            | Self::Empty(subject)
+            | Self::Rewrite(subject)
+            | Self::Patch(subject)
+            | Self::Edit(subject)
+            | Self::Template(subject)
+            | Self::Query(subject)
+            | Self::Budget(subject) => (subject.clone(), None, None),
+        };
+
+        ErrorDetails {
+            code: self.error_code(),
+            subject,
+            offset,
+            expected,
+            message: self.to_string(),
+        }
+    }
+
+    /// Render the message of the error with `render`, e.g. to localize the messages. Falls back
+    /// to the default message if `render` returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{ErrorCode, SpdxExpression};
+    /// #
+    /// let error = SpdxExpression::parse("MIT AND (ISC").unwrap_err();
+    /// let message = error.render_with(|details| match details.code {
+    ///     ErrorCode::UnbalancedParenthesis => Some(format!(
+    ///         "Sulkeet puuttuvat lausekkeesta `{}`",
+    ///         details.subject
+    ///     )),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(message, "Sulkeet puuttuvat lausekkeesta `MIT AND (ISC`");
+    /// ```
+    pub fn render_with<F>(&self, render: F) -> String
+    where
+        F: FnOnce(&ErrorDetails) -> Option<String>,
+    {
+        let details = self.details();
+        render(&details).unwrap_or(details.message)
+    }
+}
+
+/// Templates for the messages of errors by their codes, see
+/// [`SpdxExpressionError::render_with`] for rendering messages with a function.
+///
+/// The templates can refer to the fields of [`ErrorDetails`] with `${code}`, `${subject}`,
+/// `${offset}`, `${expected}` and `${message}`. The offset and the expected text are empty if
+/// the error doesn't have them. Errors without a template are rendered with the default
+/// message.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{ErrorCode, ErrorTemplates, SpdxExpression};
+/// #
+/// let mut templates = ErrorTemplates::default();
+/// templates.insert(
+///     ErrorCode::MissingOperand,
+///     "[${code}] `${subject}`: license missing at ${offset}",
+/// );
+///
+/// let error = SpdxExpression::parse("MIT AND").unwrap_err();
+/// assert_eq!(
+///     templates.render(&error),
+///     "[E0102] `MIT AND`: license missing at 7"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorTemplates {
+    templates: HashMap<ErrorCode, String>,
+}
+
+impl ErrorTemplates {
+    /// Set the template of the errors with `code`, replacing the previous template.
+    pub fn insert(&mut self, code: ErrorCode, template: &str) {
+        self.templates.insert(code, template.to_string());
+    }
+
+    /// Render the message of `error`.
+    pub fn render(&self, error: &SpdxExpressionError) -> String {
+        error.render_with(|details| {
+            let template = self.templates.get(&details.code)?;
+            Some(fill(template, details))
+        })
+    }
+}
+
+/// Replace the placeholders of `template` with the fields of `details` in a single pass, so
+/// placeholders in the values are not replaced.
+fn fill(template: &str, details: &ErrorDetails) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        match &rest[2..end] {
+            "code" => output.push_str(details.code.code()),
+            "subject" => output.push_str(&details.subject),
+            "offset" => {
+                if let Some(offset) = details.offset {
+                    output.push_str(&offset.to_string());
+                }
+            }
+            "expected" => output.push_str(details.expected.unwrap_or_default()),
+            "message" => output.push_str(&details.message),
+            _ => output.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpdxExpression;
+
+    #[test]
+    fn details_of_errors() {
+        let details = SpdxExpression::parse("MIT WITH ").unwrap_err().details();
+        assert_eq!(
+            details,
+            ErrorDetails {
+                code: ErrorCode::MissingException,
+                subject: "MIT WITH ".to_string(),
+                offset: Some(9),
+                expected: Some("exception identifier"),
+                message: "Parsing for expression `MIT WITH ` failed at byte 9: expected \
+                          exception identifier."
+                    .to_string(),
+            }
+        );
+
+        let details = SpdxExpressionError::Empty("MIT".to_string()).details();
+        assert_eq!(details.code, ErrorCode::EmptyResult);
+        assert_eq!(details.subject, "MIT");
+        assert_eq!(details.offset, None);
+    }
+
+    #[test]
+    fn render_templates() {
+        let mut templates = ErrorTemplates::default();
+        templates.insert(ErrorCode::EmptyResult, "${message} (${code}, ${offset})");
+        templates.insert(ErrorCode::UnexpectedText, "${subject}: ${expected}");
+
+        assert_eq!(
+            templates.render(&SpdxExpressionError::Empty("MIT".to_string())),
+            "Removing `MIT` would leave the expression empty. (E0302, )"
+        );
+        assert_eq!(
+            templates.render(&SpdxExpression::parse("MIT ISC").unwrap_err()),
+            "MIT ISC: "
+        );
+
+        let error = SpdxExpression::parse("MIT AND").unwrap_err();
+        assert_eq!(templates.render(&error), error.to_string());
+    }
+
+    #[test]
+    fn placeholders_in_values_are_not_replaced() {
+        let mut templates = ErrorTemplates::default();
+        templates.insert(ErrorCode::EmptyResult, "${subject} ${unknown} ${code");
+
+        assert_eq!(
+            templates.render(&SpdxExpressionError::Empty("${code}".to_string())),
+            "${code} ${unknown} ${code"
+        );
+    }
+}
@@ -0,0 +1,528 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Migration of expressions between versions of the SPDX License List.
+
+use std::{cmp::Ordering, collections::BTreeSet};
+
+use crate::{
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    license_list::LicenseList,
+};
+
+/// Identifiers deprecated on the SPDX License List and the expressions documented to replace
+/// them, as the version of the list that deprecated the identifier, the identifier and the
+/// replacement at that version. Replacements that were later deprecated themselves are migrated
+/// further by the later entries.
+const SPDX_RENAMES: &[(&str, &str, &str)] = &[
+    ("2.0", "eCos-2.0", "GPL-2.0+ WITH eCos-exception-2.0"),
+    (
+        "2.0",
+        "GPL-2.0-with-autoconf-exception",
+        "GPL-2.0 WITH Autoconf-exception-2.0",
+    ),
+    (
+        "2.0",
+        "GPL-2.0-with-bison-exception",
+        "GPL-2.0 WITH Bison-exception-2.2",
+    ),
+    (
+        "2.0",
+        "GPL-2.0-with-classpath-exception",
+        "GPL-2.0 WITH Classpath-exception-2.0",
+    ),
+    (
+        "2.0",
+        "GPL-2.0-with-font-exception",
+        "GPL-2.0 WITH Font-exception-2.0",
+    ),
+    (
+        "2.0",
+        "GPL-2.0-with-GCC-exception",
+        "GPL-2.0 WITH GCC-exception-2.0",
+    ),
+    (
+        "2.0",
+        "GPL-3.0-with-autoconf-exception",
+        "GPL-3.0 WITH Autoconf-exception-3.0",
+    ),
+    (
+        "2.0",
+        "GPL-3.0-with-GCC-exception",
+        "GPL-3.0 WITH GCC-exception-3.1",
+    ),
+    ("3.0", "AGPL-3.0", "AGPL-3.0-only"),
+    ("3.0", "AGPL-3.0+", "AGPL-3.0-or-later"),
+    ("3.0", "GFDL-1.1", "GFDL-1.1-only"),
+    ("3.0", "GFDL-1.1+", "GFDL-1.1-or-later"),
+    ("3.0", "GFDL-1.2", "GFDL-1.2-only"),
+    ("3.0", "GFDL-1.2+", "GFDL-1.2-or-later"),
+    ("3.0", "GFDL-1.3", "GFDL-1.3-only"),
+    ("3.0", "GFDL-1.3+", "GFDL-1.3-or-later"),
+    ("3.0", "GPL-1.0", "GPL-1.0-only"),
+    ("3.0", "GPL-1.0+", "GPL-1.0-or-later"),
+    ("3.0", "GPL-2.0", "GPL-2.0-only"),
+    ("3.0", "GPL-2.0+", "GPL-2.0-or-later"),
+    ("3.0", "GPL-3.0", "GPL-3.0-only"),
+    ("3.0", "GPL-3.0+", "GPL-3.0-or-later"),
+    ("3.0", "LGPL-2.0", "LGPL-2.0-only"),
+    ("3.0", "LGPL-2.0+", "LGPL-2.0-or-later"),
+    ("3.0", "LGPL-2.1", "LGPL-2.1-only"),
+    ("3.0", "LGPL-2.1+", "LGPL-2.1-or-later"),
+    ("3.0", "LGPL-3.0", "LGPL-3.0-only"),
+    ("3.0", "LGPL-3.0+", "LGPL-3.0-or-later"),
+];
+
+/// Identifier deprecated in a version of the license list, see [`Migrations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    /// Version of the license list that deprecated the identifier, e.g. `3.0`.
+    pub version: String,
+
+    /// The deprecated identifier, including a possible `+`, e.g. `GPL-2.0+`.
+    pub identifier: String,
+
+    /// Expression replacing the identifier, or `None` if it was deprecated without a
+    /// replacement.
+    pub replacement: Option<SpdxExpression>,
+}
+
+/// Exception deprecated in a version of the license list, see [`Migrations`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExceptionRename {
+    /// Version of the license list that deprecated the exception, e.g. `3.0`.
+    pub version: String,
+
+    /// The deprecated exception.
+    pub identifier: String,
+
+    /// Exception replacing the exception, or `None` if it was deprecated without a replacement.
+    pub replacement: Option<String>,
+}
+
+/// Result of migrating an expression with [`Migrations::migrate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// The migrated expression.
+    pub expression: SpdxExpression,
+
+    /// The replaced license and exception identifiers and their replacements, in the order
+    /// they were applied.
+    pub renamed: Vec<(String, String)>,
+
+    /// License and exception identifiers of the migrated expression that were deprecated
+    /// without a replacement, and need to be migrated manually.
+    pub removed: BTreeSet<String>,
+}
+
+/// Table of the license and exception identifiers deprecated in each version of the SPDX License
+/// List, for migrating expressions from one version of the list to another.
+///
+/// Deprecations without documented replacements can be added from the license list data with
+/// [`Migrations::insert_deprecations`], so expressions using them are reported by
+/// [`Migrations::migrate`].
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Migrations, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut migrations = Migrations::spdx();
+/// migrations.insert("3.21", "Acme-1.0", None);
+///
+/// let expression = SpdxExpression::parse("GPL-2.0+ AND (eCos-2.0 OR Acme-1.0)")?;
+/// let migration = migrations.migrate(&expression, "1.20", "3.21");
+///
+/// assert_eq!(
+///     migration.expression.to_string(),
+///     "GPL-2.0-or-later AND (GPL-2.0-or-later WITH eCos-exception-2.0 OR Acme-1.0)"
+/// );
+/// assert_eq!(migration.renamed.len(), 2);
+/// assert_eq!(migration.removed, ["Acme-1.0".to_string()].into());
+///
+/// let migration = migrations.migrate(&expression, "1.20", "2.6");
+/// assert_eq!(
+///     migration.expression.to_string(),
+///     "GPL-2.0+ AND (GPL-2.0+ WITH eCos-exception-2.0 OR Acme-1.0)"
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Migrations {
+    renames: Vec<Rename>,
+    exception_renames: Vec<ExceptionRename>,
+}
+
+impl Migrations {
+    /// Create a table without any deprecated identifiers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table of the identifiers documented as deprecated on the SPDX License List
+    /// with a replacement, like the GNU licenses without an `-only` or `-or-later` suffix and
+    /// the licenses with exceptions in their identifiers.
+    pub fn spdx() -> Self {
+        let mut migrations = Self::new();
+        for (version, identifier, replacement) in SPDX_RENAMES {
+            migrations.insert(version, identifier, SpdxExpression::parse(replacement).ok());
+        }
+        migrations
+    }
+
+    /// Add an identifier deprecated in `version` of the list, with its `replacement` or `None`
+    /// if it has no replacement. The identifier includes a possible `+`.
+    pub fn insert(&mut self, version: &str, identifier: &str, replacement: Option<SpdxExpression>) {
+        self.renames.push(Rename {
+            version: version.to_string(),
+            identifier: identifier.to_string(),
+            replacement,
+        });
+    }
+
+    /// Add an exception deprecated in `version` of the list, with its `replacement` or `None`
+    /// if it has no replacement.
+    pub fn insert_exception(&mut self, version: &str, identifier: &str, replacement: Option<&str>) {
+        self.exception_renames.push(ExceptionRename {
+            version: version.to_string(),
+            identifier: identifier.to_string(),
+            replacement: replacement.map(ToString::to_string),
+        });
+    }
+
+    /// Add the licenses and exceptions deprecated or removed in `newer` compared to `older`,
+    /// see [`LicenseList::diff`], that are not in the table yet. They are added without a
+    /// replacement, as the license list data doesn't include the replacements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{LicenseList, ListEntry, Migrations, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let mut older = LicenseList::new("3.20");
+    /// older.insert_license(ListEntry::new("GPL-2.0-only", "GNU General Public License v2.0"));
+    /// older.insert_exception(ListEntry::new("Old-exception", "Old exception"));
+    ///
+    /// let mut newer = LicenseList::new("3.21");
+    /// newer.insert_license(ListEntry::new("GPL-2.0-only", "GNU General Public License v2.0"));
+    /// newer.insert_exception(ListEntry {
+    ///     deprecated: true,
+    ///     ..ListEntry::new("Old-exception", "Old exception")
+    /// });
+    ///
+    /// let mut migrations = Migrations::spdx();
+    /// migrations.insert_deprecations(&older, &newer);
+    ///
+    /// let expression = SpdxExpression::parse("GPL-2.0-only WITH Old-exception")?;
+    /// let migration = migrations.migrate(&expression, "3.20", "3.21");
+    /// assert_eq!(migration.removed, ["Old-exception".to_string()].into());
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// [`LicenseList::diff`]: crate::LicenseList::diff
+    pub fn insert_deprecations(&mut self, older: &LicenseList, newer: &LicenseList) {
+        let diff = older.diff(newer);
+        for identifier in diff
+            .deprecated_licenses
+            .iter()
+            .chain(&diff.removed_licenses)
+        {
+            if !self
+                .renames
+                .iter()
+                .any(|rename| rename.identifier.eq_ignore_ascii_case(identifier))
+            {
+                self.insert(newer.version(), identifier, None);
+            }
+        }
+        for identifier in diff
+            .deprecated_exceptions
+            .iter()
+            .chain(&diff.removed_exceptions)
+        {
+            if !self
+                .exception_renames
+                .iter()
+                .any(|rename| rename.identifier.eq_ignore_ascii_case(identifier))
+            {
+                self.insert_exception(newer.version(), identifier, None);
+            }
+        }
+    }
+
+    /// Get the licenses deprecated after `from_version` up to and including `to_version`,
+    /// ordered by version.
+    pub fn between(&self, from_version: &str, to_version: &str) -> Vec<&Rename> {
+        let mut renames = self
+            .renames
+            .iter()
+            .filter(|rename| is_between(&rename.version, from_version, to_version))
+            .collect::<Vec<_>>();
+        renames.sort_by(|left, right| compare_versions(&left.version, &right.version));
+        renames
+    }
+
+    /// Get the exceptions deprecated after `from_version` up to and including `to_version`,
+    /// ordered by version.
+    pub fn exceptions_between(
+        &self,
+        from_version: &str,
+        to_version: &str,
+    ) -> Vec<&ExceptionRename> {
+        let mut renames = self
+            .exception_renames
+            .iter()
+            .filter(|rename| is_between(&rename.version, from_version, to_version))
+            .collect::<Vec<_>>();
+        renames.sort_by(|left, right| compare_versions(&left.version, &right.version));
+        renames
+    }
+
+    /// Migrate `expression` written for `from_version` of the list to `to_version`, replacing
+    /// the identifiers deprecated in between in the order they were deprecated. The licenses are
+    /// migrated before the exceptions, so exceptions added by replacements of licenses are
+    /// migrated as well. Identifiers are matched ignoring case, and exceptions of replaced
+    /// licenses are kept. Expressions are never migrated to older versions.
+    pub fn migrate(
+        &self,
+        expression: &SpdxExpression,
+        from_version: &str,
+        to_version: &str,
+    ) -> Migration {
+        let mut migrated = expression.clone();
+        let mut renamed = Vec::new();
+        let mut removed = BTreeSet::new();
+
+        for rename in self.between(from_version, to_version) {
+            let Some(replacement) = &rename.replacement else {
+                removed.insert(rename.identifier.clone());
+                continue;
+            };
+            let replacement = replacement.inner().unparenthesized();
+            let mapped = migrated.inner().map_leaves(&mut |leaf| match leaf {
+                ExpressionVariant::Simple(license) if is_renamed(license, rename) => {
+                    Some(replacement.clone())
+                }
+                ExpressionVariant::With(with) if is_renamed(&with.license, rename) => {
+                    Some(replacement.with_exception(&with.exception))
+                }
+                _ => None,
+            });
+            if let Some(mapped) = mapped {
+                migrated = SpdxExpression::from_inner(mapped);
+                renamed.push((rename.identifier.clone(), replacement.to_string()));
+            }
+        }
+
+        let mut removed_exceptions = BTreeSet::new();
+        for rename in self.exceptions_between(from_version, to_version) {
+            let Some(replacement) = &rename.replacement else {
+                removed_exceptions.insert(rename.identifier.clone());
+                continue;
+            };
+            let mapped = migrated.inner().map_leaves(&mut |leaf| match leaf {
+                ExpressionVariant::With(with)
+                    if with.exception.eq_ignore_ascii_case(&rename.identifier) =>
+                {
+                    Some(ExpressionVariant::With(Box::new(WithExpression::new(
+                        with.license.clone(),
+                        replacement.clone(),
+                    ))))
+                }
+                _ => None,
+            });
+            if let Some(mapped) = mapped {
+                migrated = SpdxExpression::from_inner(mapped);
+                renamed.push((rename.identifier.clone(), replacement.clone()));
+            }
+        }
+
+        removed.retain(|identifier| {
+            migrated.leaves().any(|(license, _)| {
+                !license.license_ref
+                    && license.document_ref.is_none()
+                    && license.to_string().eq_ignore_ascii_case(identifier)
+            })
+        });
+        removed_exceptions.retain(|identifier| {
+            migrated.leaves().any(|(_, exception)| {
+                exception.is_some_and(|exception| exception.eq_ignore_ascii_case(identifier))
+            })
+        });
+        removed.append(&mut removed_exceptions);
+        Migration {
+            expression: migrated,
+            renamed,
+            removed,
+        }
+    }
+}
+
+impl SpdxExpression {
+    /// Migrate `self` written for `from_version` of the SPDX License List to `to_version`, with
+    /// the identifiers documented as deprecated in between, see [`Migrations::migrate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::SpdxExpression;
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let expression = SpdxExpression::parse("GPL-2.0-with-classpath-exception OR MIT")?;
+    /// assert_eq!(
+    ///     expression.migrate("1.20", "3.21").expression.to_string(),
+    ///     "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    pub fn migrate(&self, from_version: &str, to_version: &str) -> Migration {
+        Migrations::spdx().migrate(self, from_version, to_version)
+    }
+}
+
+fn is_renamed(license: &SimpleExpression, rename: &Rename) -> bool {
+    !license.license_ref
+        && license.document_ref.is_none()
+        && license.to_string().eq_ignore_ascii_case(&rename.identifier)
+}
+
+/// Check if `version` is after `from_version` and not after `to_version`.
+fn is_between(version: &str, from_version: &str, to_version: &str) -> bool {
+    compare_versions(version, from_version) == Ordering::Greater
+        && compare_versions(version, to_version) != Ordering::Greater
+}
+
+/// Compare versions of the license list like `3.9` and `3.21` by their numeric components.
+/// Suffixes of the components, like `rc2` in `2.0rc2`, are ignored.
+fn compare_versions(left: &str, right: &str) -> Ordering {
+    let components = |version: &str| {
+        version
+            .split('.')
+            .map(|component| {
+                let digits = component
+                    .find(|character: char| !character.is_ascii_digit())
+                    .map_or(component, |end| &component[..end]);
+                digits.parse::<u64>().unwrap_or(0)
+            })
+            .collect::<Vec<_>>()
+    };
+    let (mut left, mut right) = (components(left), components(right));
+    let length = left.len().max(right.len());
+    left.resize(length, 0);
+    right.resize(length, 0);
+    left.cmp(&right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::license_list::ListEntry;
+
+    #[test]
+    fn compare_list_versions() {
+        assert_eq!(compare_versions("3.9", "3.21"), Ordering::Less);
+        assert_eq!(compare_versions("3.0", "3"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0rc2", "2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("3.10", "2.6"), Ordering::Greater);
+    }
+
+    #[test]
+    fn migrate_through_several_versions() {
+        let expression =
+            SpdxExpression::parse("gpl-2.0-with-gcc-exception AND LicenseRef-GPL-2.0").unwrap();
+
+        let migration = expression.migrate("1.0", "3.21");
+        assert_eq!(
+            migration.expression.to_string(),
+            "GPL-2.0-only WITH GCC-exception-2.0 AND LicenseRef-GPL-2.0"
+        );
+        assert_eq!(
+            migration.renamed,
+            [
+                (
+                    "GPL-2.0-with-GCC-exception".to_string(),
+                    "GPL-2.0 WITH GCC-exception-2.0".to_string()
+                ),
+                ("GPL-2.0".to_string(), "GPL-2.0-only".to_string()),
+            ]
+        );
+        assert!(migration.removed.is_empty());
+    }
+
+    #[test]
+    fn keep_exceptions_and_skip_other_versions() {
+        let expression = SpdxExpression::parse("LGPL-2.1+ WITH exc OR GPL-3.0").unwrap();
+        assert_eq!(
+            expression.migrate("2.0", "3.0").expression.to_string(),
+            "LGPL-2.1-or-later WITH exc OR GPL-3.0-only"
+        );
+        assert_eq!(expression.migrate("3.0", "3.21").expression, expression);
+        assert_eq!(expression.migrate("3.21", "2.0").expression, expression);
+    }
+
+    #[test]
+    fn migrate_exceptions_after_licenses() {
+        let mut migrations = Migrations::spdx();
+        migrations.insert_exception("3.5", "ecos-exception-2.0", Some("eCos-exception-3.0"));
+        migrations.insert_exception("3.5", "Unreplaced-exception", None);
+
+        let expression =
+            SpdxExpression::parse("eCos-2.0 OR MIT WITH Unreplaced-exception").unwrap();
+        let migration = migrations.migrate(&expression, "1.0", "3.5");
+        assert_eq!(
+            migration.expression.to_string(),
+            "GPL-2.0-or-later WITH eCos-exception-3.0 OR MIT WITH Unreplaced-exception"
+        );
+        assert_eq!(
+            migration.renamed.last(),
+            Some(&(
+                "ecos-exception-2.0".to_string(),
+                "eCos-exception-3.0".to_string()
+            ))
+        );
+        assert_eq!(
+            migration.removed,
+            BTreeSet::from(["Unreplaced-exception".to_string()])
+        );
+        assert_eq!(migrations.exceptions_between("3.4", "3.5").len(), 2);
+    }
+
+    #[test]
+    fn insert_deprecations_from_lists() {
+        let mut older = LicenseList::new("3.0");
+        older.insert_license(ListEntry::new("GPL-2.0", ""));
+        older.insert_license(ListEntry::new("Dropped", ""));
+        let mut newer = LicenseList::new("3.1");
+        newer.insert_license(ListEntry {
+            deprecated: true,
+            ..ListEntry::new("GPL-2.0", "")
+        });
+
+        let mut migrations = Migrations::spdx();
+        migrations.insert_deprecations(&older, &newer);
+        let added = migrations.between("3.0", "3.1");
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].identifier, "Dropped");
+        assert_eq!(added[0].replacement, None);
+    }
+
+    #[test]
+    fn report_removed_identifiers_only_if_present() {
+        let mut migrations = Migrations::new();
+        migrations.insert("3.5", "Old", None);
+        migrations.insert("3.5", "Unused", None);
+
+        let expression = SpdxExpression::parse("old OR MIT").unwrap();
+        let migration = migrations.migrate(&expression, "3.4", "3.5");
+        assert_eq!(migration.expression, expression);
+        assert_eq!(migration.removed, BTreeSet::from(["Old".to_string()]));
+        assert!(migrations
+            .migrate(&expression, "3.5", "3.6")
+            .removed
+            .is_empty());
+    }
+}
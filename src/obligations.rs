@@ -0,0 +1,180 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Obligations of common licenses.
+
+use std::collections::BTreeSet;
+
+use crate::expression_variant::{ExpressionVariant, SimpleExpression};
+
+/// Obligation or grant of a license, see [`SpdxExpression::obligations`].
+///
+/// The data is a coarse summary of the licenses for a first pass of compliance work, not legal
+/// advice.
+///
+/// [`SpdxExpression::obligations`]: crate::SpdxExpression::obligations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Obligation {
+    /// The copyright and license notices need to be included with the distributed work.
+    Attribution,
+
+    /// The source code needs to be made available when the work is distributed.
+    SourceDisclosure,
+
+    /// The license includes an explicit patent grant.
+    PatentGrant,
+
+    /// The source code needs to be made available to users interacting with the work over a
+    /// network.
+    NetworkClause,
+}
+
+use Obligation::{Attribution, NetworkClause, PatentGrant, SourceDisclosure};
+
+/// Obligations of common licenses, including the deprecated identifiers of the GNU licenses.
+pub const OBLIGATIONS: &[(&str, &[Obligation])] = &[
+    ("0BSD", &[]),
+    (
+        "AGPL-3.0",
+        &[Attribution, SourceDisclosure, PatentGrant, NetworkClause],
+    ),
+    (
+        "AGPL-3.0-only",
+        &[Attribution, SourceDisclosure, PatentGrant, NetworkClause],
+    ),
+    (
+        "AGPL-3.0-or-later",
+        &[Attribution, SourceDisclosure, PatentGrant, NetworkClause],
+    ),
+    ("Apache-2.0", &[Attribution, PatentGrant]),
+    ("BSD-2-Clause", &[Attribution]),
+    ("BSD-3-Clause", &[Attribution]),
+    ("BSL-1.0", &[Attribution]),
+    ("CC0-1.0", &[]),
+    ("CDDL-1.0", &[Attribution, SourceDisclosure, PatentGrant]),
+    ("EPL-1.0", &[Attribution, SourceDisclosure, PatentGrant]),
+    ("EPL-2.0", &[Attribution, SourceDisclosure, PatentGrant]),
+    ("GPL-2.0", &[Attribution, SourceDisclosure]),
+    ("GPL-2.0-only", &[Attribution, SourceDisclosure]),
+    ("GPL-2.0-or-later", &[Attribution, SourceDisclosure]),
+    ("GPL-3.0", &[Attribution, SourceDisclosure, PatentGrant]),
+    (
+        "GPL-3.0-only",
+        &[Attribution, SourceDisclosure, PatentGrant],
+    ),
+    (
+        "GPL-3.0-or-later",
+        &[Attribution, SourceDisclosure, PatentGrant],
+    ),
+    ("ISC", &[Attribution]),
+    ("LGPL-2.0", &[Attribution, SourceDisclosure]),
+    ("LGPL-2.0-only", &[Attribution, SourceDisclosure]),
+    ("LGPL-2.0-or-later", &[Attribution, SourceDisclosure]),
+    ("LGPL-2.1", &[Attribution, SourceDisclosure]),
+    ("LGPL-2.1-only", &[Attribution, SourceDisclosure]),
+    ("LGPL-2.1-or-later", &[Attribution, SourceDisclosure]),
+    ("LGPL-3.0", &[Attribution, SourceDisclosure, PatentGrant]),
+    (
+        "LGPL-3.0-only",
+        &[Attribution, SourceDisclosure, PatentGrant],
+    ),
+    (
+        "LGPL-3.0-or-later",
+        &[Attribution, SourceDisclosure, PatentGrant],
+    ),
+    ("MIT", &[Attribution]),
+    ("MIT-0", &[]),
+    ("MPL-2.0", &[Attribution, SourceDisclosure, PatentGrant]),
+    ("Unlicense", &[]),
+    ("Zlib", &[Attribution]),
+];
+
+impl Obligation {
+    /// Get the obligations of the license `identifier`, or `None` if the license is not known.
+    /// Identifiers are compared case-insensitively.
+    pub fn of_license(identifier: &str) -> Option<&'static [Self]> {
+        OBLIGATIONS
+            .iter()
+            .find(|(license, _)| license.eq_ignore_ascii_case(identifier))
+            .map(|(_, obligations)| *obligations)
+    }
+}
+
+/// Obligations of the chosen licenses of an expression, see [`SpdxExpression::obligations`].
+///
+/// [`SpdxExpression::obligations`]: crate::SpdxExpression::obligations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Obligations {
+    /// Union of the obligations of the known licenses.
+    pub obligations: BTreeSet<Obligation>,
+
+    /// Chosen licenses whose obligations are not known, e.g. `LicenseRef`s.
+    pub unknown: BTreeSet<String>,
+}
+
+pub fn obligations<F>(expression: &ExpressionVariant, mut is_chosen: F) -> Option<Obligations>
+where
+    F: FnMut(&SimpleExpression) -> bool,
+{
+    if !expression.is_satisfied_by(&mut is_chosen) {
+        return None;
+    }
+
+    let mut obligations = Obligations::default();
+    for license in expression.licenses() {
+        if !is_chosen(license) {
+            continue;
+        }
+        match Obligation::of_license(&license.identifier).filter(|_| !license.license_ref) {
+            Some(license_obligations) => {
+                obligations
+                    .obligations
+                    .extend(license_obligations.iter().copied());
+            }
+            None => {
+                obligations.unknown.insert(license.to_string());
+            }
+        }
+    }
+
+    Some(obligations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obligations_of(expression: &str, chosen: &[&str]) -> Option<Obligations> {
+        obligations(&ExpressionVariant::parse(expression).unwrap(), |license| {
+            chosen.contains(&license.to_string().as_str())
+        })
+    }
+
+    #[test]
+    fn union_of_chosen_licenses() {
+        assert_eq!(
+            obligations_of(
+                "MIT AND (Apache-2.0 OR AGPL-3.0-only) AND LicenseRef-a",
+                &["MIT", "Apache-2.0", "LicenseRef-a"]
+            ),
+            Some(Obligations {
+                obligations: BTreeSet::from([Attribution, PatentGrant]),
+                unknown: BTreeSet::from(["LicenseRef-a".to_string()]),
+            })
+        );
+        assert_eq!(
+            obligations_of("0BSD OR GPL-2.0-only WITH exc", &["GPL-2.0-only"]),
+            Some(Obligations {
+                obligations: BTreeSet::from([Attribution, SourceDisclosure]),
+                unknown: BTreeSet::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn choice_must_satisfy_expression() {
+        assert_eq!(obligations_of("MIT AND ISC", &["MIT"]), None);
+        assert_eq!(obligations_of("MIT OR ISC", &["Apache-2.0"]), None);
+    }
+}
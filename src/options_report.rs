@@ -0,0 +1,317 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Reports of the license choices allowed by an expression.
+
+use std::collections::BTreeMap;
+
+use serde::{ser::SerializeStruct, Serialize};
+
+use crate::{
+    copyleft::Copyleft,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    SpdxExpression,
+};
+
+/// Licenses of a choice set, each with its optional exception.
+pub type Terms<'a> = Vec<(&'a SimpleExpression, Option<&'a str>)>;
+
+/// Report of the ways to satisfy an expression, see [`SpdxExpression::options_report`].
+///
+/// Serializes to a structure meant for legal review, e.g. as JSON with `serde_json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionsReport {
+    /// The expression the report is about.
+    pub expression: SpdxExpression,
+
+    /// The minimal choice sets satisfying the expression, in the order they appear in it.
+    pub options: Vec<LicenseOption>,
+}
+
+/// One way to satisfy an expression: licenses that together comply with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseOption {
+    /// The `AND` of the licenses of the option, with their exceptions.
+    pub expression: SpdxExpression,
+
+    /// The licenses of the option, without their exceptions.
+    pub licenses: Vec<SimpleExpression>,
+
+    /// The exceptions the option relies on.
+    pub exceptions: Vec<String>,
+
+    /// The licenses of the option by copyleft category.
+    pub categories: BTreeMap<Copyleft, Vec<SimpleExpression>>,
+
+    /// The licenses of the option without a copyleft category.
+    pub uncategorized: Vec<SimpleExpression>,
+}
+
+impl LicenseOption {
+    /// Get the strongest copyleft category of the licenses of the option, or `None` if no
+    /// license has a category.
+    pub fn strongest_copyleft(&self) -> Option<Copyleft> {
+        self.categories.keys().next_back().copied()
+    }
+}
+
+impl OptionsReport {
+    pub(crate) fn new<F>(expression: &SpdxExpression, mut category: F) -> Self
+    where
+        F: FnMut(&SimpleExpression) -> Option<Copyleft>,
+    {
+        let options = choices(expression.inner())
+            .into_iter()
+            .map(|terms| LicenseOption::new(&terms, &mut category))
+            .collect();
+
+        Self {
+            expression: expression.clone(),
+            options,
+        }
+    }
+}
+
+impl LicenseOption {
+    fn new<F>(terms: &Terms<'_>, category: &mut F) -> Self
+    where
+        F: FnMut(&SimpleExpression) -> Option<Copyleft>,
+    {
+        let mut licenses: Vec<SimpleExpression> = Vec::new();
+        let mut exceptions: Vec<String> = Vec::new();
+        for (license, exception) in terms {
+            if !licenses.contains(license) {
+                licenses.push((*license).clone());
+            }
+            if let Some(exception) = exception {
+                if !exceptions.iter().any(|known| known == exception) {
+                    exceptions.push((*exception).to_string());
+                }
+            }
+        }
+
+        let mut categories: BTreeMap<Copyleft, Vec<SimpleExpression>> = BTreeMap::new();
+        let mut uncategorized = Vec::new();
+        for license in &licenses {
+            match category(license) {
+                Some(license_category) => categories
+                    .entry(license_category)
+                    .or_default()
+                    .push(license.clone()),
+                None => uncategorized.push(license.clone()),
+            }
+        }
+
+        Self {
+            expression: to_expression(terms),
+            licenses,
+            exceptions,
+            categories,
+            uncategorized,
+        }
+    }
+}
+
+impl Serialize for OptionsReport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut report = serializer.serialize_struct("OptionsReport", 2)?;
+        report.serialize_field("expression", &self.expression)?;
+        report.serialize_field("options", &self.options)?;
+        report.end()
+    }
+}
+
+impl Serialize for LicenseOption {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut option = serializer.serialize_struct("LicenseOption", 6)?;
+        option.serialize_field("expression", &self.expression)?;
+        option.serialize_field("licenses", &self.licenses)?;
+        option.serialize_field("exceptions", &self.exceptions)?;
+        option.serialize_field("categories", &self.categories)?;
+        option.serialize_field("uncategorized", &self.uncategorized)?;
+        option.serialize_field("strongest_copyleft", &self.strongest_copyleft())?;
+        option.end()
+    }
+}
+
+/// Get the minimal choice sets satisfying `expression`, in the order they appear in it.
+pub fn choices(expression: &ExpressionVariant) -> Vec<Terms<'_>> {
+    minimize(&choice_sets(expression))
+}
+
+/// Join the licenses of a choice set with `AND`.
+pub fn to_expression(terms: &Terms<'_>) -> SpdxExpression {
+    terms
+        .iter()
+        .map(|(license, exception)| {
+            exception.map_or_else(
+                || ExpressionVariant::Simple((*license).clone()),
+                |exception| {
+                    ExpressionVariant::With(Box::new(WithExpression::new(
+                        (*license).clone(),
+                        exception.to_string(),
+                    )))
+                },
+            )
+        })
+        .reduce(ExpressionVariant::and)
+        .map(SpdxExpression::from_inner)
+        .unwrap_or_default()
+}
+
+/// Get every choice set satisfying `expression`, including the ones that aren't minimal.
+fn choice_sets(expression: &ExpressionVariant) -> Vec<Terms<'_>> {
+    match expression {
+        ExpressionVariant::Simple(license) => vec![vec![(license, None)]],
+        ExpressionVariant::With(with) => {
+            vec![vec![(&with.license, Some(with.exception.as_str()))]]
+        }
+        ExpressionVariant::And(left, right) => {
+            let right = minimize(&choice_sets(right));
+            minimize(&choice_sets(left))
+                .iter()
+                .flat_map(|left| {
+                    right.iter().map(move |right| {
+                        let mut terms = left.clone();
+                        for term in right {
+                            if !terms.contains(term) {
+                                terms.push(*term);
+                            }
+                        }
+                        terms
+                    })
+                })
+                .collect()
+        }
+        ExpressionVariant::Or(left, right) => {
+            let mut sets = choice_sets(left);
+            sets.extend(choice_sets(right));
+            sets
+        }
+        ExpressionVariant::Parens(inner) => choice_sets(inner),
+    }
+}
+
+/// Remove duplicate choice sets and the ones containing another choice set, keeping the order.
+fn minimize<'a>(sets: &[Terms<'a>]) -> Vec<Terms<'a>> {
+    let is_subset =
+        |subset: &Terms<'_>, set: &Terms<'_>| subset.iter().all(|term| set.contains(term));
+
+    sets.iter()
+        .enumerate()
+        .filter(|(index, set)| {
+            !sets.iter().enumerate().any(|(other_index, other)| {
+                is_subset(other, set)
+                    && (other.len() < set.len() || other_index < *index && other.len() == set.len())
+            })
+        })
+        .map(|(_, set)| set.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(license: &SimpleExpression) -> Option<Copyleft> {
+        match license.identifier.as_str() {
+            "MIT" | "Apache-2.0" => Some(Copyleft::Permissive),
+            "MPL-2.0" => Some(Copyleft::Weak),
+            "GPL-2.0-only" => Some(Copyleft::Strong),
+            _ => None,
+        }
+    }
+
+    fn options(expression: &str) -> Vec<String> {
+        let expression = SpdxExpression::parse(expression).unwrap();
+        OptionsReport::new(&expression, category)
+            .options
+            .iter()
+            .map(|option| option.expression.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn enumerate_minimal_choice_sets() {
+        assert_eq!(options("MIT"), ["MIT"]);
+        assert_eq!(
+            options("(MIT OR Apache-2.0) AND (MPL-2.0 OR MIT)"),
+            ["MIT", "Apache-2.0 AND MPL-2.0"]
+        );
+        assert_eq!(
+            options("MIT OR MIT AND ISC OR (ISC AND MIT) OR ISC AND MIT"),
+            ["MIT"]
+        );
+        assert_eq!(
+            options("GPL-2.0-only WITH Classpath-exception-2.0 OR GPL-2.0-only AND ISC"),
+            [
+                "GPL-2.0-only WITH Classpath-exception-2.0",
+                "GPL-2.0-only AND ISC"
+            ]
+        );
+    }
+
+    #[test]
+    fn summarize_categories() {
+        let expression = SpdxExpression::parse(
+            "MIT AND (GPL-2.0-only WITH Classpath-exception-2.0 AND LicenseRef-a OR MPL-2.0)",
+        )
+        .unwrap();
+        let report = OptionsReport::new(&expression, category);
+
+        let [first, second] = &report.options[..] else {
+            panic!("unexpected options: {:?}", report.options);
+        };
+        assert_eq!(first.exceptions, ["Classpath-exception-2.0"]);
+        assert_eq!(first.licenses.len(), 3);
+        assert_eq!(first.strongest_copyleft(), Some(Copyleft::Strong));
+        assert_eq!(
+            first.uncategorized,
+            [SimpleExpression::parse("LicenseRef-a").unwrap()]
+        );
+        assert!(second.exceptions.is_empty());
+        assert_eq!(second.strongest_copyleft(), Some(Copyleft::Weak));
+        assert_eq!(
+            second.categories[&Copyleft::Permissive],
+            [SimpleExpression::parse("MIT").unwrap()]
+        );
+    }
+
+    #[test]
+    fn serialize_to_json() {
+        let expression = SpdxExpression::parse("MIT OR GPL-2.0-only WITH exc").unwrap();
+        let report = OptionsReport::new(&expression, category);
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "expression": "MIT OR GPL-2.0-only WITH exc",
+                "options": [
+                    {
+                        "expression": "MIT",
+                        "licenses": ["MIT"],
+                        "exceptions": [],
+                        "categories": { "Permissive": ["MIT"] },
+                        "uncategorized": [],
+                        "strongest_copyleft": "Permissive",
+                    },
+                    {
+                        "expression": "GPL-2.0-only WITH exc",
+                        "licenses": ["GPL-2.0-only"],
+                        "exceptions": ["exc"],
+                        "categories": { "Strong": ["GPL-2.0-only"] },
+                        "uncategorized": [],
+                        "strongest_copyleft": "Strong",
+                    },
+                ],
+            })
+        );
+    }
+}
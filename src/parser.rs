@@ -9,48 +9,78 @@
 //! which is licensed under the MIT License. The source project includes the following copyright
 //! statement: Copyright (c) 2014-2019 Geoffroy Couprie.
 
+use std::{ops::Range, sync::Arc};
+
 use nom::{
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_while1},
-    character::{
-        complete::{multispace0, multispace1},
-        streaming::char,
-    },
-    combinator::{complete, map, opt, recognize},
+    character::complete::{char, multispace0, multispace1},
+    combinator::{cut, map, opt},
+    error::{context, VerboseError},
     multi::many0,
     sequence::{delimited, pair, preceded, separated_pair},
-    AsChar, IResult,
+    AsChar,
 };
 
-use crate::expression_variant::{ExpressionVariant, SimpleExpression, WithExpression};
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser_options,
+};
 
-#[derive(Debug)]
-enum Operator {
+/// Operator joining two expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
     And,
     Or,
 }
 
+/// Context of the closing parenthesis of a parenthesized expression in parse errors.
+pub const CLOSING_PARENTHESIS: &str = "closing parenthesis";
+
+/// Context of the exception of a `WITH` expression in parse errors.
+pub const EXCEPTION: &str = "exception identifier";
+
+/// Result of the parsers. The errors keep the context of the failure for
+/// [`crate::error::ParseError`].
+pub type IResult<I, O> = nom::IResult<I, O, VerboseError<I>>;
+
+/// Terminal expression at the top level of an expression, i.e. outside of any parentheses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    /// The operator joining the term to the previous one. `None` for the first term.
+    pub operator: Option<Operator>,
+
+    /// Byte range of the term in the input, excluding surrounding whitespace.
+    pub range: Range<usize>,
+
+    pub expression: ExpressionVariant,
+}
+
 fn parentheses(i: &str) -> IResult<&str, ExpressionVariant> {
     delimited(
         multispace0,
         delimited(
             tag("("),
-            map(or_expression, |e| ExpressionVariant::Parens(Box::new(e))),
-            tag(")"),
+            map(or_expression, |e| ExpressionVariant::Parens(Arc::new(e))),
+            context(CLOSING_PARENTHESIS, tag(")")),
         ),
         multispace0,
     )(i)
 }
 
 fn terminal_expression(i: &str) -> IResult<&str, ExpressionVariant> {
-    alt((
-        delimited(multispace0, with_expression, multispace0),
-        map(
-            delimited(multispace0, simple_expression, multispace0),
-            ExpressionVariant::Simple,
-        ),
-        parentheses,
-    ))(i)
+    parser_options::step(i)?;
+    context(
+        "license or parenthesized expression",
+        alt((
+            delimited(multispace0, with_expression, multispace0),
+            map(
+                delimited(multispace0, simple_expression, multispace0),
+                ExpressionVariant::Simple,
+            ),
+            parentheses,
+        )),
+    )(i)
 }
 
 fn with_expression(i: &str) -> IResult<&str, ExpressionVariant> {
@@ -58,9 +88,10 @@ fn with_expression(i: &str) -> IResult<&str, ExpressionVariant> {
         separated_pair(
             simple_expression,
             delimited(multispace1, tag_no_case("WITH"), multispace1),
-            idstring,
+            // Nothing else can follow `WITH`, so failing here fails the whole parse.
+            cut(context(EXCEPTION, idstring)),
         ),
-        |(lic, exc)| ExpressionVariant::With(WithExpression::new(lic, exc.to_string())),
+        |(lic, exc)| ExpressionVariant::With(Box::new(WithExpression::new(lic, exc.to_string()))),
     )(i)
 }
 
@@ -71,8 +102,8 @@ fn fold_expressions(
     remainder.into_iter().fold(initial, |acc, pair| {
         let (oper, expr) = pair;
         match oper {
-            Operator::And => ExpressionVariant::And(Box::new(acc), Box::new(expr)),
-            Operator::Or => ExpressionVariant::Or(Box::new(acc), Box::new(expr)),
+            Operator::And => ExpressionVariant::And(Arc::new(acc), Arc::new(expr)),
+            Operator::Or => ExpressionVariant::Or(Arc::new(acc), Arc::new(expr)),
         }
     })
 }
@@ -80,7 +111,7 @@ fn fold_expressions(
 fn and_expression(i: &str) -> IResult<&str, ExpressionVariant> {
     let (i, initial) = terminal_expression(i)?;
     let (i, remainder) = many0(|i| {
-        let (i, and) = preceded(tag_no_case("AND"), terminal_expression)(i)?;
+        let (i, and) = preceded(tag_no_case("AND"), cut(terminal_expression))(i)?;
         Ok((i, (Operator::And, and)))
     })(i)?;
 
@@ -90,7 +121,7 @@ fn and_expression(i: &str) -> IResult<&str, ExpressionVariant> {
 fn or_expression(i: &str) -> IResult<&str, ExpressionVariant> {
     let (i, initial) = and_expression(i)?;
     let (i, remainder) = many0(|i| {
-        let (i, or) = preceded(tag_no_case("OR"), and_expression)(i)?;
+        let (i, or) = preceded(tag_no_case("OR"), cut(and_expression))(i)?;
         Ok((i, (Operator::Or, or)))
     })(i)?;
 
@@ -101,12 +132,94 @@ pub fn parse_expression(i: &str) -> IResult<&str, ExpressionVariant> {
     or_expression(i)
 }
 
+/// Byte range of the text between `start` and `end` in `whole`, excluding whitespace at either
+/// end. Both `start` and `end` need to be suffixes of `whole`.
+fn trimmed_range(whole: &str, start: &str, end: &str) -> Range<usize> {
+    let from = whole.len() - start.len();
+    let to = whole.len() - end.len();
+    let text = &whole[from..to];
+    let leading = text.len() - text.trim_start().len();
+    let trailing = text.len() - text.trim_end().len();
+
+    from + leading..to - trailing
+}
+
+/// Parse the expression as a flat sequence of top-level terms. The sequence accepts exactly the
+/// same inputs as [`parse_expression`], and folding it with [`fold_terms`] results in the same
+/// expression.
+pub fn top_level_terms(i: &str) -> IResult<&str, Vec<Term>> {
+    let (mut remaining, expression) = terminal_expression(i)?;
+    let mut terms = vec![Term {
+        operator: None,
+        range: trimmed_range(i, i, remaining),
+        expression,
+    }];
+
+    loop {
+        let (after_operator, operator) = match alt((
+            map(tag_no_case("AND"), |_| Operator::And),
+            map(tag_no_case("OR"), |_| Operator::Or),
+        ))(remaining)
+        {
+            Ok(result) => result,
+            Err(nom::Err::Error(_)) => break,
+            Err(err) => return Err(err),
+        };
+
+        match terminal_expression(after_operator) {
+            Ok((after_term, expression)) => {
+                terms.push(Term {
+                    operator: Some(operator),
+                    range: trimmed_range(i, after_operator, after_term),
+                    expression,
+                });
+                remaining = after_term;
+            }
+            Err(nom::Err::Error(_)) => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok((remaining, terms))
+}
+
+/// Combine a sequence of terms into an expression, binding `AND` stronger than `OR`. Returns
+/// `None` if there are no terms.
+pub fn fold_terms(
+    terms: impl IntoIterator<Item = (Option<Operator>, ExpressionVariant)>,
+) -> Option<ExpressionVariant> {
+    let mut alternatives = Vec::new();
+    let mut current: Option<ExpressionVariant> = None;
+
+    for (operator, expression) in terms {
+        current = Some(match (current.take(), operator) {
+            (Some(previous), Some(Operator::And)) => ExpressionVariant::and(previous, expression),
+            (Some(previous), _) => {
+                alternatives.push(previous);
+                expression
+            }
+            (None, _) => expression,
+        });
+    }
+    alternatives.extend(current);
+
+    let mut alternatives = alternatives.into_iter();
+    let initial = alternatives.next()?;
+    Some(alternatives.fold(initial, ExpressionVariant::or))
+}
+
 fn idstring(i: &str) -> IResult<&str, &str> {
     take_while1(|c: char| c.is_alphanum() || c == '-' || c == '.')(i)
 }
 
-fn license_idstring(i: &str) -> IResult<&str, &str> {
-    recognize(pair(idstring, opt(complete(char('+')))))(i)
+/// Check if `value` is a valid idstring, i.e. the identifier of a license, license or document
+/// reference or exception.
+pub fn is_idstring(value: &str) -> bool {
+    matches!(idstring(value), Ok(("", _)))
+}
+
+fn license_idstring(i: &str) -> IResult<&str, (&str, bool)> {
+    pair(idstring, map(opt(char('+')), |plus| plus.is_some()))(i)
 }
 
 fn document_ref(i: &str) -> IResult<&str, &str> {
@@ -118,15 +231,18 @@ fn license_ref(i: &str) -> IResult<&str, (Option<&str>, &str)> {
 }
 
 pub fn simple_expression(i: &str) -> IResult<&str, SimpleExpression> {
-    alt((
-        map(license_ref, |(document_ref, id)| {
-            let document_ref = document_ref.map(std::string::ToString::to_string);
-            SimpleExpression::new(id.to_string(), document_ref, true)
-        }),
-        map(license_idstring, |id| {
-            SimpleExpression::new(id.to_string(), None, false)
-        }),
-    ))(i)
+    context(
+        "license identifier",
+        alt((
+            map(license_ref, |(document_ref, id)| {
+                let document_ref = document_ref.map(std::string::ToString::to_string);
+                SimpleExpression::new(id.to_string(), document_ref, true)
+            }),
+            map(license_idstring, |(id, or_later)| {
+                SimpleExpression::new(id.to_string(), None, false).with_or_later(or_later)
+            }),
+        )),
+    )(i)
 }
 
 #[cfg(test)]
@@ -138,6 +254,7 @@ mod tests {
     //! Copyright (C) 2017-2019 HERE Europe B.V.
 
     use super::*;
+    use crate::error::SpdxExpressionError;
 
     use pretty_assertions::assert_eq;
 
@@ -168,7 +285,9 @@ mod tests {
         let parsed = ExpressionVariant::parse("license+").unwrap();
         assert_eq!(
             parsed,
-            ExpressionVariant::Simple(SimpleExpression::new("license+".to_string(), None, false))
+            ExpressionVariant::Simple(
+                SimpleExpression::new("license".to_string(), None, false).with_or_later(true)
+            )
         );
     }
 
@@ -199,10 +318,10 @@ mod tests {
         let parsed = ExpressionVariant::parse("license WITH exception").unwrap();
         assert_eq!(
             parsed,
-            ExpressionVariant::With(WithExpression::new(
+            ExpressionVariant::With(Box::new(WithExpression::new(
                 SimpleExpression::new("license".to_string(), None, false),
                 "exception".to_string()
-            ))
+            )))
         );
     }
 
@@ -216,28 +335,25 @@ mod tests {
         assert_eq!(
             parsed,
             ExpressionVariant::And(
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
-                    "license1+".to_string(),
-                    None,
-                    false
-                ))),
-                Box::new(ExpressionVariant::Parens(Box::new(ExpressionVariant::Or(
-                    Box::new(ExpressionVariant::Parens(Box::new(
-                        ExpressionVariant::With(WithExpression::new(
+                Arc::new(ExpressionVariant::Simple(
+                    SimpleExpression::new("license1".to_string(), None, false).with_or_later(true)
+                )),
+                Arc::new(ExpressionVariant::Parens(Arc::new(ExpressionVariant::Or(
+                    Arc::new(ExpressionVariant::Parens(Arc::new(
+                        ExpressionVariant::With(Box::new(WithExpression::new(
                             SimpleExpression::new("license2".to_string(), None, false),
                             "exception1".to_string()
-                        ))
+                        )))
                     ))),
-                    Box::new(ExpressionVariant::And(
-                        Box::new(ExpressionVariant::Simple(SimpleExpression::new(
-                            "license3+".to_string(),
-                            None,
-                            false
-                        ))),
-                        Box::new(ExpressionVariant::With(WithExpression::new(
+                    Arc::new(ExpressionVariant::And(
+                        Arc::new(ExpressionVariant::Simple(
+                            SimpleExpression::new("license3".to_string(), None, false)
+                                .with_or_later(true)
+                        )),
+                        Arc::new(ExpressionVariant::With(Box::new(WithExpression::new(
                             SimpleExpression::new("license4".to_string(), None, false),
                             "exception2".to_string()
-                        )))
+                        ))))
                     )),
                 ))))
             )
@@ -249,10 +365,10 @@ mod tests {
         let parsed = ExpressionVariant::parse("license+ WITH exception").unwrap();
         assert_eq!(
             parsed,
-            ExpressionVariant::With(WithExpression::new(
-                SimpleExpression::new("license+".to_string(), None, false),
+            ExpressionVariant::With(Box::new(WithExpression::new(
+                SimpleExpression::new("license".to_string(), None, false).with_or_later(true),
                 "exception".to_string()
-            ))
+            )))
         );
     }
 
@@ -262,15 +378,15 @@ mod tests {
         assert_eq!(
             parsed,
             ExpressionVariant::And(
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license1".to_string(),
                     None,
                     false
                 ))),
-                Box::new(ExpressionVariant::With(WithExpression::new(
+                Arc::new(ExpressionVariant::With(Box::new(WithExpression::new(
                     SimpleExpression::new("license2".to_string(), None, false),
                     "exception".to_string()
-                )))
+                ))))
             )
         );
     }
@@ -281,18 +397,18 @@ mod tests {
         assert_eq!(
             parsed,
             ExpressionVariant::Or(
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license1".to_string(),
                     None,
                     false
                 ))),
-                Box::new(ExpressionVariant::And(
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::And(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license2".to_string(),
                         None,
                         false
                     ))),
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license3".to_string(),
                         None,
                         false
@@ -308,19 +424,19 @@ mod tests {
         assert_eq!(
             parsed,
             ExpressionVariant::And(
-                Box::new(ExpressionVariant::And(
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::And(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license1".to_string(),
                         None,
                         false
                     ))),
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license2".to_string(),
                         None,
                         false
                     )))
                 )),
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license3".to_string(),
                     None,
                     false
@@ -335,19 +451,19 @@ mod tests {
         assert_eq!(
             parsed,
             ExpressionVariant::Or(
-                Box::new(ExpressionVariant::Or(
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Or(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license1".to_string(),
                         None,
                         false
                     ))),
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license2".to_string(),
                         None,
                         false
                     )))
                 )),
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license3".to_string(),
                     None,
                     false
@@ -362,19 +478,19 @@ mod tests {
         assert_eq!(
             parsed,
             ExpressionVariant::And(
-                Box::new(ExpressionVariant::Parens(Box::new(ExpressionVariant::Or(
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Parens(Arc::new(ExpressionVariant::Or(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license1".to_string(),
                         None,
                         false
                     ))),
-                    Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                    Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                         "license2".to_string(),
                         None,
                         false
                     )))
                 )))),
-                Box::new(ExpressionVariant::Simple(SimpleExpression::new(
+                Arc::new(ExpressionVariant::Simple(SimpleExpression::new(
                     "license3".to_string(),
                     None,
                     false
@@ -383,6 +499,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn folding_top_level_terms_matches_parse_expression() {
+        for input in [
+            "MIT",
+            "license1 OR license2 AND license3",
+            "(A)AND(B) or C and D WITH exc OR (E OR F)",
+            "a AND b AND c OR d OR e AND f",
+        ] {
+            let (remaining, terms) = top_level_terms(input).unwrap();
+            assert!(remaining.is_empty());
+
+            let folded = fold_terms(
+                terms
+                    .into_iter()
+                    .map(|term| (term.operator, term.expression)),
+            );
+            assert_eq!(folded, Some(ExpressionVariant::parse(input).unwrap()));
+        }
+    }
+
+    #[test]
+    fn top_level_term_ranges_exclude_whitespace() {
+        let input = " MIT AND (ISC OR X)  OR  Y WITH Z ";
+
+        let (_, terms) = top_level_terms(input).unwrap();
+
+        assert_eq!(
+            terms
+                .iter()
+                .map(|term| &input[term.range.clone()])
+                .collect::<Vec<_>>(),
+            ["MIT", "(ISC OR X)", "Y WITH Z"]
+        );
+        assert_eq!(
+            terms.iter().map(|term| term.operator).collect::<Vec<_>>(),
+            [None, Some(Operator::And), Some(Operator::Or)]
+        );
+    }
+
     #[test]
     fn fail_if_plus_is_used_in_an_exception_expression() {
         let parsed = ExpressionVariant::parse("license WITH exception+");
@@ -406,4 +561,28 @@ mod tests {
         let parsed = ExpressionVariant::parse("((");
         assert!(parsed.is_err());
     }
+
+    #[test]
+    fn errors_locate_the_failure() {
+        for (input, offset, expected) in [
+            ("", 0, Some("license or parenthesized expression")),
+            ("MIT AND", 7, Some("license or parenthesized expression")),
+            ("MIT OR (ISC AND 0BSD", 20, Some("closing parenthesis")),
+            ("MIT WITH +", 9, Some("exception identifier")),
+            ("MIT ) OR ISC", 4, None),
+        ] {
+            let Err(SpdxExpressionError::Parse(error)) = ExpressionVariant::parse(input) else {
+                panic!("{} should fail to parse", input);
+            };
+            assert_eq!(error.offset, Some(offset), "{input}");
+            assert_eq!(error.context.first().copied(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn document_ref_without_license_ref_does_not_panic() {
+        assert!(ExpressionVariant::parse("MIT AND DocumentRef-a").is_ok());
+        assert!(ExpressionVariant::parse("DocumentRef-a:").is_err());
+        assert!(SimpleExpression::parse("DocumentRef-a:").is_err());
+    }
 }
@@ -0,0 +1,268 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Options of the parser.
+
+use std::{
+    cell::{Cell, RefCell},
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+use nom::error::{ErrorKind, ParseError, VerboseError};
+
+use crate::alias::AliasTable;
+
+/// Options for parsing expressions with [`SpdxExpression::parse_with`].
+///
+/// The defaults parse like [`SpdxExpression::parse`], without any limits.
+///
+/// The options used by [`FromStr`](std::str::FromStr) and deserialization of
+/// [`SpdxExpression`] can be set for the whole process with [`ParserOptions::set_default`] and
+/// for the current thread with [`ParserOptions::set_thread_default`], so code that doesn't
+/// take options follows the policy of the application.
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use spdx_expression::{AliasTable, ParserOptions, SpdxExpression};
+/// #
+/// ParserOptions::set_default(Some(ParserOptions {
+///     aliases: Some(Arc::new(AliasTable::fedora())),
+///     ..ParserOptions::default()
+/// }));
+///
+/// let expression: SpdxExpression = "ASL 2.0 or MIT".parse()?;
+/// assert_eq!(expression.to_string(), "Apache-2.0 OR MIT");
+///
+/// let expression: SpdxExpression = "GPLv2 OR CC0".parse()?;
+/// assert_eq!(expression.to_string(), "GPL-2.0-only OR CC0-1.0");
+///
+/// let expression: SpdxExpression = serde_json::from_str("\"Public Domain AND ASL 2.0\"")?;
+/// assert_eq!(
+///     expression.to_string(),
+///     "LicenseRef-Fedora-Public-Domain AND Apache-2.0"
+/// );
+///
+/// ParserOptions::set_thread_default(Some(ParserOptions {
+///     uppercase_operators: true,
+///     ..ParserOptions::default()
+/// }));
+/// assert!("ASL 2.0 OR MIT".parse::<SpdxExpression>().is_err());
+/// assert!("MIT or ISC".parse::<SpdxExpression>().is_err());
+/// assert!("MIT OR ISC".parse::<SpdxExpression>().is_ok());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`SpdxExpression`]: crate::SpdxExpression
+/// [`SpdxExpression::parse`]: crate::SpdxExpression::parse
+/// [`SpdxExpression::parse_with`]: crate::SpdxExpression::parse_with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Maximum number of steps the parser may take, where a step is an attempt to parse an
+    /// operand, i.e. a license or a parenthesized expression. Parsing an expression takes a few
+    /// steps per operand, so this bounds the work spent on adversarial inputs regardless of the
+    /// speed of the machine.
+    pub max_steps: Option<usize>,
+
+    /// Maximum time parsing an expression may take.
+    pub timeout: Option<Duration>,
+
+    /// Reject `AND`, `OR` and `WITH` operators that are not in uppercase, as recommended by the
+    /// SPDX specification. By default operators are accepted in any case.
+    pub uppercase_operators: bool,
+
+    /// Aliases to translate the licenses of expressions with, like
+    /// [`SpdxExpression::parse_lenient`](crate::SpdxExpression::parse_lenient). Aliases are
+    /// translated both in valid SPDX expressions, e.g. `GPLv2` with
+    /// [`AliasTable::fedora`](crate::AliasTable::fedora), and in expressions that only parse
+    /// after translating multi-word aliases.
+    pub aliases: Option<Arc<AliasTable>>,
+}
+
+/// Options set with [`ParserOptions::set_default`].
+static DEFAULT: RwLock<Option<Arc<ParserOptions>>> = RwLock::new(None);
+
+thread_local! {
+    /// Options set with [`ParserOptions::set_thread_default`].
+    static THREAD_DEFAULT: RefCell<Option<Arc<ParserOptions>>> = const { RefCell::new(None) };
+}
+
+impl ParserOptions {
+    /// Set the default options of the process, used by [`FromStr`](std::str::FromStr) and
+    /// deserialization of [`SpdxExpression`](crate::SpdxExpression) on threads without options
+    /// of their own. `None` restores the built-in defaults.
+    pub fn set_default(options: Option<Self>) {
+        *DEFAULT.write().unwrap_or_else(PoisonError::into_inner) = options.map(Arc::new);
+    }
+
+    /// Set the default options of the current thread, overriding the default options of the
+    /// process. `None` falls back to the default options of the process.
+    pub fn set_thread_default(options: Option<Self>) {
+        THREAD_DEFAULT.with(|default| *default.borrow_mut() = options.map(Arc::new));
+    }
+
+    /// Get the default options in effect on the current thread: the options of the thread, the
+    /// options of the process or the built-in defaults, in that order.
+    pub fn current() -> Arc<Self> {
+        THREAD_DEFAULT
+            .with(|default| default.borrow().clone())
+            .or_else(|| {
+                DEFAULT
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone()
+            })
+            .unwrap_or_default()
+    }
+
+    const fn has_budget(&self) -> bool {
+        self.max_steps.is_some() || self.timeout.is_some()
+    }
+}
+
+/// The deadline is only checked every this many steps, as reading the clock is relatively
+/// expensive.
+const STEPS_PER_CLOCK_CHECK: usize = 64;
+
+/// Remaining work budget of the parse running on the current thread.
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    steps_left: Option<usize>,
+    deadline: Option<Instant>,
+    steps_taken: usize,
+    exceeded: bool,
+}
+
+thread_local! {
+    /// Budget of the parse running on the current thread, `None` if it's unlimited. The parser
+    /// functions are plain functions, so the budget is kept here instead of being passed to
+    /// them.
+    static BUDGET: Cell<Option<Budget>> = const { Cell::new(None) };
+}
+
+/// Restores the budget of an enclosing parse when dropped.
+struct BudgetGuard {
+    previous: Option<Budget>,
+}
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        BUDGET.with(|budget| budget.set(self.previous));
+    }
+}
+
+/// Run `parse` with the budget of `options`. Returns the result of `parse` and whether the
+/// budget was exceeded.
+pub fn with_budget<T, F>(options: &ParserOptions, parse: F) -> (T, bool)
+where
+    F: FnOnce() -> T,
+{
+    let budget = options.has_budget().then(|| Budget {
+        steps_left: options.max_steps,
+        deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+        steps_taken: 0,
+        exceeded: false,
+    });
+    let _guard = BudgetGuard {
+        previous: BUDGET.with(|current| current.replace(budget)),
+    };
+
+    let result = parse();
+    let exceeded = BUDGET.with(|current| current.get().is_some_and(|budget| budget.exceeded));
+    (result, exceeded)
+}
+
+/// Take a step of the budget of the current parse. Fails the parse at `i` if the budget is
+/// exceeded.
+pub fn step(i: &str) -> Result<(), nom::Err<VerboseError<&str>>> {
+    let exceeded = BUDGET.with(|current| {
+        let Some(mut budget) = current.get() else {
+            return false;
+        };
+
+        match &mut budget.steps_left {
+            Some(0) => budget.exceeded = true,
+            Some(steps_left) => *steps_left -= 1,
+            None => {}
+        }
+        if budget.steps_taken % STEPS_PER_CLOCK_CHECK == 0
+            && budget
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            budget.exceeded = true;
+        }
+        budget.steps_taken += 1;
+
+        current.set(Some(budget));
+        budget.exceeded
+    });
+
+    if exceeded {
+        Err(nom::Err::Failure(VerboseError::from_error_kind(
+            i,
+            ErrorKind::TooLarge,
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_budget_is_never_exceeded() {
+        let (steps, exceeded) = with_budget(&ParserOptions::default(), || {
+            (0..1000).filter(|_| step("").is_ok()).count()
+        });
+        assert_eq!((steps, exceeded), (1000, false));
+    }
+
+    #[test]
+    fn step_budget_is_exceeded() {
+        let options = ParserOptions {
+            max_steps: Some(10),
+            ..ParserOptions::default()
+        };
+        let (steps, exceeded) =
+            with_budget(&options, || (0..1000).filter(|_| step("").is_ok()).count());
+        assert_eq!((steps, exceeded), (10, true));
+    }
+
+    #[test]
+    fn nested_budgets_are_restored() {
+        let outer = ParserOptions {
+            max_steps: Some(1),
+            ..ParserOptions::default()
+        };
+        let ((), exceeded) = with_budget(&outer, || {
+            let (_, inner_exceeded) = with_budget(&ParserOptions::default(), || step(""));
+            assert!(!inner_exceeded);
+            assert!(step("").is_ok());
+        });
+        assert!(!exceeded);
+    }
+
+    #[test]
+    fn thread_default_overrides_process_default() {
+        let strict = ParserOptions {
+            uppercase_operators: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(*ParserOptions::current(), ParserOptions::default());
+
+        ParserOptions::set_thread_default(Some(strict.clone()));
+        assert_eq!(*ParserOptions::current(), strict);
+        std::thread::spawn(|| assert_eq!(*ParserOptions::current(), ParserOptions::default()))
+            .join()
+            .unwrap();
+
+        ParserOptions::set_thread_default(None);
+        assert_eq!(*ParserOptions::current(), ParserOptions::default());
+    }
+}
@@ -0,0 +1,299 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Patches changing expressions.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diff::{key, operands, strip_parens, Located},
+    error::SpdxExpressionError,
+    expression::SpdxExpression,
+    expression_variant::ExpressionVariant,
+};
+
+/// Replacement of a part of an expression, see [`Patch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// Path of the replaced part as indices of the flattened operands of `AND` and `OR` from
+    /// the root, ignoring parentheses and the grouping of operands of the same operator, so in
+    /// `MIT OR (ISC AND 0BSD)` the path of `0BSD` is `[1, 1]`.
+    pub path: Vec<usize>,
+
+    /// The part of the expression before the replacement.
+    pub from: SpdxExpression,
+
+    /// The part of the expression after the replacement.
+    pub to: SpdxExpression,
+}
+
+/// Changes from one expression to another that can be stored and applied to expressions later,
+/// e.g. to record curations in an audit log and replay them on other datasets.
+///
+/// A patch consists of replacements of the parts of the expression that changed. Applying the
+/// patch checks that the replaced parts are still the same as when the patch was created, so
+/// patches are never applied to expressions they weren't made for. The parts are located and
+/// compared regardless of parentheses and the grouping of operands of the same operator.
+///
+/// Patches are serialized as sequences of `[path, from, to]` triples.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Patch, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let suggested = SpdxExpression::parse("MIT AND (GPL-2.0 OR LicenseRef-scanner-bsd)")?;
+/// let curated = SpdxExpression::parse("MIT AND (GPL-2.0-only OR BSD-3-Clause)")?;
+///
+/// let patch = Patch::new(&suggested, &curated);
+/// assert_eq!(patch.apply(&suggested)?, curated);
+///
+/// let other = SpdxExpression::parse("ISC AND (GPL-2.0 OR LicenseRef-scanner-bsd)")?;
+/// assert!(patch.apply(&other).is_ok());
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Patch {
+    replacements: Vec<Replacement>,
+}
+
+impl Patch {
+    /// Create a patch changing `from` to `to`. Operands of the same operator are replaced
+    /// one by one if both expressions have the same number of them, otherwise the whole
+    /// compound expression is replaced.
+    pub fn new(from: &SpdxExpression, to: &SpdxExpression) -> Self {
+        let mut replacements = Vec::new();
+        replace_nodes(
+            Vec::new(),
+            from.inner().unparenthesized(),
+            to.inner().unparenthesized(),
+            &mut replacements,
+        );
+        Self { replacements }
+    }
+
+    /// Create a patch from replacements.
+    pub const fn from_replacements(replacements: Vec<Replacement>) -> Self {
+        Self { replacements }
+    }
+
+    /// Get the replacements of the patch.
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replacements
+    }
+
+    /// Check if the patch doesn't change anything.
+    pub const fn is_empty(&self) -> bool {
+        self.replacements.is_empty()
+    }
+
+    /// Apply the patch to `expression`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Patch` if the part of `expression` at the path of a
+    /// replacement doesn't exist or doesn't match the replaced part.
+    pub fn apply(
+        &self,
+        expression: &SpdxExpression,
+    ) -> Result<SpdxExpression, SpdxExpressionError> {
+        let mut patched = expression.inner().clone();
+
+        for Replacement { path, from, to } in &self.replacements {
+            let missing =
+                || SpdxExpressionError::Patch(format!("no part of `{expression}` at {path:?}"));
+
+            let (tree_path, current) = locate(&patched, path).ok_or_else(missing)?;
+            if key(current) != key(from.inner()) {
+                return Err(SpdxExpressionError::Patch(format!(
+                    "expected `{from}` at {path:?} of `{expression}`, found `{current}`"
+                )));
+            }
+
+            patched = patched
+                .replace_at(&tree_path, to.inner().unparenthesized().clone())
+                .ok_or_else(missing)?;
+        }
+
+        Ok(SpdxExpression::from_inner(patched))
+    }
+}
+
+impl Serialize for Patch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.replacements
+            .iter()
+            .map(|Replacement { path, from, to }| (path, from, to))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Patch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Vec::<(Vec<usize>, SpdxExpression, SpdxExpression)>::deserialize(deserializer).map(
+            |replacements| Self {
+                replacements: replacements
+                    .into_iter()
+                    .map(|(path, from, to)| Replacement { path, from, to })
+                    .collect(),
+            },
+        )
+    }
+}
+
+fn replace_nodes(
+    path: Vec<usize>,
+    from: &ExpressionVariant,
+    to: &ExpressionVariant,
+    replacements: &mut Vec<Replacement>,
+) {
+    if key(from) == key(to) {
+        return;
+    }
+
+    match (from.flatten(), to.flatten()) {
+        (Some((from_operator, from_operands)), Some((to_operator, to_operands)))
+            if from_operator == to_operator && from_operands.len() == to_operands.len() =>
+        {
+            for (index, (from, to)) in from_operands.into_iter().zip(to_operands).enumerate() {
+                let mut path = path.clone();
+                path.push(index);
+                replace_nodes(path, from, to, replacements);
+            }
+        }
+        _ => replacements.push(Replacement {
+            path,
+            from: SpdxExpression::from_inner(from.clone()),
+            to: SpdxExpression::from_inner(to.clone()),
+        }),
+    }
+}
+
+/// Find the part of `expression` at the operand path `path` of a [`Replacement`], with its path
+/// of child indices in the tree.
+fn locate<'a>(expression: &'a ExpressionVariant, path: &[usize]) -> Option<Located<'a>> {
+    let mut located = strip_parens(expression, Vec::new());
+    for &index in path {
+        let (_, operands) = operands(&located)?;
+        located = operands.into_iter().nth(index)?;
+    }
+    Some(located)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(expression: &str) -> SpdxExpression {
+        SpdxExpression::parse(expression).unwrap()
+    }
+
+    #[test]
+    fn patch_changes_expression() {
+        for (from, to) in [
+            ("MIT", "ISC"),
+            ("MIT AND ISC", "MIT AND (ISC OR 0BSD)"),
+            ("MIT OR ISC AND 0BSD", "MIT OR ISC AND Zlib"),
+            ("(MIT OR ISC) AND 0BSD", "(MIT AND ISC) AND 0BSD"),
+            ("MIT AND ISC AND 0BSD", "MIT AND ISC"),
+            ("MIT WITH exc OR ISC", "MIT OR ISC WITH exc"),
+        ] {
+            let (from, to) = (parse(from), parse(to));
+            let patched = Patch::new(&from, &to).apply(&from).unwrap();
+
+            assert!(
+                patched.diff(&to).is_empty(),
+                "{} -> {}: {}",
+                from,
+                to,
+                patched
+            );
+            assert_eq!(parse(&patched.to_string()), patched);
+        }
+    }
+
+    #[test]
+    fn replace_only_changed_operands() {
+        let patch = Patch::new(
+            &parse("MIT AND (ISC OR 0BSD) AND Zlib"),
+            &parse("Apache-2.0 AND (ISC OR 0BSD) AND Zlib"),
+        );
+
+        assert_eq!(
+            patch.replacements(),
+            [Replacement {
+                path: vec![0],
+                from: parse("MIT"),
+                to: parse("Apache-2.0"),
+            }]
+        );
+    }
+
+    #[test]
+    fn apply_to_regrouped_expressions() {
+        for (from, to, regrouped, expected) in [
+            (
+                "A AND (B OR C) AND E",
+                "A AND (B OR D) AND E",
+                "(A AND (B OR C)) AND E",
+                "(A AND (B OR D)) AND E",
+            ),
+            (
+                "A AND B AND C",
+                "A AND B AND D",
+                "A AND (B AND C)",
+                "A AND (B AND D)",
+            ),
+            (
+                "A AND B AND C",
+                "X AND B AND C",
+                "((A) AND B) AND C",
+                "((X) AND B) AND C",
+            ),
+        ] {
+            let patch = Patch::new(&parse(from), &parse(to));
+
+            assert_eq!(
+                patch.apply(&parse(regrouped)).unwrap().to_string(),
+                expected,
+                "{from} -> {to}"
+            );
+        }
+    }
+
+    #[test]
+    fn reject_mismatching_expressions() {
+        let patch = Patch::new(&parse("MIT AND ISC"), &parse("MIT AND 0BSD"));
+
+        assert!(matches!(
+            patch.apply(&parse("MIT AND Zlib")),
+            Err(SpdxExpressionError::Patch(_))
+        ));
+        assert!(matches!(
+            patch.apply(&parse("MIT")),
+            Err(SpdxExpressionError::Patch(_))
+        ));
+        assert!(Patch::new(&parse("MIT"), &parse("MIT")).is_empty());
+    }
+
+    #[test]
+    fn serialize_patch() {
+        let patch = Patch::new(
+            &parse("MIT OR (ISC AND 0BSD)"),
+            &parse("MIT OR (ISC AND Zlib)"),
+        );
+
+        let serialized = serde_json::to_value(&patch).unwrap();
+        assert_eq!(serialized, serde_json::json!([[[1, 1], "0BSD", "Zlib"]]));
+        assert_eq!(serde_json::from_value::<Patch>(serialized).unwrap(), patch);
+    }
+}
@@ -0,0 +1,378 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Policies of allowed licenses, with a loader for the license configuration of cargo-deny.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::SpdxExpressionError, expression::SpdxExpression, expression_variant::ExpressionVariant,
+};
+
+/// Licenses allowed for the packages of a project.
+///
+/// A package is accepted if its license expression can be satisfied with allowed licenses.
+/// Licenses are allowed for every package or only for specific packages, and denied licenses are
+/// never allowed. The license expressions of packages can be clarified, e.g. when a package's
+/// license field is missing or wrong.
+///
+/// Licenses are given as a license with an optional exception, like `MIT` or
+/// `Apache-2.0 WITH LLVM-exception`, and are compared case-insensitively. A license with an
+/// exception is only allowed if it's listed with the exception, and `+` is a part of the
+/// license, so allowing `GPL-2.0` doesn't allow `GPL-2.0+`.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Policy, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut policy = Policy::new();
+/// policy.allow("MIT")?;
+/// policy.allow("Apache-2.0 WITH LLVM-exception")?;
+/// policy.allow_for("ring", "OpenSSL")?;
+///
+/// let expression = SpdxExpression::parse("Apache-2.0 WITH LLVM-exception OR GPL-3.0-only")?;
+/// assert!(policy.check("wasmtime", &expression));
+///
+/// let expression = SpdxExpression::parse("MIT AND OpenSSL")?;
+/// assert!(policy.check("ring", &expression));
+/// assert!(!policy.check("other", &expression));
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    allowed: HashSet<String>,
+    denied: HashSet<String>,
+    exceptions: HashMap<String, HashSet<String>>,
+    clarifications: HashMap<String, SpdxExpression>,
+}
+
+impl Policy {
+    /// Create a policy that allows no licenses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow a license for every package.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Policy` if `license` is not a license with an optional
+    /// exception.
+    pub fn allow(&mut self, license: &str) -> Result<(), SpdxExpressionError> {
+        self.allowed.insert(key(license)?);
+        Ok(())
+    }
+
+    /// Deny a license, also for the packages it's allowed for.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Policy` if `license` is not a license with an optional
+    /// exception.
+    pub fn deny(&mut self, license: &str) -> Result<(), SpdxExpressionError> {
+        self.denied.insert(key(license)?);
+        Ok(())
+    }
+
+    /// Allow a license only for the package named `package`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Policy` if `license` is not a license with an optional
+    /// exception.
+    pub fn allow_for(&mut self, package: &str, license: &str) -> Result<(), SpdxExpressionError> {
+        self.exceptions
+            .entry(package.to_string())
+            .or_default()
+            .insert(key(license)?);
+        Ok(())
+    }
+
+    /// Use `expression` as the license expression of the package named `package`, instead of
+    /// the expression declared by the package.
+    pub fn clarify(&mut self, package: &str, expression: SpdxExpression) {
+        self.clarifications.insert(package.to_string(), expression);
+    }
+
+    /// Get the clarified license expression of the package named `package`.
+    pub fn clarification(&self, package: &str) -> Option<&SpdxExpression> {
+        self.clarifications.get(package)
+    }
+
+    /// Check if `license`, a license with an optional exception, is allowed for the package
+    /// named `package`. Returns `false` if `license` is not a license with an optional
+    /// exception.
+    pub fn is_allowed(&self, package: &str, license: &str) -> bool {
+        key(license).is_ok_and(|license| self.is_key_allowed(package, &license))
+    }
+
+    /// Check if the package named `package` with the license `expression` is accepted, i.e. if
+    /// its clarified expression, or `expression` if it isn't clarified, is satisfied by the
+    /// licenses allowed for the package.
+    pub fn check(&self, package: &str, expression: &SpdxExpression) -> bool {
+        let expression = self.clarification(package).unwrap_or(expression);
+        self.is_satisfied(package, expression.inner())
+    }
+
+    fn is_satisfied(&self, package: &str, expression: &ExpressionVariant) -> bool {
+        match expression {
+            ExpressionVariant::Simple(_) | ExpressionVariant::With(_) => {
+                self.is_key_allowed(package, &expression.to_string().to_ascii_lowercase())
+            }
+            ExpressionVariant::And(left, right) => {
+                self.is_satisfied(package, left) && self.is_satisfied(package, right)
+            }
+            ExpressionVariant::Or(left, right) => {
+                self.is_satisfied(package, left) || self.is_satisfied(package, right)
+            }
+            ExpressionVariant::Parens(inner) => self.is_satisfied(package, inner),
+        }
+    }
+
+    fn is_key_allowed(&self, package: &str, license: &str) -> bool {
+        !self.denied.contains(license)
+            && (self.allowed.contains(license)
+                || self
+                    .exceptions
+                    .get(package)
+                    .is_some_and(|allowed| allowed.contains(license)))
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl Policy {
+    /// Load the `[licenses]` table of a cargo-deny configuration, e.g. read from `deny.toml` with
+    /// the `toml` crate and converted to JSON.
+    ///
+    /// `allow`, `deny`, `exceptions` and `clarify` are loaded, and other settings, like the
+    /// confidence threshold of license detection, are ignored. Version requirements in the
+    /// crate specs of exceptions and clarifications, like `ring@0.17`, are ignored, so they apply
+    /// to every version of the crate. Both the `crate` key of current cargo-deny versions and the
+    /// older `name` key are accepted.
+    ///
+    /// Requires the `serde_json` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use spdx_expression::{Policy, SpdxExpression};
+    /// # use spdx_expression::SpdxExpressionError;
+    /// #
+    /// let licenses = serde_json::json!({
+    ///     "allow": ["MIT", "Apache-2.0"],
+    ///     "exceptions": [{ "allow": ["Unicode-3.0"], "crate": "unicode-ident" }],
+    ///     "clarify": [{
+    ///         "crate": "ring",
+    ///         "expression": "MIT AND ISC AND OpenSSL",
+    ///         "license-files": [{ "path": "LICENSE", "hash": 3171872035_u32 }]
+    ///     }]
+    /// });
+    ///
+    /// let policy = Policy::from_cargo_deny(&licenses)?;
+    ///
+    /// let expression = SpdxExpression::parse("(MIT OR Apache-2.0) AND Unicode-3.0")?;
+    /// assert!(policy.check("unicode-ident", &expression));
+    /// assert!(!policy.check("serde", &expression));
+    /// assert_eq!(
+    ///     policy.clarification("ring").unwrap().to_string(),
+    ///     "MIT AND ISC AND OpenSSL"
+    /// );
+    /// # Ok::<(), SpdxExpressionError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Policy` if the configuration is malformed or a license is
+    /// not a license with an optional exception, or the parse error of an invalid clarified
+    /// expression.
+    pub fn from_cargo_deny(licenses: &serde_json::Value) -> Result<Self, SpdxExpressionError> {
+        let mut policy = Self::new();
+
+        for license in strings(licenses, "allow")? {
+            policy.allow(license)?;
+        }
+        for license in strings(licenses, "deny")? {
+            policy.deny(license)?;
+        }
+        for exception in array(licenses, "exceptions")? {
+            let package = crate_name(exception)?;
+            for license in strings(exception, "allow")? {
+                policy.allow_for(package, license)?;
+            }
+        }
+        for clarification in array(licenses, "clarify")? {
+            let package = crate_name(clarification)?;
+            let expression = clarification["expression"].as_str().ok_or_else(|| {
+                SpdxExpressionError::Policy(format!(
+                    "missing expression in the clarification of `{package}`"
+                ))
+            })?;
+            policy.clarify(package, SpdxExpression::parse(expression)?);
+        }
+
+        Ok(policy)
+    }
+}
+
+/// Get the lowercase form of `license` for looking up licenses.
+fn key(license: &str) -> Result<String, SpdxExpressionError> {
+    match ExpressionVariant::parse(license).map(|expression| expression.unparenthesized().clone()) {
+        Ok(expression @ (ExpressionVariant::Simple(_) | ExpressionVariant::With(_))) => {
+            Ok(expression.to_string().to_ascii_lowercase())
+        }
+        _ => Err(SpdxExpressionError::Policy(format!(
+            "`{license}` is not a license with an optional exception"
+        ))),
+    }
+}
+
+#[cfg(feature = "serde_json")]
+fn array<'a>(
+    value: &'a serde_json::Value,
+    field: &str,
+) -> Result<&'a [serde_json::Value], SpdxExpressionError> {
+    match &value[field] {
+        serde_json::Value::Null => Ok(&[]),
+        serde_json::Value::Array(values) => Ok(values),
+        _ => Err(SpdxExpressionError::Policy(format!(
+            "`{field}` is not an array"
+        ))),
+    }
+}
+
+#[cfg(feature = "serde_json")]
+fn strings<'a>(
+    value: &'a serde_json::Value,
+    field: &str,
+) -> Result<Vec<&'a str>, SpdxExpressionError> {
+    array(value, field)?
+        .iter()
+        .map(|value| {
+            value.as_str().ok_or_else(|| {
+                SpdxExpressionError::Policy(format!("`{field}` has a value that is not a string"))
+            })
+        })
+        .collect()
+}
+
+/// Get the name of the crate of an exception or clarification, without the version requirement.
+#[cfg(feature = "serde_json")]
+fn crate_name(value: &serde_json::Value) -> Result<&str, SpdxExpressionError> {
+    value["crate"]
+        .as_str()
+        .or_else(|| value["name"].as_str())
+        .map(|spec| spec.split(['@', ':']).next().unwrap_or(spec))
+        .ok_or_else(|| SpdxExpressionError::Policy(format!("missing crate in `{value}`")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(policy: &Policy, package: &str, expression: &str) -> bool {
+        policy.check(package, &SpdxExpression::parse(expression).unwrap())
+    }
+
+    #[test]
+    fn accept_allowed_licenses() {
+        let mut policy = Policy::new();
+        policy.allow("MIT").unwrap();
+        policy.allow("apache-2.0 WITH llvm-exception").unwrap();
+        policy.allow("GPL-2.0").unwrap();
+
+        assert!(check(&policy, "a", "mit"));
+        assert!(check(&policy, "a", "(MIT AND GPL-2.0) OR ISC"));
+        assert!(check(&policy, "a", "Apache-2.0 WITH LLVM-exception"));
+        assert!(!check(&policy, "a", "Apache-2.0"));
+        assert!(!check(&policy, "a", "GPL-2.0+"));
+        assert!(!check(&policy, "a", "MIT AND ISC"));
+        assert!(policy.is_allowed("a", "Mit"));
+        assert!(!policy.is_allowed("a", "MIT OR ISC"));
+    }
+
+    #[test]
+    fn deny_licenses_allowed_for_packages() {
+        let mut policy = Policy::new();
+        policy.allow("MIT").unwrap();
+        policy.allow_for("b", "ISC").unwrap();
+        policy.allow_for("b", "GPL-3.0-only").unwrap();
+        policy.deny("GPL-3.0-only").unwrap();
+
+        assert!(check(&policy, "b", "MIT AND ISC"));
+        assert!(!check(&policy, "a", "MIT AND ISC"));
+        assert!(!check(&policy, "b", "GPL-3.0-only"));
+        assert!(check(&policy, "b", "GPL-3.0-only OR MIT"));
+    }
+
+    #[test]
+    fn use_clarified_expressions() {
+        let mut policy = Policy::new();
+        policy.allow("MIT").unwrap();
+        policy.clarify("c", SpdxExpression::parse("MIT").unwrap());
+
+        assert!(check(&policy, "c", "NOASSERTION"));
+        assert!(!check(&policy, "d", "NOASSERTION"));
+    }
+
+    #[test]
+    fn reject_compound_licenses() {
+        let mut policy = Policy::new();
+        assert!(matches!(
+            policy.allow("MIT OR ISC"),
+            Err(SpdxExpressionError::Policy(_))
+        ));
+        assert!(policy.deny("MIT AND").is_err());
+        assert_eq!(policy, Policy::new());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn load_cargo_deny_config() {
+        let licenses = serde_json::json!({
+            "allow": ["MIT", "Apache-2.0 WITH LLVM-exception"],
+            "deny": ["GPL-3.0-only"],
+            "confidence-threshold": 0.9,
+            "exceptions": [
+                { "allow": ["ISC"], "crate": "ring@0.17" },
+                { "allow": ["Zlib"], "name": "adler32" }
+            ],
+            "clarify": [{ "name": "webpki", "expression": "MIT" }]
+        });
+
+        let policy = Policy::from_cargo_deny(&licenses).unwrap();
+
+        assert!(policy.is_allowed("ring", "ISC"));
+        assert!(policy.is_allowed("adler32", "Zlib"));
+        assert!(!policy.is_allowed("ring", "Zlib"));
+        assert!(!policy.is_allowed("ring", "GPL-3.0-only"));
+        assert!(check(&policy, "webpki", "MIT AND ISC"));
+        assert!(check(
+            &policy,
+            "ring",
+            "Apache-2.0 WITH LLVM-exception AND ISC"
+        ));
+
+        assert_eq!(
+            Policy::from_cargo_deny(&serde_json::json!({})).unwrap(),
+            Policy::new()
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn reject_malformed_cargo_deny_config() {
+        for licenses in [
+            serde_json::json!({ "allow": "MIT" }),
+            serde_json::json!({ "allow": [1] }),
+            serde_json::json!({ "deny": ["MIT OR ISC"] }),
+            serde_json::json!({ "exceptions": [{ "allow": ["MIT"] }] }),
+            serde_json::json!({ "clarify": [{ "crate": "a" }] }),
+            serde_json::json!({ "clarify": [{ "crate": "a", "expression": "MIT OR" }] }),
+        ] {
+            assert!(Policy::from_cargo_deny(&licenses).is_err(), "{}", licenses);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Conversion of expressions to and from postfix token sequences.
+
+use crate::{
+    error::SpdxExpressionError,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser,
+};
+
+/// Token of an expression in postfix order, see [`SpdxExpression::to_postfix`].
+///
+/// [`SpdxExpression::to_postfix`]: crate::SpdxExpression::to_postfix
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PostfixToken {
+    /// A license, pushed on the stack.
+    License(SimpleExpression),
+
+    /// An exception, pushed on the stack to be consumed by the following `With`.
+    Exception(String),
+
+    /// Pops an exception and a license and pushes the license with the exception.
+    With,
+
+    /// Pops two operands and pushes them joined with `AND`.
+    And,
+
+    /// Pops two operands and pushes them joined with `OR`.
+    Or,
+}
+
+/// Entry on the stack when rebuilding an expression.
+enum Operand {
+    Expression(ExpressionVariant),
+    Exception(String),
+}
+
+pub fn to_postfix(expression: &ExpressionVariant) -> Vec<PostfixToken> {
+    let mut tokens = Vec::new();
+    push_tokens(expression, &mut tokens);
+    tokens
+}
+
+fn push_tokens(expression: &ExpressionVariant, tokens: &mut Vec<PostfixToken>) {
+    match expression {
+        ExpressionVariant::Simple(license) => tokens.push(PostfixToken::License(license.clone())),
+        ExpressionVariant::With(with) => {
+            tokens.push(PostfixToken::License(with.license.clone()));
+            tokens.push(PostfixToken::Exception(with.exception.clone()));
+            tokens.push(PostfixToken::With);
+        }
+        ExpressionVariant::And(left, right) => {
+            push_tokens(left, tokens);
+            push_tokens(right, tokens);
+            tokens.push(PostfixToken::And);
+        }
+        ExpressionVariant::Or(left, right) => {
+            push_tokens(left, tokens);
+            push_tokens(right, tokens);
+            tokens.push(PostfixToken::Or);
+        }
+        ExpressionVariant::Parens(inner) => push_tokens(inner, tokens),
+    }
+}
+
+pub fn from_postfix<I>(tokens: I) -> Result<ExpressionVariant, SpdxExpressionError>
+where
+    I: IntoIterator<Item = PostfixToken>,
+{
+    let mut stack = Vec::new();
+
+    for (position, token) in tokens.into_iter().enumerate() {
+        let error = |message: &str| {
+            SpdxExpressionError::Conversion(format!("{message} at postfix token {position}"))
+        };
+
+        match token {
+            PostfixToken::License(license) => {
+                stack.push(Operand::Expression(ExpressionVariant::Simple(license)));
+            }
+            PostfixToken::Exception(exception) => {
+                if !parser::is_idstring(&exception) {
+                    return Err(error(&format!("invalid exception `{exception}`")));
+                }
+                stack.push(Operand::Exception(exception));
+            }
+            PostfixToken::With => {
+                let (Some(Operand::Exception(exception)), Some(Operand::Expression(license))) =
+                    (stack.pop(), stack.pop())
+                else {
+                    return Err(error("WITH needs a license and an exception"));
+                };
+                let ExpressionVariant::Simple(license) = license else {
+                    return Err(error("WITH can only be applied to a single license"));
+                };
+                stack.push(Operand::Expression(ExpressionVariant::With(Box::new(
+                    WithExpression::new(license, exception),
+                ))));
+            }
+            PostfixToken::And | PostfixToken::Or => {
+                let (Some(Operand::Expression(right)), Some(Operand::Expression(left))) =
+                    (stack.pop(), stack.pop())
+                else {
+                    return Err(error("operator needs two expressions"));
+                };
+                stack.push(Operand::Expression(if token == PostfixToken::And {
+                    ExpressionVariant::and(left, right)
+                } else {
+                    ExpressionVariant::or(left, right)
+                }));
+            }
+        }
+    }
+
+    match (stack.pop(), stack.is_empty()) {
+        (Some(Operand::Expression(expression)), true) => Ok(expression),
+        _ => Err(SpdxExpressionError::Conversion(
+            "postfix tokens don't form a single expression".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn license(identifier: &str) -> PostfixToken {
+        PostfixToken::License(SimpleExpression::parse(identifier).unwrap())
+    }
+
+    #[test]
+    fn convert_to_postfix() {
+        let expression =
+            ExpressionVariant::parse("MIT AND (ISC OR GPL-2.0-only WITH Classpath-exception-2.0)")
+                .unwrap();
+
+        assert_eq!(
+            to_postfix(&expression),
+            [
+                license("MIT"),
+                license("ISC"),
+                license("GPL-2.0-only"),
+                PostfixToken::Exception("Classpath-exception-2.0".to_string()),
+                PostfixToken::With,
+                PostfixToken::Or,
+                PostfixToken::And,
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trip_through_postfix() {
+        for input in &[
+            "MIT",
+            "MIT AND (ISC OR 0BSD)",
+            "(MIT AND ISC) OR 0BSD",
+            "MIT OR (ISC OR 0BSD)",
+            "LicenseRef-a WITH exc AND DocumentRef-doc:LicenseRef-b",
+        ] {
+            let expression = ExpressionVariant::parse(input).unwrap();
+            let rebuilt = from_postfix(to_postfix(&expression)).unwrap();
+
+            assert_eq!(
+                ExpressionVariant::parse(&rebuilt.to_string()).unwrap(),
+                rebuilt,
+                "{input}"
+            );
+            assert_eq!(to_postfix(&rebuilt), to_postfix(&expression), "{input}");
+        }
+    }
+
+    #[test]
+    fn reject_invalid_sequences() {
+        let exception = || PostfixToken::Exception("exc".to_string());
+
+        for tokens in [
+            vec![],
+            vec![PostfixToken::And],
+            vec![license("MIT"), PostfixToken::Or],
+            vec![license("MIT"), license("ISC")],
+            vec![exception()],
+            vec![license("MIT"), exception(), PostfixToken::And],
+            vec![license("MIT"), PostfixToken::With],
+            vec![
+                license("MIT"),
+                license("ISC"),
+                PostfixToken::And,
+                exception(),
+                PostfixToken::With,
+            ],
+        ] {
+            assert!(from_postfix(tokens.clone()).is_err(), "{:?}", tokens);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_exceptions() {
+        for exception in ["", "exc OR GPL-3.0-only", "exc+"] {
+            let tokens = [
+                license("MIT"),
+                PostfixToken::Exception(exception.to_string()),
+                PostfixToken::With,
+            ];
+
+            assert!(matches!(
+                from_postfix(tokens),
+                Err(SpdxExpressionError::Conversion(message)) if message.ends_with("postfix token 1")
+            ));
+        }
+    }
+}
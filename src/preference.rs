@@ -0,0 +1,205 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Choosing between the alternatives of an expression by license weights.
+
+use std::{collections::HashMap, iter::FromIterator};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    options_report, SpdxExpression,
+};
+
+/// Weights of licenses for choosing between the alternatives of expressions, see
+/// [`SpdxExpression::resolve`].
+///
+/// Weights are penalties: the choice with the lowest total weight of its licenses is preferred.
+/// Licenses without a weight weigh zero, so negative weights favor licenses. Licenses can also be
+/// forbidden, so that they are never chosen.
+///
+/// Licenses are looked up by their identifiers, including the `+`, `LicenseRef-` and
+/// `DocumentRef-` parts, compared case-insensitively. Weights can be serialized and deserialized
+/// as maps from identifiers to weights, with `null` for the forbidden licenses, so they can be
+/// loaded from configuration files.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{LicenseWeights, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let weights: LicenseWeights = serde_json::from_str(
+///     r#"{ "MIT": 1, "Apache-2.0": 2, "GPL-2.0-only": 10, "AGPL-3.0-only": null }"#,
+/// )
+/// .unwrap();
+///
+/// let expression = SpdxExpression::parse("(MIT OR GPL-2.0-only) AND (Apache-2.0 OR MIT)")?;
+/// assert_eq!(expression.resolve(&weights).unwrap().to_string(), "MIT");
+///
+/// let expression = SpdxExpression::parse("AGPL-3.0-only OR GPL-2.0-only")?;
+/// assert_eq!(expression.resolve(&weights).unwrap().to_string(), "GPL-2.0-only");
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LicenseWeights {
+    weights: HashMap<String, Option<i64>>,
+}
+
+impl LicenseWeights {
+    /// Create weights where every license weighs zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the weight of a license, replacing its previous weight.
+    pub fn insert(&mut self, identifier: &str, weight: i64) {
+        self.weights
+            .insert(identifier.to_ascii_lowercase(), Some(weight));
+    }
+
+    /// Forbid a license, so that it's never chosen.
+    pub fn forbid(&mut self, identifier: &str) {
+        self.weights.insert(identifier.to_ascii_lowercase(), None);
+    }
+
+    /// Get the weight of a license, or `None` if the license is forbidden.
+    pub fn weight(&self, license: &SimpleExpression) -> Option<i64> {
+        self.weights
+            .get(&license.to_string().to_ascii_lowercase())
+            .copied()
+            .unwrap_or(Some(0))
+    }
+}
+
+impl FromIterator<(String, Option<i64>)> for LicenseWeights {
+    fn from_iter<T: IntoIterator<Item = (String, Option<i64>)>>(iter: T) -> Self {
+        Self {
+            weights: iter
+                .into_iter()
+                .map(|(identifier, weight)| (identifier.to_ascii_lowercase(), weight))
+                .collect(),
+        }
+    }
+}
+
+impl Serialize for LicenseWeights {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.weights.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LicenseWeights {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        HashMap::<String, Option<i64>>::deserialize(deserializer).map(Self::from_iter)
+    }
+}
+
+/// Get the choice of licenses satisfying `expression` with the lowest total weight, or `None` if
+/// every choice has a forbidden license. Ties are broken by the order of the choices in the
+/// expression.
+pub fn resolve(expression: &ExpressionVariant, weights: &LicenseWeights) -> Option<SpdxExpression> {
+    let mut best: Option<(i64, options_report::Terms<'_>)> = None;
+
+    for terms in options_report::choices(expression) {
+        let weight = terms.iter().try_fold(0_i64, |total, (license, _)| {
+            weights
+                .weight(license)
+                .map(|weight| total.saturating_add(weight))
+        });
+        let Some(weight) = weight else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(best, _)| weight < *best) {
+            best = Some((weight, terms));
+        }
+    }
+
+    best.map(|(_, terms)| options_report::to_expression(&terms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_str(expression: &str, weights: &LicenseWeights) -> Option<String> {
+        let expression = ExpressionVariant::parse(expression).unwrap();
+        resolve(&expression, weights).map(|resolved| resolved.to_string())
+    }
+
+    #[test]
+    fn choose_lowest_total_weight() {
+        let mut weights = LicenseWeights::new();
+        weights.insert("MIT", 1);
+        weights.insert("Apache-2.0", 2);
+        weights.insert("gpl-2.0-only", 5);
+
+        assert_eq!(
+            resolve_str("GPL-2.0-only OR MIT AND Apache-2.0", &weights).as_deref(),
+            Some("MIT AND Apache-2.0")
+        );
+        assert_eq!(
+            resolve_str("MIT AND ISC OR GPL-2.0-only", &weights).as_deref(),
+            Some("MIT AND ISC")
+        );
+        assert_eq!(
+            resolve_str("(GPL-2.0-only OR MIT) AND (Apache-2.0 OR MIT)", &weights).as_deref(),
+            Some("MIT")
+        );
+        assert_eq!(
+            resolve_str("Apache-2.0 OR ISC OR 0BSD", &weights).as_deref(),
+            Some("ISC")
+        );
+    }
+
+    #[test]
+    fn prefer_negative_weights() {
+        let mut weights = LicenseWeights::new();
+        weights.insert("Apache-2.0", -1);
+
+        assert_eq!(
+            resolve_str("MIT OR Apache-2.0", &weights).as_deref(),
+            Some("Apache-2.0")
+        );
+    }
+
+    #[test]
+    fn skip_forbidden_licenses() {
+        let mut weights = LicenseWeights::new();
+        weights.insert("MIT", 100);
+        weights.forbid("GPL-2.0-only");
+
+        assert_eq!(
+            resolve_str("GPL-2.0-only WITH exc OR MIT", &weights).as_deref(),
+            Some("MIT")
+        );
+        assert_eq!(resolve_str("GPL-2.0-only AND (MIT OR ISC)", &weights), None);
+    }
+
+    #[test]
+    fn deserialize_weights() {
+        let weights: LicenseWeights =
+            serde_json::from_value(serde_json::json!({ "MIT": 3, "GPL-2.0+": null })).unwrap();
+
+        assert_eq!(
+            weights.weight(&SimpleExpression::parse("mit").unwrap()),
+            Some(3)
+        );
+        assert_eq!(
+            weights.weight(&SimpleExpression::parse("GPL-2.0+").unwrap()),
+            None
+        );
+        assert_eq!(
+            weights.weight(&SimpleExpression::parse("GPL-2.0").unwrap()),
+            Some(0)
+        );
+    }
+}
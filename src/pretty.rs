@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rendering long expressions on multiple lines.
+
+use crate::{expression_variant::ExpressionVariant, parser::Operator};
+
+/// Options for rendering expressions on multiple lines with [`SpdxExpression::to_pretty`].
+///
+/// [`SpdxExpression::to_pretty`]: crate::SpdxExpression::to_pretty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// Maximum length of the lines. Licenses longer than the width are not broken, so lines can
+    /// be longer than this. Defaults to 80.
+    pub width: usize,
+
+    /// Number of spaces each level of nesting is indented with. Defaults to 2.
+    pub indent: usize,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            indent: 2,
+        }
+    }
+}
+
+pub fn render(expression: &ExpressionVariant, options: &PrettyOptions) -> String {
+    lines(expression, options.width, options).join("\n")
+}
+
+/// Operands of a chain of the same operator, not looking through parentheses, which are kept
+/// as they are.
+fn chain(node: &ExpressionVariant) -> Option<(Operator, Vec<&ExpressionVariant>)> {
+    let operator = match node {
+        ExpressionVariant::And(..) => Operator::And,
+        ExpressionVariant::Or(..) => Operator::Or,
+        _ => return None,
+    };
+
+    let mut operands = Vec::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        match (node, operator) {
+            (ExpressionVariant::And(left, right), Operator::And)
+            | (ExpressionVariant::Or(left, right), Operator::Or) => {
+                stack.push(right);
+                stack.push(left);
+            }
+            _ => operands.push(node),
+        }
+    }
+
+    Some((operator, operands))
+}
+
+/// Render `node` in lines no longer than `width` where possible. The lines are not indented
+/// relative to each other's starting column.
+fn lines(node: &ExpressionVariant, width: usize, options: &PrettyOptions) -> Vec<String> {
+    let flat = node.to_string();
+    if flat.len() <= width {
+        return vec![flat];
+    }
+
+    let indentation = " ".repeat(options.indent);
+    let nested_width = width.saturating_sub(options.indent);
+
+    if let ExpressionVariant::Parens(inner) = node {
+        let mut lines = vec!["(".to_string()];
+        lines.extend(
+            self::lines(inner, nested_width, options)
+                .into_iter()
+                .map(|line| format!("{indentation}{line}")),
+        );
+        lines.push(")".to_string());
+        return lines;
+    }
+
+    let Some((operator, operands)) = chain(node) else {
+        return vec![flat];
+    };
+    let operator = match operator {
+        Operator::And => "AND ",
+        Operator::Or => "OR ",
+    };
+
+    let mut lines = Vec::new();
+    for (index, operand) in operands.into_iter().enumerate() {
+        let prefix = if index == 0 { "" } else { operator };
+        // The lines of parenthesized operands are indented by the parentheses already.
+        let continuation = if matches!(operand, ExpressionVariant::Parens(_)) {
+            ""
+        } else {
+            indentation.as_str()
+        };
+
+        let operand_width = width.saturating_sub(prefix.len().max(continuation.len()));
+        for (line_index, line) in self::lines(operand, operand_width, options)
+            .into_iter()
+            .enumerate()
+        {
+            if line_index == 0 {
+                lines.push(format!("{prefix}{line}"));
+            } else {
+                lines.push(format!("{continuation}{line}"));
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pretty(expression: &str, width: usize) -> String {
+        render(
+            &ExpressionVariant::parse(expression).unwrap(),
+            &PrettyOptions {
+                width,
+                ..PrettyOptions::default()
+            },
+        )
+    }
+
+    #[test]
+    fn short_expressions_stay_on_one_line() {
+        assert_eq!(pretty("MIT OR (ISC AND 0BSD)", 80), "MIT OR (ISC AND 0BSD)");
+        assert_eq!(pretty("GPL-2.0-only WITH exc", 5), "GPL-2.0-only WITH exc");
+    }
+
+    #[test]
+    fn long_expressions_are_wrapped() {
+        assert_eq!(
+            pretty("MIT OR (Apache-2.0 AND ISC AND BSD-3-Clause) OR 0BSD", 30),
+            "MIT\n\
+             OR (\n  Apache-2.0\n  AND ISC\n  AND BSD-3-Clause\n)\n\
+             OR 0BSD"
+        );
+        assert_eq!(
+            pretty("Apache-2.0 AND ISC OR MIT AND BSD-3-Clause", 20),
+            "Apache-2.0 AND ISC\n\
+             OR MIT\n  AND BSD-3-Clause"
+        );
+    }
+
+    #[test]
+    fn wrapped_expressions_parse_to_the_same_expression() {
+        for expression in [
+            "MIT OR (Apache-2.0 AND (ISC OR 0BSD WITH exc) AND BSD-3-Clause) OR 0BSD",
+            "((MIT AND ISC) OR (Apache-2.0 AND BSD-2-Clause)) AND GPL-2.0-or-later",
+        ] {
+            let parsed = ExpressionVariant::parse(expression).unwrap();
+            for width in [0, 10, 20, 40] {
+                let rendered = render(&parsed, &PrettyOptions { width, indent: 4 });
+                assert_eq!(
+                    ExpressionVariant::parse(&rendered).unwrap(),
+                    parsed,
+                    "{rendered}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Checks of the invariants of expressions, for property tests of pipelines embedding
+//! expressions.
+//!
+//! The checks return a [`PropertyViolation`] describing the failure instead of panicking, so
+//! they can be used with any property testing framework, e.g. with `?` in [`proptest`] tests,
+//! or with expressions from the [`generator`](crate::generator).
+//!
+//! # Examples
+//!
+//! ```
+//! # use spdx_expression::generator::{Generator, GeneratorOptions};
+//! # use spdx_expression::properties::{self, PropertyViolation};
+//! #
+//! for expression in Generator::new(0, GeneratorOptions::default()).take(100) {
+//!     properties::check_all(&expression)?;
+//!
+//!     // Check the invariants of the pipeline's own normalization.
+//!     properties::check_idempotent(&expression, "strip exceptions", |expression| {
+//!         expression.strip_exceptions().0
+//!     })?;
+//! }
+//! # Ok::<(), PropertyViolation>(())
+//! ```
+//!
+//! [`proptest`]: https://docs.rs/proptest
+
+use crate::expression::SpdxExpression;
+
+/// Failure of an invariant check.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{property} doesn't hold for `{expression}`: {details}")]
+pub struct PropertyViolation {
+    /// Name of the violated property.
+    pub property: String,
+
+    /// The expression the property was checked for.
+    pub expression: String,
+
+    /// Description of the failure.
+    pub details: String,
+}
+
+impl PropertyViolation {
+    fn new(property: &str, expression: &SpdxExpression, details: String) -> Self {
+        Self {
+            property: property.to_string(),
+            expression: expression.to_string(),
+            details,
+        }
+    }
+}
+
+/// Check that displaying `expression` and parsing the result gives an equal expression, which
+/// is displayed the same.
+///
+/// # Errors
+///
+/// Returns a [`PropertyViolation`] if the displayed expression can't be parsed or is parsed
+/// to a different expression.
+pub fn check_round_trip(expression: &SpdxExpression) -> Result<(), PropertyViolation> {
+    const PROPERTY: &str = "display and parse round trip";
+
+    let displayed = expression.to_string();
+    let parsed = SpdxExpression::parse(&displayed)
+        .map_err(|error| PropertyViolation::new(PROPERTY, expression, error.to_string()))?;
+
+    if parsed != *expression {
+        return Err(PropertyViolation::new(
+            PROPERTY,
+            expression,
+            format!("parsed to {parsed:?}"),
+        ));
+    }
+    if parsed.to_string() != displayed {
+        return Err(PropertyViolation::new(
+            PROPERTY,
+            expression,
+            format!("displayed as `{parsed}` after parsing"),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that applying `normalize` to its own result doesn't change the result. `name` names
+/// the normalization in the violation.
+///
+/// # Errors
+///
+/// Returns a [`PropertyViolation`] if normalizing twice gives a different result than
+/// normalizing once.
+pub fn check_idempotent<F>(
+    expression: &SpdxExpression,
+    name: &str,
+    normalize: F,
+) -> Result<(), PropertyViolation>
+where
+    F: Fn(&SpdxExpression) -> SpdxExpression,
+{
+    let once = normalize(expression);
+    let twice = normalize(&once);
+    if once == twice {
+        Ok(())
+    } else {
+        Err(PropertyViolation::new(
+            &format!("idempotence of {name}"),
+            expression,
+            format!("normalized to `{once}` and then to `{twice}`"),
+        ))
+    }
+}
+
+/// Check that `equivalent` considers `expression` equivalent to itself and to the expression
+/// parsed from its displayed form. `name` names the equivalence in the violation.
+///
+/// # Errors
+///
+/// Returns a [`PropertyViolation`] if `equivalent` returns `false`.
+pub fn check_reflexive<F>(
+    expression: &SpdxExpression,
+    name: &str,
+    equivalent: F,
+) -> Result<(), PropertyViolation>
+where
+    F: Fn(&SpdxExpression, &SpdxExpression) -> bool,
+{
+    let property = format!("reflexivity of {name}");
+    if !equivalent(expression, expression) {
+        return Err(PropertyViolation::new(
+            &property,
+            expression,
+            "not equivalent to itself".to_string(),
+        ));
+    }
+
+    let reparsed = SpdxExpression::parse(&expression.to_string())
+        .map_err(|error| PropertyViolation::new(&property, expression, error.to_string()))?;
+    if !equivalent(expression, &reparsed) {
+        return Err(PropertyViolation::new(
+            &property,
+            expression,
+            "not equivalent to its displayed form".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check the normalizations of this crate for idempotence: simplification, sorting of the
+/// operands and removal of redundant parentheses.
+///
+/// # Errors
+///
+/// Returns a [`PropertyViolation`] for the first normalization that is not idempotent.
+pub fn check_normalizations(expression: &SpdxExpression) -> Result<(), PropertyViolation> {
+    check_idempotent(expression, "simplify", SpdxExpression::simplify)?;
+    check_idempotent(expression, "sort_operands", SpdxExpression::sort_operands)?;
+    check_idempotent(
+        expression,
+        "remove_redundant_parentheses",
+        SpdxExpression::remove_redundant_parentheses,
+    )
+}
+
+/// Check the equivalences of this crate for reflexivity: equality, equality ignoring case and
+/// `DocumentRef`s, and equality of canonical keys.
+///
+/// # Errors
+///
+/// Returns a [`PropertyViolation`] for the first equivalence that is not reflexive.
+pub fn check_equivalences(expression: &SpdxExpression) -> Result<(), PropertyViolation> {
+    check_reflexive(expression, "equality", PartialEq::eq)?;
+    check_reflexive(
+        expression,
+        "eq_ignoring_case",
+        SpdxExpression::eq_ignoring_case,
+    )?;
+    check_reflexive(
+        expression,
+        "eq_ignoring_document_refs",
+        SpdxExpression::eq_ignoring_document_refs,
+    )?;
+    check_reflexive(expression, "canonical_key", |left, right| {
+        left.canonical_key() == right.canonical_key()
+    })
+}
+
+/// Run all the checks of this module for `expression`.
+///
+/// # Errors
+///
+/// Returns the first [`PropertyViolation`] found.
+pub fn check_all(expression: &SpdxExpression) -> Result<(), PropertyViolation> {
+    check_round_trip(expression)?;
+    check_normalizations(expression)?;
+    check_equivalences(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{Generator, GeneratorOptions};
+
+    #[test]
+    fn properties_hold_for_generated_expressions() {
+        for expression in Generator::new(11, GeneratorOptions::default()).take(300) {
+            check_all(&expression).unwrap();
+        }
+    }
+
+    #[test]
+    fn report_violations() {
+        let expression = SpdxExpression::parse("MIT OR ISC").unwrap();
+
+        let violation = check_idempotent(&expression, "swap", |expression| {
+            let (left, right) = expression.dual_license_pair().unwrap();
+            SpdxExpression::parse(&format!("{right} OR {left}")).unwrap()
+        })
+        .unwrap_err();
+        assert_eq!(
+            violation.to_string(),
+            "idempotence of swap doesn't hold for `MIT OR ISC`: normalized to `ISC OR MIT` and \
+             then to `MIT OR ISC`"
+        );
+
+        let violation =
+            check_reflexive(&expression, "inequality", |left, right| left != right).unwrap_err();
+        assert_eq!(violation.property, "reflexivity of inequality");
+        assert_eq!(violation.details, "not equivalent to itself");
+    }
+}
@@ -0,0 +1,101 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rendering expressions as natural language.
+
+use crate::{expression_variant::ExpressionVariant, parser::Operator};
+
+pub fn render(expression: &ExpressionVariant) -> String {
+    match expression.flatten() {
+        Some((Operator::Or, alternatives)) => {
+            let alternatives = alternatives.into_iter().map(phrase).collect::<Vec<_>>();
+            format!(
+                "Licensed under {}",
+                alternatives.join(", or alternatively under ")
+            )
+        }
+        _ => format!("Licensed under {}", phrase(expression)),
+    }
+}
+
+/// Describe `expression` as a phrase that can be nested in other phrases.
+fn phrase(expression: &ExpressionVariant) -> String {
+    match expression.flatten() {
+        Some((operator, operands)) => {
+            let operands = operands.into_iter().map(phrase).collect::<Vec<_>>();
+            match operator {
+                Operator::And if operands.len() == 2 => format!("both {}", list(&operands, "and")),
+                Operator::And => format!("all of {}", list(&operands, "and")),
+                Operator::Or => format!("either {}", list(&operands, "or")),
+            }
+        }
+        None => match expression.unparenthesized() {
+            ExpressionVariant::With(with) => format!(
+                "{} with {}",
+                license(&with.license.to_string()),
+                with.exception
+            ),
+            other => license(&other.to_string()),
+        },
+    }
+}
+
+fn license(license: &str) -> String {
+    license.strip_suffix('+').map_or_else(
+        || license.to_string(),
+        |license| format!("{license} or any later version"),
+    )
+}
+
+/// Join `items` into a list like `a, b and c`.
+fn list(items: &[String], conjunction: &str) -> String {
+    match items.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} {conjunction} {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_str(expression: &str) -> String {
+        render(&ExpressionVariant::parse(expression).unwrap())
+    }
+
+    #[test]
+    fn render_single_license() {
+        assert_eq!(render_str("MIT"), "Licensed under MIT");
+        assert_eq!(
+            render_str("GPL-2.0-only WITH Classpath-exception-2.0"),
+            "Licensed under GPL-2.0-only with Classpath-exception-2.0"
+        );
+        assert_eq!(
+            render_str("(GPL-2.0+)"),
+            "Licensed under GPL-2.0 or any later version"
+        );
+    }
+
+    #[test]
+    fn render_alternatives() {
+        assert_eq!(
+            render_str("MIT OR Apache-2.0 OR (ISC)"),
+            "Licensed under MIT, or alternatively under Apache-2.0, or alternatively under ISC"
+        );
+    }
+
+    #[test]
+    fn render_nested() {
+        assert_eq!(
+            render_str("MIT AND (ISC OR 0BSD)"),
+            "Licensed under both MIT and either ISC or 0BSD"
+        );
+        assert_eq!(
+            render_str("MIT OR Apache-2.0 AND ISC AND (0BSD OR Unlicense OR CC0-1.0)"),
+            "Licensed under MIT, or alternatively under all of Apache-2.0, ISC and either 0BSD, \
+             Unlicense or CC0-1.0"
+        );
+    }
+}
@@ -0,0 +1,442 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Queries matching the structure of expressions.
+
+use std::fmt::Display;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_while},
+    character::complete::{multispace0, multispace1},
+    combinator::{map, peek, value},
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, preceded, separated_pair, terminated},
+    Finish, IResult,
+};
+
+use crate::{error::SpdxExpressionError, expression::SpdxExpression};
+
+/// Pattern matching a license with its optional exception, see [`Query`].
+///
+/// The patterns are matched case-insensitively, and `*` matches any sequence of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafPattern {
+    /// Pattern of the license, including the `LicenseRef-` and `DocumentRef-` prefixes and the
+    /// `+` suffix.
+    pub license: String,
+
+    /// Pattern of the exception. `None` matches licenses both with and without an exception.
+    pub exception: Option<String>,
+}
+
+impl LeafPattern {
+    fn matches(&self, license: &str, exception: Option<&str>) -> bool {
+        glob_matches(&self.license, license)
+            && self.exception.as_ref().is_none_or(|pattern| {
+                exception.is_some_and(|exception| glob_matches(pattern, exception))
+            })
+    }
+}
+
+/// Condition on the licenses and exceptions of an expression, e.g. for license policies or for
+/// searching expressions.
+///
+/// Queries can be built with the methods of `Query` or parsed from a small query language with
+/// [`Query::parse`]:
+///
+/// - `contains("GPL-*")` matches expressions with a license matching the pattern, with or
+///   without an exception.
+/// - `contains(WITH(license = "GPL-*", exception = "Classpath-*"))` matches expressions with a
+///   license with an exception matching the patterns. Both fields are optional.
+/// - Conditions are combined with `AND`, `OR`, `NOT` and parentheses. `NOT` binds tightest and
+///   `OR` loosest, like `AND` and `OR` in expressions.
+///
+/// Patterns are matched case-insensitively, and `*` matches any sequence of characters.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Query, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let query =
+///     Query::parse(r#"contains(WITH(exception = "Classpath-*")) AND NOT contains("AGPL-*")"#)?;
+///
+/// let expression = SpdxExpression::parse("GPL-2.0-only WITH Classpath-exception-2.0 OR MIT")?;
+/// assert!(query.matches(&expression));
+///
+/// let expression = SpdxExpression::parse("MIT WITH Classpath-exception-2.0 AND AGPL-3.0")?;
+/// assert!(!query.matches(&expression));
+///
+/// let built = Query::exception("Classpath-*").and(!Query::license("AGPL-*"));
+/// assert_eq!(built, query);
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// Some license of the expression matches the pattern.
+    Contains(LeafPattern),
+
+    /// Both queries match.
+    And(Box<Self>, Box<Self>),
+
+    /// Either query matches.
+    Or(Box<Self>, Box<Self>),
+
+    /// The query doesn't match.
+    Not(Box<Self>),
+}
+
+impl Query {
+    /// Parse a query, see [`Query`] for the syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Query` if the query is invalid.
+    pub fn parse(query: &str) -> Result<Self, SpdxExpressionError> {
+        let (remaining, parsed) = delimited(multispace0, or_query, multispace0)(query)
+            .finish()
+            .map_err(|_| SpdxExpressionError::Query(query.to_string()))?;
+
+        if remaining.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(SpdxExpressionError::Query(query.to_string()))
+        }
+    }
+
+    /// Query for a license matching `pattern`, with or without an exception.
+    pub fn license(pattern: &str) -> Self {
+        Self::Contains(LeafPattern {
+            license: pattern.to_string(),
+            exception: None,
+        })
+    }
+
+    /// Query for any license with an exception matching `pattern`.
+    pub fn exception(pattern: &str) -> Self {
+        Self::with(ANY, pattern)
+    }
+
+    /// Query for a license matching `license` with an exception matching `exception`.
+    pub fn with(license: &str, exception: &str) -> Self {
+        Self::Contains(LeafPattern {
+            license: license.to_string(),
+            exception: Some(exception.to_string()),
+        })
+    }
+
+    /// Combine the query with `other`, matching if both match.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        Self::And(Box::new(self), Box::new(other))
+    }
+
+    /// Combine the query with `other`, matching if either matches.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Check if `expression` matches the query.
+    pub fn matches(&self, expression: &SpdxExpression) -> bool {
+        match self {
+            Self::Contains(pattern) => expression
+                .leaves()
+                .any(|(license, exception)| pattern.matches(&license.to_string(), exception)),
+            Self::And(left, right) => left.matches(expression) && right.matches(expression),
+            Self::Or(left, right) => left.matches(expression) || right.matches(expression),
+            Self::Not(query) => !query.matches(expression),
+        }
+    }
+
+    /// Write `self`, parenthesized if it binds looser than `precedence`.
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>, precedence: u8) -> std::fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+
+    const fn precedence(&self) -> u8 {
+        match self {
+            Self::Or(..) => 0,
+            Self::And(..) => 1,
+            Self::Not(_) | Self::Contains(_) => 2,
+        }
+    }
+}
+
+impl std::ops::Not for Query {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Contains(LeafPattern {
+                license,
+                exception: None,
+            }) => write!(f, "contains({license:?})"),
+            Self::Contains(LeafPattern {
+                license,
+                exception: Some(exception),
+            }) => {
+                if license == ANY {
+                    write!(f, "contains(WITH(exception = {exception:?}))")
+                } else {
+                    write!(
+                        f,
+                        "contains(WITH(license = {license:?}, exception = {exception:?}))"
+                    )
+                }
+            }
+            Self::And(left, right) => {
+                left.fmt_operand(f, 1)?;
+                write!(f, " AND ")?;
+                right.fmt_operand(f, 2)
+            }
+            Self::Or(left, right) => {
+                left.fmt_operand(f, 0)?;
+                write!(f, " OR ")?;
+                right.fmt_operand(f, 1)
+            }
+            Self::Not(query) => {
+                write!(f, "NOT ")?;
+                query.fmt_operand(f, 2)
+            }
+        }
+    }
+}
+
+/// Pattern matching anything.
+const ANY: &str = "*";
+
+/// Match `text` against `pattern` case-insensitively, where `*` matches any sequence of
+/// characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    // matches[j] tells if the pattern so far matches the first j characters of the text.
+    let mut matches = vec![false; text.len() + 1];
+    matches[0] = true;
+    for character in pattern {
+        if character == '*' {
+            for j in 1..=text.len() {
+                matches[j] = matches[j] || matches[j - 1];
+            }
+        } else {
+            for j in (1..=text.len()).rev() {
+                matches[j] = matches[j - 1] && text[j - 1] == character;
+            }
+            matches[0] = false;
+        }
+    }
+
+    matches[text.len()]
+}
+
+/// Keyword followed by whitespace or parentheses.
+fn keyword<'a>(name: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag_no_case(name), peek(alt((multispace1, tag("(")))))
+}
+
+fn string(i: &str) -> IResult<&str, String> {
+    map(
+        delimited(tag("\""), take_while(|c| c != '"'), tag("\"")),
+        ToString::to_string,
+    )(i)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    License,
+    Exception,
+}
+
+fn with_pattern(i: &str) -> IResult<&str, LeafPattern> {
+    let field = separated_pair(
+        alt((
+            value(Field::License, tag_no_case("license")),
+            value(Field::Exception, tag_no_case("exception")),
+        )),
+        delimited(multispace0, tag("="), multispace0),
+        string,
+    );
+    let (i, fields) = preceded(
+        pair(tag_no_case("WITH"), multispace0),
+        delimited(
+            pair(tag("("), multispace0),
+            separated_list0(delimited(multispace0, tag(","), multispace0), field),
+            pair(multispace0, tag(")")),
+        ),
+    )(i)?;
+
+    let mut pattern = LeafPattern {
+        license: ANY.to_string(),
+        exception: Some(ANY.to_string()),
+    };
+    for (field, value) in fields {
+        match field {
+            Field::License => pattern.license = value,
+            Field::Exception => pattern.exception = Some(value),
+        }
+    }
+
+    Ok((i, pattern))
+}
+
+fn contains(i: &str) -> IResult<&str, Query> {
+    map(
+        preceded(
+            pair(tag_no_case("contains"), multispace0),
+            delimited(
+                pair(tag("("), multispace0),
+                alt((
+                    with_pattern,
+                    map(string, |license| LeafPattern {
+                        license,
+                        exception: None,
+                    }),
+                )),
+                pair(multispace0, tag(")")),
+            ),
+        ),
+        Query::Contains,
+    )(i)
+}
+
+fn unary_query(i: &str) -> IResult<&str, Query> {
+    alt((
+        map(
+            preceded(pair(keyword("NOT"), multispace0), unary_query),
+            |query| !query,
+        ),
+        delimited(
+            pair(tag("("), multispace0),
+            or_query,
+            pair(multispace0, tag(")")),
+        ),
+        contains,
+    ))(i)
+}
+
+fn and_query(i: &str) -> IResult<&str, Query> {
+    let (i, first) = unary_query(i)?;
+    let (i, rest) = many0(preceded(
+        delimited(multispace0, keyword("AND"), multispace0),
+        unary_query,
+    ))(i)?;
+
+    Ok((i, rest.into_iter().fold(first, Query::and)))
+}
+
+fn or_query(i: &str) -> IResult<&str, Query> {
+    let (i, first) = and_query(i)?;
+    let (i, rest) = many0(preceded(
+        delimited(multispace0, keyword("OR"), multispace0),
+        and_query,
+    ))(i)?;
+
+    Ok((i, rest.into_iter().fold(first, Query::or)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, expression: &str) -> bool {
+        Query::parse(query)
+            .unwrap()
+            .matches(&SpdxExpression::parse(expression).unwrap())
+    }
+
+    #[test]
+    fn match_glob_patterns() {
+        assert!(glob_matches("GPL-*", "gpl-2.0-only"));
+        assert!(glob_matches("*-only", "GPL-2.0-only"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("G*L*", "GPL-2.0"));
+        assert!(!glob_matches("GPL-*", "LGPL-2.1"));
+        assert!(!glob_matches("MIT", "MIT-0"));
+    }
+
+    #[test]
+    fn match_licenses_and_exceptions() {
+        assert!(matches(r#"contains("mit")"#, "ISC AND MIT WITH exc"));
+        assert!(!matches(r#"contains("MIT")"#, "ISC AND MIT-0"));
+        assert!(matches(r#"contains("LicenseRef-*")"#, "LicenseRef-a"));
+        assert!(matches(r#"contains("GPL-2.0+")"#, "GPL-2.0+"));
+        assert!(matches("contains(WITH())", "ISC OR MIT WITH exc"));
+        assert!(!matches("contains(WITH())", "ISC OR MIT"));
+        assert!(matches(
+            r#"contains(WITH(license = "GPL-*", exception = "Classpath-*"))"#,
+            "MIT OR GPL-2.0-only WITH Classpath-exception-2.0"
+        ));
+        assert!(!matches(
+            r#"contains(WITH(license = "GPL-*"))"#,
+            "MIT WITH exc OR GPL-2.0-only"
+        ));
+    }
+
+    #[test]
+    fn combine_queries() {
+        let query = r#"contains("MIT") OR NOT contains("GPL-*") AND contains("ISC")"#;
+        assert!(matches(query, "MIT AND GPL-2.0-only"));
+        assert!(matches(query, "ISC"));
+        assert!(!matches(query, "ISC OR GPL-2.0-only"));
+        assert!(!matches(query, "Apache-2.0"));
+
+        let query = r#"NOT (contains("MIT") OR contains("ISC"))"#;
+        assert!(matches(query, "Apache-2.0"));
+        assert!(!matches(query, "Apache-2.0 AND ISC"));
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for query in [
+            r#"contains("MIT")"#,
+            r#"contains(WITH(exception = "exc")) AND NOT contains("GPL-*")"#,
+            r#"(contains("a") OR contains("b")) AND NOT (contains("c") AND contains("d"))"#,
+            r#"contains("a") OR contains(WITH(license = "b", exception = "c*"))"#,
+        ] {
+            let parsed = Query::parse(query).unwrap();
+            assert_eq!(parsed.to_string(), query);
+            assert_eq!(Query::parse(&parsed.to_string()).unwrap(), parsed);
+        }
+        assert_eq!(
+            Query::license("a")
+                .and(Query::license("b").and(Query::license("c")))
+                .to_string(),
+            r#"contains("a") AND (contains("b") AND contains("c"))"#
+        );
+    }
+
+    #[test]
+    fn reject_invalid_queries() {
+        for query in [
+            "",
+            "MIT",
+            "contains(MIT)",
+            r#"contains("MIT""#,
+            r#"contains("MIT") AND"#,
+            r#"contains("MIT") contains("ISC")"#,
+            r#"NOTcontains("MIT")"#,
+            r#"contains(WITH(name = "a"))"#,
+        ] {
+            assert!(
+                matches!(Query::parse(query), Err(SpdxExpressionError::Query(_))),
+                "{}",
+                query
+            );
+        }
+    }
+}
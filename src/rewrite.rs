@@ -0,0 +1,310 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Rewriting expressions with user defined rules.
+
+use std::collections::HashSet;
+
+use crate::{
+    error::SpdxExpressionError,
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    parser::Operator,
+};
+
+/// Default for [`RewriteRules::set_max_passes`].
+const DEFAULT_MAX_PASSES: usize = 100;
+
+type Replace = Box<dyn Fn(&SpdxExpression) -> Option<SpdxExpression> + Send + Sync>;
+
+/// Pattern matching parts of an expression for a rewrite rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// A license without an exception.
+    License(SimpleExpression),
+
+    /// Any license with the exception.
+    Exception(String),
+
+    /// Operands joined with the operator. Operands joined with the same operator are matched
+    /// together, regardless of parentheses.
+    Operator(Operator),
+
+    /// An expression with the same structure as the subtree, regardless of parentheses.
+    Subtree(SpdxExpression),
+}
+
+impl Pattern {
+    fn matches(&self, node: &ExpressionVariant) -> bool {
+        match (self, node.unparenthesized()) {
+            (Self::License(license), ExpressionVariant::Simple(simple)) => license == simple,
+            (Self::Exception(exception), ExpressionVariant::With(with)) => {
+                *exception == with.exception
+            }
+            (Self::Operator(operator), node) => {
+                node.flatten().is_some_and(|(other, _)| other == *operator)
+            }
+            (Self::Subtree(subtree), node) => {
+                crate::postfix::to_postfix(node) == subtree.to_postfix()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Rules for rewriting expressions, applied with [`SpdxExpression::rewrite`].
+///
+/// Every rule has a [`Pattern`] and a replacement for the parts of the expression that match
+/// it. Rewriting applies the rules to the expression from the licenses up, using the first
+/// matching rule for every part, and repeats until no rule matches. Rules that would rewrite an
+/// expression back to an earlier form, or keep rewriting it for more than the maximum number of
+/// passes, cause an error instead of looping forever.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{Pattern, RewriteRules, SimpleExpression, SpdxExpression};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let mut rules = RewriteRules::new();
+/// rules.add(
+///     Pattern::License(SimpleExpression::parse("GPL-2.0")?),
+///     SpdxExpression::parse("GPL-2.0-only")?,
+/// );
+/// rules.add(
+///     Pattern::Subtree(SpdxExpression::parse("GPL-2.0-only OR GPL-3.0-only")?),
+///     SpdxExpression::parse("LicenseRef-GPL-any")?,
+/// );
+///
+/// let expression = SpdxExpression::parse("MIT AND (GPL-2.0 OR GPL-3.0-only)")?;
+/// assert_eq!(
+///     expression.rewrite(&rules)?.to_string(),
+///     "MIT AND LicenseRef-GPL-any"
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+pub struct RewriteRules {
+    rules: Vec<(Pattern, Replace)>,
+    max_passes: usize,
+}
+
+impl RewriteRules {
+    /// Create an empty set of rules.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            max_passes: DEFAULT_MAX_PASSES,
+        }
+    }
+
+    /// Add a rule replacing the parts of expressions matching `pattern` with `replacement`.
+    pub fn add(&mut self, pattern: Pattern, replacement: SpdxExpression) {
+        self.add_fn(pattern, move |_| Some(replacement.clone()));
+    }
+
+    /// Add a rule replacing the parts of expressions matching `pattern` with the result of
+    /// `replace`, called with the matching part. Parts for which `replace` returns `None` are
+    /// kept, and the following rules are tried.
+    pub fn add_fn<F>(&mut self, pattern: Pattern, replace: F)
+    where
+        F: Fn(&SpdxExpression) -> Option<SpdxExpression> + Send + Sync + 'static,
+    {
+        self.rules.push((pattern, Box::new(replace)));
+    }
+
+    /// Set the maximum number of passes over an expression. Rewriting fails if the expression
+    /// still changes on the last pass. Defaults to 100.
+    pub const fn set_max_passes(&mut self, max_passes: usize) {
+        self.max_passes = max_passes;
+    }
+
+    pub(crate) fn rewrite(
+        &self,
+        expression: &SpdxExpression,
+    ) -> Result<SpdxExpression, SpdxExpressionError> {
+        let mut seen = HashSet::from([expression.clone()]);
+        let mut current = expression.clone();
+
+        for _ in 0..self.max_passes {
+            let Some(rewritten) = self.pass(current.inner()) else {
+                return Ok(current);
+            };
+            let rewritten = SpdxExpression::from_inner(rewritten);
+
+            if !seen.insert(rewritten.clone()) {
+                return Err(SpdxExpressionError::Rewrite(format!(
+                    "the rules rewrite `{rewritten}` in a cycle"
+                )));
+            }
+            current = rewritten;
+        }
+
+        Err(SpdxExpressionError::Rewrite(format!(
+            "the expression still changed after {} passes",
+            self.max_passes
+        )))
+    }
+
+    /// Rewrite the operands of `node` and then `node` itself. Returns `None` if nothing changed.
+    fn pass(&self, node: &ExpressionVariant) -> Option<ExpressionVariant> {
+        let rewritten_operands = node.flatten().and_then(|(operator, operands)| {
+            let rewritten = operands
+                .iter()
+                .map(|operand| self.pass(operand))
+                .collect::<Vec<_>>();
+            rewritten.iter().any(Option::is_some).then(|| {
+                operands
+                    .iter()
+                    .zip(rewritten)
+                    .map(|(operand, rewritten)| rewritten.unwrap_or_else(|| (*operand).clone()))
+                    .reduce(|left, right| match operator {
+                        Operator::And => ExpressionVariant::and(left, right),
+                        Operator::Or => ExpressionVariant::or(left, right),
+                    })
+                    .expect("compound expressions have operands")
+            })
+        });
+
+        let current = rewritten_operands.as_ref().unwrap_or(node);
+        self.apply(current).or(rewritten_operands)
+    }
+
+    /// Apply the first matching rule to `node`.
+    fn apply(&self, node: &ExpressionVariant) -> Option<ExpressionVariant> {
+        let matched = SpdxExpression::from_inner(node.unparenthesized().clone());
+
+        self.rules
+            .iter()
+            .filter(|(pattern, _)| pattern.matches(node))
+            .find_map(|(_, replace)| replace(&matched))
+            .filter(|replacement| *replacement != matched)
+            .map(|replacement| replacement.inner().unparenthesized().clone())
+    }
+}
+
+impl Default for RewriteRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for RewriteRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RewriteRules")
+            .field(
+                "patterns",
+                &self
+                    .rules
+                    .iter()
+                    .map(|(pattern, _)| pattern)
+                    .collect::<Vec<_>>(),
+            )
+            .field("max_passes", &self.max_passes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(expression: &str) -> SpdxExpression {
+        SpdxExpression::parse(expression).unwrap()
+    }
+
+    fn license(identifier: &str) -> Pattern {
+        Pattern::License(SimpleExpression::parse(identifier).unwrap())
+    }
+
+    #[test]
+    fn rewrite_to_fixpoint() {
+        let mut rules = RewriteRules::new();
+        rules.add(license("A"), parse("B"));
+        rules.add(license("B"), parse("C OR D"));
+        rules.add(Pattern::Exception("old".to_string()), parse("MIT WITH new"));
+
+        assert_eq!(
+            rules
+                .rewrite(&parse("A AND ISC OR MIT WITH old"))
+                .unwrap()
+                .to_string(),
+            "(C OR D) AND ISC OR MIT WITH new"
+        );
+    }
+
+    #[test]
+    fn rewrite_operators_with_functions() {
+        let mut rules = RewriteRules::new();
+        // Replace alternatives with their first option.
+        rules.add_fn(Pattern::Operator(Operator::Or), |matched| {
+            matched.to_postfix().first().map(|token| match token {
+                crate::PostfixToken::License(license) => {
+                    SpdxExpression::from_inner(ExpressionVariant::Simple(license.clone()))
+                }
+                _ => unreachable!("postfix sequences start with a license"),
+            })
+        });
+
+        assert_eq!(
+            rules
+                .rewrite(&parse(
+                    "MIT AND (ISC OR 0BSD OR Zlib) AND (Apache-2.0 OR MIT)"
+                ))
+                .unwrap()
+                .to_string(),
+            "MIT AND ISC AND Apache-2.0"
+        );
+    }
+
+    #[test]
+    fn match_subtrees_regardless_of_parentheses() {
+        let mut rules = RewriteRules::new();
+        rules.add(Pattern::Subtree(parse("(A AND B) AND C")), parse("D"));
+
+        assert_eq!(
+            rules
+                .rewrite(&parse("X OR A AND (B AND C)"))
+                .unwrap()
+                .to_string(),
+            "X OR A AND (B AND C)"
+        );
+        assert_eq!(
+            rules
+                .rewrite(&parse("X OR ((A AND B) AND C)"))
+                .unwrap()
+                .to_string(),
+            "X OR D"
+        );
+    }
+
+    #[test]
+    fn detect_cycles_and_growth() {
+        let mut cycle = RewriteRules::new();
+        cycle.add(license("A"), parse("B"));
+        cycle.add(license("B"), parse("A"));
+
+        assert!(matches!(
+            cycle.rewrite(&parse("A")),
+            Err(SpdxExpressionError::Rewrite(_))
+        ));
+
+        let mut growth = RewriteRules::new();
+        growth.add(license("A"), parse("A AND MIT"));
+        growth.set_max_passes(10);
+
+        assert!(matches!(
+            growth.rewrite(&parse("A")),
+            Err(SpdxExpressionError::Rewrite(_))
+        ));
+    }
+
+    #[test]
+    fn keep_expressions_without_matches() {
+        let mut rules = RewriteRules::new();
+        rules.add(license("A"), parse("B"));
+        let expression = parse("MIT OR (ISC)");
+
+        assert_eq!(rules.rewrite(&expression).unwrap(), expression);
+    }
+}
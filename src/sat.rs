@@ -0,0 +1,369 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! SAT solver backend for analyses of large expressions.
+//!
+//! Enumerating the choices of an expression takes exponential time in the number of `AND`ed
+//! choices, which is infeasible for expressions aggregated from whole monorepos. The analyses here
+//! encode the expressions as boolean formulas and solve them with the conflict-driven clause
+//! learning solver of [`varisat`] instead.
+
+use std::collections::{HashMap, HashSet};
+
+use varisat::{ExtendFormula, Lit, Solver};
+
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    options_report, SpdxExpression,
+};
+
+/// Add clauses that allow at most `bound` of `lits` to be true, with the sequential counter
+/// encoding. Returns the outputs of the counter, the `j`th of which is true if more than `j` of
+/// `lits` are true, so the bound can be tightened later by adding a clause negating an output.
+fn add_at_most(formula: &mut impl ExtendFormula, lits: &[Lit], bound: usize) -> Vec<Lit> {
+    if bound == 0 {
+        for lit in lits {
+            formula.add_clause(&[!*lit]);
+        }
+        return Vec::new();
+    }
+
+    // `counts[i][j]` is true if at least `j + 1` of the first `i + 1` literals are true.
+    let mut counts: Vec<Vec<Lit>> = Vec::new();
+    for lit in lits {
+        let count = (0..bound).map(|_| formula.new_lit()).collect::<Vec<_>>();
+        formula.add_clause(&[!*lit, count[0]]);
+        if let Some(previous) = counts.last() {
+            formula.add_clause(&[!*lit, !previous[bound - 1]]);
+            for j in 0..bound {
+                formula.add_clause(&[!previous[j], count[j]]);
+                if j > 0 {
+                    formula.add_clause(&[!*lit, !previous[j - 1], count[j]]);
+                }
+            }
+        } else {
+            for later in &count[1..] {
+                formula.add_clause(&[!*later]);
+            }
+        }
+        counts.push(count);
+    }
+    counts.pop().unwrap_or_default()
+}
+
+/// Get the key of the variable of a license with an optional exception.
+fn atom_key(license: &SimpleExpression, exception: Option<&str>) -> String {
+    let mut key = license.to_string().to_ascii_lowercase();
+    if let Some(exception) = exception {
+        key.push_str(" with ");
+        key.push_str(&exception.to_ascii_lowercase());
+    }
+    key
+}
+
+/// Encoding of expressions as formulas for the solver. Every distinct license, with its
+/// exception, is a variable, compared case-insensitively.
+#[derive(Default)]
+struct Encoding<'a> {
+    solver: Solver<'static>,
+    atoms: Vec<(&'a SimpleExpression, Option<&'a str>, Lit)>,
+    vars: HashMap<String, Lit>,
+}
+
+impl<'a> Encoding<'a> {
+    /// Get a literal that is true exactly when `expression` is satisfied.
+    fn encode(&mut self, expression: &'a ExpressionVariant) -> Lit {
+        match expression {
+            ExpressionVariant::Simple(license) => self.atom(license, None),
+            ExpressionVariant::With(with) => self.atom(&with.license, Some(&with.exception)),
+            ExpressionVariant::And(left, right) => {
+                let (left, right) = (self.encode(left), self.encode(right));
+                let lit = self.solver.new_lit();
+                self.solver.add_clause(&[!lit, left]);
+                self.solver.add_clause(&[!lit, right]);
+                self.solver.add_clause(&[lit, !left, !right]);
+                lit
+            }
+            ExpressionVariant::Or(left, right) => {
+                let (left, right) = (self.encode(left), self.encode(right));
+                let lit = self.solver.new_lit();
+                self.solver.add_clause(&[!lit, left, right]);
+                self.solver.add_clause(&[lit, !left]);
+                self.solver.add_clause(&[lit, !right]);
+                lit
+            }
+            ExpressionVariant::Parens(inner) => self.encode(inner),
+        }
+    }
+
+    fn atom(&mut self, license: &'a SimpleExpression, exception: Option<&'a str>) -> Lit {
+        let key = atom_key(license, exception);
+        if let Some(lit) = self.vars.get(&key) {
+            return *lit;
+        }
+
+        let lit = self.solver.new_lit();
+        self.vars.insert(key, lit);
+        self.atoms.push((license, exception, lit));
+        lit
+    }
+
+    /// Get the true literals of an assignment satisfying the formula, or `None` if there is
+    /// none.
+    fn solve(&mut self) -> Option<HashSet<Lit>> {
+        // Solving only fails if the solver is interrupted, which never happens here.
+        if self.solver.solve().ok()? {
+            self.solver.model().map(|model| model.into_iter().collect())
+        } else {
+            None
+        }
+    }
+}
+
+/// Check if `left` and `right` are satisfied by exactly the same choices of licenses.
+pub fn is_equivalent(left: &ExpressionVariant, right: &ExpressionVariant) -> bool {
+    let mut encoding = Encoding::default();
+    let left = encoding.encode(left);
+    let right = encoding.encode(right);
+
+    // The expressions are equivalent if no choice satisfies exactly one of them.
+    encoding.solver.add_clause(&[left, right]);
+    encoding.solver.add_clause(&[!left, !right]);
+    matches!(encoding.solver.solve(), Ok(false))
+}
+
+/// Get a choice satisfying `expression` with the fewest licenses, using only the licenses for
+/// which `is_allowed` returns `true`. Returns `None` if no such choice exists.
+pub fn smallest_choice<F>(
+    expression: &ExpressionVariant,
+    mut is_allowed: F,
+) -> Option<SpdxExpression>
+where
+    F: FnMut(&SimpleExpression) -> bool,
+{
+    let mut encoding = Encoding::default();
+    let root = encoding.encode(expression);
+    encoding.solver.add_clause(&[root]);
+    for (license, _, lit) in &encoding.atoms {
+        if !is_allowed(license) {
+            encoding.solver.add_clause(&[!*lit]);
+        }
+    }
+
+    let atoms = encoding
+        .atoms
+        .iter()
+        .map(|(_, _, lit)| *lit)
+        .collect::<Vec<_>>();
+    // Every bound is tighter than the previous one, so the bounds are added to the same solver,
+    // which keeps what it learnt between the searches. The counter is only added once, later
+    // bounds negate its outputs.
+    let mut best = encoding.solve()?;
+    let mut more_than = Vec::new();
+    loop {
+        let count = atoms.iter().filter(|lit| best.contains(lit)).count();
+        if count == 0 {
+            break;
+        }
+        if more_than.is_empty() {
+            more_than = add_at_most(&mut encoding.solver, &atoms, count - 1);
+        } else {
+            encoding.solver.add_clause(&[!more_than[count - 1]]);
+        }
+        match encoding.solve() {
+            Some(model) => best = model,
+            None => break,
+        }
+    }
+
+    let terms = encoding
+        .atoms
+        .iter()
+        .filter(|(_, _, lit)| best.contains(lit))
+        .map(|(license, exception, _)| (*license, *exception))
+        .collect::<Vec<_>>();
+    Some(options_report::to_expression(&terms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(expression: &str) -> ExpressionVariant {
+        ExpressionVariant::parse(expression).unwrap()
+    }
+
+    /// Generator of small random expressions, seeded for reproducible failures.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, bound: u64) -> u64 {
+            // xorshift64
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+
+        fn expression(&mut self, depth: usize) -> String {
+            const LICENSES: [&str; 4] = ["MIT", "isc", "0BSD", "MIT WITH exc"];
+            match if depth == 0 { 0 } else { self.below(3) } {
+                0 => {
+                    // The bound is the length of `LICENSES`.
+                    #[allow(clippy::cast_possible_truncation)]
+                    let index = self.below(LICENSES.len() as u64) as usize;
+                    LICENSES[index].to_string()
+                }
+                operator => format!(
+                    "({} {} {})",
+                    self.expression(depth - 1),
+                    if operator == 1 { "AND" } else { "OR" },
+                    self.expression(depth - 1)
+                ),
+            }
+        }
+    }
+
+    /// Check if `expression` is satisfied by the atoms with the keys in `chosen`.
+    fn holds(expression: &ExpressionVariant, chosen: &HashSet<String>) -> bool {
+        match expression {
+            ExpressionVariant::Simple(license) => chosen.contains(&atom_key(license, None)),
+            ExpressionVariant::With(with) => {
+                chosen.contains(&atom_key(&with.license, Some(&with.exception)))
+            }
+            ExpressionVariant::And(left, right) => holds(left, chosen) && holds(right, chosen),
+            ExpressionVariant::Or(left, right) => holds(left, chosen) || holds(right, chosen),
+            ExpressionVariant::Parens(inner) => holds(inner, chosen),
+        }
+    }
+
+    /// Get every choice of the atoms of the expressions.
+    fn choices(expressions: &[&ExpressionVariant]) -> Vec<HashSet<String>> {
+        let mut keys = Vec::new();
+        for expression in expressions {
+            let mut encoding = Encoding::default();
+            encoding.encode(expression);
+            for (license, exception, _) in encoding.atoms {
+                let key = atom_key(license, exception);
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        (0..1_usize << keys.len())
+            .map(|mask| {
+                keys.iter()
+                    .enumerate()
+                    .filter(|(index, _)| mask >> index & 1 == 1)
+                    .map(|(_, key)| key.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn count_true_literals() {
+        let mut solver = Solver::new();
+        let lits = solver.new_lit_iter(5).collect::<Vec<_>>();
+        solver.add_clause(&[lits[0], lits[1]]);
+        solver.add_clause(&[lits[2], lits[3]]);
+        solver.add_clause(&[lits[4]]);
+
+        let more_than = add_at_most(&mut solver, &lits, 3);
+        assert_eq!(solver.solve().ok(), Some(true));
+        let model = solver.model().unwrap();
+        assert_eq!(lits.iter().filter(|lit| model.contains(lit)).count(), 3);
+
+        solver.add_clause(&[!more_than[2]]);
+        assert_eq!(solver.solve().ok(), Some(false));
+    }
+
+    #[test]
+    fn check_equivalence() {
+        assert!(is_equivalent(
+            &parse("MIT AND (ISC OR Apache-2.0)"),
+            &parse("MIT AND ISC OR Apache-2.0 AND MIT")
+        ));
+        assert!(is_equivalent(&parse("MIT OR MIT AND ISC"), &parse("mit")));
+        assert!(!is_equivalent(&parse("MIT OR ISC"), &parse("MIT AND ISC")));
+        assert!(!is_equivalent(
+            &parse("GPL-2.0-only WITH Classpath-exception-2.0"),
+            &parse("GPL-2.0-only")
+        ));
+    }
+
+    #[test]
+    fn find_smallest_choice() {
+        let expression = parse("ISC AND (MIT OR Apache-2.0 AND 0BSD) AND (MIT OR ISC)");
+        let smallest = smallest_choice(&expression, |_| true).unwrap();
+        assert_eq!(smallest.to_string(), "ISC AND MIT");
+
+        let smallest = smallest_choice(&expression, |license| license.identifier != "MIT");
+        assert_eq!(smallest.unwrap().to_string(), "ISC AND Apache-2.0 AND 0BSD");
+
+        assert!(
+            smallest_choice(&parse("MIT AND ISC"), |license| license.identifier != "MIT").is_none()
+        );
+    }
+
+    #[test]
+    fn check_equivalence_like_enumeration() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for _ in 0..500 {
+            let left = parse(&random.expression(3));
+            let right = parse(&random.expression(3));
+            let expected = choices(&[&left, &right])
+                .iter()
+                .all(|chosen| holds(&left, chosen) == holds(&right, chosen));
+            assert_eq!(is_equivalent(&left, &right), expected, "{left} and {right}");
+        }
+    }
+
+    #[test]
+    fn find_smallest_choice_like_enumeration() {
+        let mut random = Random(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..500 {
+            let expression = parse(&random.expression(3));
+            let is_allowed = |license: &SimpleExpression| license.identifier != "0BSD";
+            let expected = choices(&[&expression])
+                .into_iter()
+                .filter(|chosen| holds(&expression, chosen) && !chosen.contains("0bsd"))
+                .map(|chosen| chosen.len())
+                .min();
+
+            let smallest = smallest_choice(&expression, is_allowed);
+            assert_eq!(
+                smallest
+                    .as_ref()
+                    .map(|choice| choice.to_string().split(" AND ").count()),
+                expected,
+                "{expression}"
+            );
+            if let Some(smallest) = smallest {
+                let chosen = choices(&[smallest.inner()]).pop().unwrap_or_default();
+                assert!(
+                    holds(&expression, &chosen),
+                    "{} for {}",
+                    smallest,
+                    expression
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn analyze_large_expressions() {
+        // Enumerating the choices of these expressions is infeasible.
+        let mut chain = (0..200)
+            .map(|index| format!("(LicenseRef-{index} OR LicenseRef-{})", index + 1))
+            .collect::<Vec<_>>();
+        let expression = parse(&chain.join(" AND "));
+        chain.reverse();
+        assert!(is_equivalent(&expression, &parse(&chain.join(" AND "))));
+
+        let smallest = smallest_choice(&expression, |_| true).unwrap();
+        assert_eq!(smallest.licenses().len(), 100);
+    }
+}
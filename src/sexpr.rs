@@ -0,0 +1,222 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! S-expression representation of expressions, e.g. `(or MIT (with GPL-2.0-only exception))`.
+
+use std::fmt::Write;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_while1},
+    character::complete::{multispace0, multispace1},
+    combinator::{cut, map, map_res, peek, value, verify},
+    multi::many1,
+    sequence::{delimited, pair, preceded, terminated},
+};
+
+use crate::{
+    error::{ParseError, SpdxExpressionError},
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser::{self, IResult, Operator},
+};
+
+pub fn render(expression: &ExpressionVariant) -> String {
+    let mut output = String::new();
+    render_node(expression, &mut output);
+    output
+}
+
+fn render_node(expression: &ExpressionVariant, output: &mut String) {
+    if let Some((operator, operands)) = expression.flatten() {
+        output.push_str(match operator {
+            Operator::And => "(and",
+            Operator::Or => "(or",
+        });
+        for operand in operands {
+            output.push(' ');
+            render_node(operand, output);
+        }
+        output.push(')');
+        return;
+    }
+
+    match expression.unparenthesized() {
+        ExpressionVariant::Simple(license) => output.push_str(&license.to_string()),
+        ExpressionVariant::With(with) => {
+            // Writing to a `String` can't fail.
+            let _ = write!(output, "(with {} {})", with.license, with.exception);
+        }
+        ExpressionVariant::And(..) | ExpressionVariant::Or(..) | ExpressionVariant::Parens(_) => {
+            unreachable!("compound expressions are flattened")
+        }
+    }
+}
+
+pub fn parse(i: &str) -> Result<ExpressionVariant, SpdxExpressionError> {
+    let (remaining, expression) = delimited(multispace0, node, multispace0)(i)
+        .map_err(|err| SpdxExpressionError::Parse(ParseError::from_nom(i, &err)))?;
+
+    if remaining.is_empty() {
+        Ok(expression)
+    } else {
+        Err(SpdxExpressionError::Parse(ParseError::unexpected(
+            i, remaining,
+        )))
+    }
+}
+
+fn atom(i: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| !c.is_whitespace() && c != '(' && c != ')')(i)
+}
+
+/// License operand. Keywords only start lists, so licenses named like them are operands too.
+fn license(i: &str) -> IResult<&str, SimpleExpression> {
+    map_res(atom, SimpleExpression::parse)(i)
+}
+
+fn exception(i: &str) -> IResult<&str, &str> {
+    verify(atom, parser::is_idstring)(i)
+}
+
+/// Keyword at the start of a list, which needs to be followed by whitespace or a list.
+fn keyword<'a>(name: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag_no_case(name), peek(alt((multispace1, tag("(")))))
+}
+
+fn with_list(i: &str) -> IResult<&str, ExpressionVariant> {
+    map(
+        preceded(
+            keyword("with"),
+            cut(pair(
+                preceded(multispace0, license),
+                preceded(multispace1, exception),
+            )),
+        ),
+        |(license, exception)| {
+            ExpressionVariant::With(Box::new(WithExpression::new(
+                license,
+                exception.to_string(),
+            )))
+        },
+    )(i)
+}
+
+fn operator_list(i: &str) -> IResult<&str, ExpressionVariant> {
+    let (i, operator) = alt((
+        value(Operator::And, keyword("and")),
+        value(Operator::Or, keyword("or")),
+    ))(i)?;
+    // Only operands can follow the operator, so failing here fails the whole parse.
+    let (i, first) = cut(preceded(multispace0, node))(i)?;
+    let (i, rest) = cut(many1(preceded(multispace0, node)))(i)?;
+
+    let join = match operator {
+        Operator::And => ExpressionVariant::and,
+        Operator::Or => ExpressionVariant::or,
+    };
+
+    Ok((i, rest.into_iter().fold(first, join)))
+}
+
+fn node(i: &str) -> IResult<&str, ExpressionVariant> {
+    alt((
+        preceded(
+            tag("("),
+            cut(terminated(
+                delimited(multispace0, alt((with_list, operator_list)), multispace0),
+                tag(")"),
+            )),
+        ),
+        map(license, ExpressionVariant::Simple),
+    ))(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_nested_expression() {
+        let expression = ExpressionVariant::parse(
+            "MIT OR (GPL-2.0-only AND LGPL-2.1 WITH exception) OR LicenseRef-a",
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&expression),
+            "(or MIT (and GPL-2.0-only (with LGPL-2.1 exception)) LicenseRef-a)"
+        );
+    }
+
+    #[test]
+    fn parse_nested_expression() {
+        let expression =
+            parse(" (AND (or MIT ISC)(with GPL-2.0-or-later Classpath-exception-2.0) 0BSD ) ")
+                .unwrap();
+
+        assert_eq!(
+            expression.to_string(),
+            "(MIT OR ISC) AND GPL-2.0-or-later WITH Classpath-exception-2.0 AND 0BSD"
+        );
+    }
+
+    #[test]
+    fn round_trip_through_sexpr() {
+        for input in [
+            "MIT",
+            "MIT OR (ISC AND (Apache-2.0 OR DocumentRef-doc:LicenseRef-a)) AND X+",
+            "(a OR b) AND (c OR d WITH e)",
+            "MIT AND or",
+            "with WITH and OR AND",
+        ] {
+            let expression = ExpressionVariant::parse(input).unwrap();
+            let rendered = render(&expression);
+
+            let parsed = parse(&rendered).unwrap();
+
+            assert_eq!(render(&parsed), rendered);
+            assert_eq!(
+                ExpressionVariant::parse(&parsed.to_string()).unwrap(),
+                parsed
+            );
+        }
+    }
+
+    #[test]
+    fn fail_on_invalid_sexpr() {
+        for input in [
+            "",
+            "(or MIT)",
+            "(and MIT ISC",
+            "(xor MIT ISC)",
+            "(orMIT ISC)",
+            "(with MIT)",
+            "(with (or MIT ISC) exception)",
+            "MIT ISC",
+            "(or MIT (ISC))",
+            "(and MIT (or))",
+            "(with and)",
+            "(with MIT exc+)",
+            "(with MIT a:b)",
+        ] {
+            assert!(parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn report_offset_of_errors() {
+        for (input, offset) in [
+            ("(or MIT ISC", 11),
+            ("(or MIT ISC) 0BSD", 13),
+            ("(with MIT exc+)", 10),
+            ("  (or MIT a+b)", 10),
+        ] {
+            let error_offset = match parse(input) {
+                Err(SpdxExpressionError::Parse(error)) => error.offset,
+                _ => None,
+            };
+            assert_eq!(error_offset, Some(offset), "{input}");
+        }
+    }
+}
@@ -0,0 +1,304 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Simplification of expressions.
+
+use crate::{
+    error::BuildError,
+    expression_variant::ExpressionVariant,
+    parser::Operator,
+    special::{self, SpecialLicenses},
+};
+
+/// Options of [`SpdxExpression::simplify_with`].
+///
+/// [`SpdxExpression::simplify_with`]: crate::SpdxExpression::simplify_with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimplifyOptions {
+    /// How to simplify operands of the same operator with the same license, where some of them
+    /// have an exception, like `GPL-2.0-only AND GPL-2.0-only WITH Classpath-exception-2.0`.
+    /// Defaults to [`ExceptionOverlap::Keep`].
+    pub exception_overlap: ExceptionOverlap,
+
+    /// How to simplify operands that are `NONE` or `NOASSERTION`. Defaults to
+    /// [`SpecialLicenses::Unknown`], which keeps them like other licenses.
+    /// [`SpecialLicenses::Error`] only has an effect with
+    /// [`SpdxExpression::try_simplify_with`].
+    ///
+    /// [`SpdxExpression::try_simplify_with`]: crate::SpdxExpression::try_simplify_with
+    pub special_licenses: SpecialLicenses,
+}
+
+/// Rule for simplifying operands of the same operator where a license appears both with and
+/// without an exception, see [`SimplifyOptions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExceptionOverlap {
+    /// Keep all operands.
+    #[default]
+    Keep,
+
+    /// Keep the operands whose terms apply. An exception grants additional permissions, so the
+    /// license without the exception is kept if both are required (`AND`), and the licenses
+    /// with exceptions are kept if they are alternatives (`OR`).
+    Effective,
+
+    /// Keep the licenses with exceptions, e.g. to keep the information about the exceptions in
+    /// reports.
+    PreferException,
+
+    /// Keep the license without an exception.
+    PreferLicense,
+}
+
+pub fn simplify(expression: &ExpressionVariant) -> ExpressionVariant {
+    simplify_with(expression, SimplifyOptions::default())
+}
+
+pub fn simplify_with(
+    expression: &ExpressionVariant,
+    mut options: SimplifyOptions,
+) -> ExpressionVariant {
+    if options.special_licenses == SpecialLicenses::Error {
+        options.special_licenses = SpecialLicenses::Unknown;
+    }
+    try_simplify_with(expression, options).expect("only erroring special licenses fail")
+}
+
+pub fn try_simplify_with(
+    expression: &ExpressionVariant,
+    options: SimplifyOptions,
+) -> Result<ExpressionVariant, BuildError> {
+    let Some((operator, operands)) = expression.flatten() else {
+        return Ok(expression.unparenthesized().clone());
+    };
+
+    let mut simplified: Vec<ExpressionVariant> = Vec::new();
+    for operand in operands {
+        let operand = try_simplify_with(operand, options)?;
+        // Simplifying an operand can leave an expression with the same operator, whose operands
+        // are then operands of this expression.
+        let nested = match operand.flatten() {
+            Some((nested_operator, nested)) if nested_operator == operator => {
+                nested.into_iter().cloned().collect()
+            }
+            _ => vec![operand],
+        };
+        for operand in nested {
+            if !simplified.contains(&operand) {
+                simplified.push(operand);
+            }
+        }
+    }
+
+    special::resolve(operator, &mut simplified, options.special_licenses)?;
+    remove_exception_overlaps(&mut simplified, operator, options.exception_overlap);
+
+    // Absorption: `A AND (A OR B)` is `A`, and `A OR (A AND B)` is `A`.
+    let is_absorbed = |operand: &ExpressionVariant| {
+        operand.flatten().is_some_and(|(_, nested)| {
+            simplified
+                .iter()
+                .any(|other| other != operand && nested.contains(&other))
+        })
+    };
+
+    Ok(simplified
+        .iter()
+        .filter(|operand| !is_absorbed(operand))
+        .cloned()
+        .reduce(|left, right| match operator {
+            Operator::And => ExpressionVariant::and(left, right),
+            Operator::Or => ExpressionVariant::or(left, right),
+        })
+        .expect("compound expressions have operands"))
+}
+
+/// Remove the operands of licenses that are both with and without an exception in `operands`
+/// according to `rule`.
+fn remove_exception_overlaps(
+    operands: &mut Vec<ExpressionVariant>,
+    operator: Operator,
+    rule: ExceptionOverlap,
+) {
+    let keep_exceptions = match (rule, operator) {
+        (ExceptionOverlap::Keep, _) => return,
+        (ExceptionOverlap::Effective, Operator::And) | (ExceptionOverlap::PreferLicense, _) => {
+            false
+        }
+        (ExceptionOverlap::Effective, Operator::Or) | (ExceptionOverlap::PreferException, _) => {
+            true
+        }
+    };
+
+    let with_exception = operands
+        .iter()
+        .filter_map(|operand| match operand {
+            ExpressionVariant::With(with) => Some(with.license.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let without_exception = operands
+        .iter()
+        .filter_map(|operand| match operand {
+            ExpressionVariant::Simple(license) => Some(license.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    operands.retain(|operand| match operand {
+        ExpressionVariant::Simple(license) => !keep_exceptions || !with_exception.contains(license),
+        ExpressionVariant::With(with) => {
+            keep_exceptions || !without_exception.contains(&with.license)
+        }
+        _ => true,
+    });
+}
+
+/// Remove parentheses that don't change the meaning of the expression, i.e. parentheses around
+/// licenses, around the whole expression, around operands with a higher precedence than their
+/// operator and around operands with the same operator. Only the operands are regrouped, so
+/// their order and any duplicates are kept.
+pub fn remove_redundant_parentheses(expression: &ExpressionVariant) -> ExpressionVariant {
+    let Some((operator, operands)) = expression.flatten() else {
+        return expression.unparenthesized().clone();
+    };
+
+    operands
+        .into_iter()
+        .map(remove_redundant_parentheses)
+        .reduce(|left, right| match operator {
+            Operator::And => ExpressionVariant::and(left, right),
+            Operator::Or => ExpressionVariant::or(left, right),
+        })
+        .expect("compound expressions have operands")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simplify_str(expression: &str) -> String {
+        simplify(&ExpressionVariant::parse(expression).unwrap()).to_string()
+    }
+
+    #[test]
+    fn remove_redundant_parentheses_and_duplicates() {
+        assert_eq!(simplify_str("((MIT))"), "MIT");
+        assert_eq!(simplify_str("MIT AND (ISC AND MIT)"), "MIT AND ISC");
+        assert_eq!(simplify_str("(MIT OR MIT) AND ISC"), "MIT AND ISC");
+        assert_eq!(simplify_str("MIT OR (MIT)"), "MIT");
+        assert_eq!(
+            simplify_str("(MIT OR ISC) AND (ISC OR MIT)"),
+            "(MIT OR ISC) AND (ISC OR MIT)"
+        );
+    }
+
+    #[test]
+    fn simplify_special_licenses() {
+        let simplify = |expression: &str, special_licenses| {
+            let options = SimplifyOptions {
+                special_licenses,
+                ..SimplifyOptions::default()
+            };
+            try_simplify_with(&ExpressionVariant::parse(expression).unwrap(), options)
+                .map(|simplified| simplified.to_string())
+        };
+
+        let expression = "(MIT OR NONE) AND NOASSERTION AND ISC";
+        assert_eq!(
+            simplify(expression, SpecialLicenses::Unknown),
+            Ok(expression.to_string())
+        );
+        assert_eq!(
+            simplify(expression, SpecialLicenses::Satisfied),
+            Ok("ISC".to_string())
+        );
+        assert_eq!(
+            simplify("NONE AND NOASSERTION", SpecialLicenses::Satisfied),
+            Ok("NONE".to_string())
+        );
+        assert_eq!(
+            simplify(expression, SpecialLicenses::Error),
+            Err(BuildError::SpecialLicense("NONE".to_string()))
+        );
+        assert_eq!(
+            simplify("NOASSERTION", SpecialLicenses::Error),
+            Ok("NOASSERTION".to_string())
+        );
+    }
+
+    #[test]
+    fn absorb_operands() {
+        assert_eq!(simplify_str("(MIT OR ISC) AND MIT"), "MIT");
+        assert_eq!(simplify_str("MIT OR MIT AND ISC"), "MIT");
+        assert_eq!(
+            simplify_str("0BSD AND ((MIT OR ISC) AND MIT OR Apache-2.0)"),
+            "0BSD AND (MIT OR Apache-2.0)"
+        );
+    }
+
+    #[test]
+    fn remove_only_redundant_parentheses() {
+        let remove = |expression: &str| {
+            remove_redundant_parentheses(&ExpressionVariant::parse(expression).unwrap()).to_string()
+        };
+
+        assert_eq!(remove("((MIT)) AND ISC"), "MIT AND ISC");
+        assert_eq!(remove("((MIT AND ISC))"), "MIT AND ISC");
+        assert_eq!(remove("MIT AND (ISC AND (MIT))"), "MIT AND ISC AND MIT");
+        assert_eq!(remove("MIT OR (ISC AND 0BSD)"), "MIT OR ISC AND 0BSD");
+        assert_eq!(remove("(MIT OR ISC) AND (0BSD)"), "(MIT OR ISC) AND 0BSD");
+        assert_eq!(
+            remove("(MIT WITH exc) AND ((ISC OR (0BSD)))"),
+            "MIT WITH exc AND (ISC OR 0BSD)"
+        );
+    }
+
+    #[test]
+    fn simplify_exception_overlaps() {
+        let simplify_with_rule = |expression: &str, rule| {
+            let options = SimplifyOptions {
+                exception_overlap: rule,
+                ..SimplifyOptions::default()
+            };
+            simplify_with(&ExpressionVariant::parse(expression).unwrap(), options).to_string()
+        };
+        let and = "GPL-2.0-only AND MIT AND GPL-2.0-only WITH Classpath-exception-2.0";
+        let or = "GPL-2.0-only WITH Classpath-exception-2.0 OR GPL-2.0-only OR MIT WITH exc";
+
+        assert_eq!(simplify_with_rule(and, ExceptionOverlap::Keep), and);
+        assert_eq!(
+            simplify_with_rule(and, ExceptionOverlap::Effective),
+            "GPL-2.0-only AND MIT"
+        );
+        assert_eq!(
+            simplify_with_rule(or, ExceptionOverlap::Effective),
+            "GPL-2.0-only WITH Classpath-exception-2.0 OR MIT WITH exc"
+        );
+        assert_eq!(
+            simplify_with_rule(and, ExceptionOverlap::PreferException),
+            "MIT AND GPL-2.0-only WITH Classpath-exception-2.0"
+        );
+        assert_eq!(
+            simplify_with_rule(or, ExceptionOverlap::PreferLicense),
+            "GPL-2.0-only OR MIT WITH exc"
+        );
+    }
+
+    #[test]
+    fn keep_structure_when_displayed() {
+        for input in &[
+            "MIT AND (ISC OR 0BSD) AND (ISC OR 0BSD)",
+            "(MIT OR ISC) AND (Apache-2.0 OR MIT) OR 0BSD",
+            "MIT WITH exc OR MIT",
+        ] {
+            let simplified = simplify(&ExpressionVariant::parse(input).unwrap());
+            assert_eq!(
+                ExpressionVariant::parse(&simplified.to_string()).unwrap(),
+                simplified,
+                "{input}"
+            );
+        }
+    }
+}
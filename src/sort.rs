@@ -0,0 +1,92 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Deterministic ordering of operands.
+
+use crate::{expression_variant::ExpressionVariant, parser::Operator};
+
+/// Sort the operands of every operator in `expression`. Returns `None` if the operands are
+/// already sorted.
+pub fn sort_operands(expression: &ExpressionVariant) -> Option<ExpressionVariant> {
+    let (operator, operands) = expression.flatten()?;
+
+    let mut changed = false;
+    let mut sorted = operands
+        .into_iter()
+        .map(|operand| {
+            sort_operands(operand).map_or_else(
+                || operand.clone(),
+                |sorted| {
+                    changed = true;
+                    sorted
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let keys = sorted.iter().map(sort_key).collect::<Vec<_>>();
+    if !changed && keys.windows(2).all(|pair| pair[0] <= pair[1]) {
+        return None;
+    }
+
+    sorted.sort_by_cached_key(sort_key);
+    sorted.into_iter().reduce(|left, right| match operator {
+        Operator::And => ExpressionVariant::and(left, right),
+        Operator::Or => ExpressionVariant::or(left, right),
+    })
+}
+
+/// Key ordering licenses on the SPDX License List first, then `LicenseRef`s, then `LicenseRef`s
+/// of other documents and last compound expressions. Operands in the same group are ordered
+/// alphabetically ignoring case, and by case if they only differ in case.
+fn sort_key(operand: &ExpressionVariant) -> (u8, String, String) {
+    let operand = operand.unparenthesized();
+    let group = match operand.license() {
+        Some(license) if license.document_ref.is_some() => 2,
+        Some(license) if license.license_ref => 1,
+        Some(_) => 0,
+        None => 3,
+    };
+    let text = operand.to_string();
+
+    (group, text.to_lowercase(), text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(expression: &str) -> String {
+        let expression = ExpressionVariant::parse(expression).unwrap();
+        sort_operands(&expression).unwrap_or(expression).to_string()
+    }
+
+    #[test]
+    fn sort_by_group_and_name() {
+        assert_eq!(
+            sort("LicenseRef-b OR DocumentRef-x:LicenseRef-a OR mit OR (ISC AND 0BSD) OR Apache-2.0 OR LicenseRef-A"),
+            "Apache-2.0 OR mit OR LicenseRef-A OR LicenseRef-b OR DocumentRef-x:LicenseRef-a OR 0BSD AND ISC"
+        );
+        assert_eq!(
+            sort("Zlib WITH exc AND (MIT OR ISC) AND (Apache-2.0 OR 0BSD)"),
+            "Zlib WITH exc AND (0BSD OR Apache-2.0) AND (ISC OR MIT)"
+        );
+    }
+
+    #[test]
+    fn keep_sorted_expressions() {
+        let expression = ExpressionVariant::parse("ISC AND (MIT OR Zlib)").unwrap();
+        assert_eq!(sort_operands(&expression), None);
+        assert_eq!(
+            sort_operands(&ExpressionVariant::parse("MIT").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn sorting_is_idempotent() {
+        let once = sort("(MIT OR ISC) AND Zlib AND (0BSD AND Apache-2.0 OR MIT)");
+        assert_eq!(sort(&once), once);
+    }
+}
@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Locations of the nodes of expressions in their source text.
+
+use std::ops::Range;
+
+use crate::expression_variant::ExpressionVariant;
+
+/// Location of a node of an expression in the text it was parsed from, see
+/// [`SpdxExpression::spans`].
+///
+/// The children are in the same order as the children of the node, i.e. the left and right
+/// operands of `AND` and `OR` and the expression in parentheses, so they can be looked up with
+/// the same paths as the nodes.
+///
+/// [`SpdxExpression::spans`]: crate::SpdxExpression::spans
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeSpan {
+    /// Byte range of the node, without surrounding whitespace.
+    pub range: Range<usize>,
+
+    /// Byte range of the `AND`, `OR` or `WITH` operator of the node.
+    pub operator: Option<Range<usize>>,
+
+    pub children: Vec<Self>,
+}
+
+impl NodeSpan {
+    /// Get the span of the node at `path`, a sequence of child indices from `self`.
+    pub fn get(&self, path: &[usize]) -> Option<&Self> {
+        path.iter()
+            .try_fold(self, |span, &index| span.children.get(index))
+    }
+}
+
+/// Locate the nodes of `expression` in `source`, which needs to be the text `expression` was
+/// parsed from. Returns `None` if the nodes can't be found in `source`.
+pub fn locate(expression: &ExpressionVariant, source: &str) -> Option<NodeSpan> {
+    let mut locator = Locator {
+        source,
+        position: 0,
+    };
+    let span = locator.node(expression)?;
+    locator.skip_whitespace();
+    (locator.position == source.len()).then_some(span)
+}
+
+/// Walks the source text alongside the parsed expression. The source of a parsed expression
+/// only differs from its displayed form in whitespace and the case of the operators, so every
+/// node can be found by matching its displayed parts in order.
+struct Locator<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl Locator<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Match `text` after whitespace, ignoring case if `ignore_case` is `true`.
+    fn expect(&mut self, text: &str, ignore_case: bool) -> Option<Range<usize>> {
+        self.skip_whitespace();
+        let start = self.position;
+        let candidate = self.source.get(start..start + text.len())?;
+        let matches = if ignore_case {
+            candidate.eq_ignore_ascii_case(text)
+        } else {
+            candidate == text
+        };
+        if !matches {
+            return None;
+        }
+        self.position += text.len();
+        Some(start..self.position)
+    }
+
+    fn node(&mut self, node: &ExpressionVariant) -> Option<NodeSpan> {
+        match node {
+            ExpressionVariant::Simple(license) => Some(NodeSpan {
+                range: self.expect(&license.to_string(), false)?,
+                operator: None,
+                children: Vec::new(),
+            }),
+            ExpressionVariant::With(with) => {
+                let license = self.expect(&with.license.to_string(), false)?;
+                let operator = self.expect("WITH", true)?;
+                let exception = self.expect(&with.exception, false)?;
+                Some(NodeSpan {
+                    range: license.start..exception.end,
+                    operator: Some(operator),
+                    children: Vec::new(),
+                })
+            }
+            ExpressionVariant::And(left, right) | ExpressionVariant::Or(left, right) => {
+                let left = self.node(left)?;
+                let operator = if matches!(node, ExpressionVariant::And(..)) {
+                    "AND"
+                } else {
+                    "OR"
+                };
+                let operator = self.expect(operator, true)?;
+                let right = self.node(right)?;
+                Some(NodeSpan {
+                    range: left.range.start..right.range.end,
+                    operator: Some(operator),
+                    children: vec![left, right],
+                })
+            }
+            ExpressionVariant::Parens(inner) => {
+                let open = self.expect("(", false)?;
+                let inner = self.node(inner)?;
+                let close = self.expect(")", false)?;
+                Some(NodeSpan {
+                    range: open.start..close.end,
+                    operator: None,
+                    children: vec![inner],
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_nodes_in_source() {
+        let source = " ( mit  and(GPL-2.0+ with exc) )OR LicenseRef-a";
+        let expression = ExpressionVariant::parse(source).unwrap();
+        let span = locate(&expression, source).unwrap();
+
+        assert_eq!(&source[span.range.clone()], source.trim());
+        assert_eq!(&source[span.operator.clone().unwrap()], "OR");
+
+        let parens = &span.children[0];
+        assert_eq!(
+            &source[parens.range.clone()],
+            "( mit  and(GPL-2.0+ with exc) )"
+        );
+        let and = &parens.children[0];
+        assert_eq!(&source[and.operator.clone().unwrap()], "and");
+        assert_eq!(&source[and.children[0].range.clone()], "mit");
+
+        let with = &and.children[1].children[0];
+        assert_eq!(&source[with.range.clone()], "GPL-2.0+ with exc");
+        assert_eq!(&source[with.operator.clone().unwrap()], "with");
+        assert_eq!(&source[span.children[1].range.clone()], "LicenseRef-a");
+    }
+
+    #[test]
+    fn get_spans_by_path() {
+        let source = "MIT AND (ISC OR 0BSD)";
+        let span = locate(&ExpressionVariant::parse(source).unwrap(), source).unwrap();
+        assert_eq!(
+            span.get(&[1, 0, 1]).map(|span| span.range.clone()),
+            Some(16..20)
+        );
+        assert_eq!(span.get(&[]), Some(&span));
+        assert_eq!(span.get(&[0, 0]), None);
+    }
+
+    #[test]
+    fn other_source_is_not_located() {
+        let expression = ExpressionVariant::parse("MIT OR ISC").unwrap();
+        assert_eq!(locate(&expression, "MIT OR ISC AND 0BSD"), None);
+        assert_eq!(locate(&expression, "MIT AND ISC"), None);
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Conversions between [`SpdxExpression`] and [`spdx::Expression`].
+
+use std::convert::TryFrom;
+
+use spdx::expression::{ExprNode, Operator};
+
+use crate::{
+    error::SpdxExpressionError, expression::SpdxExpression, expression_variant::ExpressionVariant,
+};
+
+/// Convert an expression of the `spdx` crate. Fails if a license requirement can't be represented
+/// in this crate, e.g. for `AdditionRef` exceptions with a `DocumentRef`.
+impl TryFrom<&spdx::Expression> for SpdxExpression {
+    type Error = SpdxExpressionError;
+
+    fn try_from(expression: &spdx::Expression) -> Result<Self, Self::Error> {
+        let mut stack: Vec<ExpressionVariant> = Vec::new();
+
+        for node in expression.iter() {
+            match node {
+                ExprNode::Req(requirement) => {
+                    stack.push(ExpressionVariant::parse(&requirement.req.to_string())?);
+                }
+                ExprNode::Op(operator) => {
+                    let (Some(right), Some(left)) = (stack.pop(), stack.pop()) else {
+                        return Err(SpdxExpressionError::Conversion(format!(
+                            "missing operand in `{expression}`"
+                        )));
+                    };
+
+                    stack.push(match operator {
+                        Operator::And => ExpressionVariant::and(left, right),
+                        Operator::Or => ExpressionVariant::or(left, right),
+                    });
+                }
+            }
+        }
+
+        match (stack.pop(), stack.is_empty()) {
+            (Some(inner), true) => Ok(Self::from_inner(inner)),
+            _ => Err(SpdxExpressionError::Conversion(format!(
+                "`{expression}` doesn't consist of a single expression"
+            ))),
+        }
+    }
+}
+
+/// Convert to an expression of the `spdx` crate. The conversion uses the strict parsing mode of
+/// the `spdx` crate, so it fails for identifiers that are not on the SPDX license list and are
+/// not `LicenseRef`s.
+impl TryFrom<&SpdxExpression> for spdx::Expression {
+    type Error = SpdxExpressionError;
+
+    fn try_from(expression: &SpdxExpression) -> Result<Self, Self::Error> {
+        Self::parse(&expression.to_string())
+            .map_err(|err| SpdxExpressionError::Conversion(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_from_spdx_expression() {
+        let expression = spdx::Expression::parse(
+            "(MIT OR Apache-2.0) AND GPL-2.0-or-later WITH Classpath-exception-2.0 OR LicenseRef-a",
+        )
+        .unwrap();
+
+        let converted = SpdxExpression::try_from(&expression).unwrap();
+
+        assert_eq!(
+            converted.to_string(),
+            "(MIT OR Apache-2.0) AND GPL-2.0-or-later WITH Classpath-exception-2.0 OR LicenseRef-a"
+        );
+    }
+
+    #[test]
+    fn keep_grouping_of_right_operands() {
+        for input in [
+            "MIT OR (ISC OR 0BSD)",
+            "MIT AND (ISC AND 0BSD)",
+            "MIT AND (ISC OR 0BSD)",
+            "(MIT OR ISC) AND 0BSD",
+        ] {
+            let converted =
+                SpdxExpression::try_from(&spdx::Expression::parse(input).unwrap()).unwrap();
+
+            assert_eq!(
+                SpdxExpression::parse(&converted.to_string()).unwrap(),
+                converted
+            );
+            assert_eq!(converted.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn convert_to_spdx_expression() {
+        let expression =
+            SpdxExpression::parse("MIT AND (ISC OR DocumentRef-doc:LicenseRef-a)").unwrap();
+
+        let converted = spdx::Expression::try_from(&expression).unwrap();
+
+        assert_eq!(
+            converted,
+            spdx::Expression::parse("MIT AND (ISC OR DocumentRef-doc:LicenseRef-a)").unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trip_through_spdx_expression() {
+        let expression =
+            SpdxExpression::parse("(MIT OR ISC) AND (Apache-2.0 OR BSD-3-Clause)").unwrap();
+
+        let converted =
+            SpdxExpression::try_from(&spdx::Expression::try_from(&expression).unwrap()).unwrap();
+
+        assert_eq!(converted, expression);
+    }
+
+    #[test]
+    fn fail_to_convert_unknown_license_to_spdx_expression() {
+        let expression = SpdxExpression::parse("MIT OR NotALicense").unwrap();
+
+        assert!(spdx::Expression::try_from(&expression).is_err());
+    }
+}
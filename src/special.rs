@@ -0,0 +1,198 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Semantics of `NONE` and `NOASSERTION` in logical operations.
+
+use crate::{
+    error::BuildError,
+    expression_variant::{ExpressionVariant, SimpleExpression},
+    parser::Operator,
+};
+
+/// How `NONE` and `NOASSERTION` behave when expressions containing them are evaluated,
+/// simplified or aggregated.
+///
+/// The SPDX specification doesn't define their meaning in compound expressions, and compliance
+/// policies disagree on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SpecialLicenses {
+    /// The licenses are unknown. Evaluation follows three-valued logic, so `MIT OR NOASSERTION`
+    /// is satisfied by choosing `MIT`, but `MIT AND NOASSERTION` is unknown. Simplification and
+    /// aggregation keep the licenses like any other license.
+    #[default]
+    Unknown,
+
+    /// The licenses are always satisfied, i.e. they don't restrict the use of the software.
+    /// `MIT AND NOASSERTION` simplifies to `MIT` and `MIT OR NOASSERTION` to `NOASSERTION`.
+    Satisfied,
+
+    /// Combining or evaluating the licenses is an error.
+    Error,
+}
+
+/// Check if `license` is `NONE` or `NOASSERTION`.
+pub fn is_special(license: &SimpleExpression) -> bool {
+    !license.license_ref
+        && license.document_ref.is_none()
+        && matches!(license.identifier.as_str(), "NONE" | "NOASSERTION")
+}
+
+/// Get the special license `node` consists of, if any.
+fn special_license(node: &ExpressionVariant) -> Option<&SimpleExpression> {
+    match node.unparenthesized() {
+        ExpressionVariant::Simple(license) if is_special(license) => Some(license),
+        ExpressionVariant::With(with) if is_special(&with.license) => Some(&with.license),
+        _ => None,
+    }
+}
+
+/// Evaluate `expression` with the licenses for which `is_chosen` returns `true` chosen. Returns
+/// `None` if the result is unknown.
+pub fn evaluate<F>(
+    expression: &ExpressionVariant,
+    is_chosen: &mut F,
+    semantics: SpecialLicenses,
+) -> Result<Option<bool>, BuildError>
+where
+    F: FnMut(&SimpleExpression) -> bool,
+{
+    if let Some(license) = special_license(expression) {
+        return match semantics {
+            SpecialLicenses::Unknown => Ok(None),
+            SpecialLicenses::Satisfied => Ok(Some(true)),
+            SpecialLicenses::Error => Err(BuildError::SpecialLicense(license.to_string())),
+        };
+    }
+
+    match expression {
+        ExpressionVariant::Simple(license) => Ok(Some(is_chosen(license))),
+        ExpressionVariant::With(with) => Ok(Some(is_chosen(&with.license))),
+        ExpressionVariant::And(left, right) => {
+            let left = evaluate(left, is_chosen, semantics)?;
+            let right = evaluate(right, is_chosen, semantics)?;
+            Ok(match (left, right) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            })
+        }
+        ExpressionVariant::Or(left, right) => {
+            let left = evaluate(left, is_chosen, semantics)?;
+            let right = evaluate(right, is_chosen, semantics)?;
+            Ok(match (left, right) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            })
+        }
+        ExpressionVariant::Parens(inner) => evaluate(inner, is_chosen, semantics),
+    }
+}
+
+/// Apply `semantics` to `operands` of `operator`. With [`SpecialLicenses::Satisfied`], special
+/// licenses are dropped from the operands of `AND` unless all operands are special, and an `OR`
+/// with a special operand is reduced to that operand.
+pub fn resolve(
+    operator: Operator,
+    operands: &mut Vec<ExpressionVariant>,
+    semantics: SpecialLicenses,
+) -> Result<(), BuildError> {
+    if operands.len() < 2 {
+        return Ok(());
+    }
+    let Some(position) = operands
+        .iter()
+        .position(|operand| special_license(operand).is_some())
+    else {
+        return Ok(());
+    };
+
+    match (semantics, operator) {
+        (SpecialLicenses::Unknown, _) => {}
+        (SpecialLicenses::Satisfied, Operator::And) => {
+            if operands
+                .iter()
+                .all(|operand| special_license(operand).is_some())
+            {
+                operands.truncate(1);
+            } else {
+                operands.retain(|operand| special_license(operand).is_none());
+            }
+        }
+        (SpecialLicenses::Satisfied, Operator::Or) => {
+            let special = operands.swap_remove(position);
+            *operands = vec![special];
+        }
+        (SpecialLicenses::Error, _) => {
+            let license = special_license(&operands[position]).map(ToString::to_string);
+            return Err(BuildError::SpecialLicense(license.unwrap_or_default()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate_str(
+        expression: &str,
+        chosen: &[&str],
+        semantics: SpecialLicenses,
+    ) -> Result<Option<bool>, BuildError> {
+        evaluate(
+            &ExpressionVariant::parse(expression).unwrap(),
+            &mut |license| chosen.contains(&license.identifier.as_str()),
+            semantics,
+        )
+    }
+
+    #[test]
+    fn evaluate_unknown_licenses_with_three_valued_logic() {
+        let unknown = SpecialLicenses::Unknown;
+        assert_eq!(
+            evaluate_str("MIT OR NOASSERTION", &["MIT"], unknown),
+            Ok(Some(true))
+        );
+        assert_eq!(evaluate_str("MIT OR NOASSERTION", &[], unknown), Ok(None));
+        assert_eq!(evaluate_str("MIT AND NONE", &["MIT"], unknown), Ok(None));
+        assert_eq!(evaluate_str("MIT AND NONE", &[], unknown), Ok(Some(false)));
+        assert_eq!(evaluate_str("(MIT)", &["MIT"], unknown), Ok(Some(true)));
+    }
+
+    #[test]
+    fn evaluate_satisfied_licenses() {
+        let satisfied = SpecialLicenses::Satisfied;
+        assert_eq!(
+            evaluate_str("MIT OR NOASSERTION", &[], satisfied),
+            Ok(Some(true))
+        );
+        assert_eq!(
+            evaluate_str("MIT AND NONE", &["MIT"], satisfied),
+            Ok(Some(true))
+        );
+        assert_eq!(
+            evaluate_str("MIT AND NONE", &[], satisfied),
+            Ok(Some(false))
+        );
+    }
+
+    #[test]
+    fn evaluating_special_licenses_can_fail() {
+        assert_eq!(
+            evaluate_str("MIT OR NOASSERTION", &["MIT"], SpecialLicenses::Error),
+            Err(BuildError::SpecialLicense("NOASSERTION".to_string()))
+        );
+        assert_eq!(
+            evaluate_str("MIT OR ISC", &["MIT"], SpecialLicenses::Error),
+            Ok(Some(true))
+        );
+    }
+
+    #[test]
+    fn license_refs_are_not_special() {
+        let expression = ExpressionVariant::parse("LicenseRef-NONE").unwrap();
+        assert_eq!(special_license(&expression), None);
+    }
+}
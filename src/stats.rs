@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Statistics of collections of expressions.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{expression::SpdxExpression, parser::Operator};
+
+/// Statistics of a collection of expressions, e.g. for reporting the quality of the license
+/// information of an SBOM.
+///
+/// Licenses and exceptions are counted once per expression they appear in. Operators are counted
+/// like [`SpdxExpression::operators`], so operands of the same operator are counted as a single
+/// operator. The complexity of an expression is the number of licenses in it, counting licenses
+/// that appear more than once every time.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::Stats;
+/// #
+/// let stats = Stats::from_strs(["MIT", "MIT AND (ISC OR Apache-2.0)", "MIT OR", "ISC"]);
+///
+/// assert_eq!(stats.expressions, 3);
+/// assert_eq!(stats.unparseable, 1);
+/// assert_eq!(stats.licenses["MIT"], 2);
+/// assert_eq!(stats.or_operators, 1);
+/// assert_eq!(stats.complexity[&1], 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of expressions added.
+    pub expressions: usize,
+
+    /// Number of inputs that failed to parse.
+    pub unparseable: usize,
+
+    /// Number of expressions with each license.
+    pub licenses: BTreeMap<String, usize>,
+
+    /// Number of expressions with each exception.
+    pub exceptions: BTreeMap<String, usize>,
+
+    /// Number of `AND` operators.
+    pub and_operators: usize,
+
+    /// Number of `OR` operators.
+    pub or_operators: usize,
+
+    /// Number of `WITH` operators.
+    pub with_operators: usize,
+
+    /// Number of expressions with each number of licenses.
+    pub complexity: BTreeMap<usize, usize>,
+}
+
+impl Stats {
+    /// Collect statistics of `expressions`, counting the ones that fail to parse as
+    /// unparseable.
+    pub fn from_strs<I, S>(expressions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut stats = Self::default();
+        for expression in expressions {
+            stats.add_str(expression.as_ref());
+        }
+        stats
+    }
+
+    /// Add an expression to the statistics.
+    pub fn add(&mut self, expression: &SpdxExpression) {
+        self.expressions += 1;
+
+        let mut licenses = BTreeSet::new();
+        let mut exceptions = BTreeSet::new();
+        let mut complexity = 0;
+        for (license, exception) in expression.leaves() {
+            licenses.insert(license.to_string());
+            if let Some(exception) = exception {
+                exceptions.insert(exception);
+                self.with_operators += 1;
+            }
+            complexity += 1;
+        }
+        for license in licenses {
+            *self.licenses.entry(license).or_default() += 1;
+        }
+        for exception in exceptions {
+            *self.exceptions.entry(exception.to_string()).or_default() += 1;
+        }
+        *self.complexity.entry(complexity).or_default() += 1;
+
+        for (operator, _) in expression.operators() {
+            match operator {
+                Operator::And => self.and_operators += 1,
+                Operator::Or => self.or_operators += 1,
+            }
+        }
+    }
+
+    /// Parse `expression` and add it to the statistics, or count it as unparseable if it fails
+    /// to parse.
+    pub fn add_str(&mut self, expression: &str) {
+        match SpdxExpression::parse(expression) {
+            Ok(expression) => self.add(&expression),
+            Err(_) => self.unparseable += 1,
+        }
+    }
+}
+
+impl<'a> Extend<&'a SpdxExpression> for Stats {
+    fn extend<I: IntoIterator<Item = &'a SpdxExpression>>(&mut self, expressions: I) {
+        for expression in expressions {
+            self.add(expression);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_statistics() {
+        let stats = Stats::from_strs([
+            "MIT AND MIT AND (ISC OR GPL-2.0-only WITH exc)",
+            "(MIT)",
+            "GPL-2.0-only WITH exc OR GPL-2.0-only WITH exc",
+            "MIT AND",
+        ]);
+
+        assert_eq!(
+            stats,
+            Stats {
+                expressions: 3,
+                unparseable: 1,
+                licenses: BTreeMap::from([
+                    ("GPL-2.0-only".to_string(), 2),
+                    ("ISC".to_string(), 1),
+                    ("MIT".to_string(), 2),
+                ]),
+                exceptions: BTreeMap::from([("exc".to_string(), 2)]),
+                and_operators: 1,
+                or_operators: 2,
+                with_operators: 3,
+                complexity: BTreeMap::from([(1, 1), (2, 1), (4, 1)]),
+            }
+        );
+    }
+
+    #[test]
+    fn extend_with_parsed_expressions() {
+        let expressions = [
+            SpdxExpression::parse("MIT").unwrap(),
+            SpdxExpression::parse("ISC").unwrap(),
+        ];
+        let mut stats = Stats::default();
+        stats.extend(&expressions);
+
+        assert_eq!(stats.expressions, 2);
+        assert_eq!(stats.complexity, BTreeMap::from([(1, 2)]));
+    }
+}
@@ -0,0 +1,112 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! [`proptest`] strategies for generating expressions.
+//!
+//! The generated expressions are structured like the parser would structure them, so that
+//! displaying and parsing a generated expression results in an equal expression.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use spdx_expression::{strategies, SpdxExpression};
+//!
+//! proptest!(|(expression in strategies::expression(3))| {
+//!     prop_assert_eq!(SpdxExpression::parse(&expression.to_string()).unwrap(), expression);
+//! });
+//! ```
+
+use std::sync::Arc;
+
+use proptest::{prelude::*, strategy::BoxedStrategy};
+
+use crate::{
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+};
+
+/// Words that can't be used as identifiers without changing the meaning of an expression.
+const RESERVED: [&str; 3] = ["AND", "OR", "WITH"];
+
+/// Strategy for an `idstring` of the SPDX specification, excluding operator keywords.
+fn idstring() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9][A-Za-z0-9.-]{0,15}".prop_filter("identifier is an operator", |id| {
+        !RESERVED
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(id))
+    })
+}
+
+/// Strategy for simple expressions: SPDX license identifiers with optional `+`, `LicenseRef`s
+/// and `LicenseRef`s with a `DocumentRef`.
+pub fn simple_expression() -> BoxedStrategy<SimpleExpression> {
+    prop_oneof![
+        (idstring(), any::<bool>())
+            .prop_filter("identifier is a reference", |(id, _)| {
+                !id.starts_with("LicenseRef-") && !id.starts_with("DocumentRef-")
+            })
+            .prop_map(
+                |(id, or_later)| SimpleExpression::new(id, None, false).with_or_later(or_later)
+            ),
+        (idstring(), proptest::option::of(idstring()))
+            .prop_map(|(id, document_ref)| SimpleExpression::new(id, document_ref, true)),
+    ]
+    .boxed()
+}
+
+fn with_variant() -> impl Strategy<Value = ExpressionVariant> {
+    (simple_expression(), idstring()).prop_map(|(license, exception)| {
+        ExpressionVariant::With(Box::new(WithExpression::new(license, exception)))
+    })
+}
+
+fn leaf_variant() -> impl Strategy<Value = ExpressionVariant> {
+    prop_oneof![
+        3 => simple_expression().prop_map(ExpressionVariant::Simple),
+        1 => with_variant(),
+    ]
+}
+
+/// Strategy for expressions consisting of a single license with an exception.
+pub fn with_expression() -> BoxedStrategy<SpdxExpression> {
+    with_variant().prop_map(SpdxExpression::from_inner).boxed()
+}
+
+/// Strategy for compound expressions nested at most `depth` levels deep. A depth of zero only
+/// generates simple expressions and expressions with exceptions.
+pub fn expression(depth: u32) -> BoxedStrategy<SpdxExpression> {
+    leaf_variant()
+        .prop_recursive(depth, 64, 2, |inner| {
+            prop_oneof![
+                (inner.clone(), inner.clone())
+                    .prop_map(|(left, right)| ExpressionVariant::and(left, right)),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(left, right)| ExpressionVariant::or(left, right)),
+                inner.prop_map(|expression| ExpressionVariant::Parens(Arc::new(expression))),
+            ]
+        })
+        .prop_map(SpdxExpression::from_inner)
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn simple_expressions_round_trip(license in simple_expression()) {
+            prop_assert_eq!(SimpleExpression::parse(&license.to_string()).unwrap(), license);
+        }
+
+        #[test]
+        fn with_expressions_round_trip(expression in with_expression()) {
+            prop_assert_eq!(SpdxExpression::parse(&expression.to_string()).unwrap(), expression);
+        }
+
+        #[test]
+        fn expressions_round_trip(expression in expression(4)) {
+            prop_assert_eq!(SpdxExpression::parse(&expression.to_string()).unwrap(), expression);
+        }
+    }
+}
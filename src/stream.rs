@@ -0,0 +1,112 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Lazy parsing of multiple expressions from a reader.
+
+use std::{collections::VecDeque, io::BufRead};
+
+use crate::{error::SpdxExpressionError, expression::SpdxExpression};
+
+/// Iterator over expressions read from a [`BufRead`]. Created with
+/// [`SpdxExpression::parse_many`].
+///
+/// Expressions are separated by newlines or commas. Empty entries are skipped. The reader is
+/// consumed one line at a time, so the whole input is never buffered.
+#[derive(Debug)]
+pub struct ParseMany<R> {
+    reader: R,
+    line: String,
+    pending: VecDeque<String>,
+    done: bool,
+}
+
+impl<R: BufRead> ParseMany<R> {
+    pub(crate) const fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Read lines until at least one non-empty entry is pending or the reader is exhausted.
+    fn fill(&mut self) -> Result<(), std::io::Error> {
+        while self.pending.is_empty() && !self.done {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                self.done = true;
+            } else {
+                self.pending.extend(
+                    self.line
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|entry| !entry.is_empty())
+                        .map(ToString::to_string),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for ParseMany<R> {
+    type Item = Result<SpdxExpression, SpdxExpressionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(err) = self.fill() {
+            self.done = true;
+            return Some(Err(err.into()));
+        }
+
+        self.pending
+            .pop_front()
+            .map(|entry| SpdxExpression::parse(&entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn parse_newline_and_comma_separated_expressions() {
+        let input =
+            "MIT OR Apache-2.0\nISC, GPL-2.0-only WITH Classpath-exception-2.0\n\n ,BSD-3-Clause";
+
+        let expressions = SpdxExpression::parse_many(Cursor::new(input))
+            .map(|result| result.unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            expressions,
+            [
+                "MIT OR Apache-2.0",
+                "ISC",
+                "GPL-2.0-only WITH Classpath-exception-2.0",
+                "BSD-3-Clause"
+            ]
+        );
+    }
+
+    #[test]
+    fn yield_errors_for_invalid_entries_and_continue() {
+        let input = "MIT\n((\nISC\n";
+
+        let results = SpdxExpression::parse_many(Cursor::new(input)).collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn empty_input_yields_nothing() {
+        assert_eq!(SpdxExpression::parse_many(Cursor::new("")).count(), 0);
+    }
+}
@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Classification of the structure of expressions.
+
+use crate::{expression_variant::ExpressionVariant, parser::Operator};
+
+/// Overall structure of an expression, see [`SpdxExpression::structure_kind`].
+///
+/// A term is a license with an optional exception. Parentheses and the grouping of operands of
+/// the same operator don't affect the structure.
+///
+/// [`SpdxExpression::structure_kind`]: crate::SpdxExpression::structure_kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructureKind {
+    /// A single term, e.g. `MIT` or `GPL-2.0-only WITH Classpath-exception-2.0`.
+    SingleTerm,
+
+    /// A choice between terms, e.g. `MIT OR Apache-2.0`.
+    Choice,
+
+    /// Terms that are all required, e.g. `MIT AND Apache-2.0`.
+    Conjunction,
+
+    /// Both `AND` and `OR`, e.g. `MIT AND (ISC OR Apache-2.0)`.
+    Mixed,
+}
+
+pub fn structure_kind(expression: &ExpressionVariant) -> StructureKind {
+    let Some((operator, operands)) = expression.flatten() else {
+        return StructureKind::SingleTerm;
+    };
+
+    if operands.iter().any(|operand| operand.flatten().is_some()) {
+        StructureKind::Mixed
+    } else {
+        match operator {
+            Operator::And => StructureKind::Conjunction,
+            Operator::Or => StructureKind::Choice,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_structures() {
+        for (expression, kind) in [
+            ("((MIT WITH exc))", StructureKind::SingleTerm),
+            ("MIT OR (ISC OR 0BSD WITH exc)", StructureKind::Choice),
+            ("(MIT AND ISC) AND 0BSD", StructureKind::Conjunction),
+            ("MIT AND (ISC OR 0BSD)", StructureKind::Mixed),
+            ("MIT OR ISC AND 0BSD", StructureKind::Mixed),
+        ] {
+            assert_eq!(
+                structure_kind(&ExpressionVariant::parse(expression).unwrap()),
+                kind,
+                "{expression}"
+            );
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Serialization of expressions as trees instead of strings.
+//!
+//! [`SpdxExpression`] serializes as a string, like license fields in SPDX documents. For storing
+//! and querying expressions, e.g. in document databases, the tree of the expression is easier to
+//! work with. The functions of this module serialize and deserialize expressions as trees and can
+//! be used with `#[serde(with = "spdx_expression::structured")]` on fields of type
+//! [`SpdxExpression`], and [`Structured`] wraps an expression to serialize it as a tree.
+//!
+//! Licenses are maps with the license and an optional exception, and compound expressions are
+//! maps from the operator to the list of its operands, with operands combined with the same
+//! operator flattened. Redundant parentheses are not kept.
+//!
+//! # Examples
+//!
+//! ```
+//! # use spdx_expression::{structured::Structured, SpdxExpression};
+//! # use spdx_expression::SpdxExpressionError;
+//! #
+//! let expression = SpdxExpression::parse(
+//!     "MIT AND (GPL-2.0-only WITH Classpath-exception-2.0 OR Apache-2.0) AND ISC",
+//! )?;
+//!
+//! let json = serde_json::to_value(Structured(expression.clone())).unwrap();
+//! assert_eq!(
+//!     json,
+//!     serde_json::json!({
+//!         "and": [
+//!             { "license": "MIT" },
+//!             { "or": [
+//!                 { "license": "GPL-2.0-only", "exception": "Classpath-exception-2.0" },
+//!                 { "license": "Apache-2.0" },
+//!             ] },
+//!             { "license": "ISC" },
+//!         ]
+//!     })
+//! );
+//!
+//! let Structured(deserialized) = serde_json::from_value(json).unwrap();
+//! assert_eq!(deserialized, expression);
+//! # Ok::<(), SpdxExpressionError>(())
+//! ```
+
+use std::fmt::Formatter;
+
+use serde::{
+    de::{self, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser::{self, Operator},
+};
+
+const FIELDS: &[&str] = &["license", "exception", "and", "or"];
+
+/// Expression that serializes and deserializes as a tree, see the [module](self)
+/// documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Structured(pub SpdxExpression);
+
+impl From<SpdxExpression> for Structured {
+    fn from(expression: SpdxExpression) -> Self {
+        Self(expression)
+    }
+}
+
+impl From<Structured> for SpdxExpression {
+    fn from(structured: Structured) -> Self {
+        structured.0
+    }
+}
+
+impl Serialize for Structured {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Structured {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+/// Serialize `expression` as a tree.
+///
+/// # Errors
+///
+/// Returns the errors of `serializer`.
+pub fn serialize<S>(expression: &SpdxExpression, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Node(expression.inner()).serialize(serializer)
+}
+
+/// Deserialize an expression serialized as a tree.
+///
+/// # Errors
+///
+/// Returns an error if the input is not a tree of an expression, or the errors of
+/// `deserializer`.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<SpdxExpression, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    OwnedNode::deserialize(deserializer).map(|node| SpdxExpression::from_inner(node.0))
+}
+
+/// Node of an expression to serialize.
+struct Node<'a>(&'a ExpressionVariant);
+
+impl Serialize for Node<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if let Some((operator, operands)) = self.0.flatten() {
+            let key = match operator {
+                Operator::And => "and",
+                Operator::Or => "or",
+            };
+            let operands = operands.into_iter().map(Node).collect::<Vec<_>>();
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(key, &operands)?;
+            return map.end();
+        }
+
+        match self.0.unparenthesized() {
+            ExpressionVariant::With(with) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("license", &with.license)?;
+                map.serialize_entry("exception", &with.exception)?;
+                map.end()
+            }
+            ExpressionVariant::Simple(license) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("license", license)?;
+                map.end()
+            }
+            _ => unreachable!("compound expressions are flattened"),
+        }
+    }
+}
+
+/// Deserialized node of an expression.
+struct OwnedNode(ExpressionVariant);
+
+impl<'de> Deserialize<'de> for OwnedNode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = OwnedNode;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a license or a compound expression")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut license: Option<SimpleExpression> = None;
+        let mut exception: Option<String> = None;
+        let mut compound: Option<(Operator, Vec<OwnedNode>)> = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "license" if license.is_none() => license = Some(map.next_value()?),
+                "exception" if exception.is_none() => {
+                    let value: String = map.next_value()?;
+                    if !parser::is_idstring(&value) {
+                        return Err(de::Error::invalid_value(
+                            de::Unexpected::Str(&value),
+                            &"an exception identifier",
+                        ));
+                    }
+                    exception = Some(value);
+                }
+                "and" | "or" if compound.is_none() => {
+                    let operator = if key == "and" {
+                        Operator::And
+                    } else {
+                        Operator::Or
+                    };
+                    compound = Some((operator, map.next_value()?));
+                }
+                "license" | "exception" | "and" | "or" => {
+                    return Err(de::Error::custom(format!("duplicate field `{key}`")));
+                }
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+
+        let node = match (license, exception, compound) {
+            (Some(license), None, None) => ExpressionVariant::Simple(license),
+            (Some(license), Some(exception), None) => {
+                ExpressionVariant::With(Box::new(WithExpression::new(license, exception)))
+            }
+            (None, None, Some((operator, operands))) => operands
+                .into_iter()
+                .map(|operand| operand.0)
+                .reduce(|left, right| match operator {
+                    Operator::And => ExpressionVariant::and(left, right),
+                    Operator::Or => ExpressionVariant::or(left, right),
+                })
+                .ok_or_else(|| de::Error::invalid_length(0, &"at least one operand"))?,
+            (None, _, None) => return Err(de::Error::missing_field("license")),
+            _ => {
+                return Err(de::Error::custom(
+                    "expected either a license or an operator, not both",
+                ))
+            }
+        };
+        Ok(OwnedNode(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn round_trip(expression: &str) -> String {
+        let expression = Structured(SpdxExpression::parse(expression).unwrap());
+        let json = serde_json::to_value(&expression).unwrap();
+        let deserialized: Structured = serde_json::from_value(json).unwrap();
+        deserialized.0.to_string()
+    }
+
+    #[test]
+    fn round_trip_expressions() {
+        for expression in [
+            "MIT",
+            "LicenseRef-a",
+            "DocumentRef-x:LicenseRef-a WITH exc",
+            "GPL-2.0+ OR MIT AND ISC",
+            "(MIT OR ISC) AND (0BSD OR Apache-2.0 AND Zlib)",
+        ] {
+            assert_eq!(round_trip(expression), expression);
+        }
+        assert_eq!(
+            round_trip("((MIT)) AND (ISC AND 0BSD)"),
+            "MIT AND ISC AND 0BSD"
+        );
+    }
+
+    #[test]
+    fn round_trip_in_binary_formats() {
+        let expression = Structured(SpdxExpression::parse("MIT OR ISC WITH exc").unwrap());
+
+        let bincode: Structured =
+            bincode::deserialize(&bincode::serialize(&expression).unwrap()).unwrap();
+        assert_eq!(bincode, expression);
+
+        let postcard: Structured =
+            postcard::from_bytes(&postcard::to_allocvec(&expression).unwrap()).unwrap();
+        assert_eq!(postcard, expression);
+    }
+
+    #[test]
+    fn serialize_flattened_operands() {
+        let expression = SpdxExpression::parse("MIT OR (ISC OR 0BSD WITH exc)").unwrap();
+        assert_eq!(
+            serde_json::to_value(Structured(expression)).unwrap(),
+            json!({
+                "or": [
+                    { "license": "MIT" },
+                    { "license": "ISC" },
+                    { "license": "0BSD", "exception": "exc" },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn reject_invalid_trees() {
+        let invalid = [
+            json!("MIT"),
+            json!({}),
+            json!({ "exception": "exc" }),
+            json!({ "license": "MIT OR ISC" }),
+            json!({ "license": "MIT", "exception": "exc OR GPL-3.0-only" }),
+            json!({ "license": "MIT", "exception": "" }),
+            json!({ "license": "MIT", "and": [{ "license": "ISC" }] }),
+            json!({ "and": [] }),
+            json!({ "xor": [{ "license": "MIT" }] }),
+        ];
+        for value in invalid {
+            assert!(
+                serde_json::from_value::<Structured>(value.clone()).is_err(),
+                "{}",
+                value
+            );
+        }
+
+        let single: Structured =
+            serde_json::from_value(json!({ "and": [{ "license": "MIT" }] })).unwrap();
+        assert_eq!(single.0.to_string(), "MIT");
+    }
+}
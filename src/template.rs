@@ -0,0 +1,328 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Expressions with placeholders.
+
+use std::{borrow::Borrow, collections::HashMap, fmt::Display, hash::Hash};
+
+use crate::{
+    error::{ParseError, SpdxExpressionError},
+    expression::SpdxExpression,
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+};
+
+/// Prefix of the identifiers that stand for placeholders in the parsed template, followed by the
+/// index of the placeholder.
+const PLACEHOLDER_PREFIX: &str = "TEMPLATE-PLACEHOLDER.";
+
+/// Expression with `${name}` placeholders for licenses and exceptions.
+///
+/// The placeholders are filled in with [`ExpressionTemplate::instantiate`], e.g. to generate
+/// families of similar expressions like a license with different exceptions.
+///
+/// A placeholder stands for a whole license, which can be replaced with any expression, or a
+/// whole exception. The same placeholder can be used more than once.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use spdx_expression::ExpressionTemplate;
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// let template = ExpressionTemplate::parse("GPL-2.0-only WITH ${exception} AND ${other}")?;
+/// assert_eq!(template.placeholders(), ["exception", "other"]);
+///
+/// let expression = template.instantiate(&HashMap::from([
+///     ("exception", "Classpath-exception-2.0"),
+///     ("other", "MIT OR ISC"),
+/// ]))?;
+/// assert_eq!(
+///     expression.to_string(),
+///     "GPL-2.0-only WITH Classpath-exception-2.0 AND (MIT OR ISC)"
+/// );
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionTemplate {
+    /// The template as given.
+    source: String,
+
+    /// The template with the placeholders replaced with identifiers with
+    /// [`PLACEHOLDER_PREFIX`].
+    parsed: ExpressionVariant,
+
+    /// Names of the placeholders in the order of their first use.
+    placeholders: Vec<String>,
+}
+
+impl ExpressionTemplate {
+    /// Parse a template.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Template` if a placeholder is malformed or isn't a whole
+    /// license or exception, and `SpdxExpressionError::Parse` if the template is otherwise not
+    /// a valid expression.
+    pub fn parse(template: &str) -> Result<Self, SpdxExpressionError> {
+        if template.contains(PLACEHOLDER_PREFIX) {
+            return Err(SpdxExpressionError::Template(format!(
+                "`{PLACEHOLDER_PREFIX}` is reserved for placeholders"
+            )));
+        }
+
+        let mut placeholders: Vec<String> = Vec::new();
+        let mut expression = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| {
+                    SpdxExpressionError::Template(format!("unclosed placeholder in `{template}`"))
+                })?;
+            let name = &rest[start + 2..end];
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'))
+            {
+                return Err(SpdxExpressionError::Template(format!(
+                    "invalid placeholder name `{name}`"
+                )));
+            }
+
+            let index = placeholders
+                .iter()
+                .position(|placeholder| placeholder == name)
+                .unwrap_or_else(|| {
+                    placeholders.push(name.to_string());
+                    placeholders.len() - 1
+                });
+            expression.push_str(&rest[..start]);
+            expression.push_str(PLACEHOLDER_PREFIX);
+            expression.push_str(&index.to_string());
+            rest = &rest[end + 1..];
+        }
+        expression.push_str(rest);
+
+        let parsed = ExpressionVariant::parse(&expression)
+            .map_err(|_| SpdxExpressionError::Parse(ParseError::new(template)))?;
+
+        let is_partial = |identifier: &str| {
+            identifier.contains(PLACEHOLDER_PREFIX) && placeholder_index(identifier).is_none()
+        };
+        let partial = parsed.licenses().into_iter().any(|license| {
+            // A placeholder for a whole license has no prefixes or `+`.
+            let is_decorated =
+                license.license_ref || license.document_ref.is_some() || license.or_later;
+            is_partial(&license.identifier)
+                || is_decorated && license.identifier.contains(PLACEHOLDER_PREFIX)
+                || license
+                    .document_ref
+                    .as_ref()
+                    .is_some_and(|document| document.contains(PLACEHOLDER_PREFIX))
+        }) || parsed.exceptions().into_iter().any(is_partial);
+        if partial {
+            return Err(SpdxExpressionError::Template(format!(
+                "placeholders in `{template}` must be whole licenses or exceptions"
+            )));
+        }
+
+        Ok(Self {
+            source: template.to_string(),
+            parsed,
+            placeholders,
+        })
+    }
+
+    /// Get the names of the placeholders in the order of their first use.
+    pub fn placeholders(&self) -> &[String] {
+        &self.placeholders
+    }
+
+    /// Fill in the placeholders with `values`. Values of license placeholders are parsed as
+    /// expressions, and values of exception placeholders must be exception identifiers. A
+    /// placeholder for a license with an exception can only be filled in with a license.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SpdxExpressionError::Template` if a placeholder has no value, and
+    /// `SpdxExpressionError::Parse` if a value is not valid in the place of the placeholder.
+    pub fn instantiate<K, V>(
+        &self,
+        values: &HashMap<K, V>,
+    ) -> Result<SpdxExpression, SpdxExpressionError>
+    where
+        K: Borrow<str> + Eq + Hash,
+        V: AsRef<str>,
+    {
+        let values = self
+            .placeholders
+            .iter()
+            .map(|name| {
+                values.get(name.as_str()).map(AsRef::as_ref).ok_or_else(|| {
+                    SpdxExpressionError::Template(format!("no value for placeholder `{name}`"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut error = None;
+        let instantiated = self.parsed.map_leaves(&mut |leaf| {
+            let result = match leaf {
+                ExpressionVariant::Simple(license) => {
+                    let value = values[placeholder_index(&license.identifier)?];
+                    SpdxExpression::parse(value).map(|value| value.inner().clone())
+                }
+                ExpressionVariant::With(with) => {
+                    let license = placeholder_index(&with.license.identifier);
+                    let exception = placeholder_index(&with.exception);
+                    if license.is_none() && exception.is_none() {
+                        return None;
+                    }
+
+                    license
+                        .map_or_else(
+                            || Ok(with.license.clone()),
+                            |index| SimpleExpression::parse(values[index]),
+                        )
+                        .and_then(|license| {
+                            let exception = exception.map_or_else(
+                                || Ok(with.exception.as_str()),
+                                |index| exception_identifier(values[index]),
+                            )?;
+                            Ok(ExpressionVariant::With(Box::new(WithExpression::new(
+                                license,
+                                exception.to_string(),
+                            ))))
+                        })
+                }
+                ExpressionVariant::And(..)
+                | ExpressionVariant::Or(..)
+                | ExpressionVariant::Parens(_) => return None,
+            };
+            result.map_err(|err| error = Some(err)).ok()
+        });
+
+        error.map_or_else(
+            || {
+                Ok(SpdxExpression::from_inner(
+                    instantiated.unwrap_or_else(|| self.parsed.clone()),
+                ))
+            },
+            Err,
+        )
+    }
+}
+
+impl Display for ExpressionTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn placeholder_index(identifier: &str) -> Option<usize> {
+    identifier.strip_prefix(PLACEHOLDER_PREFIX)?.parse().ok()
+}
+
+fn exception_identifier(value: &str) -> Result<&str, SpdxExpressionError> {
+    let valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.'));
+    if valid {
+        Ok(value)
+    } else {
+        Err(SpdxExpressionError::Parse(ParseError::new(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_in_placeholders() {
+        let template = ExpressionTemplate::parse("${a} AND (${b} WITH ${c} OR ${a})").unwrap();
+        assert_eq!(template.placeholders(), ["a", "b", "c"]);
+        assert_eq!(template.to_string(), "${a} AND (${b} WITH ${c} OR ${a})");
+
+        let expression = template
+            .instantiate(&HashMap::from([
+                ("a", "MIT OR ISC"),
+                ("b", "GPL-2.0-or-later"),
+                ("c", "Classpath-exception-2.0"),
+            ]))
+            .unwrap();
+
+        assert_eq!(
+            expression.to_string(),
+            "(MIT OR ISC) AND (GPL-2.0-or-later WITH Classpath-exception-2.0 OR (MIT OR ISC))"
+        );
+    }
+
+    #[test]
+    fn templates_without_placeholders() {
+        let template = ExpressionTemplate::parse("MIT WITH exc OR LicenseRef-a").unwrap();
+        let values: HashMap<String, String> = HashMap::new();
+
+        assert!(template.placeholders().is_empty());
+        assert_eq!(
+            template.instantiate(&values).unwrap().to_string(),
+            "MIT WITH exc OR LicenseRef-a"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_templates() {
+        for template in [
+            "MIT AND ${a",
+            "MIT AND ${}",
+            "MIT AND ${a b}",
+            "LicenseRef-${a}",
+            "DocumentRef-${a}:LicenseRef-b",
+            "GPL-${version}-only",
+            "${a}+",
+            "MIT WITH exception-${version}",
+            "TEMPLATE-PLACEHOLDER.0",
+        ] {
+            assert!(
+                matches!(
+                    ExpressionTemplate::parse(template),
+                    Err(SpdxExpressionError::Template(_))
+                ),
+                "{}",
+                template
+            );
+        }
+        assert!(matches!(
+            ExpressionTemplate::parse("${a} AND"),
+            Err(SpdxExpressionError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn reject_missing_and_invalid_values() {
+        let template = ExpressionTemplate::parse("${license} WITH ${exception}").unwrap();
+
+        assert!(matches!(
+            template.instantiate(&HashMap::from([("license", "MIT")])),
+            Err(SpdxExpressionError::Template(_))
+        ));
+        assert!(matches!(
+            template.instantiate(&HashMap::from([
+                ("license", "MIT OR ISC"),
+                ("exception", "exc")
+            ])),
+            Err(SpdxExpressionError::Parse(_))
+        ));
+        assert!(matches!(
+            template.instantiate(&HashMap::from([
+                ("license", "MIT"),
+                ("exception", "exc AND ISC")
+            ])),
+            Err(SpdxExpressionError::Parse(_))
+        ));
+    }
+}
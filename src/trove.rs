@@ -0,0 +1,273 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Conversion between Python trove license classifiers and SPDX expressions.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    alias::AliasTable, error::SpdxExpressionError, expression::SpdxExpression,
+    expression_variant::ExpressionVariant,
+};
+
+/// Classifiers that identify a single license, and the SPDX identifiers of the licenses. The
+/// classifiers are converted to expressions with [`AliasTable::trove`].
+pub const CLASSIFIERS: &[(&str, &str)] = &[
+    (
+        "License :: CC0 1.0 Universal (CC0 1.0) Public Domain Dedication",
+        "CC0-1.0",
+    ),
+    (
+        "License :: OSI Approved :: Boost Software License 1.0 (BSL-1.0)",
+        "BSL-1.0",
+    ),
+    (
+        "License :: OSI Approved :: Eclipse Public License 1.0 (EPL-1.0)",
+        "EPL-1.0",
+    ),
+    (
+        "License :: OSI Approved :: Eclipse Public License 2.0 (EPL-2.0)",
+        "EPL-2.0",
+    ),
+    (
+        "License :: OSI Approved :: European Union Public Licence 1.1 (EUPL 1.1)",
+        "EUPL-1.1",
+    ),
+    (
+        "License :: OSI Approved :: European Union Public Licence 1.2 (EUPL 1.2)",
+        "EUPL-1.2",
+    ),
+    (
+        "License :: OSI Approved :: GNU Affero General Public License v3",
+        "AGPL-3.0-only",
+    ),
+    (
+        "License :: OSI Approved :: GNU Affero General Public License v3 or later (AGPLv3+)",
+        "AGPL-3.0-or-later",
+    ),
+    (
+        "License :: OSI Approved :: GNU General Public License v2 (GPLv2)",
+        "GPL-2.0-only",
+    ),
+    (
+        "License :: OSI Approved :: GNU General Public License v2 or later (GPLv2+)",
+        "GPL-2.0-or-later",
+    ),
+    (
+        "License :: OSI Approved :: GNU General Public License v3 (GPLv3)",
+        "GPL-3.0-only",
+    ),
+    (
+        "License :: OSI Approved :: GNU General Public License v3 or later (GPLv3+)",
+        "GPL-3.0-or-later",
+    ),
+    (
+        "License :: OSI Approved :: GNU Lesser General Public License v2 (LGPLv2)",
+        "LGPL-2.0-only",
+    ),
+    (
+        "License :: OSI Approved :: GNU Lesser General Public License v2 or later (LGPLv2+)",
+        "LGPL-2.0-or-later",
+    ),
+    (
+        "License :: OSI Approved :: GNU Lesser General Public License v3 (LGPLv3)",
+        "LGPL-3.0-only",
+    ),
+    (
+        "License :: OSI Approved :: GNU Lesser General Public License v3 or later (LGPLv3+)",
+        "LGPL-3.0-or-later",
+    ),
+    ("License :: OSI Approved :: ISC License (ISCL)", "ISC"),
+    ("License :: OSI Approved :: MIT License", "MIT"),
+    (
+        "License :: OSI Approved :: MIT No Attribution License (MIT-0)",
+        "MIT-0",
+    ),
+    (
+        "License :: OSI Approved :: Mozilla Public License 1.0 (MPL)",
+        "MPL-1.0",
+    ),
+    (
+        "License :: OSI Approved :: Mozilla Public License 1.1 (MPL 1.1)",
+        "MPL-1.1",
+    ),
+    (
+        "License :: OSI Approved :: Mozilla Public License 2.0 (MPL 2.0)",
+        "MPL-2.0",
+    ),
+    (
+        "License :: OSI Approved :: Python Software Foundation License",
+        "PSF-2.0",
+    ),
+    (
+        "License :: OSI Approved :: The Unlicense (Unlicense)",
+        "Unlicense",
+    ),
+    (
+        "License :: OSI Approved :: Universal Permissive License (UPL)",
+        "UPL-1.0",
+    ),
+    ("License :: OSI Approved :: zlib/libpng License", "Zlib"),
+];
+
+/// Classifiers for families of licenses. They don't identify a license, so they are only used
+/// when converting licenses to classifiers.
+const FAMILY_CLASSIFIERS: &[(&str, &str)] = &[
+    (
+        "License :: OSI Approved :: Apache Software License",
+        "Apache-1.1",
+    ),
+    (
+        "License :: OSI Approved :: Apache Software License",
+        "Apache-2.0",
+    ),
+    ("License :: OSI Approved :: BSD License", "0BSD"),
+    ("License :: OSI Approved :: BSD License", "BSD-2-Clause"),
+    ("License :: OSI Approved :: BSD License", "BSD-3-Clause"),
+];
+
+pub fn from_classifiers<I, S>(
+    classifiers: I,
+    aliases: &AliasTable,
+) -> Result<SpdxExpression, SpdxExpressionError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut expression: Option<ExpressionVariant> = None;
+
+    for classifier in classifiers {
+        let classifier = classifier.as_ref().trim();
+        if !classifier.starts_with("License ::") {
+            continue;
+        }
+
+        let license = aliases.get(classifier).ok_or_else(|| {
+            SpdxExpressionError::Conversion(format!(
+                "classifier `{classifier}` doesn't identify a license"
+            ))
+        })?;
+        let license = license.inner().clone();
+
+        expression = Some(match expression {
+            Some(expression) => ExpressionVariant::and(expression, license),
+            None => license,
+        });
+    }
+
+    expression
+        .map(SpdxExpression::from_inner)
+        .ok_or_else(|| SpdxExpressionError::Conversion("no license classifiers".to_string()))
+}
+
+pub fn to_classifiers(
+    expression: &SpdxExpression,
+) -> Result<BTreeSet<&'static str>, SpdxExpressionError> {
+    expression
+        .licenses()
+        .into_iter()
+        .map(|license| {
+            let identifier = license.to_string();
+            CLASSIFIERS
+                .iter()
+                .chain(FAMILY_CLASSIFIERS)
+                .find(|(_, candidate)| *candidate == identifier)
+                .map(|(classifier, _)| *classifier)
+                .ok_or_else(|| {
+                    SpdxExpressionError::Conversion(format!(
+                        "no classifier for license `{identifier}`"
+                    ))
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression_variant::SimpleExpression;
+
+    fn from_bundled<'a>(
+        classifiers: impl IntoIterator<Item = &'a str>,
+    ) -> Result<SpdxExpression, SpdxExpressionError> {
+        from_classifiers(classifiers, AliasTable::bundled_trove())
+    }
+
+    #[test]
+    fn classifier_table_is_valid() {
+        for (classifier, identifier) in CLASSIFIERS.iter().chain(FAMILY_CLASSIFIERS) {
+            assert!(classifier.starts_with("License :: "));
+            assert!(
+                SimpleExpression::parse(identifier).is_ok(),
+                "{}",
+                identifier
+            );
+        }
+    }
+
+    #[test]
+    fn convert_classifiers() {
+        let expression = from_bundled([
+            "Programming Language :: Python :: 3",
+            "License :: OSI Approved :: MIT License",
+            "License :: OSI Approved :: GNU General Public License v2 or later (GPLv2+)",
+        ])
+        .unwrap();
+
+        assert_eq!(expression.to_string(), "MIT AND GPL-2.0-or-later");
+    }
+
+    #[test]
+    fn reject_unknown_and_missing_classifiers() {
+        assert!(from_bundled(["License :: OSI Approved :: BSD License"]).is_err());
+        assert!(from_bundled(["Programming Language :: Python :: 3"]).is_err());
+    }
+
+    #[test]
+    fn convert_to_classifiers() {
+        let expression =
+            SpdxExpression::parse("MIT OR (Apache-2.0 AND BSD-3-Clause) OR MIT").unwrap();
+
+        assert_eq!(
+            to_classifiers(&expression).unwrap(),
+            BTreeSet::from([
+                "License :: OSI Approved :: Apache Software License",
+                "License :: OSI Approved :: BSD License",
+                "License :: OSI Approved :: MIT License",
+            ])
+        );
+        assert!(to_classifiers(&SpdxExpression::parse("LicenseRef-a").unwrap()).is_err());
+    }
+
+    #[test]
+    fn round_trip_classifiers() {
+        for (classifier, _) in CLASSIFIERS {
+            let expression = from_bundled([*classifier]).unwrap();
+            assert_eq!(
+                to_classifiers(&expression).unwrap(),
+                BTreeSet::from([*classifier])
+            );
+        }
+    }
+
+    #[test]
+    fn convert_custom_classifiers() {
+        let mut aliases = AliasTable::trove();
+        aliases.insert(
+            "License :: OSI Approved :: BSD License",
+            SpdxExpression::parse("BSD-3-Clause").unwrap(),
+        );
+
+        let expression = from_classifiers(
+            [
+                "License :: OSI Approved :: BSD License",
+                "License :: OSI Approved :: MIT License",
+            ],
+            &aliases,
+        )
+        .unwrap();
+
+        assert_eq!(expression.to_string(), "BSD-3-Clause AND MIT");
+    }
+}
@@ -0,0 +1,546 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Successive versions of licenses.
+
+use std::{cmp::Ordering, fmt::Display, ops::Range};
+
+use crate::{
+    expression_variant::{ExpressionVariant, SimpleExpression, WithExpression},
+    parser::Operator,
+};
+
+/// Families of licenses on the SPDX License List whose later versions can be chosen under the
+/// "or later" terms of earlier versions, from the oldest version to the newest.
+const SPDX_FAMILIES: &[&[&str]] = &[
+    &["AGPL-1.0-only", "AGPL-3.0-only"],
+    &["Apache-1.0", "Apache-1.1", "Apache-2.0"],
+    &["Artistic-1.0", "Artistic-2.0"],
+    &["CDDL-1.0", "CDDL-1.1"],
+    &["EPL-1.0", "EPL-2.0"],
+    &["EUPL-1.0", "EUPL-1.1", "EUPL-1.2"],
+    &["GFDL-1.1-only", "GFDL-1.2-only", "GFDL-1.3-only"],
+    &["GPL-1.0-only", "GPL-2.0-only", "GPL-3.0-only"],
+    &["LGPL-2.0-only", "LGPL-2.1-only", "LGPL-3.0-only"],
+    &["LPPL-1.0", "LPPL-1.1", "LPPL-1.2", "LPPL-1.3a", "LPPL-1.3c"],
+    &["MPL-1.0", "MPL-1.1", "MPL-2.0"],
+    &["OFL-1.0", "OFL-1.1"],
+];
+
+/// Successive versions of licenses, used to interpret "or later" licenses like
+/// `GPL-2.0-or-later` or `Apache-1.1+`.
+///
+/// Every family of licenses lists the identifiers of its versions from the oldest to the
+/// newest, using the identifiers of single versions, like `GPL-2.0-only`. The "or later"
+/// identifiers of a version are the identifier with a `+` suffix, and for identifiers ending
+/// with `-only` the identifier with `-or-later` instead.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::LicenseVersions;
+/// #
+/// let mut versions = LicenseVersions::spdx();
+/// versions.insert_family(["LicenseRef-Acme-1", "LicenseRef-Acme-2"]);
+///
+/// assert_eq!(
+///     versions.later_versions("GPL-2.0-only"),
+///     Some(&["GPL-2.0-only".to_string(), "GPL-3.0-only".to_string()][..])
+/// );
+/// assert_eq!(versions.later_versions("LicenseRef-Acme-2").map(<[_]>::len), Some(1));
+/// assert_eq!(versions.later_versions("MIT"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LicenseVersions {
+    families: Vec<Vec<String>>,
+}
+
+impl LicenseVersions {
+    /// Create a table without any families.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table of the families of licenses on the SPDX License List, like the GNU
+    /// licenses and the Apache, Mozilla and Eclipse licenses.
+    pub fn spdx() -> Self {
+        Self {
+            families: SPDX_FAMILIES
+                .iter()
+                .map(|family| family.iter().map(ToString::to_string).collect())
+                .collect(),
+        }
+    }
+
+    /// Add a family of licenses, from the oldest version to the newest. Versions that are also
+    /// in earlier families are looked up in this family from now on.
+    pub fn insert_family<I, S>(&mut self, versions: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.families
+            .push(versions.into_iter().map(Into::into).collect());
+    }
+
+    /// Get the identifier of a single version and the identifiers of the later versions of its
+    /// family, or `None` if the identifier is not in any family.
+    pub fn later_versions(&self, identifier: &str) -> Option<&[String]> {
+        let (family, index) = self.find(identifier)?;
+        Some(&self.families[family][index..])
+    }
+
+    /// Get the single versions allowed by an "or later" license, or `None` if `license` is not
+    /// an "or later" license of a known family.
+    pub(crate) fn or_later_versions(
+        &self,
+        license: &SimpleExpression,
+    ) -> Option<Vec<SimpleExpression>> {
+        let (family, versions) = self.versions_of(license)?;
+        if versions.len() == 1 && !is_or_later(license) {
+            return None;
+        }
+
+        self.families[family][versions]
+            .iter()
+            .map(|version| SimpleExpression::parse(version).ok())
+            .collect()
+    }
+
+    /// Get the index of the family of `license` and the range of the versions in the family that
+    /// `license` allows, either a single version or a version and all later versions.
+    pub(crate) fn versions_of(&self, license: &SimpleExpression) -> Option<(usize, Range<usize>)> {
+        let identifier = &license.to_string();
+        let candidates = if let Some(base) = identifier.strip_suffix("-or-later") {
+            [format!("{base}-only"), base.to_string()]
+        } else if let Some(base) = identifier.strip_suffix('+') {
+            [base.to_string(), format!("{base}-only")]
+        } else {
+            let (family, index) = self.find(identifier)?;
+            return Some((family, index..index + 1));
+        };
+
+        let (family, index) = candidates
+            .iter()
+            .find_map(|candidate| self.find(candidate))?;
+        Some((family, index..self.families[family].len()))
+    }
+
+    /// Get the "or later" license of the version at `index` of `family`.
+    pub(crate) fn or_later(&self, family: usize, index: usize) -> Option<SimpleExpression> {
+        let version = &self.families[family][index];
+        let or_later = version
+            .strip_suffix("-only")
+            .map_or_else(|| format!("{version}+"), |base| format!("{base}-or-later"));
+        SimpleExpression::parse(&or_later).ok()
+    }
+
+    fn find(&self, identifier: &str) -> Option<(usize, usize)> {
+        self.families
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(family, versions)| {
+                let index = versions.iter().position(|version| version == identifier)?;
+                Some((family, index))
+            })
+    }
+}
+
+/// Suffix of a versioned license identifier, see [`VersionedIdentifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VersionSuffix {
+    /// No suffix, e.g. `Apache-2.0`.
+    None,
+
+    /// `-only`, e.g. `GPL-2.0-only`.
+    Only,
+
+    /// `-or-later`, e.g. `GPL-2.0-or-later`.
+    OrLater,
+
+    /// `+`, e.g. `GPL-2.0+` or `Apache-1.1+`.
+    Plus,
+}
+
+impl VersionSuffix {
+    /// Check if the suffix allows later versions of the license.
+    pub const fn is_or_later(self) -> bool {
+        matches!(self, Self::OrLater | Self::Plus)
+    }
+}
+
+/// License identifier split into the name of the license, its version and the suffix telling if
+/// later versions are allowed, e.g. `GPL-2.0-or-later` into `GPL`, `2.0` and `-or-later`.
+///
+/// Versions are compared component by component, numerically where possible, so `LGPL-2.1` is a
+/// later version than `LGPL-2.0`, and `LPPL-1.3c` is a later version than `LPPL-1.3a`. Names are
+/// compared case-insensitively. Licenses with different names are not comparable, so this
+/// doesn't know that e.g. `GPL-2.0-only` can be relicensed as `EUPL-1.2`.
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{VersionedIdentifier, VersionSuffix};
+/// #
+/// let gpl2 = VersionedIdentifier::parse("GPL-2.0-or-later").unwrap();
+/// assert_eq!((gpl2.name.as_str(), gpl2.version.as_str()), ("GPL", "2.0"));
+/// assert_eq!(gpl2.suffix, VersionSuffix::OrLater);
+///
+/// let gpl3 = VersionedIdentifier::parse("GPL-3.0-only").unwrap();
+/// assert!(gpl3.is_later_version_of(&gpl2));
+/// assert!(gpl2.allows(&gpl3));
+/// assert!(!gpl3.allows(&gpl2));
+///
+/// assert_eq!(VersionedIdentifier::parse("MIT"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionedIdentifier {
+    /// Name of the license without the version, e.g. `GPL`.
+    pub name: String,
+
+    /// Version of the license, e.g. `2.0`.
+    pub version: String,
+
+    /// Suffix telling if later versions are allowed, e.g. `-or-later`.
+    pub suffix: VersionSuffix,
+}
+
+impl VersionedIdentifier {
+    /// Split `identifier` into its name, version and suffix. Returns `None` if the identifier
+    /// doesn't end with a version, possibly followed by a suffix.
+    pub fn parse(identifier: &str) -> Option<Self> {
+        let (rest, suffix) = [
+            ("+", VersionSuffix::Plus),
+            ("-or-later", VersionSuffix::OrLater),
+            ("-only", VersionSuffix::Only),
+        ]
+        .iter()
+        .find_map(|(suffix, kind)| Some((identifier.strip_suffix(suffix)?, *kind)))
+        .unwrap_or((identifier, VersionSuffix::None));
+
+        let (name, version) = rest.rsplit_once('-')?;
+        let is_version = version.split('.').all(|component| {
+            let letters = component.trim_start_matches(|c: char| c.is_ascii_digit());
+            letters.len() < component.len() && letters.chars().all(|c| c.is_ascii_alphabetic())
+        });
+        if name.is_empty() || !is_version {
+            return None;
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            suffix,
+        })
+    }
+
+    /// Compare the versions of two identifiers of the same license. Returns `None` if the
+    /// licenses have different names.
+    pub fn compare_version(&self, other: &Self) -> Option<Ordering> {
+        self.name
+            .eq_ignore_ascii_case(&other.name)
+            .then(|| version_key(&self.version).cmp(&version_key(&other.version)))
+    }
+
+    /// Check if `self` is a later version of the same license than `other`.
+    pub fn is_later_version_of(&self, other: &Self) -> bool {
+        self.compare_version(other) == Some(Ordering::Greater)
+    }
+
+    /// Check if the version of `other` can be chosen under `self`, i.e. if it's the same version
+    /// or `self` allows later versions and `other` is a later version.
+    pub fn allows(&self, other: &Self) -> bool {
+        match self.compare_version(other) {
+            Some(Ordering::Equal) => true,
+            Some(Ordering::Less) => self.suffix.is_or_later(),
+            Some(Ordering::Greater) | None => false,
+        }
+    }
+}
+
+impl Display for VersionedIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = match self.suffix {
+            VersionSuffix::None => "",
+            VersionSuffix::Only => "-only",
+            VersionSuffix::OrLater => "-or-later",
+            VersionSuffix::Plus => "+",
+        };
+        write!(f, "{}-{}{suffix}", self.name, self.version)
+    }
+}
+
+/// Key for comparing versions, with numeric and alphabetic parts of every component and without
+/// trailing zero components, so `2` and `2.0` are equal.
+fn version_key(version: &str) -> Vec<(u64, String)> {
+    let mut key = version
+        .split('.')
+        .map(|component| {
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(component.len());
+            (
+                component[..digits].parse().unwrap_or(u64::MAX),
+                component[digits..].to_ascii_lowercase(),
+            )
+        })
+        .collect::<Vec<_>>();
+    while key.last().is_some_and(|last| *last == (0, String::new())) {
+        key.pop();
+    }
+    key
+}
+
+fn is_or_later(license: &SimpleExpression) -> bool {
+    license.or_later || license.identifier.ends_with("-or-later")
+}
+
+/// Replace alternatives of all versions of a license from some version on with the "or later"
+/// license of the version. Returns `None` if nothing was replaced.
+pub fn collapse_or_later(
+    expression: &ExpressionVariant,
+    versions: &LicenseVersions,
+) -> Option<ExpressionVariant> {
+    let (operator, operands) = expression.flatten()?;
+
+    let mut changed = false;
+    let mut operands = operands
+        .into_iter()
+        .map(|operand| {
+            collapse_or_later(operand, versions).map_or_else(
+                || operand.clone(),
+                |collapsed| {
+                    changed = true;
+                    collapsed
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if operator == Operator::Or {
+        changed |= collapse_alternatives(&mut operands, versions);
+    }
+
+    if !changed {
+        return None;
+    }
+    operands.into_iter().reduce(|left, right| match operator {
+        Operator::And => ExpressionVariant::and(left, right),
+        Operator::Or => ExpressionVariant::or(left, right),
+    })
+}
+
+/// Collapse the alternatives of every family and exception in `operands`. Returns `true` if any
+/// alternatives were collapsed.
+fn collapse_alternatives(
+    operands: &mut Vec<ExpressionVariant>,
+    versions: &LicenseVersions,
+) -> bool {
+    let versions_of = |operand: &ExpressionVariant| {
+        let (family, range) = versions.versions_of(operand.license()?)?;
+        let exception = match operand {
+            ExpressionVariant::With(with) => Some(with.exception.clone()),
+            _ => None,
+        };
+        Some(((family, exception), range))
+    };
+
+    let mut groups: Vec<(usize, Option<String>)> = Vec::new();
+    for (group, _) in operands.iter().filter_map(versions_of) {
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+
+    let mut changed = false;
+    for group in groups {
+        let members = operands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, operand)| {
+                let (operand_group, range) = versions_of(operand)?;
+                (operand_group == group).then_some((index, range))
+            })
+            .collect::<Vec<_>>();
+
+        // The earliest version from which all later versions are allowed.
+        let family_length = versions.families[group.0].len();
+        let mut covered = vec![false; family_length];
+        for (_, range) in &members {
+            for version in range.clone() {
+                covered[version] = true;
+            }
+        }
+        let start = (0..family_length)
+            .rev()
+            .take_while(|&version| covered[version])
+            .last();
+        let Some(start) = start else {
+            continue;
+        };
+
+        let collapsed = members
+            .iter()
+            .filter(|(_, range)| range.start >= start)
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+        if collapsed.len() < 2 {
+            continue;
+        }
+        let Some(or_later) = versions.or_later(group.0, start) else {
+            continue;
+        };
+
+        let replacement = match group.1 {
+            Some(exception) => {
+                ExpressionVariant::With(Box::new(WithExpression::new(or_later, exception)))
+            }
+            None => ExpressionVariant::Simple(or_later),
+        };
+        operands[collapsed[0]] = replacement;
+        for index in collapsed[1..].iter().rev() {
+            operands.remove(*index);
+        }
+        changed = true;
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn or_later(identifier: &str) -> Option<Vec<String>> {
+        LicenseVersions::spdx()
+            .or_later_versions(&SimpleExpression::parse(identifier).unwrap())
+            .map(|versions| versions.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn bundled_families_are_valid() {
+        for family in SPDX_FAMILIES {
+            for version in *family {
+                assert!(SimpleExpression::parse(version).is_ok(), "{}", version);
+            }
+        }
+    }
+
+    #[test]
+    fn find_or_later_versions() {
+        assert_eq!(
+            or_later("LGPL-2.0-or-later").unwrap(),
+            ["LGPL-2.0-only", "LGPL-2.1-only", "LGPL-3.0-only"]
+        );
+        assert_eq!(
+            or_later("GPL-2.0+").unwrap(),
+            ["GPL-2.0-only", "GPL-3.0-only"]
+        );
+        assert_eq!(or_later("MPL-1.1+").unwrap(), ["MPL-1.1", "MPL-2.0"]);
+        assert_eq!(or_later("GPL-3.0-or-later").unwrap(), ["GPL-3.0-only"]);
+        assert_eq!(or_later("GPL-2.0-only"), None);
+        assert_eq!(or_later("MIT+"), None);
+    }
+
+    #[test]
+    fn collapse_complete_alternatives() {
+        let collapse = |expression: &str| {
+            let expression = ExpressionVariant::parse(expression).unwrap();
+            collapse_or_later(&expression, &LicenseVersions::spdx())
+                .unwrap_or(expression)
+                .to_string()
+        };
+
+        assert_eq!(collapse("GPL-2.0-only OR GPL-3.0-only"), "GPL-2.0-or-later");
+        assert_eq!(
+            collapse("MIT OR LGPL-3.0-only OR LGPL-2.1-only OR ISC"),
+            "MIT OR LGPL-2.1-or-later OR ISC"
+        );
+        assert_eq!(collapse("GPL-2.0-only OR GPL-3.0+"), "GPL-2.0-or-later");
+        assert_eq!(
+            collapse("MPL-1.1 WITH exc OR MPL-2.0 WITH exc OR MPL-2.0"),
+            "MPL-1.1+ WITH exc OR MPL-2.0"
+        );
+        assert_eq!(
+            collapse("MIT AND (Apache-2.0 OR Apache-1.1 OR Apache-1.0)"),
+            "MIT AND Apache-1.0+"
+        );
+    }
+
+    #[test]
+    fn keep_incomplete_alternatives() {
+        for expression in [
+            "GPL-1.0-only OR GPL-2.0-only",
+            "GPL-3.0-only",
+            "GPL-2.0-only AND GPL-3.0-only",
+            "GPL-2.0-only WITH exc OR GPL-3.0-only",
+        ] {
+            let parsed = ExpressionVariant::parse(expression).unwrap();
+            assert_eq!(
+                collapse_or_later(&parsed, &LicenseVersions::spdx()),
+                None,
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_versioned_identifiers() {
+        for (identifier, name, version, suffix) in [
+            ("GPL-2.0-only", "GPL", "2.0", VersionSuffix::Only),
+            ("GPL-2.0+", "GPL", "2.0", VersionSuffix::Plus),
+            ("LGPL-2.1-or-later", "LGPL", "2.1", VersionSuffix::OrLater),
+            ("CC-BY-SA-4.0", "CC-BY-SA", "4.0", VersionSuffix::None),
+            ("LPPL-1.3c", "LPPL", "1.3c", VersionSuffix::None),
+            (
+                "LicenseRef-Acme-1",
+                "LicenseRef-Acme",
+                "1",
+                VersionSuffix::None,
+            ),
+        ] {
+            let parsed = VersionedIdentifier::parse(identifier).unwrap();
+            assert_eq!(
+                (parsed.name.as_str(), parsed.version.as_str(), parsed.suffix),
+                (name, version, suffix)
+            );
+            assert_eq!(parsed.to_string(), identifier);
+        }
+        for identifier in ["MIT", "BSD-3-Clause", "GPL-only", "-2.0", "Apache-2.0.x"] {
+            assert_eq!(VersionedIdentifier::parse(identifier), None, "{identifier}");
+        }
+    }
+
+    #[test]
+    fn compare_versions() {
+        let parse = |identifier| VersionedIdentifier::parse(identifier).unwrap();
+
+        assert!(parse("LGPL-2.1-only").is_later_version_of(&parse("lgpl-2.0-only")));
+        assert!(parse("LPPL-1.3c").is_later_version_of(&parse("LPPL-1.3a")));
+        assert!(parse("Foo-10").is_later_version_of(&parse("Foo-9.1")));
+        assert_eq!(
+            parse("Apache-2").compare_version(&parse("Apache-2.0")),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(parse("GPL-2.0").compare_version(&parse("LGPL-2.0")), None);
+
+        assert!(parse("GPL-2.0+").allows(&parse("GPL-3.0-only")));
+        assert!(parse("GPL-2.0-only").allows(&parse("GPL-2.0-or-later")));
+        assert!(!parse("GPL-2.0-only").allows(&parse("GPL-3.0-only")));
+        assert!(!parse("GPL-3.0-or-later").allows(&parse("GPL-2.0-only")));
+    }
+
+    #[test]
+    fn custom_families_override_bundled_ones() {
+        let mut versions = LicenseVersions::spdx();
+        versions.insert_family(["GPL-2.0-only", "LicenseRef-GPL-4.0"]);
+
+        assert_eq!(
+            versions.later_versions("GPL-2.0-only").unwrap(),
+            ["GPL-2.0-only", "LicenseRef-GPL-4.0"]
+        );
+        assert_eq!(versions.later_versions("GPL-1.0-only").unwrap().len(), 3);
+    }
+}
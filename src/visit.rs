@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: 2022 HH Partners
+//
+// SPDX-License-Identifier: MIT
+
+//! Mutable traversal of expressions.
+
+use crate::{expression_variant::SimpleExpression, parser::Operator};
+
+/// Visitor editing the parts of an expression in place, used with [`SpdxExpression::walk_mut`].
+///
+/// The parts are visited in the order they appear in the displayed expression. Every method
+/// does nothing by default, so visitors only implement the methods for the parts they edit.
+///
+/// [`SpdxExpression::walk_mut`]: crate::SpdxExpression::walk_mut
+///
+/// # Examples
+///
+/// ```
+/// # use spdx_expression::{SimpleExpression, SpdxExpression, VisitorMut};
+/// # use spdx_expression::SpdxExpressionError;
+/// #
+/// struct Prefix;
+///
+/// impl VisitorMut for Prefix {
+///     fn visit_license(&mut self, license: &mut SimpleExpression) {
+///         if license.license_ref {
+///             license.identifier = format!("acme-{}", license.identifier);
+///         }
+///     }
+/// }
+///
+/// let mut expression = SpdxExpression::parse("MIT OR LicenseRef-eula")?;
+/// expression.walk_mut(&mut Prefix);
+///
+/// assert_eq!(expression.to_string(), "MIT OR LicenseRef-acme-eula");
+/// # Ok::<(), SpdxExpressionError>(())
+/// ```
+pub trait VisitorMut {
+    /// Visit a license, including licenses with an exception.
+    fn visit_license(&mut self, _license: &mut SimpleExpression) {}
+
+    /// Visit the exception of a license, after the license.
+    fn visit_exception(&mut self, _exception: &mut String) {}
+
+    /// Visit the operator joining two operands, after the left operand and before the right
+    /// operand.
+    fn visit_operator(&mut self, _operator: &mut Operator) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpdxExpression;
+
+    /// Records the visited parts and flips the first operator.
+    #[derive(Default)]
+    struct Recorder {
+        visited: Vec<String>,
+        flipped: bool,
+    }
+
+    impl VisitorMut for Recorder {
+        fn visit_license(&mut self, license: &mut SimpleExpression) {
+            self.visited.push(license.to_string());
+        }
+
+        fn visit_exception(&mut self, exception: &mut String) {
+            self.visited.push(exception.clone());
+        }
+
+        fn visit_operator(&mut self, operator: &mut Operator) {
+            self.visited.push(format!("{operator:?}"));
+            if !self.flipped {
+                self.flipped = true;
+                *operator = match operator {
+                    Operator::And => Operator::Or,
+                    Operator::Or => Operator::And,
+                };
+            }
+        }
+    }
+
+    #[test]
+    fn visit_in_display_order() {
+        let mut expression =
+            SpdxExpression::parse("MIT OR (ISC AND GPL-2.0-only WITH exc)").unwrap();
+        let mut recorder = Recorder::default();
+        expression.walk_mut(&mut recorder);
+
+        assert_eq!(
+            recorder.visited,
+            ["MIT", "Or", "ISC", "And", "GPL-2.0-only", "exc"]
+        );
+        assert_eq!(
+            expression.to_string(),
+            "MIT AND (ISC AND GPL-2.0-only WITH exc)"
+        );
+    }
+
+    #[test]
+    fn parenthesize_changed_operators() {
+        for (input, expected) in [
+            ("A AND B AND C", "(A OR B) AND C"),
+            ("A OR B OR C", "A AND B OR C"),
+            ("A OR B AND C", "A AND (B AND C)"),
+        ] {
+            let mut expression = SpdxExpression::parse(input).unwrap();
+            expression.walk_mut(&mut Recorder::default());
+
+            assert_eq!(expression.to_string(), expected, "{input}");
+            assert_eq!(
+                SpdxExpression::parse(expected).unwrap(),
+                expression,
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn keep_clones_unchanged() {
+        let original = SpdxExpression::parse("MIT AND (ISC OR 0BSD)").unwrap();
+        let mut edited = original.clone();
+        edited.walk_mut(&mut Recorder::default());
+
+        assert_eq!(original.to_string(), "MIT AND (ISC OR 0BSD)");
+        assert_eq!(edited.to_string(), "MIT OR (ISC OR 0BSD)");
+        assert_ne!(original, edited);
+    }
+}